@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use rustyknife::behaviour::{Intl, Legacy};
+use rustyknife::rfc5322::{from, reply_to, sender};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = from::<Intl>(data);
+    let _ = from::<Legacy>(data);
+    let _ = reply_to::<Intl>(data);
+    let _ = sender::<Intl>(data);
+});
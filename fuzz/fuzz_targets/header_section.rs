@@ -0,0 +1,19 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use rustyknife::headersection::{header_section, header_section_spans};
+
+fuzz_target!(|data: &[u8]| {
+    let plain = header_section(data);
+    let spans = header_section_spans(data);
+
+    // Both splitters must agree on the headers and the remainder.
+    if let (Ok((rem1, headers)), Ok((rem2, spanned))) = (plain, spans) {
+        assert_eq!(rem1, rem2);
+        assert_eq!(headers.len(), spanned.len());
+        for (header, (spanned, span)) in headers.iter().zip(spanned.iter()) {
+            assert_eq!(header, spanned);
+            assert!(span.end <= data.len());
+        }
+    }
+});
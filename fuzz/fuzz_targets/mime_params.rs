@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use rustyknife::rfc2231::{content_disposition, content_transfer_encoding, content_type};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = content_type(data);
+    let _ = content_disposition(data);
+    let _ = content_transfer_encoding(data);
+});
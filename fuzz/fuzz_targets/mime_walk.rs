@@ -0,0 +1,15 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use rustyknife::mime::{attachments, decode_text_part, parse_message};
+
+fuzz_target!(|data: &[u8]| {
+    let message = parse_message(data);
+
+    for entity in message.walk() {
+        let _ = entity.decoded_body();
+        let _ = decode_text_part(entity);
+        let _ = entity.header(b"content-disposition");
+    }
+    let _ = attachments(&message).count();
+});
@@ -0,0 +1,8 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use rustyknife::proxy::proxy_header;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = proxy_header(data);
+});
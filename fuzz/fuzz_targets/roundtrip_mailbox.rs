@@ -0,0 +1,15 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use rustyknife::types::Mailbox;
+
+fuzz_target!(|data: &[u8]| {
+    // Any address that parses must serialize to a string that parses
+    // back to the same value.
+    if let Ok(parsed) = Mailbox::from_smtp(data) {
+        let serialized = parsed.to_string();
+        let reparsed =
+            Mailbox::from_smtp(serialized.as_bytes()).expect("serialized form must parse");
+        assert_eq!(parsed, reparsed);
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use rustyknife::behaviour::{Intl, Legacy};
+use rustyknife::rfc5321::command;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = command::<Intl>(data);
+    let _ = command::<Legacy>(data);
+});
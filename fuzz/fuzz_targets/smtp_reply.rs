@@ -0,0 +1,17 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use rustyknife::rfc5321::reply::{ehlo_response, greeting, reply};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = greeting(data);
+    let _ = ehlo_response(data);
+
+    // A parsed reply must parse back to itself once serialized.
+    if let Ok((_, parsed)) = reply(data) {
+        let bytes = parsed.to_bytes();
+        let (rem, reparsed) = reply(&bytes).unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(parsed, reparsed);
+    }
+});
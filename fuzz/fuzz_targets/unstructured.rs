@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use rustyknife::behaviour::{Intl, Legacy};
+use rustyknife::rfc5322::unstructured;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = unstructured::<Intl>(data);
+    let _ = unstructured::<Legacy>(data);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use rustyknife::xforward::xforward_params;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = xforward_params(data);
+});
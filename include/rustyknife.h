@@ -0,0 +1,37 @@
+/* C API of rustyknife, built with the "c-api" feature. */
+#ifndef RUSTYKNIFE_H
+#define RUSTYKNIFE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define RK_HEADER_MORE 0
+#define RK_HEADER_DONE 1
+#define RK_HEADER_TOO_LONG (-1)
+#define RK_HEADER_ABORTED (-2)
+#define RK_HEADER_ERROR (-3)
+#define RK_HEADER_TOO_LARGE (-4)
+
+typedef struct HeaderContext rk_header_ctx;
+
+/* Called for each header. name is NULL for an invalid line, which is
+ * then passed as the value. Return non-zero to stop parsing. */
+typedef int (*rk_header_cb)(void *user, const uint8_t *name, size_t name_len,
+                            const uint8_t *value, size_t value_len);
+
+rk_header_ctx *rk_header_new(size_t max_header_len, void *user);
+/* 0 disables a limit. */
+int rk_header_set_limits(rk_header_ctx *ctx, uint64_t max_bytes, uint64_t max_lines);
+int rk_header_feed(rk_header_ctx *ctx, const uint8_t *buf, size_t len, rk_header_cb callback);
+uint64_t rk_header_consumed(const rk_header_ctx *ctx);
+void rk_header_free(rk_header_ctx *ctx);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
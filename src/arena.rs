@@ -0,0 +1,122 @@
+//! Reusable storage for decoded unstructured text
+//!
+//! Decoding the unstructured headers of every message, such as
+//! `"Subject:"`, allocates one string per header. A [`ParseArena`]
+//! stores these decoded values for one message in a single buffer
+//! instead. Resetting it between messages keeps the buffer, so the
+//! buffer stops growing once the largest message has been seen.
+//!
+//! Only [`ParseArena::unstructured`] and [`ParseArena::push_str`]
+//! write to the arena. The other parsers of this crate, addresses,
+//! dates, MIME parameters and SMTP commands included, allocate their
+//! results as usual and are not covered. Within `unstructured`, only
+//! the result string is saved: encoded words and folded white space
+//! are still decoded into short-lived temporaries that are then
+//! copied into the buffer.
+
+use std::ops::Range;
+
+use crate::rfc5322::{unstructured_into, UTF8Policy};
+use crate::util::*;
+
+/// A handle to a string stored in a [`ParseArena`].
+///
+/// Handles are only valid until the arena is reset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ArenaStr(usize);
+
+/// A resettable buffer holding decoded strings.
+/// # Examples
+/// ```
+/// use rustyknife::arena::ParseArena;
+/// use rustyknife::behaviour::Intl;
+///
+/// let mut arena = ParseArena::new();
+///
+/// for subject in [&b"=?utf-8?Q?caf=C3=A9?="[..], b"Hello"].iter() {
+///     let decoded = arena.unstructured::<Intl>(subject).unwrap();
+///     let prefix = arena.push_str("Re: ");
+///     assert_eq!(arena.get(prefix), "Re: ");
+///     assert!(["café", "Hello"].contains(&arena.get(decoded)));
+///     arena.reset();
+/// }
+/// assert!(arena.is_empty());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ParseArena {
+    text: String,
+    strings: Vec<Range<usize>>,
+}
+
+impl ParseArena {
+    /// Create an empty arena.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create an empty arena with room for `bytes` bytes of text.
+    pub fn with_capacity(bytes: usize) -> Self {
+        ParseArena {
+            text: String::with_capacity(bytes),
+            strings: Vec::new(),
+        }
+    }
+
+    fn finish(&mut self, start: usize) -> ArenaStr {
+        self.strings.push(start..self.text.len());
+        ArenaStr(self.strings.len() - 1)
+    }
+
+    /// Copy `s` into the arena.
+    pub fn push_str(&mut self, s: &str) -> ArenaStr {
+        let start = self.text.len();
+        self.text.push_str(s);
+        self.finish(start)
+    }
+
+    /// Decode an unstructured header into the arena.
+    ///
+    /// Decodes the same text as
+    /// [`rfc5322::unstructured`](crate::rfc5322::unstructured) without
+    /// allocating a string for the result. Plain text is written to
+    /// the arena directly, encoded words and folded white space go
+    /// through a temporary first.
+    pub fn unstructured<'a, P: UTF8Policy>(
+        &mut self,
+        input: &'a [u8],
+    ) -> Result<ArenaStr, nom::Err<NomError<'a>>> {
+        let start = self.text.len();
+
+        match unstructured_into::<P, _>(input, &mut self.text, |_, _| ()) {
+            Ok(_) => Ok(self.finish(start)),
+            Err(e) => {
+                self.text.truncate(start);
+                Err(e)
+            }
+        }
+    }
+
+    /// Return a string stored in the arena.
+    ///
+    /// Panics or returns another string if `s` was returned before the
+    /// last reset or by another arena.
+    pub fn get(&self, s: ArenaStr) -> &str {
+        &self.text[self.strings[s.0].clone()]
+    }
+
+    /// Number of strings stored.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns `true` if no string is stored.
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Remove all strings, keeping the allocated memory.
+    pub fn reset(&mut self) {
+        self.text.clear();
+        self.strings.clear();
+    }
+}
@@ -0,0 +1,169 @@
+//! [iCalendar] MIME parameters
+//!
+//! Exposes the `method` and `component` parameters of
+//! `"text/calendar"` entities used by [iMIP] meeting invitations.
+//! The calendar data itself is not parsed.
+//!
+//! [iCalendar]: https://tools.ietf.org/html/rfc5545#section-8.1
+//! [iMIP]: https://tools.ietf.org/html/rfc6047
+
+use std::fmt::{self, Display};
+
+use crate::mime::Entity;
+use crate::rfc2231::MimeType;
+
+/// An [iTIP] scheduling method.
+///
+/// [iTIP]: https://tools.ietf.org/html/rfc5546#section-1.4
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Method {
+    /// `PUBLISH`
+    Publish,
+    /// `REQUEST`, a meeting invitation or update.
+    Request,
+    /// `REPLY`, an attendee response.
+    Reply,
+    /// `ADD`
+    Add,
+    /// `CANCEL`
+    Cancel,
+    /// `REFRESH`
+    Refresh,
+    /// `COUNTER`
+    Counter,
+    /// `DECLINECOUNTER`
+    DeclineCounter,
+    /// Any other method, stored in uppercase.
+    Other(String),
+}
+
+impl From<&str> for Method {
+    fn from(value: &str) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "PUBLISH" => Method::Publish,
+            "REQUEST" => Method::Request,
+            "REPLY" => Method::Reply,
+            "ADD" => Method::Add,
+            "CANCEL" => Method::Cancel,
+            "REFRESH" => Method::Refresh,
+            "COUNTER" => Method::Counter,
+            "DECLINECOUNTER" => Method::DeclineCounter,
+            other => Method::Other(other.into()),
+        }
+    }
+}
+
+impl Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Method::Publish => write!(f, "PUBLISH"),
+            Method::Request => write!(f, "REQUEST"),
+            Method::Reply => write!(f, "REPLY"),
+            Method::Add => write!(f, "ADD"),
+            Method::Cancel => write!(f, "CANCEL"),
+            Method::Refresh => write!(f, "REFRESH"),
+            Method::Counter => write!(f, "COUNTER"),
+            Method::DeclineCounter => write!(f, "DECLINECOUNTER"),
+            Method::Other(m) => write!(f, "{}", m),
+        }
+    }
+}
+
+/// An iCalendar component type.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Component {
+    /// `VEVENT`
+    Event,
+    /// `VTODO`
+    Todo,
+    /// `VJOURNAL`
+    Journal,
+    /// `VFREEBUSY`
+    FreeBusy,
+    /// Any other component, stored in uppercase.
+    Other(String),
+}
+
+impl From<&str> for Component {
+    fn from(value: &str) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "VEVENT" => Component::Event,
+            "VTODO" => Component::Todo,
+            "VJOURNAL" => Component::Journal,
+            "VFREEBUSY" => Component::FreeBusy,
+            other => Component::Other(other.into()),
+        }
+    }
+}
+
+impl Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Component::Event => write!(f, "VEVENT"),
+            Component::Todo => write!(f, "VTODO"),
+            Component::Journal => write!(f, "VJOURNAL"),
+            Component::FreeBusy => write!(f, "VFREEBUSY"),
+            Component::Other(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+/// The typed parameters of a `"text/calendar"` MIME type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CalendarParams {
+    /// The `method` parameter, `None` if missing.
+    pub method: Option<Method>,
+    /// The `component` parameter, `None` if missing.
+    pub component: Option<Component>,
+}
+
+impl CalendarParams {
+    /// Extract the calendar parameters of `mime_type`.
+    ///
+    /// Returns `None` if the type is not `"text/calendar"`.
+    pub fn from_mime_type(mime_type: &MimeType) -> Option<Self> {
+        if mime_type.mime_type != "text/calendar" {
+            return None;
+        }
+
+        Some(CalendarParams {
+            method: mime_type.param("method").map(Method::from),
+            component: mime_type.param("component").map(Component::from),
+        })
+    }
+}
+
+/// Find the calendar part of a message.
+///
+/// The first `"text/calendar"` entity with a `method` parameter is
+/// preferred since it carries the scheduling message, otherwise the
+/// first `"text/calendar"` entity is returned.
+/// # Examples
+/// ```
+/// use rustyknife::calendar::{calendar_part, Component, Method};
+/// use rustyknife::mime::parse_message;
+///
+/// let input = b"Content-Type: multipart/alternative; boundary=a\r\n\r\n\
+///               --a\r\n\r\nYou are invited.\r\n\
+///               --a\r\nContent-Type: text/calendar; method=REQUEST; component=vevent\r\n\r\nBEGIN:VCALENDAR\r\n--a--\r\n";
+/// let message = parse_message(input);
+/// let (entity, params) = calendar_part(&message).unwrap();
+///
+/// assert_eq!(params.method, Some(Method::Request));
+/// assert_eq!(params.component, Some(Component::Event));
+/// assert!(entity.is("text/calendar"));
+/// ```
+pub fn calendar_part<'e, 'a>(message: &'e Entity<'a>) -> Option<(&'e Entity<'a>, CalendarParams)> {
+    let mut first = None;
+
+    for entity in message.walk() {
+        if let Some(params) = CalendarParams::from_mime_type(&entity.content_type) {
+            if params.method.is_some() {
+                return Some((entity, params));
+            }
+            first = first.or(Some((entity, params)));
+        }
+    }
+
+    first
+}
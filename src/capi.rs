@@ -0,0 +1,239 @@
+//! C API
+//!
+//! Push parser for the header section of a message. Input is fed in
+//! arbitrary chunks and a callback is invoked for every complete
+//! header. Only the incomplete trailing header is buffered, in a
+//! buffer whose size is fixed when the context is created.
+//!
+//! See `include/rustyknife.h` for the C declarations.
+
+use std::convert::TryFrom;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+use std::slice;
+
+use crate::headersection::{header, SizeLimits};
+
+/// Callback invoked for each header.
+///
+/// `name` is null for a line that is not a valid header, in which
+/// case `value` holds the whole line. A non-zero return value stops
+/// parsing.
+pub type HeaderCallback = unsafe extern "C" fn(
+    user: *mut c_void,
+    name: *const u8,
+    name_len: usize,
+    value: *const u8,
+    value_len: usize,
+) -> c_int;
+
+/// More input is needed.
+pub const RK_HEADER_MORE: c_int = 0;
+/// The end of the header section was reached.
+pub const RK_HEADER_DONE: c_int = 1;
+/// A single header exceeds the buffer size of the context.
+pub const RK_HEADER_TOO_LONG: c_int = -1;
+/// The callback returned a non-zero value.
+pub const RK_HEADER_ABORTED: c_int = -2;
+/// Invalid arguments or unparsable input.
+pub const RK_HEADER_ERROR: c_int = -3;
+/// The header section exceeds the limits set with
+/// [`rk_header_set_limits`].
+pub const RK_HEADER_TOO_LARGE: c_int = -4;
+
+/// Header push parser state.
+pub struct HeaderContext {
+    pending: Vec<u8>,
+    limit: usize,
+    consumed: u64,
+    lines: u64,
+    limits: SizeLimits,
+    state: c_int,
+    user: *mut c_void,
+}
+
+// Invoke the callback for each complete header at the start of
+// `input`. Returns the number of bytes consumed and the new state.
+fn emit(ctx: &mut HeaderContext, input: &[u8], callback: HeaderCallback) -> (usize, c_int) {
+    let mut rem = input;
+
+    loop {
+        match header(rem) {
+            Ok((next, Some(field))) => {
+                let raw = &rem[..rem.len() - next.len()];
+                let bytes = ctx.consumed + (input.len() - next.len()) as u64;
+                let lines = ctx.lines + raw.iter().filter(|c| **c == b'\n').count() as u64;
+                let checked = ctx.limits.check(
+                    usize::try_from(bytes).unwrap_or(usize::MAX),
+                    usize::try_from(lines).unwrap_or(usize::MAX),
+                );
+                if checked.is_err() {
+                    return (input.len() - rem.len(), RK_HEADER_TOO_LARGE);
+                }
+                ctx.lines = lines;
+
+                let (name, name_len, value) = match field {
+                    Ok((name, value)) => (name.as_ptr(), name.len(), value),
+                    Err(line) => (ptr::null(), 0, line),
+                };
+                rem = next;
+
+                if unsafe { callback(ctx.user, name, name_len, value.as_ptr(), value.len()) } != 0 {
+                    return (input.len() - rem.len(), RK_HEADER_ABORTED);
+                }
+            }
+            Ok((next, None)) => return (input.len() - next.len(), RK_HEADER_DONE),
+            Err(nom::Err::Incomplete(_)) => return (input.len() - rem.len(), RK_HEADER_MORE),
+            Err(_) => return (input.len() - rem.len(), RK_HEADER_ERROR),
+        }
+    }
+}
+
+impl HeaderContext {
+    fn feed(&mut self, mut input: &[u8], callback: HeaderCallback) -> c_int {
+        while self.state == RK_HEADER_MORE && !input.is_empty() {
+            if self.pending.is_empty() {
+                let (n, state) = emit(self, input, callback);
+                self.consumed += n as u64;
+                self.state = state;
+
+                let tail = &input[n..];
+                if state == RK_HEADER_MORE {
+                    if tail.len() > self.limit {
+                        self.state = RK_HEADER_TOO_LONG;
+                    } else {
+                        self.pending.extend_from_slice(tail);
+                    }
+                }
+                break;
+            }
+
+            let old = self.pending.len();
+            let take = (self.limit - old).min(input.len());
+            self.pending.extend_from_slice(&input[..take]);
+
+            let pending = std::mem::take(&mut self.pending);
+            let (n, state) = emit(self, &pending, callback);
+            self.pending = pending;
+            self.consumed += n as u64;
+            self.state = state;
+
+            if n >= old {
+                // The buffered bytes are consumed, continue on the input.
+                self.pending.clear();
+                input = &input[n - old..];
+            } else if n == 0 && self.pending.len() == self.limit {
+                self.state = RK_HEADER_TOO_LONG;
+            } else {
+                self.pending.drain(..n);
+                input = &input[take..];
+            }
+        }
+
+        if self.state != RK_HEADER_MORE {
+            self.pending = Vec::new();
+        }
+        self.state
+    }
+}
+
+/// Create a header push parser.
+///
+/// `max_header_len` is the size of the buffer holding a header split
+/// across calls to [`rk_header_feed`]. `user` is passed to the
+/// callback. Free the context with [`rk_header_free`].
+#[no_mangle]
+pub extern "C" fn rk_header_new(max_header_len: usize, user: *mut c_void) -> *mut HeaderContext {
+    Box::into_raw(Box::new(HeaderContext {
+        pending: Vec::with_capacity(max_header_len),
+        limit: max_header_len,
+        consumed: 0,
+        lines: 0,
+        limits: SizeLimits::default(),
+        state: RK_HEADER_MORE,
+        user,
+    }))
+}
+
+/// Limit the size of the header section.
+///
+/// Parsing stops with [`RK_HEADER_TOO_LARGE`] before the header that
+/// would make the header section exceed `max_bytes` bytes or
+/// `max_lines` lines. Zero means no limit.
+///
+/// # Safety
+/// `ctx` must come from [`rk_header_new`].
+#[no_mangle]
+pub unsafe extern "C" fn rk_header_set_limits(
+    ctx: *mut HeaderContext,
+    max_bytes: u64,
+    max_lines: u64,
+) -> c_int {
+    let limit = |max: u64| (max != 0).then(|| usize::try_from(max).unwrap_or(usize::MAX));
+
+    match ctx.as_mut() {
+        Some(ctx) => {
+            ctx.limits = SizeLimits {
+                max_bytes: limit(max_bytes),
+                max_lines: limit(max_lines),
+            };
+            RK_HEADER_MORE
+        }
+        None => RK_HEADER_ERROR,
+    }
+}
+
+/// Feed `len` bytes of input to the parser.
+///
+/// Returns [`RK_HEADER_MORE`] when more input is needed,
+/// [`RK_HEADER_DONE`] once the blank line ending the header section
+/// was seen or a negative error code. Once a value other than
+/// [`RK_HEADER_MORE`] was returned, it is returned by every later
+/// call.
+///
+/// # Safety
+/// `ctx` must come from [`rk_header_new`] and `buf` must point to `len`
+/// readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rk_header_feed(
+    ctx: *mut HeaderContext,
+    buf: *const u8,
+    len: usize,
+    callback: Option<HeaderCallback>,
+) -> c_int {
+    let (ctx, callback) = match (ctx.as_mut(), callback) {
+        (Some(ctx), Some(callback)) if !buf.is_null() || len == 0 => (ctx, callback),
+        _ => return RK_HEADER_ERROR,
+    };
+    let input = if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(buf, len)
+    };
+
+    ctx.feed(input, callback)
+}
+
+/// Return the number of input bytes consumed so far.
+///
+/// After [`RK_HEADER_DONE`] this is the offset of the message body in
+/// the input stream.
+///
+/// # Safety
+/// `ctx` must come from [`rk_header_new`].
+#[no_mangle]
+pub unsafe extern "C" fn rk_header_consumed(ctx: *const HeaderContext) -> u64 {
+    ctx.as_ref().map(|ctx| ctx.consumed).unwrap_or(0)
+}
+
+/// Free a header push parser.
+///
+/// # Safety
+/// `ctx` must come from [`rk_header_new`] and must not be used
+/// afterwards. Null is accepted.
+#[no_mangle]
+pub unsafe extern "C" fn rk_header_free(ctx: *mut HeaderContext) {
+    if !ctx.is_null() {
+        drop(Box::from_raw(ctx));
+    }
+}
@@ -0,0 +1,47 @@
+//! Charset label resolution
+//!
+//! Resolves the charset labels found in MIME parameters and
+//! [RFC 2047] encoded words to a decoder. The [WHATWG encoding labels]
+//! are used as a base and extended with aliases commonly produced by
+//! mail software.
+//!
+//! [RFC 2047]: https://tools.ietf.org/html/rfc2047
+//! [WHATWG encoding labels]: https://encoding.spec.whatwg.org/#names-and-labels
+
+use encoding::label::encoding_from_whatwg_label;
+use encoding::types::EncodingRef;
+
+// Labels seen in mail that are not part of the WHATWG list, mapped to
+// the WHATWG label of the closest supported encoding.
+fn mail_alias(label: &str) -> Option<&'static str> {
+    Some(match label {
+        "cp932" | "ms-932" | "windows-932" | "x-ms-cp932" | "cp943" | "cp943c" => "shift_jis",
+        "cp949" | "ms949" | "windows-949" | "x-windows-949" | "uhc" | "ks_c_5601"
+        | "ks_c_5601-1989" => "euc-kr",
+        "cp936" | "ms936" | "windows-936" => "gbk",
+        "cp950" | "ms950" | "windows-950" => "big5",
+        "cp65001" | "utf-8-bom" => "utf-8",
+        "latin-1" | "ansi" | "cp-1252" => "windows-1252",
+        "latin-2" | "cp-1250" => "windows-1250",
+        _ => return None,
+    })
+}
+
+/// Return the encoding for a charset label.
+///
+/// The label is compared case insensitively and surrounding white
+/// space is ignored. Returns `None` if the label is unknown.
+/// # Examples
+/// ```
+/// use rustyknife::charset::encoding_from_label;
+///
+/// assert_eq!(encoding_from_label("ks_c_5601-1987").unwrap().whatwg_name(), Some("euc-kr"));
+/// assert_eq!(encoding_from_label(" CP932 ").unwrap().whatwg_name(), Some("shift_jis"));
+/// assert!(encoding_from_label("x-unknown").is_none());
+/// ```
+pub fn encoding_from_label(label: &str) -> Option<EncodingRef> {
+    let label = label.trim().to_ascii_lowercase();
+
+    encoding_from_whatwg_label(&label)
+        .or_else(|| mail_alias(&label).and_then(encoding_from_whatwg_label))
+}
@@ -0,0 +1,206 @@
+//! [DKIM] body canonicalization
+//!
+//! Computes the canonical body that a DKIM signature hashes and maps
+//! the body length limit of the `l=` tag back to the raw body, so
+//! that content appended after signing can be located, for example
+//! against the MIME part offsets of [`crate::rfc2046`].
+//!
+//! [DKIM]: https://tools.ietf.org/html/rfc6376#section-3.4
+
+use std::borrow::Cow;
+
+/// A body canonicalization algorithm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BodyCanonicalization {
+    /// `simple`: trailing empty lines are removed.
+    Simple,
+    /// `relaxed`: white space is also reduced to single spaces and
+    /// removed at the end of lines.
+    Relaxed,
+}
+
+impl BodyCanonicalization {
+    /// Return the body algorithm of a `c=` tag value such as
+    /// `"relaxed/simple"`.
+    ///
+    /// The body algorithm defaults to `simple`. Returns `None` for an
+    /// unknown algorithm.
+    /// # Examples
+    /// ```
+    /// use rustyknife::dkim::BodyCanonicalization;
+    ///
+    /// assert_eq!(BodyCanonicalization::from_tag("simple/relaxed"), Some(BodyCanonicalization::Relaxed));
+    /// assert_eq!(BodyCanonicalization::from_tag("relaxed"), Some(BodyCanonicalization::Simple));
+    /// assert_eq!(BodyCanonicalization::from_tag("relaxed/nowsp"), None);
+    /// ```
+    pub fn from_tag(value: &str) -> Option<Self> {
+        match value.split('/').nth(1).map(str::trim) {
+            None => Some(BodyCanonicalization::Simple),
+            Some(body) if body.eq_ignore_ascii_case("simple") => Some(BodyCanonicalization::Simple),
+            Some(body) if body.eq_ignore_ascii_case("relaxed") => {
+                Some(BodyCanonicalization::Relaxed)
+            }
+            Some(_) => None,
+        }
+    }
+}
+
+// Relaxed canonical form of a line without its CRLF. Also returns,
+// for each canonical byte, the offset in `raw` just past the byte
+// that produced it.
+fn relaxed_line(raw: &[u8]) -> (Vec<u8>, Vec<usize>) {
+    let mut out = Vec::with_capacity(raw.len());
+    let mut ends = Vec::with_capacity(raw.len());
+    let mut space = None;
+
+    for (i, c) in raw.iter().enumerate() {
+        if matches!(c, b' ' | b'\t') {
+            space.get_or_insert(i + 1);
+        } else {
+            if let Some(end) = space.take() {
+                out.push(b' ');
+                ends.push(end);
+            }
+            out.push(*c);
+            ends.push(i + 1);
+        }
+    }
+
+    (out, ends)
+}
+
+struct Line<'a> {
+    text: Cow<'a, [u8]>,
+    start: usize,
+    content_end: usize,
+    end: usize,
+}
+
+// The canonical lines of `body` without their CRLF, trailing empty
+// lines removed.
+fn canonical_lines(body: &[u8], canon: BodyCanonicalization) -> Vec<Line> {
+    let mut out = Vec::new();
+    let mut start = 0;
+
+    while start < body.len() {
+        let (content_end, end) = match body[start..].windows(2).position(|w| w == b"\r\n") {
+            Some(pos) => (start + pos, start + pos + 2),
+            None => (body.len(), body.len()),
+        };
+        let raw = &body[start..content_end];
+        let text = match canon {
+            BodyCanonicalization::Simple => Cow::Borrowed(raw),
+            BodyCanonicalization::Relaxed => Cow::Owned(relaxed_line(raw).0),
+        };
+        out.push(Line {
+            text,
+            start,
+            content_end,
+            end,
+        });
+        start = end;
+    }
+    while matches!(out.last(), Some(line) if line.text.is_empty()) {
+        out.pop();
+    }
+
+    out
+}
+
+/// Canonicalize a message body.
+///
+/// Lines are delimited by CRLF only, see [`crate::newline`] to fix
+/// other line endings first. An empty body is a single CRLF with
+/// `simple` and empty with `relaxed`.
+/// # Examples
+/// ```
+/// use rustyknife::dkim::{canonicalize_body, BodyCanonicalization};
+///
+/// let body = b"Hi  \t there \r\n\r\n\r\n";
+/// assert_eq!(canonicalize_body(body, BodyCanonicalization::Simple), b"Hi  \t there \r\n");
+/// assert_eq!(canonicalize_body(body, BodyCanonicalization::Relaxed), b"Hi there\r\n");
+/// assert_eq!(canonicalize_body(b"", BodyCanonicalization::Simple), b"\r\n");
+/// ```
+pub fn canonicalize_body(body: &[u8], canon: BodyCanonicalization) -> Vec<u8> {
+    let lines = canonical_lines(body, canon);
+    if lines.is_empty() && canon == BodyCanonicalization::Simple {
+        return b"\r\n".to_vec();
+    }
+
+    let mut out = Vec::with_capacity(body.len());
+    for line in lines {
+        out.extend_from_slice(&line.text);
+        out.extend_from_slice(b"\r\n");
+    }
+    out
+}
+
+/// The length of the canonical body, the largest valid `l=` value.
+pub fn canonical_body_length(body: &[u8], canon: BodyCanonicalization) -> u64 {
+    let lines = canonical_lines(body, canon);
+    if lines.is_empty() && canon == BodyCanonicalization::Simple {
+        return 2;
+    }
+
+    lines.iter().map(|line| line.text.len() as u64 + 2).sum()
+}
+
+/// Locate the end of the portion of a raw body covered by an `l=`
+/// body length.
+///
+/// Returns the offset in `body` just past the byte that produced the
+/// last covered canonical byte. Content after this offset is not
+/// signed. Returns `None` if `length` exceeds the canonical body
+/// length, which makes the signature invalid. The CRLF added to an
+/// empty body or a last line without one maps to the end of the
+/// preceding content.
+/// # Examples
+/// ```
+/// use rustyknife::dkim::{body_length_offset, canonicalize_body, BodyCanonicalization};
+///
+/// let body = b"Signed  text\r\n\r\nAppended later\r\n";
+/// let canon = BodyCanonicalization::Relaxed;
+///
+/// // The signer covered the first line only.
+/// let l = canonicalize_body(b"Signed  text\r\n", canon).len() as u64;
+/// assert_eq!(l, 13);
+/// assert_eq!(body_length_offset(body, canon, l), Some(14));
+/// assert_eq!(&body[14..], b"\r\nAppended later\r\n");
+/// assert_eq!(body_length_offset(body, canon, 100), None);
+/// ```
+pub fn body_length_offset(body: &[u8], canon: BodyCanonicalization, length: u64) -> Option<usize> {
+    let lines = canonical_lines(body, canon);
+    let mut remaining = length;
+    let mut covered = 0;
+
+    for line in &lines {
+        if remaining == 0 {
+            return Some(covered);
+        }
+        let text_len = line.text.len() as u64;
+
+        if remaining <= text_len {
+            let n = remaining as usize;
+            return Some(match canon {
+                BodyCanonicalization::Simple => line.start + n,
+                BodyCanonicalization::Relaxed => {
+                    line.start + relaxed_line(&body[line.start..line.content_end]).1[n - 1]
+                }
+            });
+        }
+        if remaining <= text_len + 2 {
+            return Some(match remaining - text_len {
+                1 => (line.content_end + 1).min(line.end),
+                _ => line.end,
+            });
+        }
+        remaining -= text_len + 2;
+        covered = line.end;
+    }
+
+    let synthetic = match canon {
+        BodyCanonicalization::Simple if lines.is_empty() => 2,
+        _ => 0,
+    };
+    (remaining <= synthetic).then_some(covered)
+}
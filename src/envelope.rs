@@ -0,0 +1,207 @@
+//! Envelope and header consistency
+//!
+//! Compares the SMTP envelope of a message with its `"From:"`,
+//! `"To:"`, `"Cc:"` and `"Return-Path:"` headers. Mismatches are not
+//! errors, forwarding, mailing lists and bounces all produce some, but
+//! a header `"From:"` domain unrelated to the envelope sender is a
+//! common spoofing signal.
+
+use std::fmt::{self, Display};
+
+use crate::headersection::{HeaderField, KnownHeader};
+use crate::rfc5321::{ForwardPath, Path, ReversePath};
+use crate::rfc5322::{parse_header, ParsedHeader, UTF8Policy};
+use crate::types::{DomainPart, Mailbox};
+
+/// How domains are compared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Alignment {
+    /// The domains must be equal, ignoring case.
+    Strict,
+    /// One domain may be a subdomain of the other, as in
+    /// `"bounces.example.org"` and `"example.org"`.
+    Relaxed,
+}
+
+impl Alignment {
+    /// Check whether `a` and `b` are aligned.
+    ///
+    /// Address literals must always be equal.
+    pub fn aligned(self, a: &DomainPart, b: &DomainPart) -> bool {
+        match (a, b) {
+            (DomainPart::Domain(a), DomainPart::Domain(b)) => {
+                let (a, b) = (a.0.to_lowercase(), b.0.to_lowercase());
+                let subdomain = |long: &str, short: &str| matches!(long.strip_suffix(short), Some(rest) if rest.ends_with('.'));
+                a == b || (self == Alignment::Relaxed && (subdomain(&a, &b) || subdomain(&b, &a)))
+            }
+            (a, b) => a == b,
+        }
+    }
+}
+
+/// A difference between the envelope and the headers.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mismatch {
+    /// There is no valid `"From:"` header.
+    MissingFrom,
+    /// A `"From:"` mailbox domain is not aligned with the envelope
+    /// sender domain.
+    FromDomain {
+        /// The envelope sender.
+        envelope: Mailbox,
+        /// The `"From:"` mailbox.
+        header: Mailbox,
+    },
+    /// The `"Return-Path:"` differs from the envelope sender.
+    ReturnPath {
+        /// The envelope sender.
+        envelope: ReversePath,
+        /// The `"Return-Path:"`, `None` if invalid.
+        header: Option<ReversePath>,
+    },
+    /// An envelope recipient is not in `"To:"` or `"Cc:"`. This is
+    /// normal for Bcc recipients and mailing lists.
+    HiddenRecipient(Mailbox),
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mismatch::MissingFrom => write!(f, "no valid From header"),
+            Mismatch::FromDomain { envelope, header } => write!(
+                f,
+                "From domain {} differs from envelope sender domain {}",
+                header.domain_part(),
+                envelope.domain_part()
+            ),
+            Mismatch::ReturnPath {
+                envelope,
+                header: Some(header),
+            } => write!(
+                f,
+                "Return-Path {} differs from envelope sender {}",
+                header, envelope
+            ),
+            Mismatch::ReturnPath {
+                envelope,
+                header: None,
+            } => write!(f, "invalid Return-Path for envelope sender {}", envelope),
+            Mismatch::HiddenRecipient(rcpt) => {
+                write!(f, "recipient {} not in To or Cc", rcpt)
+            }
+        }
+    }
+}
+
+fn header_mailboxes<P: UTF8Policy>(headers: &[HeaderField], known: &[KnownHeader]) -> Vec<Mailbox> {
+    let mut out = Vec::new();
+
+    for (name, value) in headers.iter().flatten() {
+        if !matches!(KnownHeader::from_name(name), Some(k) if known.contains(&k)) {
+            continue;
+        }
+        if let ParsedHeader::AddressList(list) = parse_header::<P>(name, value) {
+            out.extend(
+                list.into_iter()
+                    .flat_map(|a| a.into_mailboxes())
+                    .map(|m| m.address),
+            );
+        }
+    }
+
+    out
+}
+
+fn return_path(value: &[u8]) -> Option<ReversePath> {
+    let value = String::from_utf8_lossy(value);
+    let value = value.trim();
+    let address = value.strip_prefix('<')?.strip_suffix('>')?.trim();
+
+    if address.is_empty() {
+        Some(ReversePath::Null)
+    } else {
+        let mailbox = Mailbox::from_imf(address.as_bytes()).ok()?;
+        Some(ReversePath::Path(Path(mailbox, Default::default())))
+    }
+}
+
+fn same_path(a: &ReversePath, b: &ReversePath) -> bool {
+    match (a, b) {
+        (ReversePath::Null, ReversePath::Null) => true,
+        (ReversePath::Path(Path(a, _)), ReversePath::Path(Path(b, _))) => a.semantic_eq(b),
+        _ => false,
+    }
+}
+
+/// Compare the envelope of a message with its headers.
+///
+/// Addresses are compared with [`Mailbox::semantic_eq`] and domains
+/// according to `alignment`. The `"From:"` domains are not checked
+/// for a null sender. The `"Return-Path:"` is only checked when
+/// present, it is added at final delivery. Postmaster recipients
+/// without a domain are skipped.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::envelope::{check_envelope, Alignment, Mismatch};
+/// use rustyknife::headersection::header_section;
+/// use rustyknife::rfc5321::{ForwardPath, ReversePath};
+///
+/// let sender: ReversePath = "<bounce@mail.example.net>".parse().unwrap();
+/// let rcpt: ForwardPath = "<bob@example.org>".parse().unwrap();
+/// let (_, headers) = header_section(b"From: Bank <support@bank.example>\r\nTo: bob@example.org\r\n\r\n").unwrap();
+///
+/// let found = check_envelope::<Intl>(&sender, &[rcpt], &headers, Alignment::Relaxed);
+/// assert!(matches!(&found[..], [Mismatch::FromDomain { .. }]));
+/// assert_eq!(found[0].to_string(), "From domain bank.example differs from envelope sender domain mail.example.net");
+/// ```
+pub fn check_envelope<P: UTF8Policy>(
+    sender: &ReversePath,
+    recipients: &[ForwardPath],
+    headers: &[HeaderField],
+    alignment: Alignment,
+) -> Vec<Mismatch> {
+    let mut out = Vec::new();
+
+    let from = header_mailboxes::<P>(headers, &[KnownHeader::From]);
+    if from.is_empty() {
+        out.push(Mismatch::MissingFrom);
+    }
+    if let ReversePath::Path(Path(envelope, _)) = sender {
+        for header in from {
+            if !alignment.aligned(envelope.domain_part(), header.domain_part()) {
+                out.push(Mismatch::FromDomain {
+                    envelope: envelope.clone(),
+                    header,
+                });
+            }
+        }
+    }
+
+    let return_paths = headers
+        .iter()
+        .flatten()
+        .filter(|(name, _)| KnownHeader::from_name(name) == Some(KnownHeader::ReturnPath));
+    for (_, value) in return_paths {
+        let header = return_path(value);
+        if !matches!(&header, Some(h) if same_path(sender, h)) {
+            out.push(Mismatch::ReturnPath {
+                envelope: sender.clone(),
+                header,
+            });
+        }
+    }
+
+    let visible = header_mailboxes::<P>(headers, &[KnownHeader::To, KnownHeader::Cc]);
+    for rcpt in recipients {
+        let rcpt = match rcpt {
+            ForwardPath::Path(Path(mailbox, _)) => mailbox,
+            ForwardPath::PostMaster(_) => continue,
+        };
+        if !visible.iter().any(|m| m.semantic_eq(rcpt)) {
+            out.push(Mismatch::HiddenRecipient(rcpt.clone()));
+        }
+    }
+
+    out
+}
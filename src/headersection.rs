@@ -7,6 +7,9 @@
 //! [RFC 5322]: https://tools.ietf.org/html/rfc5322
 
 use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Display};
+use std::ops::Range;
 use std::str;
 
 use nom::branch::alt;
@@ -62,13 +65,36 @@ fn crlf(input: &[u8]) -> NomResult<&[u8]> {
 /// Used to represent a split header.
 ///
 /// - The [`Ok`] variant is used when a valid header with a name was
-/// found. This variant contains a tuple with the header name and
-/// value.
+///   found. This variant contains a tuple with the header name and
+///   value.
 /// - The [`Err`] variant is returned when the the first line of a header
-/// does not contain a colon or contains 8bit bytes on the left hand
-/// side of the colon.
+///   does not contain a colon or contains 8bit bytes on the left hand
+///   side of the colon.
 pub type HeaderField<'a> = Result<(&'a [u8], &'a [u8]), &'a [u8]>;
 
+/// An owned [HeaderField] that can outlive the input.
+pub type OwnedHeaderField = Result<(Vec<u8>, Vec<u8>), Vec<u8>>;
+
+/// Copy a [HeaderField] out of the input.
+/// # Examples
+/// ```
+/// use rustyknife::headersection::{header_section, into_owned_field};
+///
+/// let owned = {
+///     let input = b"Subject: hi\r\n\r\n".to_vec();
+///     let (_, headers) = header_section(&input).unwrap();
+///     headers.into_iter().map(into_owned_field).collect::<Vec<_>>()
+/// };
+///
+/// assert_eq!(owned, [Ok((b"Subject".to_vec(), b" hi".to_vec()))]);
+/// ```
+pub fn into_owned_field(field: HeaderField) -> OwnedHeaderField {
+    match field {
+        Ok((name, value)) => Ok((name.to_vec(), value.to_vec())),
+        Err(line) => Err(line.to_vec()),
+    }
+}
+
 fn field_name(input: &[u8]) -> NomResult<&[u8]> {
     take_while1(|c| matches!(c, 33..=57 | 59..=126))(input)
 }
@@ -107,10 +133,1051 @@ fn invalid_field(input: &[u8]) -> NomResult<HeaderField> {
 /// Returns the remaining input (the message body) and a vector of
 /// [HeaderField] on success.
 pub fn header_section(input: &[u8]) -> NomResult<Vec<HeaderField>> {
-    terminated(many0(alt((field, invalid_field))), opt(crlf))(input)
+    traced(
+        "header_section",
+        terminated(many0(alt((field, invalid_field))), opt(crlf)),
+    )(input)
 }
 
 /// Parse a single header
 pub fn header(input: &[u8]) -> NomResult<Option<HeaderField>> {
     alt((map(alt((field, invalid_field)), Some), map(crlf, |_| None)))(input)
 }
+
+/// Lazy mail message header splitter
+///
+/// Returns an iterator yielding each [HeaderField] as it is scanned,
+/// allowing the caller to stop before the whole header section is
+/// parsed.
+/// # Examples
+/// ```
+/// use rustyknife::headersection::header_section_iter;
+///
+/// let input = b"Received: from a\r\nReceived: from b\r\nSubject: hi\r\n\r\nBody";
+/// let mut headers = header_section_iter(input);
+///
+/// let first = headers.find(|h| matches!(h, Ok((name, _)) if name.eq_ignore_ascii_case(b"received")));
+/// assert_eq!(first, Some(Ok((b"Received".as_ref(), b" from a".as_ref()))));
+///
+/// // The remaining headers can still be consumed later.
+/// assert_eq!(headers.count(), 2);
+/// ```
+pub fn header_section_iter(input: &[u8]) -> HeaderIter<'_> {
+    HeaderIter {
+        rem: input,
+        done: false,
+        incomplete: false,
+        limits: SizeLimits::default(),
+        bytes: 0,
+        lines: 0,
+        too_large: None,
+    }
+}
+
+/// Caps on the size of a header section.
+///
+/// Bytes and lines are counted over the header fields, folded
+/// continuation lines included.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SizeLimits {
+    /// Maximum number of bytes, `None` for no limit.
+    pub max_bytes: Option<usize>,
+    /// Maximum number of lines, `None` for no limit.
+    pub max_lines: Option<usize>,
+}
+
+impl SizeLimits {
+    /// Check `bytes` and `lines` against the limits.
+    pub fn check(&self, bytes: usize, lines: usize) -> Result<(), TooLarge> {
+        if matches!(self.max_bytes, Some(max) if bytes > max) {
+            Err(TooLarge::Bytes)
+        } else if matches!(self.max_lines, Some(max) if lines > max) {
+            Err(TooLarge::Lines)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The limit of [`SizeLimits`] that was exceeded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TooLarge {
+    /// Too many bytes.
+    Bytes,
+    /// Too many lines.
+    Lines,
+}
+
+impl Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TooLarge::Bytes => write!(f, "header section too large"),
+            TooLarge::Lines => write!(f, "too many header lines"),
+        }
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+/// Iterator over the headers of a message.
+///
+/// Created by [header_section_iter].
+#[derive(Clone, Debug)]
+pub struct HeaderIter<'a> {
+    rem: &'a [u8],
+    done: bool,
+    incomplete: bool,
+    limits: SizeLimits,
+    bytes: usize,
+    lines: usize,
+    too_large: Option<TooLarge>,
+}
+
+impl<'a> HeaderIter<'a> {
+    /// Return the input that was not consumed yet.
+    ///
+    /// Once the iterator is exhausted, this is the message body
+    /// following the blank line that terminates the header section.
+    pub fn remainder(&self) -> &'a [u8] {
+        self.rem
+    }
+
+    /// Returns `true` if iteration stopped because the input ended in
+    /// the middle of the header section.
+    pub fn is_incomplete(&self) -> bool {
+        self.incomplete
+    }
+
+    /// Stop iterating before the header that would exceed `limits`.
+    ///
+    /// # Examples
+    /// ```
+    /// use rustyknife::headersection::{header_section_iter, SizeLimits, TooLarge};
+    ///
+    /// let limits = SizeLimits { max_bytes: None, max_lines: Some(2) };
+    /// let mut headers = header_section_iter(b"A: 1\r\nB: 2\r\n 2\r\n\r\n").with_limits(limits);
+    ///
+    /// assert_eq!(headers.next(), Some(Ok((b"A".as_ref(), b" 1".as_ref()))));
+    /// assert_eq!(headers.next(), None);
+    /// assert_eq!(headers.too_large(), Some(TooLarge::Lines));
+    /// assert_eq!(headers.remainder(), b"B: 2\r\n 2\r\n\r\n");
+    /// ```
+    pub fn with_limits(mut self, limits: SizeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Number of bytes of the headers returned so far.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Number of lines of the headers returned so far.
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+
+    /// Return the limit that stopped iteration, if any.
+    pub fn too_large(&self) -> Option<TooLarge> {
+        self.too_large
+    }
+}
+
+impl<'a> Iterator for HeaderIter<'a> {
+    type Item = HeaderField<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match alt((field, invalid_field))(self.rem) {
+            Ok((rem, parsed)) => {
+                let consumed = &self.rem[..self.rem.len() - rem.len()];
+                let bytes = self.bytes + consumed.len();
+                let lines = self.lines + consumed.iter().filter(|c| **c == b'\n').count();
+
+                if let Err(err) = self.limits.check(bytes, lines) {
+                    self.done = true;
+                    self.too_large = Some(err);
+                    return None;
+                }
+                self.bytes = bytes;
+                self.lines = lines;
+                self.rem = rem;
+                Some(parsed)
+            }
+            Err(err) => {
+                self.done = true;
+                match opt(crlf)(self.rem) {
+                    Ok((rem, _)) if !err.is_incomplete() => self.rem = rem,
+                    _ => self.incomplete = true,
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Header splitter that also returns the position of each header.
+///
+/// Each [HeaderField] is paired with the byte range it occupies in
+/// `input`, including the name and the terminating CRLF. Parsing
+/// behaviour is otherwise identical to [header_section].
+pub fn header_section_spans(input: &[u8]) -> NomResult<Vec<(HeaderField, Range<usize>)>> {
+    let mut out = Vec::new();
+    let mut rem = input;
+
+    loop {
+        match alt((field, invalid_field))(rem) {
+            Ok((next, parsed)) => {
+                let start = input.len() - rem.len();
+                out.push((parsed, start..input.len() - next.len()));
+                rem = next;
+            }
+            Err(nom::Err::Error(_)) => break,
+            Err(err) => return Err(err),
+        }
+    }
+
+    let (rem, _) = opt(crlf)(rem)?;
+    Ok((rem, out))
+}
+
+/// Select the headers to hash for a [DKIM] signature.
+///
+/// `headers` is the output of [header_section_spans] and `signed` is
+/// the value of the `h=` tag, as returned by
+/// [`tag_list`](crate::tagvalue::tag_list) for the signature. For
+/// each name in `signed`, the last
+/// header with that name that was not selected yet is chosen, so a
+/// name listed twice selects the two bottom-most instances. Names
+/// without a matching header are skipped. Names are compared case
+/// insensitively.
+///
+/// Returns the byte ranges of the selected headers in hashing order.
+///
+/// [DKIM]: https://tools.ietf.org/html/rfc6376#section-5.4.2
+/// # Examples
+/// ```
+/// use rustyknife::headersection::{dkim_select_headers, header_section_spans};
+/// use rustyknife::tagvalue::{tag_list, TagListOptions};
+///
+/// let input = b"Received: a\r\nFrom: x\r\nReceived: b\r\nSubject: y\r\n\r\n";
+/// let (_, headers) = header_section_spans(input).unwrap();
+///
+/// let signature = b" v=1; d=example.org; h=received : from:\r\n received:received:to";
+/// let (_, tags) = tag_list(TagListOptions::default())(signature).unwrap();
+/// let (_, signed) = tags.into_iter().find(|(tag, _)| *tag == "h").unwrap();
+///
+/// let ranges = dkim_select_headers(&headers, std::str::from_utf8(signed).unwrap());
+/// let selected: Vec<_> = ranges.into_iter().map(|r| &input[r]).collect();
+///
+/// assert_eq!(selected, [&b"Received: b\r\n"[..], b"From: x\r\n", b"Received: a\r\n"]);
+/// ```
+pub fn dkim_select_headers(
+    headers: &[(HeaderField, Range<usize>)],
+    signed: &str,
+) -> Vec<Range<usize>> {
+    let mut used = vec![false; headers.len()];
+    let mut out = Vec::new();
+
+    for name in signed.split(':') {
+        let name = name.trim_matches(|c: char| c.is_ascii_whitespace());
+
+        let found = headers.iter().enumerate().rev().find(|(i, (field, _))| {
+            !used[*i] && matches!(field, Ok((n, _)) if n.eq_ignore_ascii_case(name.as_bytes()))
+        });
+        if let Some((i, (_, range))) = found {
+            used[i] = true;
+            out.push(range.clone());
+        }
+    }
+
+    out
+}
+
+macro_rules! known_headers {
+    ( $( $variant:ident => $name:literal / $lower:literal, )* ) => {
+        /// A well-known header name.
+        ///
+        /// Comparing known names is a single integer comparison.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[allow(missing_docs)]
+        pub enum KnownHeader {
+            $( $variant, )*
+        }
+
+        impl KnownHeader {
+            #[cfg(test)]
+            pub(crate) const ALL: &'static [KnownHeader] = &[ $( KnownHeader::$variant, )* ];
+
+            /// Return the name with its usual capitalization.
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $( KnownHeader::$variant => $name, )*
+                }
+            }
+
+            /// Look up a header name case insensitively.
+            pub fn from_name(name: &[u8]) -> Option<Self> {
+                // Longer than any known name.
+                let mut buf = [0; 32];
+                let lower = buf.get_mut(..name.len())?;
+                lower.copy_from_slice(name);
+                lower.make_ascii_lowercase();
+
+                match &*lower {
+                    $( $lower => Some(KnownHeader::$variant), )*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+known_headers! {
+    Bcc => "Bcc" / b"bcc",
+    Cc => "Cc" / b"cc",
+    Comments => "Comments" / b"comments",
+    ContentDescription => "Content-Description" / b"content-description",
+    ContentDisposition => "Content-Disposition" / b"content-disposition",
+    ContentId => "Content-ID" / b"content-id",
+    ContentTransferEncoding => "Content-Transfer-Encoding" / b"content-transfer-encoding",
+    ContentType => "Content-Type" / b"content-type",
+    Date => "Date" / b"date",
+    DeliveredTo => "Delivered-To" / b"delivered-to",
+    DkimSignature => "DKIM-Signature" / b"dkim-signature",
+    From => "From" / b"from",
+    InReplyTo => "In-Reply-To" / b"in-reply-to",
+    Keywords => "Keywords" / b"keywords",
+    MessageId => "Message-ID" / b"message-id",
+    MimeVersion => "MIME-Version" / b"mime-version",
+    Received => "Received" / b"received",
+    References => "References" / b"references",
+    ReplyTo => "Reply-To" / b"reply-to",
+    ResentBcc => "Resent-Bcc" / b"resent-bcc",
+    ResentCc => "Resent-Cc" / b"resent-cc",
+    ResentDate => "Resent-Date" / b"resent-date",
+    ResentFrom => "Resent-From" / b"resent-from",
+    ResentMessageId => "Resent-Message-ID" / b"resent-message-id",
+    ResentSender => "Resent-Sender" / b"resent-sender",
+    ResentTo => "Resent-To" / b"resent-to",
+    ReturnPath => "Return-Path" / b"return-path",
+    Sender => "Sender" / b"sender",
+    Subject => "Subject" / b"subject",
+    To => "To" / b"to",
+}
+
+#[derive(Clone, Debug)]
+enum Name {
+    Known(KnownHeader),
+    Other(String),
+}
+
+/// A header field name validated against the RFC 5322 `ftext` rule.
+///
+/// Well-known names are interned as a [`KnownHeader`] and written
+/// with their usual capitalization. Comparison and hashing are case
+/// insensitive.
+/// # Examples
+/// ```
+/// use rustyknife::headersection::{HeaderName, KnownHeader};
+///
+/// let name = HeaderName::new(b"message-id").unwrap();
+/// assert_eq!(name.known(), Some(KnownHeader::MessageId));
+/// assert_eq!(name.as_str(), "Message-ID");
+/// assert_eq!(name, HeaderName::from(KnownHeader::MessageId));
+///
+/// assert_eq!(HeaderName::new(b"X-Spam-Flag").unwrap(), HeaderName::new(b"x-spam-flag").unwrap());
+/// assert_eq!(HeaderName::new(b"Bad Name").unwrap_err(), 3);
+/// ```
+#[derive(Clone, Debug)]
+pub struct HeaderName(Name);
+
+impl HeaderName {
+    /// Validate `name`.
+    ///
+    /// Returns the offset of the first invalid byte on error, 0 if the
+    /// name is empty.
+    pub fn new(name: &[u8]) -> Result<Self, usize> {
+        match name.iter().position(|c| !matches!(c, 33..=57 | 59..=126)) {
+            Some(offset) => return Err(offset),
+            None if name.is_empty() => return Err(0),
+            None => (),
+        }
+
+        Ok(HeaderName(match KnownHeader::from_name(name) {
+            Some(known) => Name::Known(known),
+            // Validated as ASCII above.
+            None => Name::Other(str::from_utf8(name).unwrap().into()),
+        }))
+    }
+
+    /// Return the well-known name, if any.
+    pub fn known(&self) -> Option<KnownHeader> {
+        match self.0 {
+            Name::Known(known) => Some(known),
+            Name::Other(_) => None,
+        }
+    }
+
+    /// Return the name as a string.
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            Name::Known(known) => known.as_str(),
+            Name::Other(name) => name,
+        }
+    }
+}
+
+impl From<KnownHeader> for HeaderName {
+    fn from(known: KnownHeader) -> Self {
+        HeaderName(Name::Known(known))
+    }
+}
+
+impl PartialEq for HeaderName {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Name::Known(a), Name::Known(b)) => a == b,
+            (Name::Other(a), Name::Other(b)) => a.eq_ignore_ascii_case(b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for HeaderName {}
+
+impl std::hash::Hash for HeaderName {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        match &self.0 {
+            Name::Known(known) => known.hash(state),
+            Name::Other(name) => {
+                for c in name.bytes() {
+                    c.to_ascii_lowercase().hash(state);
+                }
+            }
+        }
+    }
+}
+
+impl PartialEq<[u8]> for HeaderName {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.as_str().as_bytes().eq_ignore_ascii_case(other)
+    }
+}
+
+impl AsRef<[u8]> for HeaderName {
+    fn as_ref(&self) -> &[u8] {
+        self.as_str().as_bytes()
+    }
+}
+
+impl Display for HeaderName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Check that `value` cannot end a line when interpolated.
+///
+/// Returns the offset of the first CR, LF or NUL byte.
+/// # Examples
+/// ```
+/// use rustyknife::headersection::check_no_crlf;
+///
+/// assert_eq!(check_no_crlf(b"user@example.org"), Ok(()));
+/// assert_eq!(check_no_crlf(b"user@example.org\r\nRCPT TO:<x@example.org>"), Err(16));
+/// ```
+pub fn check_no_crlf(value: &[u8]) -> Result<(), usize> {
+    match value
+        .iter()
+        .position(|c| matches!(c, b'\r' | b'\n' | b'\0'))
+    {
+        Some(offset) => Err(offset),
+        None => Ok(()),
+    }
+}
+
+/// A header value that is safe to write after a header name.
+///
+/// Line breaks are only accepted as folding: every CRLF must be
+/// followed by a space or tab, so the value cannot end the header or
+/// the header section early. A continuation line made only of spaces
+/// and tabs is also rejected as RFC 5322 forbids it. Bare CR, LF and
+/// NUL bytes are rejected.
+/// # Examples
+/// ```
+/// use rustyknife::headersection::HeaderValue;
+///
+/// assert!(HeaderValue::new(b" hello\r\n world").is_ok());
+/// assert_eq!(HeaderValue::new(b" hello\r\nBcc: x@example.org").unwrap_err(), 6);
+/// assert_eq!(HeaderValue::new(b" hello\r\n").unwrap_err(), 6);
+/// assert_eq!(HeaderValue::new(b" hello\r\n \t\r\n world").unwrap_err(), 6);
+/// assert_eq!(HeaderValue::new(b" hello\r\n ").unwrap_err(), 6);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeaderValue<'a>(&'a [u8]);
+
+impl<'a> HeaderValue<'a> {
+    /// Validate `value`.
+    ///
+    /// Returns the offset of the first offending byte on error.
+    pub fn new(value: &'a [u8]) -> Result<Self, usize> {
+        let mut rem = value;
+
+        loop {
+            let offset = value.len() - rem.len();
+            match check_no_crlf(rem) {
+                Ok(()) => return Ok(HeaderValue(value)),
+                Err(pos) => match &rem[pos..] {
+                    [b'\r', b'\n', b' ' | b'\t', line @ ..]
+                        if line
+                            .iter()
+                            .take_while(|c| !matches!(c, b'\r' | b'\n' | b'\0'))
+                            .any(|c| !matches!(c, b' ' | b'\t')) =>
+                    {
+                        rem = &rem[pos + 2..]
+                    }
+                    _ => return Err(offset + pos),
+                },
+            }
+        }
+    }
+
+    /// Return the value as bytes.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+}
+
+impl AsRef<[u8]> for HeaderValue<'_> {
+    fn as_ref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+/// Error returned when writing a header that would inject content.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InjectionError {
+    /// The header name is empty or the byte at this offset is not
+    /// allowed in a header name.
+    Name(usize),
+    /// The byte at this offset of the value is a CR, LF or NUL that
+    /// is not part of folding.
+    Value(usize),
+}
+
+impl Display for InjectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InjectionError::Name(offset) => write!(f, "invalid header name at offset {}", offset),
+            InjectionError::Value(offset) => {
+                write!(f, "unsafe line break in header value at offset {}", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InjectionError {}
+
+#[derive(Clone, Debug)]
+enum Entry<'a> {
+    Original(HeaderField<'a>, Range<usize>),
+    New(Vec<u8>, Vec<u8>),
+}
+
+impl<'a> Entry<'a> {
+    fn name(&self) -> Option<&[u8]> {
+        match self {
+            Entry::Original(Ok((name, _)), _) => Some(name),
+            Entry::Original(Err(_), _) => None,
+            Entry::New(name, _) => Some(name),
+        }
+    }
+
+    fn is_named(&self, name: &[u8]) -> bool {
+        matches!(self.name(), Some(n) if n.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Edit the headers of a message while preserving untouched bytes.
+///
+/// Headers that are not removed or replaced are copied verbatim from
+/// the original buffer, including their folding and invalid
+/// headers. Everything following the header section is also copied
+/// unmodified.
+///
+/// New header values are written as-is after the colon and should
+/// start with a space. Names and values that would end the header
+/// early are rejected with an [`InjectionError`], see [`HeaderValue`].
+/// # Examples
+/// ```
+/// use rustyknife::headersection::HeaderEditor;
+///
+/// let input = b"Subject: hello\r\nBcc: secret@example.org\r\nTo: bob@example.org\r\n\r\nBody";
+/// let mut editor = HeaderEditor::new(input).unwrap();
+///
+/// editor.remove_all(b"bcc");
+/// editor.insert(0, b"Received", b" from localhost").unwrap();
+/// let subject = editor.position(b"subject").unwrap();
+/// editor.replace(subject, b" [SPAM] hello").unwrap();
+/// assert!(editor.push(b"X-Note", b" hi\r\nBcc: x@example.org").is_err());
+///
+/// assert_eq!(editor.to_bytes(),
+///            b"Received: from localhost\r\nSubject: [SPAM] hello\r\nTo: bob@example.org\r\n\r\nBody".as_ref());
+/// ```
+#[derive(Clone, Debug)]
+pub struct HeaderEditor<'a> {
+    input: &'a [u8],
+    headers: Vec<Entry<'a>>,
+    end: usize,
+}
+
+impl<'a> HeaderEditor<'a> {
+    /// Split the header section of `input` for editing.
+    pub fn new(input: &'a [u8]) -> Result<Self, nom::Err<NomError<'a>>> {
+        let (_, headers) = header_section_spans(input)?;
+        let end = headers.last().map(|(_, span)| span.end).unwrap_or(0);
+
+        Ok(HeaderEditor {
+            input,
+            headers: headers
+                .into_iter()
+                .map(|(field, span)| Entry::Original(field, span))
+                .collect(),
+            end,
+        })
+    }
+
+    /// Number of headers, including invalid ones.
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Returns `true` if there are no headers.
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    /// Return the name of the header at `index`.
+    ///
+    /// Returns `None` if the index is out of range or the header is
+    /// invalid.
+    pub fn name(&self, index: usize) -> Option<&[u8]> {
+        self.headers.get(index).and_then(Entry::name)
+    }
+
+    /// Return the index of the first header named `name`.
+    ///
+    /// The name is compared case insensitively.
+    pub fn position(&self, name: &[u8]) -> Option<usize> {
+        self.headers.iter().position(|h| h.is_named(name))
+    }
+
+    /// Remove the header at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) {
+        self.headers.remove(index);
+    }
+
+    /// Remove all headers named `name`.
+    ///
+    /// Returns the number of headers removed.
+    pub fn remove_all(&mut self, name: &[u8]) -> usize {
+        let before = self.headers.len();
+        self.headers.retain(|h| !h.is_named(name));
+        before - self.headers.len()
+    }
+
+    /// Insert a new header at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index > len`.
+    pub fn insert(
+        &mut self,
+        index: usize,
+        name: &[u8],
+        value: &[u8],
+    ) -> Result<(), InjectionError> {
+        assert!(index <= self.headers.len(), "insertion index out of bounds");
+        HeaderName::new(name).map_err(InjectionError::Name)?;
+        let value = HeaderValue::new(value).map_err(InjectionError::Value)?;
+
+        self.headers
+            .insert(index, Entry::New(name.to_vec(), Self::render(name, value)));
+        Ok(())
+    }
+
+    /// Append a new header after all the existing ones.
+    pub fn push(&mut self, name: &[u8], value: &[u8]) -> Result<(), InjectionError> {
+        self.insert(self.headers.len(), name, value)
+    }
+
+    /// Replace the value of the header at `index`.
+    ///
+    /// The name is kept unless the header was invalid, in which case
+    /// the whole line is replaced by `value`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn replace(&mut self, index: usize, value: &[u8]) -> Result<(), InjectionError> {
+        let entry = &mut self.headers[index];
+        let value = HeaderValue::new(value).map_err(InjectionError::Value)?;
+
+        *entry = match entry.name() {
+            Some(name) => Entry::New(name.to_vec(), Self::render(name, value)),
+            None => {
+                let mut line = value.as_bytes().to_vec();
+                line.extend_from_slice(b"\r\n");
+                Entry::New(Vec::new(), line)
+            }
+        };
+        Ok(())
+    }
+
+    fn render(name: &[u8], value: HeaderValue) -> Vec<u8> {
+        let value = value.as_bytes();
+        let mut out = Vec::with_capacity(name.len() + value.len() + 3);
+        out.extend_from_slice(name);
+        out.push(b':');
+        out.extend_from_slice(value);
+        out.extend_from_slice(b"\r\n");
+        out
+    }
+
+    /// Rebuild the message with all edits applied.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.input.len());
+
+        for header in &self.headers {
+            match header {
+                Entry::Original(_, span) => out.extend_from_slice(&self.input[span.clone()]),
+                Entry::New(_, bytes) => out.extend_from_slice(bytes),
+            }
+        }
+        out.extend_from_slice(&self.input[self.end..]);
+
+        out
+    }
+}
+
+/// A header section indexed by header name.
+///
+/// Invalid header lines are skipped. Lookups compare names case
+/// insensitively and values are borrowed from the input.
+/// # Examples
+/// ```
+/// use rustyknife::headersection::HeaderMap;
+///
+/// let map = HeaderMap::new(b"Received: a\r\nsubject: hi\r\nReceived: b\r\n\r\nBody").unwrap();
+///
+/// assert_eq!(map.len(), 3);
+/// assert_eq!(map.get(b"Subject"), Some(b" hi".as_ref()));
+/// assert_eq!(map.get_all(b"received").collect::<Vec<_>>(), [b" a".as_ref(), b" b"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct HeaderMap<'a> {
+    headers: Vec<(HeaderName, &'a [u8], Range<usize>)>,
+}
+
+impl<'a> HeaderMap<'a> {
+    /// Parse the header section of `input`.
+    pub fn new(input: &'a [u8]) -> Result<Self, nom::Err<NomError<'a>>> {
+        let (_, headers) = header_section_spans(input)?;
+
+        Ok(HeaderMap {
+            headers: headers
+                .into_iter()
+                .filter_map(|(field, span)| {
+                    let (name, value) = field.ok()?;
+                    Some((HeaderName::new(name).ok()?, value, span))
+                })
+                .collect(),
+        })
+    }
+
+    /// Number of valid headers.
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Returns `true` if there are no valid headers.
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    /// Return the value of the first header named `name`.
+    pub fn get(&self, name: &[u8]) -> Option<&'a [u8]> {
+        self.get_all(name).next()
+    }
+
+    /// Iterate over the values of all headers named `name` from top
+    /// to bottom.
+    pub fn get_all<'b>(&'b self, name: &'b [u8]) -> impl Iterator<Item = &'a [u8]> + 'b {
+        self.headers
+            .iter()
+            .filter(move |(n, _, _)| n == name)
+            .map(|(_, value, _)| *value)
+    }
+
+    /// Iterate over the headers with their byte range in the input.
+    pub fn iter(&self) -> impl Iterator<Item = (&HeaderName, &'a [u8], Range<usize>)> {
+        self.headers
+            .iter()
+            .map(|(name, value, span)| (name, *value, span.clone()))
+    }
+
+    /// Compute occurrence and size statistics of the headers.
+    /// # Examples
+    /// ```
+    /// use rustyknife::headersection::{HeaderMap, KnownHeader};
+    ///
+    /// let input = b"Received: a\r\nTo: x@example.org,\r\n y@example.org,\r\n z@example.org\r\nReceived: b\r\n\r\n";
+    /// let stats = HeaderMap::new(input).unwrap().stats();
+    ///
+    /// assert_eq!(stats.counts[&KnownHeader::Received.into()], 2);
+    /// assert_eq!(stats.total_bytes, input.len() - 2);
+    /// assert_eq!(stats.max_line_length, 18);
+    /// assert_eq!(stats.folding_depth, 2);
+    /// ```
+    pub fn stats(&self) -> HeaderStats {
+        let mut stats = HeaderStats::default();
+
+        for (name, value, span) in &self.headers {
+            *stats.counts.entry(name.clone()).or_insert(0) += 1;
+            stats.total_bytes += span.len();
+
+            let mut lines = value.split(|&c| c == b'\n');
+            let first = lines.next().unwrap_or_default();
+            let mut longest = name.as_str().len() + 1 + line_length(first);
+            let mut folds = 0;
+            for line in lines.filter(|l| !l.is_empty()) {
+                longest = longest.max(line_length(line));
+                folds += 1;
+            }
+            stats.max_line_length = stats.max_line_length.max(longest);
+            stats.folding_depth = stats.folding_depth.max(folds);
+        }
+
+        stats
+    }
+}
+
+fn line_length(line: &[u8]) -> usize {
+    line.strip_suffix(b"\r").unwrap_or(line).len()
+}
+
+/// Statistics of a header section returned by [`HeaderMap::stats`].
+///
+/// Only valid headers are accounted for.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HeaderStats {
+    /// Number of headers by name.
+    pub counts: HashMap<HeaderName, usize>,
+    /// Size of the headers including their line endings.
+    pub total_bytes: usize,
+    /// Length of the longest physical line, excluding the line ending.
+    pub max_line_length: usize,
+    /// Largest number of continuation lines in a single header.
+    pub folding_depth: usize,
+}
+
+/// A difference between two header sections found by [`header_diff`].
+///
+/// Ranges are byte ranges in the input of the corresponding
+/// [`HeaderMap`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum HeaderChange {
+    /// A header only present after.
+    Added {
+        /// The header name.
+        name: HeaderName,
+        /// The header position after.
+        after: Range<usize>,
+    },
+    /// A header only present before.
+    Removed {
+        /// The header name.
+        name: HeaderName,
+        /// The header position before.
+        before: Range<usize>,
+    },
+    /// A header whose value changed.
+    Modified {
+        /// The header name.
+        name: HeaderName,
+        /// The header position before.
+        before: Range<usize>,
+        /// The header position after.
+        after: Range<usize>,
+    },
+}
+
+// Lengths of the longest common subsequences of `a` and each prefix
+// of `b`, or of each suffix of `b` when `rev` is set, indexed by the
+// length of the prefix or suffix.
+fn lcs_lengths<F>(same: &F, a: Range<usize>, b: Range<usize>, rev: bool) -> Vec<usize>
+where
+    F: Fn(usize, usize) -> bool,
+{
+    let mut prev = vec![0; b.len() + 1];
+    let mut cur = vec![0; b.len() + 1];
+
+    for n in 0..a.len() {
+        let i = if rev { a.end - 1 - n } else { a.start + n };
+        for m in 0..b.len() {
+            let j = if rev { b.end - 1 - m } else { b.start + m };
+            cur[m + 1] = if same(i, j) {
+                prev[m] + 1
+            } else {
+                prev[m + 1].max(cur[m])
+            };
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    prev
+}
+
+// Mark the items of a longest common subsequence of `a` and `b` with
+// Hirschberg's algorithm, in linear space.
+fn lcs_match<F>(
+    same: &F,
+    a: Range<usize>,
+    b: Range<usize>,
+    a_matched: &mut [bool],
+    b_matched: &mut [bool],
+) where
+    F: Fn(usize, usize) -> bool,
+{
+    if a.is_empty() || b.is_empty() {
+        return;
+    }
+    if a.len() == 1 {
+        if let Some(j) = b.clone().find(|j| same(a.start, *j)) {
+            a_matched[a.start] = true;
+            b_matched[j] = true;
+        }
+        return;
+    }
+
+    let mid = a.start + a.len() / 2;
+    let head = lcs_lengths(same, a.start..mid, b.clone(), false);
+    let tail = lcs_lengths(same, mid..a.end, b.clone(), true);
+    let split = (0..=b.len())
+        .max_by_key(|k| (head[*k] + tail[b.len() - k], std::cmp::Reverse(*k)))
+        .unwrap();
+
+    lcs_match(
+        same,
+        a.start..mid,
+        b.start..b.start + split,
+        a_matched,
+        b_matched,
+    );
+    lcs_match(
+        same,
+        mid..a.end,
+        b.start + split..b.end,
+        a_matched,
+        b_matched,
+    );
+}
+
+/// Compare two header sections.
+///
+/// Headers with identical names and values are matched preserving
+/// their order, so headers added above or below the original ones,
+/// such as new `"Received:"` headers, are reported as added. Any
+/// remaining header is paired with the next unmatched header of the
+/// same name to report a modification. Values are compared byte for
+/// byte, so refolding a header modifies it.
+///
+/// Removals and modifications are returned first in their order
+/// before, followed by the additions in their order after.
+/// # Examples
+/// ```
+/// use rustyknife::headersection::{header_diff, HeaderChange, HeaderMap};
+///
+/// let before = HeaderMap::new(b"Subject: hi\r\nBcc: x@example.org\r\n\r\n").unwrap();
+/// let after = HeaderMap::new(b"Received: by mx\r\nSubject: [SPAM] hi\r\n\r\n").unwrap();
+///
+/// let changes: Vec<_> = header_diff(&before, &after)
+///     .into_iter()
+///     .map(|change| match change {
+///         HeaderChange::Added { name, .. } => format!("+{}", name),
+///         HeaderChange::Removed { name, .. } => format!("-{}", name),
+///         HeaderChange::Modified { name, .. } => format!("~{}", name),
+///     })
+///     .collect();
+///
+/// assert_eq!(changes, ["~Subject", "-Bcc", "+Received"]);
+/// ```
+pub fn header_diff(before: &HeaderMap, after: &HeaderMap) -> Vec<HeaderChange> {
+    let (a, b) = (&before.headers, &after.headers);
+    let same = |i: usize, j: usize| a[i].0 == b[j].0 && a[i].1 == b[j].1;
+
+    // Longest common subsequence of identical headers, computed in
+    // linear space since the header counts are not trusted. The common
+    // prefix and suffix are matched first, which covers the usual case
+    // of a few headers added on top.
+    let mut a_matched = vec![false; a.len()];
+    let mut b_matched = vec![false; b.len()];
+    let prefix = (0..a.len().min(b.len()))
+        .take_while(|i| same(*i, *i))
+        .count();
+    let suffix = (1..=a.len().min(b.len()) - prefix)
+        .take_while(|n| same(a.len() - n, b.len() - n))
+        .count();
+    for i in (0..prefix).chain(a.len() - suffix..a.len()) {
+        a_matched[i] = true;
+    }
+    for j in (0..prefix).chain(b.len() - suffix..b.len()) {
+        b_matched[j] = true;
+    }
+    lcs_match(
+        &same,
+        prefix..a.len() - suffix,
+        prefix..b.len() - suffix,
+        &mut a_matched,
+        &mut b_matched,
+    );
+
+    // The unmatched headers after, by name in order.
+    let mut unmatched: HashMap<&HeaderName, VecDeque<usize>> = HashMap::new();
+    for j in (0..b.len()).filter(|j| !b_matched[*j]) {
+        unmatched.entry(&b[j].0).or_default().push_back(j);
+    }
+
+    let mut out = Vec::new();
+    for (_, (name, _, span)) in a.iter().enumerate().filter(|(i, _)| !a_matched[*i]) {
+        let pair = unmatched.get_mut(name).and_then(VecDeque::pop_front);
+
+        out.push(match pair {
+            Some(j) => {
+                b_matched[j] = true;
+                HeaderChange::Modified {
+                    name: name.clone(),
+                    before: span.clone(),
+                    after: b[j].2.clone(),
+                }
+            }
+            None => HeaderChange::Removed {
+                name: name.clone(),
+                before: span.clone(),
+            },
+        });
+    }
+    out.extend(b.iter().zip(b_matched).filter(|(_, matched)| !matched).map(
+        |((name, _, span), _)| HeaderChange::Added {
+            name: name.clone(),
+            after: span.clone(),
+        },
+    ));
+
+    out
+}
@@ -0,0 +1,167 @@
+//! JSON output
+//!
+//! Converts parse results to JSON with a stable schema for logging
+//! and bindings. Bytes that are not valid UTF-8 are replaced with
+//! U+FFFD.
+//!
+//! | Type | Schema |
+//! |------|--------|
+//! | [`HeaderField`] | `{"name": string or null, "value": string}`, the name is null for an invalid line |
+//! | [`Mailbox`] | `{"type": "mailbox", "display_name": string or null, "address": string}` |
+//! | [`Group`] | `{"type": "group", "display_name": string, "members": [mailbox]}` |
+//! | [`Entity`] | `{"content_type": string, "params": [[name, value]], "headers": [header], "body": body}` |
+//! | [`DeliveryStatus`] | `{"message": [header], "recipients": [[header]]}` |
+//!
+//! The body of an [`Entity`] is one of `{"size": number}` for a leaf
+//! body, `{"parts": [entity]}` for a multipart or `{"message": entity}`
+//! for an encapsulated message.
+
+use serde_json::{json, Value};
+
+use crate::headersection::HeaderField;
+use crate::mime::{Body, Entity};
+use crate::rfc3464::{DeliveryStatus, Fields, PerMessage, PerRecipient};
+use crate::rfc5322::{Address, Group, Mailbox};
+
+/// Conversion to JSON.
+///
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::json::ToJson;
+/// use rustyknife::rfc5322::from;
+///
+/// let (_, addresses) = from::<Intl>(b" John <john@example.org>").unwrap();
+///
+/// assert_eq!(
+///     addresses.to_json(),
+///     r#"[{"address":"john@example.org","display_name":"John","type":"mailbox"}]"#
+/// );
+/// ```
+pub trait ToJson {
+    /// Convert to a JSON value.
+    fn to_json_value(&self) -> Value;
+
+    /// Convert to a compact JSON string.
+    fn to_json(&self) -> String {
+        self.to_json_value().to_string()
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json_value(&self) -> Value {
+        Value::Array(self.iter().map(ToJson::to_json_value).collect())
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json_value(&self) -> Value {
+        self.as_slice().to_json_value()
+    }
+}
+
+impl<'a> ToJson for HeaderField<'a> {
+    fn to_json_value(&self) -> Value {
+        match self {
+            Ok((name, value)) => json!({
+                "name": String::from_utf8_lossy(name),
+                "value": String::from_utf8_lossy(value),
+            }),
+            Err(line) => json!({
+                "name": null,
+                "value": String::from_utf8_lossy(line),
+            }),
+        }
+    }
+}
+
+impl ToJson for Mailbox {
+    fn to_json_value(&self) -> Value {
+        json!({
+            "type": "mailbox",
+            "display_name": self.dname,
+            "address": self.address.to_string(),
+        })
+    }
+}
+
+impl ToJson for Group {
+    fn to_json_value(&self) -> Value {
+        json!({
+            "type": "group",
+            "display_name": self.dname,
+            "members": self.members.to_json_value(),
+        })
+    }
+}
+
+impl ToJson for Address {
+    fn to_json_value(&self) -> Value {
+        match self {
+            Address::Mailbox(m) => m.to_json_value(),
+            Address::Group(g) => g.to_json_value(),
+        }
+    }
+}
+
+impl<'a> ToJson for Entity<'a> {
+    fn to_json_value(&self) -> Value {
+        let body = match &self.body {
+            Body::Single(raw) => json!({ "size": raw.len() }),
+            Body::Multipart(parts) => json!({ "parts": parts.to_json_value() }),
+            Body::Message(message) => json!({ "message": message.to_json_value() }),
+        };
+
+        json!({
+            "content_type": self.content_type.mime_type,
+            "params": self.content_type.params,
+            "headers": self.headers.to_json_value(),
+            "body": body,
+        })
+    }
+}
+
+impl<'a> ToJson for Fields<'a> {
+    fn to_json_value(&self) -> Value {
+        self.0.to_json_value()
+    }
+}
+
+impl<'a> ToJson for PerMessage<'a> {
+    fn to_json_value(&self) -> Value {
+        self.fields.to_json_value()
+    }
+}
+
+impl<'a> ToJson for PerRecipient<'a> {
+    fn to_json_value(&self) -> Value {
+        self.fields.to_json_value()
+    }
+}
+
+/// # Examples
+/// ```
+/// use rustyknife::json::ToJson;
+/// use rustyknife::rfc3464::delivery_status;
+///
+/// let status = delivery_status(b"Reporting-MTA: dns; mx.example.org\r\n\r\n\
+///                                Final-Recipient: rfc822; a@example.com\r\n\
+///                                Action: failed\r\n");
+///
+/// assert_eq!(
+///     status.to_json(),
+///     concat!(
+///         r#"{"message":[{"name":"Reporting-MTA","value":" dns; mx.example.org"}],"#,
+///         r#""recipients":[[{"name":"Final-Recipient","value":" rfc822; a@example.com"},"#,
+///         r#"{"name":"Action","value":" failed"}]]}"#,
+///     )
+/// );
+/// ```
+impl<'a> ToJson for DeliveryStatus<'a> {
+    fn to_json_value(&self) -> Value {
+        json!({
+            "message": self.message.to_json_value(),
+            "recipients": self.recipients.to_json_value(),
+        })
+    }
+}
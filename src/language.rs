@@ -0,0 +1,208 @@
+//! Language hints from message headers
+//!
+//! Guesses the languages of a message from its header section only:
+//! the `"Content-Language:"` header of [RFC 3282], the charset of the
+//! `"Content-Type:"` and the charsets and [RFC 2231] language tags of
+//! encoded words. This is enough to route a message to a localized
+//! filter without looking at the body.
+//!
+//! [RFC 3282]: https://tools.ietf.org/html/rfc3282
+//! [RFC 2231]: https://tools.ietf.org/html/rfc2231#section-5
+
+use std::fmt::{self, Display};
+
+use crate::charset::encoding_from_label;
+use crate::headersection::HeaderField;
+use crate::rfc2047::encoded_word_raw;
+use crate::rfc2231::content_type;
+
+/// The kind of a [`Hint`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum HintKind {
+    /// A language tag such as `"en"` or `"pt-br"`.
+    Language,
+    /// A charset label such as `"iso-2022-jp"`.
+    Charset,
+}
+
+/// A language or charset hint with its score.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Hint {
+    /// The kind of the hint.
+    pub kind: HintKind,
+    /// The language tag or charset label, in lowercase.
+    pub value: String,
+    /// The score, higher is more likely.
+    pub score: u32,
+}
+
+impl Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            HintKind::Language => write!(f, "language={} ({})", self.value, self.score),
+            HintKind::Charset => write!(f, "charset={} ({})", self.value, self.score),
+        }
+    }
+}
+
+// Scores of each source, declared languages win over guesses.
+const CONTENT_LANGUAGE: u32 = 100;
+const WORD_LANGUAGE: u32 = 50;
+const CONTENT_TYPE_CHARSET: u32 = 20;
+const WORD_CHARSET: u32 = 10;
+const CHARSET_LANGUAGE: u32 = 5;
+
+/// Return the language implied by a charset label.
+///
+/// The label is resolved with
+/// [`encoding_from_label`](crate::charset::encoding_from_label), so
+/// every alias it knows maps to the same language. Only charsets used
+/// for a single language are mapped. Returns `None` for Unicode, the
+/// charsets shared by several languages and unknown labels.
+/// # Examples
+/// ```
+/// use rustyknife::language::charset_language;
+///
+/// assert_eq!(charset_language("ISO-2022-JP"), Some("ja"));
+/// assert_eq!(charset_language("cp932"), Some("ja"));
+/// assert_eq!(charset_language("koi8-r"), Some("ru"));
+/// assert_eq!(charset_language("utf-8"), None);
+/// ```
+pub fn charset_language(charset: &str) -> Option<&'static str> {
+    let encoding = encoding_from_label(charset)?;
+
+    Some(match encoding.whatwg_name()? {
+        "iso-2022-jp" | "shift_jis" | "euc-jp" => "ja",
+        "euc-kr" => "ko",
+        "gbk" | "gb18030" => "zh-hans",
+        "big5" => "zh-hant",
+        "koi8-r" => "ru",
+        "koi8-u" => "uk",
+        "iso-8859-7" | "windows-1253" => "el",
+        "iso-8859-8" | "iso-8859-8-i" | "windows-1255" => "he",
+        "windows-1254" => "tr",
+        "windows-874" => "th",
+        "windows-1258" => "vi",
+        _ => return None,
+    })
+}
+
+// Language tags of a "Content-Language:" value, comments removed.
+fn content_language(value: &[u8]) -> Vec<String> {
+    let mut text = String::new();
+    let mut depth = 0usize;
+
+    for c in String::from_utf8_lossy(value).chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if depth == 0 => text.push(c),
+            _ => (),
+        }
+    }
+
+    text.split(',')
+        .map(|tag| tag.trim().to_ascii_lowercase())
+        .filter(|tag| !tag.is_empty() && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        .collect()
+}
+
+fn is_ascii_charset(charset: &str) -> bool {
+    matches!(charset, "us-ascii" | "ascii" | "ansi_x3.4-1968")
+}
+
+#[derive(Default)]
+struct Hints(Vec<Hint>);
+
+impl Hints {
+    fn add(&mut self, kind: HintKind, value: &str, score: u32) {
+        let value = value.trim().to_ascii_lowercase();
+
+        match self
+            .0
+            .iter_mut()
+            .find(|h| h.kind == kind && h.value == value)
+        {
+            Some(hint) => hint.score += score,
+            None => self.0.push(Hint { kind, value, score }),
+        }
+    }
+
+    fn add_charset(&mut self, charset: &str, score: u32) {
+        let charset = charset.trim().to_ascii_lowercase();
+        if charset.is_empty() || is_ascii_charset(&charset) {
+            return;
+        }
+        self.add(HintKind::Charset, &charset, score);
+        if let Some(language) = charset_language(&charset) {
+            self.add(HintKind::Language, language, CHARSET_LANGUAGE);
+        }
+    }
+}
+
+/// Return the language and charset hints of a header section, best
+/// first.
+///
+/// Each occurrence of a hint adds to its score. Declared languages
+/// score highest, followed by the language tags of encoded words, the
+/// charset of the `"Content-Type:"`, the charsets of encoded words and
+/// finally the languages implied by those charsets. `"US-ASCII"` is
+/// not a useful hint and is ignored.
+/// # Examples
+/// ```
+/// use rustyknife::headersection::header_section;
+/// use rustyknife::language::{probable_languages, HintKind};
+///
+/// let input = b"Subject: =?ISO-2022-JP?B?GyRCJEskWyRzJDQbKEI=?=\r\n\
+///               Content-Language: en (fallback), ja\r\n\
+///               Content-Type: text/plain; charset=iso-2022-jp\r\n\r\n";
+/// let (_, headers) = header_section(input).unwrap();
+///
+/// let hints = probable_languages(&headers);
+/// let languages: Vec<_> = hints.iter()
+///     .filter(|h| h.kind == HintKind::Language)
+///     .map(|h| h.value.as_str())
+///     .collect();
+/// assert_eq!(languages, ["ja", "en"]);
+/// assert_eq!(hints[2].value, "iso-2022-jp");
+/// ```
+pub fn probable_languages(headers: &[HeaderField]) -> Vec<Hint> {
+    let mut hints = Hints::default();
+
+    for (name, value) in headers.iter().flatten() {
+        if name.eq_ignore_ascii_case(b"content-language") {
+            for tag in content_language(value) {
+                hints.add(HintKind::Language, &tag, CONTENT_LANGUAGE);
+            }
+        } else if name.eq_ignore_ascii_case(b"content-type") {
+            if let Ok((_, (_, params))) = content_type(value) {
+                for (_, charset) in params.iter().filter(|(n, _)| n == "charset") {
+                    hints.add_charset(charset, CONTENT_TYPE_CHARSET);
+                }
+            }
+        }
+
+        let mut rest = *value;
+        while let Some(start) = rest.windows(2).position(|w| w == b"=?") {
+            match encoded_word_raw(&rest[start..]) {
+                Ok((tail, word)) => {
+                    hints.add_charset(&word.charset, WORD_CHARSET);
+                    if let Some(language) = &word.language {
+                        hints.add(HintKind::Language, language, WORD_LANGUAGE);
+                    }
+                    rest = tail;
+                }
+                Err(_) => rest = &rest[start + 2..],
+            }
+        }
+    }
+
+    let mut hints = hints.0;
+    hints.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(a.kind.cmp(&b.kind))
+            .then_with(|| a.value.cmp(&b.value))
+    });
+    hints
+}
@@ -1,5 +1,32 @@
+//! Fast, robust and safe email parsing library
+//!
+//! Parsers are built with [nom] and operate on byte slices. Most of
+//! them are generic over a [behaviour] type that selects how octets
+//! above 127 are treated. The [prelude] exports the commonly used
+//! parsers and types.
+//!
+//! The `trace` feature emits [tracing](https://docs.rs/tracing) spans
+//! from the main parsers with the rule name and remaining input
+//! length, which helps finding out why a message failed to parse.
+//!
+//! The `compact` feature stores short domains, local parts and ESMTP
+//! parameter keywords and values inline to reduce allocations in high
+//! volume servers. It only changes private storage, the public types
+//! are the same with and without it.
+//!
+//! The `chrono` and `time` features add conversions from the
+//! [`rfc5322::DateTime`] of the `"Date:"` header to the types of those
+//! crates.
+//!
+//! The `email_address` feature adds `TryFrom` conversions between
+//! [`types::Mailbox`] and the `EmailAddress` of the `email_address`
+//! crate.
+//!
+//! The `testdata` feature exports the `testdata` corpora used by the
+//! tests of this crate for conformance checks of downstream code.
 #![warn(rust_2018_idioms)]
 #![allow(elided_lifetimes_in_paths)]
+#![allow(mismatched_lifetime_syntaxes)]
 #![warn(missing_docs)]
 
 pub use nom;
@@ -14,25 +41,126 @@ pub mod behaviour {
     ///  * Activates message/global (RFC6532) support for message content.
     ///  * Activates SMTPUTF8 support for SMTP.
     pub struct Intl;
+
+    /// Octets above 127 are handled according to the policy `E`.
+    ///
+    /// Otherwise the same as [`Legacy`].
+    /// # Examples
+    /// ```
+    /// use rustyknife::behaviour::{LegacyWith, QuestionMark, Reject, Strip};
+    /// use rustyknife::rfc5322::unstructured;
+    ///
+    /// let input = b"caf\xc3\xa9 \xff";
+    /// assert_eq!(unstructured::<LegacyWith<QuestionMark>>(input).unwrap().1, "caf?? ?");
+    /// assert_eq!(unstructured::<LegacyWith<Strip>>(input).unwrap().1, "caf ");
+    /// assert_eq!(unstructured::<LegacyWith<Reject>>(input).unwrap().0, b"\xc3\xa9 \xff");
+    /// ```
+    pub struct LegacyWith<E>(std::marker::PhantomData<E>);
+
+    /// What to do with an octet above 127.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum EightBit {
+        /// Replace the octet with a character.
+        Replace(char),
+        /// Drop the octet.
+        Strip,
+        /// Stop parsing at the octet.
+        Reject,
+    }
+
+    /// A policy for octets above 127 used by [`LegacyWith`].
+    ///
+    /// Implement it on your own type to use another replacement
+    /// character.
+    pub trait EightBitPolicy {
+        /// The action applied to every octet above 127.
+        const EIGHT_BIT: EightBit;
+    }
+
+    /// Replace with U+FFFD, as done by [`Legacy`].
+    pub struct ReplacementChar;
+
+    /// Replace with `'?'`.
+    pub struct QuestionMark;
+
+    /// Drop the octet.
+    pub struct Strip;
+
+    /// Stop parsing at the octet.
+    pub struct Reject;
+
+    impl EightBitPolicy for ReplacementChar {
+        const EIGHT_BIT: EightBit = EightBit::Replace('\u{fffd}');
+    }
+
+    impl EightBitPolicy for QuestionMark {
+        const EIGHT_BIT: EightBit = EightBit::Replace('?');
+    }
+
+    impl EightBitPolicy for Strip {
+        const EIGHT_BIT: EightBit = EightBit::Strip;
+    }
+
+    impl EightBitPolicy for Reject {
+        const EIGHT_BIT: EightBit = EightBit::Reject;
+    }
 }
 
 #[macro_use]
 mod util;
+pub mod arena;
+pub mod calendar;
+pub mod charset;
+pub mod dkim;
+pub mod envelope;
 pub mod headersection;
+pub mod language;
+pub mod mime;
+pub mod newline;
+pub mod prelude;
+pub mod priority;
+pub mod proxy;
+pub mod rfc2045;
+pub mod rfc2046;
 pub mod rfc2047;
 pub mod rfc2231;
+pub mod rfc2557;
 pub mod rfc3461;
+pub mod rfc3464;
 pub mod rfc4616;
-mod rfc5234;
+pub mod rfc4954;
+pub mod rfc5234;
 pub mod rfc5321;
 pub mod rfc5322;
+pub mod rfc6522;
+pub mod rfc6857;
+pub mod rfc8460;
+pub mod rfc8461;
+pub mod smime;
+pub mod sniff;
+pub mod tagvalue;
+#[cfg(any(test, feature = "testdata"))]
+pub mod testdata;
+pub mod transcript;
 pub mod types;
 pub mod xforward;
 
+#[cfg(feature = "c-api")]
+pub mod capi;
+
+#[cfg(feature = "json")]
+pub mod json;
+
 #[cfg(feature = "python")]
 mod pymod;
 
 #[cfg(test)]
 mod tests;
 
-pub use util::NomResult;
+pub use util::{parse_exact, with_error, ExactError, NomResult, RkParser, Trailing};
+
+pub use headersection::HeaderField;
+pub use mime::{parse_message, Body, Entity};
+pub use rfc5321::{Command, ForwardPath, Path, ReversePath};
+pub use rfc5322::{Address, Group};
+pub use types::{Domain, DomainPart, LocalPart, Mailbox};
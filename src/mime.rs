@@ -0,0 +1,578 @@
+//! [MIME] entity tree
+//!
+//! Splits a message into a tree of entities using the header sections
+//! and multipart boundaries. Bodies are borrowed from the input and
+//! are not transfer decoded.
+//!
+//! [MIME]: https://tools.ietf.org/html/rfc2045
+
+use std::borrow::Cow;
+
+use encoding::all::UTF_8;
+use encoding::types::EncodingRef;
+use encoding::DecoderTrap;
+
+use crate::behaviour::Intl;
+use crate::charset::encoding_from_label;
+use crate::headersection::{header_section_iter, into_owned_field, HeaderField, OwnedHeaderField};
+use crate::rfc2046::split_multipart;
+use crate::rfc2231::{
+    content_disposition, content_transfer_encoding, effective_content_type, CharsetError,
+    ContentDisposition, ContentTransferEncoding, ContentTypeContext, Disposition, MimeType,
+};
+use crate::rfc5322::unstructured;
+
+// Deeper entities are not split any further.
+const MAX_DEPTH: usize = 64;
+
+/// The body of a MIME entity.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Body<'a> {
+    /// A leaf body, still transfer encoded.
+    Single(&'a [u8]),
+    /// The body parts of a `"multipart/*"` entity.
+    Multipart(Vec<Entity<'a>>),
+    /// An encapsulated `"message/rfc822"` or `"message/global"`
+    /// message.
+    Message(Box<Entity<'a>>),
+}
+
+/// A MIME entity, either a message or a body part.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entity<'a> {
+    /// The header fields in message order.
+    pub headers: Vec<HeaderField<'a>>,
+    /// The effective content type with the [RFC 2045] defaults
+    /// applied.
+    ///
+    /// [RFC 2045]: https://tools.ietf.org/html/rfc2045#section-5.2
+    pub content_type: MimeType,
+    /// The body following the header section.
+    pub body: Body<'a>,
+    /// The body as found in the input, before it was split into body
+    /// parts or an encapsulated message.
+    pub raw_body: &'a [u8],
+}
+
+impl<'a> Entity<'a> {
+    /// Return the raw value of the first header named `name`.
+    ///
+    /// The name is compared case insensitively.
+    pub fn header(&self, name: &[u8]) -> Option<&'a [u8]> {
+        self.headers.iter().find_map(|h| match h {
+            Ok((n, value)) if n.eq_ignore_ascii_case(name) => Some(*value),
+            _ => None,
+        })
+    }
+
+    /// Returns `true` if the MIME type is `mime_type`.
+    ///
+    /// `mime_type` must be lowercase.
+    pub fn is(&self, mime_type: &str) -> bool {
+        self.content_type.mime_type == mime_type
+    }
+
+    /// Return the body parts of a multipart entity.
+    ///
+    /// Empty for any other entity.
+    pub fn parts(&self) -> &[Entity<'a>] {
+        match &self.body {
+            Body::Multipart(parts) => parts,
+            _ => &[],
+        }
+    }
+
+    /// Iterate over this entity and all the entities it contains,
+    /// depth first in message order.
+    pub fn walk(&self) -> Walk<'_, 'a> {
+        Walk { stack: vec![self] }
+    }
+
+    /// Undo the `"Content-Transfer-Encoding"` of a leaf body.
+    ///
+    /// Returns the decoded bytes and `true` if the encoding was
+    /// malformed or unknown, in which case the bytes are a best
+    /// effort. Returns `None` for multipart and encapsulated message
+    /// bodies.
+    /// # Examples
+    /// ```
+    /// use rustyknife::mime::parse_message;
+    ///
+    /// let message = parse_message(b"Content-Transfer-Encoding: base64\r\n\r\naGVsbG8g\r\nd29ybGQ=\r\n");
+    /// let (body, lossy) = message.decoded_body().unwrap();
+    /// assert_eq!(&body[..], b"hello world");
+    /// assert!(!lossy);
+    /// ```
+    pub fn decoded_body(&self) -> Option<(Cow<'a, [u8]>, bool)> {
+        let raw = match self.body {
+            Body::Single(raw) => raw,
+            _ => return None,
+        };
+        let cte = self
+            .header(b"content-transfer-encoding")
+            .map(|value| content_transfer_encoding(value).map(|(_, cte)| cte));
+
+        Some(match cte {
+            None
+            | Some(Ok(ContentTransferEncoding::SevenBit))
+            | Some(Ok(ContentTransferEncoding::EightBit))
+            | Some(Ok(ContentTransferEncoding::Binary)) => (Cow::Borrowed(raw), false),
+            Some(Ok(ContentTransferEncoding::Base64)) => {
+                let (body, lossy) = decode_base64_body(raw);
+                (Cow::Owned(body), lossy)
+            }
+            Some(Ok(ContentTransferEncoding::QuotedPrintable)) => {
+                let (body, lossy) = decode_qp_body(raw);
+                (Cow::Owned(body), lossy)
+            }
+            Some(_) => (Cow::Borrowed(raw), true),
+        })
+    }
+
+    /// Copy the entity and all the entities it contains out of the
+    /// input.
+    ///
+    /// The raw body of each multipart entity and encapsulated message
+    /// is copied along with the entities it contains.
+    /// # Examples
+    /// ```
+    /// use rustyknife::mime::parse_message;
+    ///
+    /// let owned = {
+    ///     let input = b"Content-Type: message/rfc822\r\n\r\nSubject: hi\r\n\r\nbody".to_vec();
+    ///     parse_message(&input).into_owned()
+    /// };
+    ///
+    /// let message = owned.as_entity();
+    /// let inner = message.walk().nth(1).unwrap();
+    /// assert_eq!(inner.header(b"subject"), Some(b" hi".as_ref()));
+    /// assert_eq!(inner.decoded_body().unwrap().0, b"body".as_ref());
+    /// ```
+    pub fn into_owned(self) -> OwnedEntity {
+        OwnedEntity {
+            headers: self.headers.into_iter().map(into_owned_field).collect(),
+            content_type: self.content_type,
+            raw_body: self.raw_body.to_vec(),
+            body: match self.body {
+                Body::Single(_) => OwnedBody::Single,
+                Body::Multipart(parts) => {
+                    OwnedBody::Multipart(parts.into_iter().map(Entity::into_owned).collect())
+                }
+                Body::Message(message) => OwnedBody::Message(Box::new(message.into_owned())),
+            },
+        }
+    }
+}
+
+/// An owned [`Body`] that can outlive the input.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OwnedBody {
+    /// A leaf body, still transfer encoded. The content is the
+    /// [`raw_body`](OwnedEntity::raw_body) of the entity.
+    Single,
+    /// The body parts of a `"multipart/*"` entity.
+    Multipart(Vec<OwnedEntity>),
+    /// An encapsulated `"message/rfc822"` or `"message/global"`
+    /// message.
+    Message(Box<OwnedEntity>),
+}
+
+/// An owned [`Entity`] that can outlive the input.
+///
+/// Created by [`Entity::into_owned`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnedEntity {
+    /// The header fields in message order.
+    pub headers: Vec<OwnedHeaderField>,
+    /// The effective content type.
+    pub content_type: MimeType,
+    /// The body following the header section.
+    pub body: OwnedBody,
+    /// The body as found in the input.
+    pub raw_body: Vec<u8>,
+}
+
+impl OwnedEntity {
+    /// Borrow the entity as an [`Entity`] to use its methods.
+    pub fn as_entity(&self) -> Entity<'_> {
+        Entity {
+            headers: self
+                .headers
+                .iter()
+                .map(|field| match field {
+                    Ok((name, value)) => Ok((&name[..], &value[..])),
+                    Err(line) => Err(&line[..]),
+                })
+                .collect(),
+            content_type: self.content_type.clone(),
+            raw_body: &self.raw_body,
+            body: match &self.body {
+                OwnedBody::Single => Body::Single(&self.raw_body),
+                OwnedBody::Multipart(parts) => {
+                    Body::Multipart(parts.iter().map(OwnedEntity::as_entity).collect())
+                }
+                OwnedBody::Message(message) => Body::Message(Box::new(message.as_entity())),
+            },
+        }
+    }
+}
+
+fn decode_base64_body(input: &[u8]) -> (Vec<u8>, bool) {
+    let mut lossy = false;
+    let mut clean: Vec<u8> = Vec::with_capacity(input.len());
+
+    for c in input {
+        match c {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'+' | b'/' => clean.push(*c),
+            b'=' | b' ' | b'\t' | b'\r' | b'\n' => (),
+            _ => lossy = true,
+        }
+    }
+    // A single trailing character does not encode a full byte.
+    if clean.len() % 4 == 1 {
+        clean.pop();
+        lossy = true;
+    }
+
+    let config = base64::STANDARD_NO_PAD.decode_allow_trailing_bits(true);
+    (
+        base64::decode_config(&clean, config).unwrap_or_default(),
+        lossy,
+    )
+}
+
+fn decode_qp_body(input: &[u8]) -> (Vec<u8>, bool) {
+    let hex = |c: u8| (c as char).to_digit(16).map(|d| d as u8);
+    let mut lossy = false;
+    let mut out = Vec::with_capacity(input.len());
+    // Literal white space at the end of the output, removed before a
+    // hard line break. White space before a soft line break is kept.
+    let mut trailing = 0;
+    let mut i = 0;
+
+    while i < input.len() {
+        match input[i] {
+            b'=' => {
+                let rest = &input[i + 1..];
+                let pad = rest
+                    .iter()
+                    .take_while(|c| matches!(c, b' ' | b'\t'))
+                    .count();
+                if let Some(eol) = rest[pad..]
+                    .strip_prefix(b"\r\n")
+                    .or_else(|| rest[pad..].strip_prefix(b"\n"))
+                    .or_else(|| rest[pad..].is_empty().then_some(&rest[pad..]))
+                {
+                    i = input.len() - eol.len();
+                    trailing = 0;
+                    continue;
+                }
+                match (
+                    rest.first().and_then(|c| hex(*c)),
+                    rest.get(1).and_then(|c| hex(*c)),
+                ) {
+                    (Some(high), Some(low)) => {
+                        out.push(high << 4 | low);
+                        i += 3;
+                    }
+                    _ => {
+                        out.push(b'=');
+                        lossy = true;
+                        i += 1;
+                    }
+                }
+                trailing = 0;
+                continue;
+            }
+            b'\r' | b'\n' => {
+                out.truncate(out.len() - trailing);
+                trailing = 0;
+            }
+            b' ' | b'\t' => trailing += 1,
+            _ => trailing = 0,
+        }
+        out.push(input[i]);
+        i += 1;
+    }
+
+    (out, lossy)
+}
+
+/// Decode the body of a leaf entity to text.
+///
+/// Undoes the transfer encoding with [`Entity::decoded_body`] then
+/// decodes the charset named by the `"Content-Type:"`, resolved with
+/// [`encoding_from_label`]. A byte order mark overrides the charset
+/// and is removed. A missing charset means `"us-ascii"` and an
+/// unknown one is decoded as UTF-8.
+///
+/// Returns the text and `true` if it is lossy: the transfer encoding
+/// was malformed, the charset unknown or some bytes were replaced by
+/// `U+FFFD`. Returns `None` for multipart and encapsulated message
+/// bodies.
+/// # Examples
+/// ```
+/// use rustyknife::mime::{decode_text_part, parse_message};
+///
+/// let message = parse_message(b"Content-Type: text/plain; charset=iso-8859-1\r\n\
+///                               Content-Transfer-Encoding: quoted-printable\r\n\r\n\
+///                               Caf=E9 cr=E8me=\r\n br=FBl=E9e");
+/// assert_eq!(decode_text_part(&message), Some(("Café crème brûlée".to_string(), false)));
+///
+/// let message = parse_message(b"Content-Type: text/plain; charset=x-bogus\r\n\r\nhello");
+/// assert_eq!(decode_text_part(&message), Some(("hello".to_string(), true)));
+/// ```
+pub fn decode_text_part(part: &Entity) -> Option<(String, bool)> {
+    let (body, mut lossy) = part.decoded_body()?;
+    let encoding: EncodingRef = match part.content_type.charset() {
+        Ok(encoding) => encoding,
+        Err(CharsetError::Missing) => encoding_from_label("us-ascii").unwrap(),
+        Err(CharsetError::Unknown(_)) => {
+            lossy = true;
+            UTF_8
+        }
+    };
+
+    let text = match encoding::decode(&body, DecoderTrap::Strict, encoding).0 {
+        Ok(text) => text,
+        Err(_) => {
+            lossy = true;
+            encoding::decode(&body, DecoderTrap::Replace, encoding)
+                .0
+                .unwrap_or_default()
+        }
+    };
+
+    Some((text, lossy))
+}
+
+/// Depth first iterator over a tree of entities.
+///
+/// Created by [`Entity::walk`].
+#[derive(Clone, Debug)]
+pub struct Walk<'e, 'a> {
+    stack: Vec<&'e Entity<'a>>,
+}
+
+impl<'e, 'a> Iterator for Walk<'e, 'a> {
+    type Item = &'e Entity<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entity = self.stack.pop()?;
+
+        match &entity.body {
+            Body::Single(_) => (),
+            Body::Multipart(parts) => self.stack.extend(parts.iter().rev()),
+            Body::Message(message) => self.stack.push(message),
+        }
+
+        Some(entity)
+    }
+}
+
+fn is_identity_encoding(value: Option<&[u8]>) -> bool {
+    match value {
+        Some(cte) => matches!(
+            String::from_utf8_lossy(cte)
+                .trim()
+                .to_ascii_lowercase()
+                .as_str(),
+            "7bit" | "8bit" | "binary"
+        ),
+        None => true,
+    }
+}
+
+fn entity(input: &[u8], context: ContentTypeContext, depth: usize) -> Entity<'_> {
+    let mut fields = header_section_iter(input);
+    let headers: Vec<_> = fields.by_ref().collect();
+    let raw = fields.remainder();
+
+    let mut out = Entity {
+        headers,
+        content_type: MimeType {
+            mime_type: String::new(),
+            params: Vec::new(),
+        },
+        body: Body::Single(raw),
+        raw_body: raw,
+    };
+    out.content_type = effective_content_type(out.header(b"content-type"), context);
+
+    if depth >= MAX_DEPTH {
+        return out;
+    }
+
+    let mime_type = out.content_type.mime_type.as_str();
+    if mime_type.starts_with("multipart/") {
+        let context = match mime_type {
+            "multipart/digest" => ContentTypeContext::Digest,
+            _ => ContentTypeContext::Default,
+        };
+        let split = out
+            .content_type
+            .param("boundary")
+            .and_then(|b| split_multipart(raw, b.as_bytes()));
+
+        if let Some(split) = split {
+            out.body = Body::Multipart(
+                split
+                    .contents()
+                    .map(|part| entity(part, context, depth + 1))
+                    .collect(),
+            );
+        }
+    } else if matches!(mime_type, "message/rfc822" | "message/global")
+        && is_identity_encoding(out.header(b"content-transfer-encoding"))
+    {
+        out.body = Body::Message(Box::new(entity(
+            raw,
+            ContentTypeContext::Default,
+            depth + 1,
+        )));
+    }
+
+    out
+}
+
+/// Parse a message into a tree of MIME entities.
+///
+/// Multipart entities without a usable boundary and encapsulated
+/// messages with a transfer encoding are left as [`Body::Single`].
+/// # Examples
+/// ```
+/// use rustyknife::mime::{parse_message, Body};
+///
+/// let input = b"Content-Type: multipart/mixed; boundary=b\r\n\r\n--b\r\n\r\nhello\r\n--b\r\nContent-Type: image/png\r\n\r\nPNG\r\n--b--\r\n";
+/// let message = parse_message(input);
+///
+/// let types: Vec<_> = message.walk().map(|e| e.content_type.mime_type.as_str()).collect();
+/// assert_eq!(types, ["multipart/mixed", "text/plain", "image/png"]);
+/// assert_eq!(message.parts()[0].body, Body::Single(b"hello"));
+/// ```
+pub fn parse_message(input: &[u8]) -> Entity<'_> {
+    entity(input, ContentTypeContext::Default, 0)
+}
+
+/// An attachment found by [`attachments`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attachment<'a> {
+    /// The file name from the `"Content-Disposition:"` or the
+    /// `"name"` parameter of the `"Content-Type:"`, with [RFC 2231]
+    /// and [RFC 2047] encodings undone.
+    ///
+    /// [RFC 2231]: https://tools.ietf.org/html/rfc2231
+    /// [RFC 2047]: https://tools.ietf.org/html/rfc2047
+    pub filename: Option<String>,
+    /// The effective content type.
+    pub content_type: MimeType,
+    /// The content with the transfer encoding undone.
+    pub content: Cow<'a, [u8]>,
+    /// `true` if the transfer encoding was malformed or unknown.
+    pub lossy: bool,
+    /// The parsed `"Content-Disposition:"`, `None` if absent or
+    /// invalid.
+    pub disposition: Option<Disposition>,
+}
+
+impl Attachment<'_> {
+    /// Copy borrowed content so the result outlives the input.
+    pub fn into_owned(self) -> Attachment<'static> {
+        Attachment {
+            filename: self.filename,
+            content_type: self.content_type,
+            content: Cow::Owned(self.content.into_owned()),
+            lossy: self.lossy,
+            disposition: self.disposition,
+        }
+    }
+}
+
+// Decodes encoded words left in a parameter value, as sent by many
+// clients in quoted file names.
+fn decode_filename(value: &str) -> String {
+    if !value.contains("=?") {
+        return value.into();
+    }
+    unstructured::<Intl>(value.as_bytes())
+        .map(|(_, decoded)| decoded)
+        .unwrap_or_else(|_| value.into())
+}
+
+/// Iterate over the attachments of a message.
+///
+/// Every leaf entity is an attachment except text parts without a
+/// file name that are not explicitly marked `"attachment"`, which are
+/// the message body and its alternatives. Encapsulated messages with
+/// a file name or marked `"attachment"`, such as a forwarded message,
+/// are a single attachment holding the raw message. Other
+/// encapsulated messages are searched.
+/// # Examples
+/// ```
+/// use rustyknife::mime::{attachments, parse_message};
+///
+/// let input = b"Content-Type: multipart/mixed; boundary=b\r\n\r\n\
+///               --b\r\nContent-Type: text/plain\r\n\r\nSee attached.\r\n\
+///               --b\r\nContent-Type: application/pdf\r\n\
+///               Content-Disposition: attachment; filename*=utf-8''r%C3%A9sum%C3%A9.pdf\r\n\
+///               Content-Transfer-Encoding: base64\r\n\r\nJVBERi0=\r\n--b--\r\n";
+/// let message = parse_message(input);
+///
+/// let found: Vec<_> = attachments(&message).collect();
+/// assert_eq!(found.len(), 1);
+/// assert_eq!(found[0].filename.as_deref(), Some("résumé.pdf"));
+/// assert_eq!(found[0].content_type.mime_type, "application/pdf");
+/// assert_eq!(&found[0].content[..], b"%PDF-");
+/// ```
+pub fn attachments<'e, 'a>(message: &'e Entity<'a>) -> impl Iterator<Item = Attachment<'a>> + 'e {
+    let mut stack = vec![message];
+
+    std::iter::from_fn(move || {
+        while let Some(entity) = stack.pop() {
+            if let Body::Multipart(parts) = &entity.body {
+                stack.extend(parts.iter().rev());
+                continue;
+            }
+
+            let disposition = entity
+                .header(b"content-disposition")
+                .and_then(|value| content_disposition(value).ok())
+                .map(|(_, parsed)| Disposition::from(parsed));
+            let filename = disposition
+                .as_ref()
+                .and_then(|d| d.filename())
+                .or_else(|| entity.content_type.param("name"))
+                .map(decode_filename);
+            let attached =
+                matches!(&disposition, Some(d) if d.disposition == ContentDisposition::Attachment);
+
+            let (content, lossy) = match &entity.body {
+                Body::Message(inner) if filename.is_none() && !attached => {
+                    stack.push(inner);
+                    continue;
+                }
+                Body::Message(_) => (Cow::Borrowed(entity.raw_body), false),
+                _ if entity.content_type.mime_type.starts_with("text/")
+                    && filename.is_none()
+                    && !attached =>
+                {
+                    continue
+                }
+                _ => match entity.decoded_body() {
+                    Some(decoded) => decoded,
+                    None => continue,
+                },
+            };
+
+            return Some(Attachment {
+                filename,
+                content_type: entity.content_type.clone(),
+                content,
+                lossy,
+                disposition,
+            });
+        }
+
+        None
+    })
+}
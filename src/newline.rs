@@ -0,0 +1,66 @@
+//! Line ending normalization
+//!
+//! SMTP and DKIM require lines to end with CRLF, but messages coming
+//! from local sources often use bare LF or even bare CR.
+
+use std::borrow::Cow;
+
+/// A line ending that was rewritten to CRLF.
+///
+/// Offsets are relative to the original input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEndingFix {
+    /// A LF not preceded by CR at this offset.
+    BareLf(usize),
+    /// A CR not followed by LF at this offset.
+    BareCr(usize),
+}
+
+impl LineEndingFix {
+    /// Offset of the bare line ending in the original input.
+    pub fn offset(&self) -> usize {
+        match self {
+            LineEndingFix::BareLf(offset) | LineEndingFix::BareCr(offset) => *offset,
+        }
+    }
+}
+
+/// Convert all line endings in `input` to CRLF.
+///
+/// Returns the normalized bytes and the list of line endings that were
+/// fixed. The input is borrowed if it was already in CRLF form.
+/// # Examples
+/// ```
+/// use rustyknife::newline::{normalize_crlf, LineEndingFix};
+///
+/// let (normalized, fixes) = normalize_crlf(b"a\nb\r\nc\r");
+///
+/// assert_eq!(normalized, &b"a\r\nb\r\nc\r\n"[..]);
+/// assert_eq!(fixes, [LineEndingFix::BareLf(1), LineEndingFix::BareCr(6)]);
+/// ```
+pub fn normalize_crlf(input: &[u8]) -> (Cow<'_, [u8]>, Vec<LineEndingFix>) {
+    let mut fixes = Vec::new();
+
+    for (i, c) in input.iter().enumerate() {
+        match c {
+            b'\n' if i == 0 || input[i - 1] != b'\r' => fixes.push(LineEndingFix::BareLf(i)),
+            b'\r' if input.get(i + 1) != Some(&b'\n') => fixes.push(LineEndingFix::BareCr(i)),
+            _ => (),
+        }
+    }
+
+    if fixes.is_empty() {
+        return (Cow::Borrowed(input), fixes);
+    }
+
+    let mut out = Vec::with_capacity(input.len() + fixes.len());
+    let mut start = 0;
+    for fix in &fixes {
+        out.extend_from_slice(&input[start..fix.offset()]);
+        out.extend_from_slice(b"\r\n");
+        start = fix.offset() + 1;
+    }
+    out.extend_from_slice(&input[start..]);
+
+    (Cow::Owned(out), fixes)
+}
@@ -0,0 +1,43 @@
+//! Commonly used parsers and types
+//!
+//! Glob import this module to get the behaviours, the main parsers
+//! and their output types without spelling out the module of each.
+//! The `"Mailbox"` of [RFC 5322] with a display name is exported as
+//! `IMFMailbox` to keep it apart from the plain [`Mailbox`].
+//! # Examples
+//! ```
+//! use rustyknife::prelude::*;
+//!
+//! let (_, from) = from::<Intl>(b" John <jdoe@example.org>").unwrap();
+//! let (_, (path, _)) = mail_command::<Intl>(b"MAIL FROM:<jdoe@example.org>\r\n").unwrap();
+//!
+//! match (&from[..], path) {
+//!     ([Address::Mailbox(IMFMailbox { address, .. })], ReversePath::Path(Path(sender, _))) => {
+//!         assert_eq!(*address, sender)
+//!     }
+//!     _ => unreachable!(),
+//! }
+//! ```
+//!
+//! [RFC 5322]: https://tools.ietf.org/html/rfc5322
+
+pub use crate::behaviour::{Intl, Legacy};
+pub use crate::headersection::{header_section, HeaderField, HeaderMap};
+pub use crate::mime::{parse_message, Body, Entity};
+pub use crate::rfc2047::encoded_word;
+pub use crate::rfc2231::{
+    content_disposition, content_transfer_encoding, content_type, ContentDisposition,
+    ContentTransferEncoding, MimeType,
+};
+pub use crate::rfc5321::{
+    command, mail_command, rcpt_command, validate_address, Command, ForwardPath, Param, Path,
+    PathCommand, ReversePath,
+};
+pub use crate::rfc5322::{
+    date_time, from, in_reply_to, msg_id, parse_header, references, reply_to, sender, unstructured,
+    Address, DateTime, Group, Mailbox as IMFMailbox, MessageId, ParsedHeader,
+};
+pub use crate::types::{
+    AddressLiteral, Domain, DomainPart, DotAtom, LocalPart, Mailbox, QuotedString,
+};
+pub use crate::{parse_exact, ExactError, NomResult, RkParser, Trailing};
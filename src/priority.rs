@@ -0,0 +1,213 @@
+//! Precedence and priority headers
+//!
+//! Normalizes the non-standard `"Precedence:"`, `"Importance:"` and
+//! `"X-Priority:"` headers described in [RFC 2076]. Matching is case
+//! insensitive and tolerates the spellings and trailing comments
+//! produced by common mail clients.
+//!
+//! [RFC 2076]: https://tools.ietf.org/html/rfc2076#section-3.9
+
+use std::fmt::{self, Display};
+
+// The first word of a header value, lowercased. Comments, separators
+// and white space end the word.
+fn first_word(value: &[u8]) -> Option<String> {
+    let value = String::from_utf8_lossy(value);
+    let word = value
+        .trim_start()
+        .split(|c: char| c.is_whitespace() || matches!(c, '(' | ';' | ','))
+        .next()?
+        .trim_matches('"')
+        .to_ascii_lowercase();
+
+    (!word.is_empty()).then_some(word)
+}
+
+/// Value of a `"Precedence:"` header.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Precedence {
+    /// `bulk`
+    Bulk,
+    /// `list`
+    List,
+    /// `junk`
+    Junk,
+    /// `auto_reply`, also spelled `auto-reply`.
+    AutoReply,
+    /// `first-class`, also spelled `first_class`.
+    FirstClass,
+    /// Any other value, stored in lowercase.
+    Other(String),
+}
+
+impl Precedence {
+    /// Returns `true` for the values that [RFC 3834] autoresponders
+    /// must not reply to.
+    ///
+    /// [RFC 3834]: https://tools.ietf.org/html/rfc3834#section-2
+    pub fn is_automated(&self) -> bool {
+        matches!(
+            self,
+            Precedence::Bulk | Precedence::List | Precedence::Junk | Precedence::AutoReply
+        )
+    }
+}
+
+impl From<&str> for Precedence {
+    fn from(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "bulk" => Precedence::Bulk,
+            "list" => Precedence::List,
+            "junk" => Precedence::Junk,
+            "auto_reply" | "auto-reply" | "autoreply" => Precedence::AutoReply,
+            "first-class" | "first_class" => Precedence::FirstClass,
+            other => Precedence::Other(other.into()),
+        }
+    }
+}
+
+impl Display for Precedence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Precedence::Bulk => write!(f, "bulk"),
+            Precedence::List => write!(f, "list"),
+            Precedence::Junk => write!(f, "junk"),
+            Precedence::AutoReply => write!(f, "auto_reply"),
+            Precedence::FirstClass => write!(f, "first-class"),
+            Precedence::Other(p) => write!(f, "{}", p),
+        }
+    }
+}
+
+/// Parse a `"Precedence:"` header value.
+///
+/// Returns `None` for an empty value.
+/// # Examples
+/// ```
+/// use rustyknife::priority::{precedence, Precedence};
+///
+/// assert_eq!(precedence(b" Bulk (mass mailing)"), Some(Precedence::Bulk));
+/// assert!(precedence(b" auto-reply").unwrap().is_automated());
+/// assert_eq!(precedence(b" special"), Some(Precedence::Other("special".into())));
+/// ```
+pub fn precedence(value: &[u8]) -> Option<Precedence> {
+    first_word(value).map(|word| Precedence::from(word.as_str()))
+}
+
+/// Value of an `"Importance:"` header, also used to normalize
+/// `"X-Priority:"`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Importance {
+    /// `low`
+    Low,
+    /// `normal`
+    Normal,
+    /// `high`
+    High,
+}
+
+impl Display for Importance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Importance::Low => write!(f, "low"),
+            Importance::Normal => write!(f, "normal"),
+            Importance::High => write!(f, "high"),
+        }
+    }
+}
+
+fn importance_word(word: &str) -> Option<Importance> {
+    Some(match word {
+        "low" | "non-urgent" | "nonurgent" => Importance::Low,
+        "normal" | "medium" => Importance::Normal,
+        "high" | "urgent" => Importance::High,
+        _ => return None,
+    })
+}
+
+/// Parse an `"Importance:"` header value.
+///
+/// The `"Priority:"` values `urgent` and `non-urgent` are also
+/// accepted. Returns `None` for unknown values.
+/// # Examples
+/// ```
+/// use rustyknife::priority::{importance, Importance};
+///
+/// assert_eq!(importance(b" HIGH"), Some(Importance::High));
+/// assert_eq!(importance(b" non-urgent"), Some(Importance::Low));
+/// assert_eq!(importance(b" whenever"), None);
+/// ```
+pub fn importance(value: &[u8]) -> Option<Importance> {
+    importance_word(&first_word(value)?)
+}
+
+/// Value of an `"X-Priority:"` header, from 1 (highest) to 5
+/// (lowest).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum XPriority {
+    /// `1`
+    Highest = 1,
+    /// `2`
+    High,
+    /// `3`
+    Normal,
+    /// `4`
+    Low,
+    /// `5`
+    Lowest,
+}
+
+impl XPriority {
+    /// Map the priority to the three levels of `"Importance:"`.
+    pub fn importance(self) -> Importance {
+        match self {
+            XPriority::Highest | XPriority::High => Importance::High,
+            XPriority::Normal => Importance::Normal,
+            XPriority::Low | XPriority::Lowest => Importance::Low,
+        }
+    }
+}
+
+impl Display for XPriority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", *self as u8)
+    }
+}
+
+/// Parse an `"X-Priority:"` header value.
+///
+/// The leading digit is used when present, as in `"2 (High)"`.
+/// Values made of a word only are matched against the usual names.
+/// Returns `None` for unknown values.
+/// # Examples
+/// ```
+/// use rustyknife::priority::{x_priority, Importance, XPriority};
+///
+/// assert_eq!(x_priority(b" 1 (Highest)"), Some(XPriority::Highest));
+/// assert_eq!(x_priority(b" 4"), Some(XPriority::Low));
+/// assert_eq!(x_priority(b" Urgent").map(XPriority::importance), Some(Importance::High));
+/// assert_eq!(x_priority(b" 9"), None);
+/// assert_eq!(x_priority(b" 10"), None);
+/// ```
+pub fn x_priority(value: &[u8]) -> Option<XPriority> {
+    let word = first_word(value)?;
+    let digits = word.bytes().take_while(u8::is_ascii_digit).count();
+
+    Some(match (digits, word.as_bytes()[0]) {
+        (1, b'1') => XPriority::Highest,
+        (1, b'2') => XPriority::High,
+        (1, b'3') => XPriority::Normal,
+        (1, b'4') => XPriority::Low,
+        (1, b'5') => XPriority::Lowest,
+        (0, _) => match word.as_str() {
+            "highest" => XPriority::Highest,
+            "lowest" => XPriority::Lowest,
+            other => match importance_word(other)? {
+                Importance::High => XPriority::High,
+                Importance::Normal => XPriority::Normal,
+                Importance::Low => XPriority::Low,
+            },
+        },
+        _ => return None,
+    })
+}
@@ -0,0 +1,180 @@
+//! [PROXY protocol] connection header
+//!
+//! Parses the header sent by HAProxy and other load balancers at the
+//! start of a proxied connection, before the SMTP greeting. Both the
+//! human readable version 1 and the binary version 2 are supported.
+//!
+//! [PROXY protocol]: https://www.haproxy.org/download/2.0/doc/proxy-protocol.txt
+
+use std::convert::TryFrom;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::{self, FromStr};
+
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take, take_until, take_while1, take_while_m_n};
+use nom::character::is_digit;
+use nom::combinator::{map, map_res, verify};
+use nom::multi::length_data;
+use nom::number::complete::{be_u16, be_u8};
+use nom::sequence::{delimited, pair, preceded, tuple};
+
+use crate::rfc5234::{crlf, sp};
+use crate::util::*;
+
+/// A parsed PROXY protocol header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProxyHeader {
+    /// The connection was proxied on behalf of a client.
+    Proxied {
+        /// Address and port of the original client.
+        source: SocketAddr,
+        /// Address and port the client connected to.
+        destination: SocketAddr,
+    },
+    /// The proxy did not provide usable addresses.
+    ///
+    /// This is the case for `PROXY UNKNOWN`, version 2 `LOCAL`
+    /// connections such as health checks and address families other
+    /// than IPv4 and IPv6. The real connection endpoints should be
+    /// used.
+    Unknown,
+}
+
+const V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Maximum length of a version 1 header including the CRLF.
+pub const V1_MAX_LENGTH: usize = 107;
+
+fn v1_ip<T: FromStr>(input: &[u8]) -> NomResult<T> {
+    map_res(take_while1(|c| c != b' ' && c != b'\r'), |ip| {
+        str::from_utf8(ip).unwrap_or("").parse()
+    })(input)
+}
+
+fn v1_port(input: &[u8]) -> NomResult<u16> {
+    map_res(
+        verify(take_while_m_n(1, 5, is_digit), |p: &[u8]| {
+            p == b"0" || p[0] != b'0'
+        }),
+        |p| str::from_utf8(p).unwrap().parse(),
+    )(input)
+}
+
+fn v1_addresses<T: Into<IpAddr> + FromStr>(input: &[u8]) -> NomResult<ProxyHeader> {
+    map(
+        tuple((
+            v1_ip::<T>,
+            preceded(sp, v1_ip::<T>),
+            preceded(sp, v1_port),
+            preceded(sp, v1_port),
+        )),
+        |(src, dst, sport, dport)| ProxyHeader::Proxied {
+            source: SocketAddr::new(src.into(), sport),
+            destination: SocketAddr::new(dst.into(), dport),
+        },
+    )(input)
+}
+
+/// Parse a version 1 PROXY protocol header.
+///
+/// Headers longer than [`V1_MAX_LENGTH`] are rejected without looking
+/// further into the input.
+/// # Examples
+/// ```
+/// use rustyknife::proxy::{proxy_v1, ProxyHeader};
+///
+/// let (rem, header) = proxy_v1(b"PROXY TCP4 192.0.2.1 198.51.100.25 56324 25\r\nEHLO").unwrap();
+///
+/// assert_eq!(header, ProxyHeader::Proxied {
+///     source: "192.0.2.1:56324".parse().unwrap(),
+///     destination: "198.51.100.25:25".parse().unwrap(),
+/// });
+/// assert_eq!(rem, b"EHLO");
+/// ```
+pub fn proxy_v1(input: &[u8]) -> NomResult<ProxyHeader> {
+    let limited = &input[..input.len().min(V1_MAX_LENGTH)];
+
+    let (rem, header) = traced(
+        "proxy_v1",
+        delimited(
+            tag("PROXY "),
+            alt((
+                preceded(tag("TCP4 "), v1_addresses::<Ipv4Addr>),
+                preceded(tag("TCP6 "), v1_addresses::<Ipv6Addr>),
+                map(pair(tag("UNKNOWN"), take_until("\r\n")), |_| {
+                    ProxyHeader::Unknown
+                }),
+            )),
+            crlf,
+        ),
+    )(limited)?;
+
+    Ok((&input[limited.len() - rem.len()..], header))
+}
+
+fn v2_addresses(family: u8, payload: &[u8]) -> NomResult<ProxyHeader> {
+    match family >> 4 {
+        0x1 => map(
+            tuple((take(4usize), take(4usize), be_u16, be_u16)),
+            |(src, dst, sport, dport): (&[u8], &[u8], u16, u16)| ProxyHeader::Proxied {
+                source: SocketAddr::new(<[u8; 4]>::try_from(src).unwrap().into(), sport),
+                destination: SocketAddr::new(<[u8; 4]>::try_from(dst).unwrap().into(), dport),
+            },
+        )(payload),
+        0x2 => map(
+            tuple((take(16usize), take(16usize), be_u16, be_u16)),
+            |(src, dst, sport, dport): (&[u8], &[u8], u16, u16)| ProxyHeader::Proxied {
+                source: SocketAddr::new(<[u8; 16]>::try_from(src).unwrap().into(), sport),
+                destination: SocketAddr::new(<[u8; 16]>::try_from(dst).unwrap().into(), dport),
+            },
+        )(payload),
+        _ => Ok((payload, ProxyHeader::Unknown)),
+    }
+}
+
+/// Parse a version 2 (binary) PROXY protocol header.
+///
+/// Type-length-value extensions following the addresses are skipped.
+/// # Examples
+/// ```
+/// use rustyknife::proxy::{proxy_v2, ProxyHeader};
+///
+/// let mut input = b"\r\n\r\n\0\r\nQUIT\n\x21\x11\x00\x0c".to_vec();
+/// input.extend_from_slice(&[192, 0, 2, 1, 198, 51, 100, 25, 0xdc, 0x04, 0, 25]);
+/// input.extend_from_slice(b"EHLO");
+///
+/// let (rem, header) = proxy_v2(&input).unwrap();
+///
+/// assert_eq!(header, ProxyHeader::Proxied {
+///     source: "192.0.2.1:56324".parse().unwrap(),
+///     destination: "198.51.100.25:25".parse().unwrap(),
+/// });
+/// assert_eq!(rem, b"EHLO");
+/// ```
+pub fn proxy_v2(input: &[u8]) -> NomResult<ProxyHeader> {
+    let (rem, (command, family, payload)) = traced(
+        "proxy_v2",
+        tuple((
+            preceded(
+                tag(V2_SIGNATURE),
+                map(verify(be_u8, |vc| vc >> 4 == 2 && vc & 0xf <= 1), |vc| {
+                    vc & 0xf
+                }),
+            ),
+            be_u8,
+            length_data(be_u16),
+        )),
+    )(input)?;
+
+    if command == 0 {
+        return Ok((rem, ProxyHeader::Unknown));
+    }
+    let (_, header) = v2_addresses(family, payload)?;
+
+    Ok((rem, header))
+}
+
+/// Parse a version 1 or version 2 PROXY protocol header.
+pub fn proxy_header(input: &[u8]) -> NomResult<ProxyHeader> {
+    alt((proxy_v1, proxy_v2))(input)
+}
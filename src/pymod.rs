@@ -2,13 +2,15 @@ use std::fmt::Debug;
 use std::fs::File;
 
 use crate::behaviour::{Intl, Legacy};
-use crate::headersection::header_section;
+use crate::headersection::{header_section, header_section_iter, HeaderField};
 use crate::rfc2231::{content_disposition, content_transfer_encoding, content_type};
 use crate::rfc3461::{dsn_mail_params, orcpt_address, DSNMailParams, DSNRet};
 use crate::rfc5321::{
-    mail_command, rcpt_command, validate_address, ForwardPath, Param as ESMTPParam, ReversePath,
+    mail_command, rcpt_command, validate_address, validate_address_detailed, validate_addresses,
+    AddressError, ForwardPath, Param as ESMTPParam, ReversePath,
 };
 use crate::rfc5322::{from, reply_to, sender, unstructured, Address, Group, Mailbox};
+use crate::types::{Domain, Mailbox as SMTPMailbox};
 use crate::util::NomResult;
 use crate::xforward::{xforward_params, Param as XFORWARDParam};
 
@@ -17,7 +19,7 @@ use memmap::Mmap;
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3::types::{PyBytes, PyDict, PyTuple};
-use pyo3::{self, PyErr, PyObject, PyResult, Python, ToPyObject};
+use pyo3::{self, PyErr, PyIterProtocol, PyObject, PyResult, Python, ToPyObject};
 
 impl IntoPy<PyObject> for Address {
     fn into_py(self, py: Python) -> PyObject {
@@ -48,7 +50,11 @@ impl IntoPy<PyObject> for Mailbox {
 
 impl IntoPy<PyObject> for XFORWARDParam {
     fn into_py(self, py: Python) -> PyObject {
-        PyTuple::new(py, &[self.0.to_object(py), self.1.to_object(py)]).to_object(py)
+        PyTuple::new(
+            py,
+            &[self.0.to_object(py), self.1.as_option().to_object(py)],
+        )
+        .to_object(py)
     }
 }
 
@@ -115,22 +121,96 @@ fn convert_result<O, E: Debug>(input: NomResult<O, E>, match_all: bool) -> PyRes
     }
 }
 
+fn detailed_result(
+    res: Result<(), (AddressError, usize)>,
+) -> (bool, Option<String>, Option<usize>) {
+    match res {
+        Ok(()) => (true, None, None),
+        Err((reason, offset)) => (false, Some(reason.to_string()), Some(offset)),
+    }
+}
+
+fn header_to_py(py: Python, header: HeaderField) -> PyObject {
+    match header {
+        Ok((name, value)) => (PyBytes::new(py, name), PyBytes::new(py, value)).to_object(py),
+        Err(invalid) => (py.None(), PyBytes::new(py, invalid)).to_object(py),
+    }
+}
+
 fn header_section_slice(py: Python, input: &[u8]) -> PyResult<PyObject> {
     let (rem, out) = header_section(input)
         .map_err(|err| PyErr::new::<PyValueError, _>(format!("{:?}.", err)))?;
 
     let header_end = input.len().checked_sub(rem.len()).unwrap();
-    let headers: Vec<_> = out
-        .into_iter()
-        .map(|h| match h {
-            Ok((name, value)) => (PyBytes::new(py, name), PyBytes::new(py, value)).to_object(py),
-            Err(invalid) => (py.None(), PyBytes::new(py, invalid)).to_object(py),
-        })
-        .collect();
+    let headers: Vec<_> = out.into_iter().map(|h| header_to_py(py, h)).collect();
 
     Ok((headers, header_end).to_object(py))
 }
 
+enum HeaderSource {
+    Bytes(Py<PyBytes>),
+    File(Mmap),
+}
+
+/// Lazy iterator over the headers of a message.
+///
+/// Yields the same tuples as :meth:`header_section`.
+#[pyclass]
+struct HeaderIterator {
+    source: HeaderSource,
+    pos: usize,
+    end: Option<usize>,
+}
+
+impl HeaderIterator {
+    fn next_header(&mut self, py: Python) -> PyResult<Option<PyObject>> {
+        if self.end.is_some() {
+            return Ok(None);
+        }
+
+        let input: &[u8] = match &self.source {
+            HeaderSource::Bytes(bytes) => bytes.as_ref(py).as_bytes(),
+            HeaderSource::File(fmap) => fmap,
+        };
+        let mut headers = header_section_iter(&input[self.pos..]);
+        let out = headers.next().map(|h| header_to_py(py, h));
+
+        if out.is_none() && headers.is_incomplete() {
+            return Err(PyErr::new::<PyValueError, _>("Incomplete header section."));
+        }
+
+        self.pos = input.len() - headers.remainder().len();
+        if out.is_none() {
+            self.end = Some(self.pos);
+        }
+
+        Ok(out)
+    }
+}
+
+#[pymethods]
+impl HeaderIterator {
+    /// Exact byte position of the end of headers.
+    ///
+    /// None until the iterator is exhausted.
+    #[getter]
+    fn end(&self) -> Option<usize> {
+        self.end
+    }
+}
+
+#[pyproto]
+impl PyIterProtocol for HeaderIterator {
+    fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<Self>) -> PyResult<Option<PyObject>> {
+        let py = slf.py();
+        slf.next_header(py)
+    }
+}
+
 #[pymodule]
 fn rustyknife(_py: Python, m: &PyModule) -> PyResult<()> {
     /// from_(input)
@@ -176,6 +256,33 @@ fn rustyknife(_py: Python, m: &PyModule) -> PyResult<()> {
         header_section_slice(py2, &fmap)
     }
 
+    /// iter_headers(input) -> iterator of headers
+    ///
+    /// Lazy variant of :meth:`header_section` and
+    /// :meth:`header_section_file`. Headers are parsed as the iterator
+    /// is advanced so the caller may stop early.
+    ///
+    /// :param input: Input string or file name to read.
+    /// :type input: bytes or str
+    /// :return: Iterator of header (name, value) tuples. Its `end`
+    ///  attribute holds the end of headers position once exhausted.
+    #[pyfn(m, "iter_headers")]
+    fn py_iter_headers(input: &PyAny) -> PyResult<HeaderIterator> {
+        let source = match input.downcast::<PyBytes>() {
+            Ok(bytes) => HeaderSource::Bytes(bytes.into()),
+            Err(_) => {
+                let file = File::open(input.extract::<&str>()?)?;
+                HeaderSource::File(unsafe { Mmap::map(&file)? })
+            }
+        };
+
+        Ok(HeaderIterator {
+            source,
+            pos: 0,
+            end: None,
+        })
+    }
+
     /// xforward_params(input)
     #[pyfn(m, "xforward_params")]
     fn py_xforward_params(input: &PyBytes) -> PyResult<Vec<XFORWARDParam>> {
@@ -186,7 +293,7 @@ fn rustyknife(_py: Python, m: &PyModule) -> PyResult<()> {
     #[pyfn(m, "orcpt_address")]
     fn py_orcpt_address(input: &str) -> PyResult<(String, String)> {
         convert_result(
-            orcpt_address(input.as_bytes()).map(|(rem, a)| (rem, (a.0.into(), a.1.into()))),
+            orcpt_address(input.as_bytes()).map(|(rem, a)| (rem, (a.0.to_string(), a.1.into()))),
             true,
         )
     }
@@ -236,6 +343,111 @@ fn rustyknife(_py: Python, m: &PyModule) -> PyResult<()> {
         validate_address::<Legacy>(input.as_bytes())
     }
 
+    /// validate_address_detailed(address, intl=False)
+    ///
+    /// Validate an address and report why it is invalid.
+    ///
+    /// :param address: Non-empty address without <> brackets.
+    /// :type address: str
+    /// :param intl: Accept UTF-8 addresses.
+    /// :type intl: bool
+    /// :return: (ok, reason, offset) where reason and offset are None
+    ///  for a valid address. offset is the byte position at which the
+    ///  address stopped being valid.
+    /// :rtype: tuple
+    #[pyfn(m, "validate_address_detailed", input, intl = false)]
+    pub fn py_validate_address_detailed(
+        input: &str,
+        intl: bool,
+    ) -> (bool, Option<String>, Option<usize>) {
+        let res = if intl {
+            validate_address_detailed::<Intl>(input.as_bytes())
+        } else {
+            validate_address_detailed::<Legacy>(input.as_bytes())
+        };
+
+        detailed_result(res)
+    }
+
+    /// validate_addresses(addresses, intl=False)
+    ///
+    /// Validate many addresses in a single call.
+    ///
+    /// :param addresses: Non-empty addresses without <> brackets.
+    /// :type addresses: list of str
+    /// :param intl: Accept UTF-8 addresses.
+    /// :type intl: bool
+    /// :return: An (ok, reason, offset) tuple for each address, as
+    ///  returned by validate_address_detailed.
+    /// :rtype: list of tuple
+    #[pyfn(m, "validate_addresses", addresses, intl = false)]
+    pub fn py_validate_addresses(
+        addresses: Vec<&str>,
+        intl: bool,
+    ) -> Vec<(bool, Option<String>, Option<usize>)> {
+        if intl {
+            validate_addresses::<Intl, _>(addresses)
+                .map(detailed_result)
+                .collect()
+        } else {
+            validate_addresses::<Legacy, _>(addresses)
+                .map(detailed_result)
+                .collect()
+        }
+    }
+
+    /// normalize_address(address)
+    ///
+    /// Normalize an address for comparison purposes.
+    ///
+    /// The local part is unquoted if quoted needlessly and the domain
+    /// is lowercased.
+    ///
+    /// :param address: Non-empty address without <> brackets.
+    /// :type address: str
+    /// :return: Normalized address
+    /// :rtype: str
+    #[pyfn(m, "normalize_address")]
+    pub fn py_normalize_address(input: &str) -> PyResult<String> {
+        let mut mailbox = SMTPMailbox::from_smtp(input.as_bytes())
+            .map_err(|_| PyErr::new::<PyValueError, _>("Invalid address"))?;
+        mailbox.normalize();
+
+        Ok(mailbox.to_string())
+    }
+
+    /// domain_to_ascii(domain)
+    ///
+    /// Convert a domain to its ASCII form with IDNA (punycode) labels.
+    ///
+    /// :param domain: Domain name
+    /// :type domain: str
+    /// :rtype: str
+    #[pyfn(m, "domain_to_ascii")]
+    pub fn py_domain_to_ascii(input: &str) -> PyResult<String> {
+        Domain::from_smtp(input.as_bytes())
+            .ok()
+            .and_then(|d| d.to_ascii().ok())
+            .map(String::from)
+            .ok_or_else(|| PyErr::new::<PyValueError, _>("Invalid domain"))
+    }
+
+    /// domain_to_unicode(domain)
+    ///
+    /// Convert the IDNA (punycode) labels of a domain to Unicode.
+    ///
+    /// :param domain: Domain name
+    /// :type domain: str
+    /// :rtype: str
+    #[pyfn(m, "domain_to_unicode")]
+    pub fn py_domain_to_unicode(input: &str) -> PyResult<String> {
+        Domain::from_smtp(input.as_bytes())
+            .ok()
+            .and_then(|d| d.to_unicode().ok())
+            .map(String::from)
+            .ok_or_else(|| PyErr::new::<PyValueError, _>("Invalid domain"))
+    }
+
     /// unstructured(input)
     ///
     /// Decode an unstructured email header.
@@ -281,9 +493,10 @@ fn rustyknife(_py: Python, m: &PyModule) -> PyResult<()> {
     ///
     #[pyfn(m, "content_transfer_encoding", input, all = false)]
     fn py_content_transfer_encoding(input: &PyBytes, all: bool) -> PyResult<String> {
-        convert_result(content_transfer_encoding(input.as_bytes()), all)
-            .map(|cte| cte.to_string().to_lowercase())
+        convert_result(content_transfer_encoding(input.as_bytes()), all).map(|cte| cte.to_string())
     }
 
+    m.add_class::<HeaderIterator>()?;
+
     Ok(())
 }
@@ -0,0 +1,93 @@
+//! [MIME] header field grammar
+//!
+//! The token and parameter syntax shared by the `"Content-Type"` and
+//! `"Content-Disposition"` headers. Other headers using the same
+//! `*(";" parameter)` structure can reuse these parsers.
+//!
+//! See [`crate::rfc2231`] for parameters with [RFC 2231] encoding and
+//! continuations.
+//!
+//! [MIME]: https://tools.ietf.org/html/rfc2045#section-5.1
+//! [RFC 2231]: https://tools.ietf.org/html/rfc2231
+
+use std::borrow::Cow;
+use std::str;
+
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while1};
+use nom::combinator::{map, opt};
+use nom::multi::many0;
+use nom::sequence::{pair, preceded, separated_pair, terminated, tuple};
+
+use crate::behaviour::Intl;
+use crate::rfc5234::crlf;
+use crate::rfc5322::{ofws, quoted_string};
+use crate::util::*;
+
+/// Returns `true` if `c` may appear in a MIME token.
+pub fn is_token_char(c: u8) -> bool {
+    (33..=126).contains(&c) && !b"()<>@,;:\\\"/[]?=".contains(&c)
+}
+
+/// Parse a MIME token.
+pub fn token(input: &[u8]) -> NomResult<&str> {
+    map(take_while1(is_token_char), |t| str::from_utf8(t).unwrap())(input)
+}
+
+/// Parse a parameter value, either a token or a quoted string.
+///
+/// Quoted strings are unquoted.
+/// # Examples
+/// ```
+/// use rustyknife::rfc2045::value;
+///
+/// assert_eq!(value(b"flowed").unwrap().1, "flowed");
+/// assert_eq!(value(b"\"a \\\"b\\\"\"").unwrap().1, "a \"b\"");
+/// ```
+pub fn value(input: &[u8]) -> NomResult<Cow<str>> {
+    alt((
+        map(token, Cow::from),
+        map(quoted_string::<Intl>, |qs| Cow::from(qs.0)),
+    ))(input)
+}
+
+pub(crate) fn equals(input: &[u8]) -> NomResult<()> {
+    map(tuple((ofws, tag("="), ofws)), |_| ())(input)
+}
+
+/// Parse a single `attribute=value` parameter.
+///
+/// The attribute is returned as written. Attribute names are case
+/// insensitive.
+pub fn parameter(input: &[u8]) -> NomResult<(&str, Cow<str>)> {
+    separated_pair(token, equals, value)(input)
+}
+
+/// Build a parser for a `*(";" parameter)` list using `param` for each
+/// parameter.
+///
+/// A trailing `";"` and CRLF are accepted.
+pub fn parameters<'a, O, F>(param: F) -> impl FnMut(&'a [u8]) -> NomResult<'a, Vec<O>>
+where
+    F: FnMut(&'a [u8]) -> NomResult<'a, O>,
+{
+    terminated(
+        many0(preceded(pair(tag(";"), ofws), param)),
+        pair(opt(tag(";")), opt(crlf)),
+    )
+}
+
+/// Parse a `*(";" parameter)` list of plain parameters.
+///
+/// # Examples
+/// ```
+/// use rustyknife::rfc2045::parameter_list;
+///
+/// let (rem, params) = parameter_list(b"; charset=utf-8; Format=\"flowed\"").unwrap();
+///
+/// assert_eq!(rem, b"");
+/// assert_eq!(params, [("charset", "utf-8".into()), ("Format", "flowed".into())]);
+/// ```
+pub fn parameter_list(input: &[u8]) -> NomResult<Vec<(&str, Cow<str>)>> {
+    traced("parameter_list", parameters(parameter))(input)
+}
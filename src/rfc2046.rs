@@ -0,0 +1,162 @@
+//! [Multipart] body splitting
+//!
+//! Splits the body of a multipart entity into its parts. The split is
+//! lossless: the preamble, every delimiter line with its surrounding
+//! line breaks and the epilogue are kept, so a message can be
+//! reassembled byte for byte when no part was modified. This is
+//! required when filtering signed messages.
+//!
+//! The split only borrows the body and has no owned form. Parse the
+//! message with [`mime::parse_message`](crate::mime::parse_message)
+//! and use [`Entity::into_owned`](crate::mime::Entity::into_owned) to
+//! keep the parts after the input is gone.
+//!
+//! [Multipart]: https://tools.ietf.org/html/rfc2046#section-5.1
+
+/// A single body part.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Part<'a> {
+    /// The line break preceding the delimiter, the delimiter line
+    /// itself including transport padding and its line break.
+    pub delimiter: &'a [u8],
+    /// The body part, headers included.
+    pub content: &'a [u8],
+}
+
+/// A split multipart body.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Multipart<'a> {
+    /// Everything before the first delimiter.
+    pub preamble: &'a [u8],
+    /// The body parts in message order.
+    pub parts: Vec<Part<'a>>,
+    /// The close delimiter including the preceding line break.
+    ///
+    /// Empty if the body was truncated before the close delimiter.
+    pub close_delimiter: &'a [u8],
+    /// Everything after the close delimiter.
+    pub epilogue: &'a [u8],
+}
+
+impl<'a> Multipart<'a> {
+    /// Iterate over the content of the body parts.
+    pub fn contents(&self) -> impl Iterator<Item = &'a [u8]> + '_ {
+        self.parts.iter().map(|p| p.content)
+    }
+
+    /// Reassemble the body exactly as it was received.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+
+        out.extend_from_slice(self.preamble);
+        for part in &self.parts {
+            out.extend_from_slice(part.delimiter);
+            out.extend_from_slice(part.content);
+        }
+        out.extend_from_slice(self.close_delimiter);
+        out.extend_from_slice(self.epilogue);
+
+        out
+    }
+}
+
+enum Delimiter {
+    Part,
+    Close,
+}
+
+// Check if the line is a delimiter for `boundary`.
+fn delimiter(line: &[u8], boundary: &[u8]) -> Option<Delimiter> {
+    let rest = line.strip_prefix(b"--")?.strip_prefix(boundary)?;
+    let (kind, rest) = match rest.strip_prefix(b"--") {
+        Some(rest) => (Delimiter::Close, rest),
+        None => (Delimiter::Part, rest),
+    };
+    let rest = rest.strip_suffix(b"\n").unwrap_or(rest);
+    let rest = rest.strip_suffix(b"\r").unwrap_or(rest);
+
+    if rest.iter().all(|c| *c == b' ' || *c == b'\t') {
+        Some(kind)
+    } else {
+        None
+    }
+}
+
+/// Split a multipart body on `boundary`.
+///
+/// Delimiter lines may end with either CRLF or LF. Returns `None` if
+/// no delimiter line was found.
+/// # Examples
+/// ```
+/// use rustyknife::rfc2046::split_multipart;
+///
+/// let body = b"preamble\r\n--b\r\n\r\nfirst\r\n--b\r\n\r\nsecond\r\n--b--\r\nepilogue";
+/// let multipart = split_multipart(body, b"b").unwrap();
+///
+/// assert_eq!(multipart.preamble, b"preamble");
+/// assert_eq!(multipart.contents().collect::<Vec<_>>(), [&b"\r\nfirst"[..], b"\r\nsecond"]);
+/// assert_eq!(multipart.epilogue, b"epilogue");
+/// assert_eq!(multipart.to_bytes(), &body[..]);
+/// ```
+pub fn split_multipart<'a>(input: &'a [u8], boundary: &[u8]) -> Option<Multipart<'a>> {
+    let mut preamble = None;
+    let mut parts: Vec<Part<'a>> = Vec::new();
+    // Start of the content of the current part.
+    let mut content_start = 0;
+    let mut line_start = 0;
+
+    while line_start < input.len() {
+        let line_end = input[line_start..]
+            .iter()
+            .position(|c| *c == b'\n')
+            .map(|i| line_start + i + 1)
+            .unwrap_or_else(|| input.len());
+
+        if let Some(kind) = delimiter(&input[line_start..line_end], boundary) {
+            // The preceding line break belongs to the delimiter unless
+            // it already ended the previous delimiter line.
+            let mut start = line_start;
+            if input[..start].ends_with(b"\n") && start > content_start {
+                start -= 1;
+                if input[..start].ends_with(b"\r") && start > content_start {
+                    start -= 1;
+                }
+            }
+
+            match parts.last_mut() {
+                Some(last) => last.content = &input[content_start..start],
+                None => preamble = Some(&input[..start]),
+            }
+
+            match kind {
+                Delimiter::Part => parts.push(Part {
+                    delimiter: &input[start..line_end],
+                    content: &[],
+                }),
+                Delimiter::Close => {
+                    return Some(Multipart {
+                        preamble: preamble?,
+                        parts,
+                        close_delimiter: &input[start..line_end],
+                        epilogue: &input[line_end..],
+                    })
+                }
+            }
+            content_start = line_end;
+        }
+
+        line_start = line_end;
+    }
+
+    let preamble = preamble?;
+    if let Some(last) = parts.last_mut() {
+        last.content = &input[content_start..];
+    }
+
+    Some(Multipart {
+        preamble,
+        parts,
+        close_delimiter: &[],
+        epilogue: &[],
+    })
+}
@@ -2,23 +2,31 @@
 //!
 //! [Header extensions for non-ASCII text]: https://tools.ietf.org/html/rfc2047
 
-use std::borrow::Cow;
-
-use encoding::all::ASCII;
-use encoding::label::encoding_from_whatwg_label;
-use encoding::DecoderTrap;
+use crate::behaviour::{EightBitPolicy, Intl, Legacy, LegacyWith};
+use crate::charset::encoding_from_label;
+use encoding::all::{ASCII, UTF_8};
+use encoding::{DecoderTrap, EncoderTrap, Encoding, EncodingRef};
 
 use nom::branch::alt;
-use nom::bytes::complete::{tag, take_while1};
-use nom::combinator::{map, opt};
+use nom::bytes::complete::{tag, take, take_while1};
+use nom::combinator::{all_consuming, map, map_opt, opt, verify};
 use nom::multi::many0;
 use nom::sequence::{delimited, preceded, terminated, tuple};
 
 use crate::rfc3461::hexpair;
 use crate::util::*;
 
+fn is_token(c: u8) -> bool {
+    (33..=126).contains(&c) && !b"()<>@,;:\\\"/[]?.=".contains(&c)
+}
+
 fn token(input: &[u8]) -> NomResult<&[u8]> {
-    take_while1(|c| (33..=126).contains(&c) && !b"()<>@,;:\\\"/[]?.=".contains(&c))(input)
+    take_while1(is_token)(input)
+}
+
+// RFC 2231 reserves "*" to introduce the language.
+fn charset(input: &[u8]) -> NomResult<&[u8]> {
+    take_while1(|c| is_token(c) && c != b'*')(input)
 }
 
 fn encoded_text(input: &[u8]) -> NomResult<&[u8]> {
@@ -47,32 +55,196 @@ fn decode_text(encoding: &[u8], text: &[u8]) -> Option<Vec<u8>> {
     }
 }
 
-fn _encoded_word(input: &[u8]) -> NomResult<(Cow<str>, Vec<u8>)> {
+/// An encoded word with the transfer encoding undone.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawEncodedWord {
+    /// The charset label as written in the encoded word.
+    pub charset: String,
+    /// The optional [RFC 2231] language tag.
+    ///
+    /// [RFC 2231]: https://tools.ietf.org/html/rfc2231#section-5
+    pub language: Option<String>,
+    /// The text after quoted-printable or base64 decoding, in the
+    /// original charset.
+    ///
+    /// Contains the encoded text unmodified if the transfer encoding
+    /// is unknown or invalid.
+    pub bytes: Vec<u8>,
+}
+
+impl RawEncodedWord {
+    /// Decode the text using the charset label.
+    ///
+    /// Returns `None` if the charset is unknown.
+    pub fn decode(&self) -> Option<String> {
+        encoding_from_label(&self.charset)
+            .map(|codec| codec.decode(&self.bytes, DecoderTrap::Replace).unwrap())
+    }
+}
+
+type WordParts<'a> = (&'a [u8], Option<&'a [u8]>, &'a [u8], &'a [u8]);
+
+// Charset, language, encoding and encoded text.
+fn encoded_word_parts(input: &[u8]) -> NomResult<WordParts> {
+    tuple((
+        preceded(tag("=?"), charset),
+        opt(preceded(tag("*"), token)),
+        delimited(tag("?"), token, tag("?")),
+        terminated(encoded_text, tag("?=")),
+    ))(input)
+}
+
+fn raw_word(charset: &[u8], language: Option<&[u8]>, bytes: Vec<u8>) -> RawEncodedWord {
+    RawEncodedWord {
+        charset: ascii_to_string(charset).into(),
+        language: language.map(|l| ascii_to_string(l).into()),
+        bytes,
+    }
+}
+
+fn _encoded_word(input: &[u8]) -> NomResult<RawEncodedWord> {
+    map(encoded_word_parts, |(charset, language, encoding, text)| {
+        let bytes = decode_text(encoding, text).unwrap_or_else(|| text.to_vec());
+        raw_word(charset, language, bytes)
+    })(input)
+}
+
+/// Where an encoded word appears, which restricts the characters
+/// allowed unencoded in Q encoded text.
+///
+/// See section 5 of [RFC 2047].
+///
+/// [RFC 2047]: https://tools.ietf.org/html/rfc2047#section-5
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WordContext {
+    /// Unstructured text such as a `"Subject:"`.
+    Text,
+    /// A comment in a structured header.
+    Comment,
+    /// A word of a phrase such as a display name.
+    Phrase,
+}
+
+impl WordContext {
+    fn allows(self, c: u8) -> bool {
+        match self {
+            WordContext::Text => matches!(c, 33..=126) && !b"=?_".contains(&c),
+            WordContext::Comment => matches!(c, 33..=126) && !b"=?_()\\".contains(&c),
+            WordContext::Phrase => c.is_ascii_alphanumeric() || b"!*+-/".contains(&c),
+        }
+    }
+}
+
+fn upper_hexpair(input: &[u8]) -> NomResult<u8> {
     map(
-        tuple((
-            preceded(tag("=?"), token),
-            opt(preceded(tag("*"), token)),
-            delimited(tag("?"), token, tag("?")),
-            terminated(encoded_text, tag("?=")),
-        )),
-        |(charset, _lang, encoding, text)| {
-            (
-                ascii_to_string(charset),
-                decode_text(encoding, text).unwrap_or_else(|| text.to_vec()),
-            )
-        },
+        verify(take(2usize), |c: &[u8]| {
+            c.iter().all(|c| matches!(c, b'0'..=b'9' | b'A'..=b'F'))
+        }),
+        |x: &[u8]| u8::from_str_radix(std::str::from_utf8(x).unwrap(), 16).unwrap(),
     )(input)
 }
 
-fn decode_charset((charset, bytes): (Cow<str>, Vec<u8>)) -> String {
-    encoding_from_whatwg_label(&charset)
-        .unwrap_or(ASCII)
-        .decode(&bytes, DecoderTrap::Replace)
-        .unwrap()
+fn decode_qp_strict(input: &[u8], context: WordContext) -> Option<Vec<u8>> {
+    all_consuming(many0(alt((
+        preceded(tag("="), upper_hexpair),
+        map(tag("_"), |_| b' '),
+        take1_filter(|c| context.allows(c)),
+    ))))(input)
+    .ok()
+    .map(|(_, o)| o)
+}
+
+/// Decode an encoded word, rejecting malformed encoded text.
+///
+/// Meant to validate messages at submission time, use
+/// [`encoded_word`] for received messages. In Q encoded text, escapes
+/// must use uppercase hexadecimal digits and characters not allowed
+/// in `context` must be escaped. Base64 encoded text must be valid
+/// and the encoding must be known.
+/// # Examples
+/// ```
+/// use rustyknife::rfc2047::{encoded_word_strict, WordContext};
+///
+/// let decode = encoded_word_strict(WordContext::Phrase);
+///
+/// assert_eq!(decode(b"=?utf-8?Q?Andr=C3=A9_M?=").unwrap().1, "André M");
+/// assert!(decode(b"=?utf-8?Q?Andr=c3=a9?=").is_err());
+/// assert!(decode(b"=?utf-8?Q?a=2?=").is_err());
+/// assert!(decode(b"=?utf-8?Q?a.b?=").is_err());
+/// assert!(encoded_word_strict(WordContext::Text)(b"=?utf-8?Q?a.b?=").is_ok());
+/// assert!(decode(b"=?utf-8?B?not base64?=").is_err());
+/// ```
+pub fn encoded_word_strict(context: WordContext) -> impl Fn(&[u8]) -> NomResult<String> + Copy {
+    move |input| {
+        traced(
+            "encoded_word_strict",
+            map_opt(encoded_word_parts, |(charset, language, encoding, text)| {
+                let bytes = match &encoding.to_ascii_lowercase()[..] {
+                    b"q" => decode_qp_strict(text, context)?,
+                    b"b" => base64::decode(text).ok()?,
+                    _ => return None,
+                };
+                Some(decode_charset(raw_word(charset, language, bytes)))
+            }),
+        )(input)
+    }
+}
+
+fn decode_charset(word: RawEncodedWord) -> String {
+    word.decode().unwrap_or_else(|| decode_ascii(&word))
+}
+
+fn decode_ascii(word: &RawEncodedWord) -> String {
+    ASCII.decode(&word.bytes, DecoderTrap::Replace).unwrap()
+}
+
+/// A conservative allow-list of charsets for [`encoded_word_with_charsets`].
+pub const SAFE_CHARSETS: &[&str] = &["us-ascii", "iso-8859-*", "utf-8"];
+
+/// Behaviour dependent charset restrictions for encoded words.
+///
+/// With [`Legacy`] only the charsets of the allow-list are decoded.
+/// With [`Intl`] the allow-list is ignored and every known charset is
+/// decoded.
+pub trait CharsetPolicy {
+    /// Returns `true` if text in `charset` may be decoded.
+    ///
+    /// Entries of `allowed` are compared case insensitively with the
+    /// charset label as written. An entry ending with `*` matches any
+    /// label starting with the rest of the entry.
+    fn allows(charset: &str, allowed: &[&str]) -> bool;
+}
+
+impl CharsetPolicy for Legacy {
+    fn allows(charset: &str, allowed: &[&str]) -> bool {
+        let charset = charset.to_ascii_lowercase();
+
+        allowed.iter().any(|entry| {
+            let entry = entry.to_ascii_lowercase();
+            match entry.strip_suffix('*') {
+                Some(prefix) => charset.starts_with(prefix),
+                None => charset == entry,
+            }
+        })
+    }
+}
+
+impl<E: EightBitPolicy> CharsetPolicy for LegacyWith<E> {
+    fn allows(charset: &str, allowed: &[&str]) -> bool {
+        Legacy::allows(charset, allowed)
+    }
+}
+
+impl CharsetPolicy for Intl {
+    fn allows(_charset: &str, _allowed: &[&str]) -> bool {
+        true
+    }
 }
 
 /// Decode an encoded word.
 ///
+/// Text in an unknown charset is decoded as ASCII with replacement
+/// characters. Use [`encoded_word_raw`] to apply another fallback.
 /// # Examples
 /// ```
 /// use rustyknife::rfc2047::encoded_word;
@@ -81,5 +253,368 @@ fn decode_charset((charset, bytes): (Cow<str>, Vec<u8>)) -> String {
 /// assert_eq!(decoded, "忍法写メ光飛ばし(笑)");
 /// ```
 pub fn encoded_word(input: &[u8]) -> NomResult<String> {
-    map(_encoded_word, decode_charset)(input)
+    traced("encoded_word", map(_encoded_word, decode_charset))(input)
+}
+
+/// Decode an encoded word, restricting the charsets according to the
+/// behaviour `P`.
+///
+/// Text in a charset that is not allowed by [`CharsetPolicy`] is
+/// decoded as ASCII with replacement characters, like text in an
+/// unknown charset.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::{Intl, Legacy};
+/// use rustyknife::rfc2047::{encoded_word_with_charsets, SAFE_CHARSETS};
+///
+/// let input = b"=?x-sjis?B?lEWWQA==?=";
+///
+/// let (_, decoded) = encoded_word_with_charsets::<Legacy>(SAFE_CHARSETS)(input).unwrap();
+/// assert_eq!(decoded, "\u{fffd}E\u{fffd}@");
+///
+/// let (_, decoded) = encoded_word_with_charsets::<Intl>(SAFE_CHARSETS)(input).unwrap();
+/// assert_eq!(decoded, "忍法");
+///
+/// let (_, decoded) = encoded_word_with_charsets::<Legacy>(SAFE_CHARSETS)(b"=?ISO-8859-1?Q?caf=E9?=").unwrap();
+/// assert_eq!(decoded, "café");
+/// ```
+pub fn encoded_word_with_charsets<'l, P: CharsetPolicy>(
+    allowed: &'l [&'l str],
+) -> impl Fn(&[u8]) -> NomResult<String> + Copy + 'l {
+    move |input| {
+        traced(
+            "encoded_word",
+            map(_encoded_word, |word| {
+                if P::allows(&word.charset, allowed) {
+                    decode_charset(word)
+                } else {
+                    decode_ascii(&word)
+                }
+            }),
+        )(input)
+    }
+}
+
+/// Parse an encoded word without decoding its charset.
+///
+/// # Examples
+/// ```
+/// use rustyknife::rfc2047::encoded_word_raw;
+///
+/// let (_, word) = encoded_word_raw(b"=?x-unknown*en?Q?caf=E9?=").unwrap();
+///
+/// assert_eq!(word.charset, "x-unknown");
+/// assert_eq!(word.language.as_deref(), Some("en"));
+/// assert_eq!(word.bytes, b"caf\xe9");
+/// assert_eq!(word.decode(), None);
+/// ```
+pub fn encoded_word_raw(input: &[u8]) -> NomResult<RawEncodedWord> {
+    traced("encoded_word_raw", _encoded_word)(input)
+}
+
+/// Maximum length of an encoded word.
+pub const MAX_WORD_LEN: usize = 75;
+
+/// The transfer encoding of the words built by [`encode_words`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WordEncoding {
+    /// Base64, compact for non-ASCII text.
+    B,
+    /// Quoted-printable, readable for mostly ASCII text.
+    ///
+    /// Characters are escaped as required in a phrase, which makes the
+    /// words valid in any context.
+    Q,
+}
+
+/// Text split into encoded words.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncodedWords {
+    /// The encoded words in order.
+    pub words: Vec<String>,
+}
+
+impl EncodedWords {
+    /// Offsets in the [`Display`](std::fmt::Display) output of the
+    /// spaces separating the words.
+    ///
+    /// A header folder may insert a CRLF before any of these spaces.
+    pub fn fold_points(&self) -> Vec<usize> {
+        let mut offset = 0;
+
+        self.words
+            .iter()
+            .take(self.words.len().saturating_sub(1))
+            .map(|word| {
+                offset += word.len() + 1;
+                offset - 1
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for EncodedWords {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.words.join(" "))
+    }
+}
+
+/// Apply the "Q" encoding to `bytes`, already in the target charset.
+///
+/// Spaces become `"_"` and the characters not allowed unencoded in
+/// `context` are escaped with uppercase hexadecimal digits, so the
+/// result is accepted by [`encoded_word_strict`]. The charset and
+/// delimiters of the encoded word are not added.
+/// # Examples
+/// ```
+/// use rustyknife::rfc2047::{q_encode, WordContext};
+///
+/// assert_eq!(q_encode("a (b)=c?".as_bytes(), WordContext::Text), "a_(b)=3Dc=3F");
+/// assert_eq!(q_encode("a (b)=c?".as_bytes(), WordContext::Comment), "a_=28b=29=3Dc=3F");
+/// assert_eq!(q_encode("caf\u{e9}.".as_bytes(), WordContext::Phrase), "caf=C3=A9=2E");
+/// ```
+pub fn q_encode(bytes: &[u8], context: WordContext) -> String {
+    let mut out = String::with_capacity(bytes.len() * 3);
+
+    for c in bytes {
+        match c {
+            b' ' => out.push('_'),
+            c if context.allows(*c) => out.push(char::from(*c)),
+            c => out.push_str(&format!("={:02X}", c)),
+        }
+    }
+    out
+}
+
+fn encode_chunk(
+    codec: EncodingRef,
+    charset: &str,
+    encoding: WordEncoding,
+    text: &str,
+) -> Option<String> {
+    let bytes = codec.encode(text, EncoderTrap::Strict).ok()?;
+
+    Some(match encoding {
+        WordEncoding::B => format!("=?{}?B?{}?=", charset, base64::encode(&bytes)),
+        WordEncoding::Q => format!(
+            "=?{}?Q?{}?=",
+            charset,
+            q_encode(&bytes, WordContext::Phrase)
+        ),
+    })
+}
+
+/// Encode `text` as a sequence of encoded words in `charset`.
+///
+/// Every word is at most [`MAX_WORD_LEN`] characters long and
+/// contains whole characters only, so multi-byte sequences such as
+/// those of UTF-8 or Shift_JIS are never split between words. Each
+/// word is encoded on its own, which keeps stateful charsets such as
+/// ISO-2022-JP valid.
+///
+/// Returns `None` if the charset is unknown, a character cannot be
+/// represented in it or a single character does not fit in a word.
+/// # Examples
+/// ```
+/// use rustyknife::rfc2047::{encode_words, encoded_word, WordEncoding};
+///
+/// let text = "忍法写メ光飛ばし(笑)".repeat(3);
+/// let encoded = encode_words(&text, "Shift_JIS", WordEncoding::B).unwrap();
+///
+/// assert!(encoded.words.iter().all(|w| w.len() <= 75));
+/// let decoded: String = encoded.words.iter().map(|w| encoded_word(w.as_bytes()).unwrap().1).collect();
+/// assert_eq!(decoded, text);
+///
+/// let header = encoded.to_string();
+/// assert!(encoded.fold_points().iter().all(|p| header.as_bytes()[*p] == b' '));
+/// ```
+pub fn encode_words(text: &str, charset: &str, encoding: WordEncoding) -> Option<EncodedWords> {
+    let codec = encoding_from_label(charset)?;
+    let words = split_words(codec, charset, encoding, text, MAX_WORD_LEN)?;
+
+    Some(EncodedWords { words })
+}
+
+// Greedily split `text` into encoded words. The first word is at most
+// `first_max` characters long and the others at most MAX_WORD_LEN.
+fn split_words(
+    codec: EncodingRef,
+    charset: &str,
+    encoding: WordEncoding,
+    text: &str,
+    first_max: usize,
+) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut current: Option<String> = None;
+
+    for (end, c) in text.char_indices().map(|(i, c)| (i + c.len_utf8(), c)) {
+        let max = if words.is_empty() {
+            first_max
+        } else {
+            MAX_WORD_LEN
+        };
+        let word = encode_chunk(codec, charset, encoding, &text[start..end])?;
+
+        if word.len() <= max {
+            current = Some(word);
+            continue;
+        }
+        // Start a new word with the current character.
+        words.push(current.take()?);
+        start = end - c.len_utf8();
+        let word = encode_chunk(codec, charset, encoding, &text[start..end])?;
+        if word.len() > MAX_WORD_LEN {
+            return None;
+        }
+        current = Some(word);
+    }
+    words.extend(current);
+
+    Some(words)
+}
+
+// The first of `charsets` that can represent `text`, UTF-8 otherwise.
+fn pick_charset<'c>(text: &str, charsets: &[&'c str]) -> (EncodingRef, &'c str) {
+    charsets
+        .iter()
+        .filter_map(|charset| encoding_from_label(charset).map(|codec| (codec, *charset)))
+        .find(|(codec, _)| codec.encode(text, EncoderTrap::Strict).is_ok())
+        .unwrap_or((UTF_8, "utf-8"))
+}
+
+// Encode with whichever of Q and B gives the shorter result.
+fn split_words_auto(
+    codec: EncodingRef,
+    charset: &str,
+    text: &str,
+    first_max: usize,
+) -> Option<Vec<String>> {
+    let length = |words: &Vec<String>| words.iter().map(|w| w.len() + 1).sum::<usize>();
+    let q = split_words(codec, charset, WordEncoding::Q, text, first_max);
+    let b = split_words(codec, charset, WordEncoding::B, text, first_max);
+
+    match (q, b) {
+        (Some(q), Some(b)) if length(&b) < length(&q) => Some(b),
+        (Some(q), _) => Some(q),
+        (None, b) => b,
+    }
+}
+
+/// Encode `text` as a sequence of encoded words, choosing the charset
+/// and the encoding.
+///
+/// The charset is the first of `charsets` that can represent the
+/// whole text, or UTF-8 if none can. Q or B is chosen to give the
+/// shortest result. Words are split as with [`encode_words`].
+/// # Examples
+/// ```
+/// use rustyknife::rfc2047::encode_word;
+///
+/// assert_eq!(encode_word("Café", &["iso-8859-1"]).unwrap().to_string(), "=?iso-8859-1?Q?Caf=E9?=");
+/// assert_eq!(encode_word("日本", &["iso-8859-1"]).unwrap().to_string(), "=?utf-8?B?5pel5pys?=");
+/// ```
+pub fn encode_word(text: &str, charsets: &[&str]) -> Option<EncodedWords> {
+    let (codec, charset) = pick_charset(text, charsets);
+    let words = split_words_auto(codec, charset, text, MAX_WORD_LEN)?;
+
+    Some(EncodedWords { words })
+}
+
+/// The maximum length of a header line containing encoded words,
+/// without its CRLF.
+pub const MAX_LINE_LEN: usize = 76;
+
+fn needs_encoding(word: &str) -> bool {
+    word.contains("=?") || !word.bytes().all(|c| (0x21..=0x7e).contains(&c))
+}
+
+// Position in the header line being folded.
+struct Line {
+    column: usize,
+    first: bool,
+}
+
+impl Line {
+    // Append a word that cannot be split, folding before it if the
+    // line would become too long. An empty word only adds a space to
+    // the current line, folding before it would leave a continuation
+    // line of white space.
+    fn place(&mut self, out: &mut String, atom: &str) {
+        if self.first {
+            self.first = false;
+            self.column += atom.len();
+        } else if !atom.is_empty() && self.column + 1 + atom.len() > MAX_LINE_LEN {
+            out.push_str("\r\n ");
+            self.column = 1 + atom.len();
+        } else {
+            out.push(' ');
+            self.column += 1 + atom.len();
+        }
+        out.push_str(atom);
+    }
+}
+
+/// Encode an unstructured header value such as a `"Subject:"`.
+///
+/// Words of printable ASCII are kept as is. Runs of other words,
+/// with the spaces between them, become encoded words built with
+/// [`encode_word`]. The value is folded before spaces so that lines
+/// are at most [`MAX_LINE_LEN`] characters long where possible.
+/// `offset` is the length of the first line before the value, such as
+/// 9 for `"Subject: "`. Only spaces separate words, other white space
+/// is encoded.
+/// # Examples
+/// ```
+/// use rustyknife::rfc2047::encode_unstructured;
+/// use rustyknife::rfc5322::unstructured;
+/// use rustyknife::behaviour::Intl;
+///
+/// let subject = "Re: Überweisung für März bestätigt, Rechnung Nr. 2024-0042 liegt bei";
+/// let value = encode_unstructured(subject, &["iso-8859-1"], 9);
+///
+/// assert!(value.starts_with("Re: =?iso-8859-1?Q?=DCberweisung_f=FCr_M=E4rz_best=E4tigt=2C?=\r\n Rechnung"));
+/// assert!(value.split("\r\n").all(|line| line.len() <= 76));
+/// assert_eq!(unstructured::<Intl>(value.as_bytes()).unwrap().1, subject);
+/// ```
+pub fn encode_unstructured(text: &str, charsets: &[&str], offset: usize) -> String {
+    let words: Vec<&str> = text.split(' ').collect();
+    let mut out = String::with_capacity(text.len());
+    let mut line = Line {
+        column: offset,
+        first: true,
+    };
+
+    let mut i = 0;
+    while i < words.len() {
+        if !needs_encoding(words[i]) {
+            line.place(&mut out, words[i]);
+            i += 1;
+            continue;
+        }
+        // Extend the run over empty words, the spaces between encoded
+        // words are not displayed.
+        let mut last = i;
+        for (j, word) in words.iter().enumerate().skip(i + 1) {
+            if needs_encoding(word) {
+                last = j;
+            } else if !word.is_empty() {
+                break;
+            }
+        }
+        let run = words[i..=last].join(" ");
+        i = last + 1;
+
+        let (codec, charset) = pick_charset(&run, charsets);
+        let room = MAX_LINE_LEN.saturating_sub(line.column + (!line.first) as usize);
+        let encoded = split_words_auto(codec, charset, &run, room.min(MAX_WORD_LEN))
+            .or_else(|| split_words_auto(codec, charset, &run, MAX_WORD_LEN))
+            .or_else(|| split_words_auto(UTF_8, "utf-8", &run, MAX_WORD_LEN))
+            .unwrap_or_default();
+        for word in encoded {
+            line.place(&mut out, &word);
+        }
+    }
+
+    out
 }
@@ -10,21 +10,22 @@ use std::collections::HashMap;
 use std::fmt::{self, Display};
 use std::str;
 
+use crate::charset::encoding_from_label;
 use encoding::all::ASCII;
-use encoding::label::encoding_from_whatwg_label;
 use encoding::types::EncodingRef;
 use encoding::DecoderTrap;
 
 use nom::branch::alt;
 use nom::bytes::complete::{tag, tag_no_case, take_while1, take_while_m_n};
 use nom::character::is_digit;
-use nom::combinator::{map, opt, recognize, verify};
+use nom::combinator::{all_consuming, consumed, map, map_opt, opt, recognize, verify};
 use nom::multi::many0;
 use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
 
+use crate::behaviour::Intl;
+use crate::rfc2045::{equals, parameters, token, value};
 use crate::rfc3461::hexpair;
-use crate::rfc5234::crlf;
-use crate::rfc5322::{ofws, quoted_string};
+use crate::rfc5322::{cfws, date_time, ofws, DateTime};
 use crate::util::*;
 
 #[derive(Debug)]
@@ -49,23 +50,20 @@ enum Value<'a> {
 enum ExtendedValue<'a> {
     Initial {
         encoding: Option<&'a [u8]>,
+        #[allow(dead_code)]
         language: Option<&'a [u8]>,
         value: Vec<u8>,
     },
     Other(Vec<u8>),
 }
 
-fn _equals(input: &[u8]) -> NomResult<()> {
-    map(tuple((ofws, tag("="), ofws)), |_| ())(input)
-}
-
 fn parameter(input: &[u8]) -> NomResult<Parameter> {
     alt((regular_parameter, extended_parameter))(input)
 }
 
 fn regular_parameter(input: &[u8]) -> NomResult<Parameter> {
     map(
-        separated_pair(regular_parameter_name, _equals, value),
+        separated_pair(regular_parameter_name, equals, value),
         |(name, value)| Parameter {
             name,
             value: Value::Regular(value),
@@ -80,13 +78,6 @@ fn regular_parameter_name(input: &[u8]) -> NomResult<Name> {
     })(input)
 }
 
-fn token(input: &[u8]) -> NomResult<&str> {
-    map(
-        take_while1(|c| (33..=126).contains(&c) && !b"()<>@,;:\\\"/[]?=".contains(&c)),
-        |t| std::str::from_utf8(t).unwrap(),
-    )(input)
-}
-
 fn is_attribute_char(c: u8) -> bool {
     (33..=126).contains(&c) && !b"*'%()<>@,;:\\\"/[]?=".contains(&c)
 }
@@ -120,14 +111,14 @@ fn other_sections(input: &[u8]) -> NomResult<u32> {
 fn extended_parameter(input: &[u8]) -> NomResult<Parameter> {
     alt((
         map(
-            separated_pair(extended_initial_name, _equals, extended_initial_value),
+            separated_pair(extended_initial_name, equals, extended_initial_value),
             |(name, value)| Parameter {
                 name,
                 value: Value::Extended(value),
             },
         ),
         map(
-            separated_pair(extended_other_names, _equals, extended_other_values),
+            separated_pair(extended_other_names, equals, extended_other_values),
             |(name, value)| Parameter {
                 name,
                 value: Value::Extended(ExtendedValue::Other(value)),
@@ -179,24 +170,12 @@ fn extended_other_values(input: &[u8]) -> NomResult<Vec<u8>> {
     many0(alt((ext_octet, attribute_char)))(input)
 }
 
-fn value(input: &[u8]) -> NomResult<Cow<str>> {
-    alt((
-        map(token, Cow::from),
-        map(quoted_string::<crate::behaviour::Intl>, |qs| {
-            Cow::from(qs.0)
-        }),
-    ))(input)
-}
-
 fn _mime_type(input: &[u8]) -> NomResult<&[u8]> {
     recognize(tuple((token, tag("/"), token)))(input)
 }
 
 fn _parameter_list(input: &[u8]) -> NomResult<Vec<Parameter>> {
-    terminated(
-        many0(preceded(pair(tag(";"), ofws), parameter)),
-        pair(opt(tag(";")), opt(crlf)),
-    )(input)
+    parameters(parameter)(input)
 }
 
 #[derive(Debug)]
@@ -206,7 +185,7 @@ enum Segment<'a> {
 }
 
 fn decode_segments(mut input: Vec<(u32, Segment)>, encoding: EncodingRef) -> String {
-    input.sort_by(|a, b| a.0.cmp(&b.0));
+    input.sort_by_key(|a| a.0);
     let mut out = String::new();
     let mut encoded = Vec::new();
 
@@ -230,20 +209,69 @@ fn decode_segments(mut input: Vec<(u32, Segment)>, encoding: EncodingRef) -> Str
     out
 }
 
-fn decode_parameter_list(input: Vec<Parameter>) -> Vec<(String, String)> {
-    let mut simple = HashMap::<String, String>::new();
-    let mut simple_encoded = HashMap::<String, String>::new();
+/// How to resolve a parameter that is given more than once.
+///
+/// A parameter given both as a regular value and as an [RFC 2231]
+/// extended or continued value, such as `filename` and `filename*`,
+/// also counts as a duplicate.
+///
+/// [RFC 2231]: https://tools.ietf.org/html/rfc2231
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Keep the first value.
+    FirstWins,
+    /// Keep the last value.
+    LastWins,
+    /// Keep the continued value if there is one, then the last
+    /// extended value, otherwise the last value. This is the behaviour
+    /// of [`content_type`] and [`content_disposition`].
+    PreferExtended,
+    /// Fail to parse.
+    Error,
+}
+
+/// A parameter that was given more than once.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParameterConflict {
+    /// The lowercase parameter name.
+    pub name: String,
+    /// The decoded values in header order.
+    pub values: Vec<String>,
+    /// Index of the value that was kept.
+    pub chosen: usize,
+}
+
+struct Candidate {
+    // 0 for regular values, 1 for extended values, 2 for continued values.
+    rank: u8,
+    // `None` for a continued value decoded once all sections are seen.
+    value: Option<String>,
+}
+
+type DecodedParameters = (Vec<(String, String)>, Vec<ParameterConflict>);
+
+fn decode_parameter_list(
+    input: Vec<Parameter>,
+    policy: DuplicatePolicy,
+) -> Option<DecodedParameters> {
+    let mut names = Vec::<String>::new();
+    let mut candidates = HashMap::<String, Vec<Candidate>>::new();
     let mut composite = HashMap::<String, Vec<(u32, Segment)>>::new();
     let mut composite_encoding = HashMap::new();
 
     for Parameter { name, value } in input {
         let name_norm = name.name.to_lowercase();
+        let entry = candidates.entry(name_norm.clone()).or_insert_with(|| {
+            names.push(name_norm.clone());
+            Vec::new()
+        });
 
         match name.section {
             None => match value {
-                Value::Regular(v) => {
-                    simple.insert(name_norm, v.into());
-                }
+                Value::Regular(v) => entry.push(Candidate {
+                    rank: 0,
+                    value: Some(v.into()),
+                }),
                 Value::Extended(ExtendedValue::Initial {
                     value,
                     encoding: encoding_name,
@@ -251,20 +279,25 @@ fn decode_parameter_list(input: Vec<Parameter>) -> Vec<(String, String)> {
                 }) => {
                     let codec = match encoding_name {
                         Some(encoding_name) => {
-                            encoding_from_whatwg_label(&ascii_to_string(encoding_name))
-                                .unwrap_or(ASCII)
+                            encoding_from_label(&ascii_to_string(encoding_name)).unwrap_or(ASCII)
                         }
                         None => ASCII,
                     };
-                    simple_encoded.insert(
-                        name_norm,
-                        codec.decode(&value, DecoderTrap::Replace).unwrap(),
-                    );
+                    entry.push(Candidate {
+                        rank: 1,
+                        value: Some(codec.decode(&value, DecoderTrap::Replace).unwrap()),
+                    });
                 }
                 Value::Extended(ExtendedValue::Other(..)) => unreachable!(),
             },
             Some(section) => {
-                let ent = composite.entry(name_norm.clone()).or_default();
+                let ent = composite.entry(name_norm.clone()).or_insert_with(|| {
+                    entry.push(Candidate {
+                        rank: 2,
+                        value: None,
+                    });
+                    Vec::new()
+                });
 
                 match value {
                     Value::Regular(v) => ent.push((section, Segment::Decoded(v))),
@@ -275,7 +308,7 @@ fn decode_parameter_list(input: Vec<Parameter>) -> Vec<(String, String)> {
                     }) => {
                         if let Some(encoding_name) = encoding_name {
                             if let Some(codec) =
-                                encoding_from_whatwg_label(&ascii_to_string(encoding_name))
+                                encoding_from_label(&ascii_to_string(encoding_name))
                             {
                                 composite_encoding.insert(name_norm, codec);
                             }
@@ -290,35 +323,281 @@ fn decode_parameter_list(input: Vec<Parameter>) -> Vec<(String, String)> {
         }
     }
 
-    let mut composite_out = Vec::new();
-    for (name, segments) in composite {
+    let mut out = Vec::with_capacity(names.len());
+    let mut conflicts = Vec::new();
+
+    for name in names {
+        let mut segments = composite.remove(&name);
         let codec = composite_encoding.get(&name).cloned().unwrap_or(ASCII);
-        composite_out.push((name, decode_segments(segments, codec)));
-    }
+        let found = candidates.remove(&name).unwrap_or_default();
+        let ranks: Vec<u8> = found.iter().map(|c| c.rank).collect();
+        let mut values: Vec<String> = found
+            .into_iter()
+            .map(|c| {
+                c.value
+                    .unwrap_or_else(|| decode_segments(segments.take().unwrap_or_default(), codec))
+            })
+            .collect();
+
+        let chosen = match policy {
+            _ if values.len() == 1 => 0,
+            DuplicatePolicy::FirstWins => 0,
+            DuplicatePolicy::LastWins => values.len() - 1,
+            DuplicatePolicy::PreferExtended => {
+                (0..values.len()).max_by_key(|i| (ranks[*i], *i)).unwrap()
+            }
+            DuplicatePolicy::Error => return None,
+        };
 
-    for (name, value) in simple_encoded.into_iter().chain(composite_out.into_iter()) {
-        simple.insert(name, value);
+        if values.len() > 1 {
+            out.push((name.clone(), values[chosen].clone()));
+            conflicts.push(ParameterConflict {
+                name,
+                values,
+                chosen,
+            });
+        } else {
+            out.push((name, values.swap_remove(chosen)));
+        }
     }
 
-    simple.into_iter().collect()
+    Some((out, conflicts))
 }
 
 /// Parse a MIME `"Content-Type"` header.
 ///
-/// Returns a tuple of the MIME type and parameters.
+/// Returns a tuple of the MIME type and parameters. Duplicate
+/// parameters are resolved with [`DuplicatePolicy::PreferExtended`].
 pub fn content_type(input: &[u8]) -> NomResult<(String, Vec<(String, String)>)> {
     map(
-        pair(delimited(ofws, _mime_type, ofws), _parameter_list),
-        |(mt, p)| (ascii_to_string(mt).to_lowercase(), decode_parameter_list(p)),
+        content_type_with_policy(DuplicatePolicy::PreferExtended),
+        |(mt, params, _)| (mt, params),
     )(input)
 }
 
+/// Parse a MIME `"Content-Type"` header, resolving duplicate
+/// parameters according to `policy`.
+///
+/// Returns a tuple of the MIME type, parameters and the conflicts
+/// that were resolved.
+/// # Examples
+/// ```
+/// use rustyknife::rfc2231::{content_type_with_policy, DuplicatePolicy};
+///
+/// let input = b"text/plain; name=a.txt; name*=utf-8''b.exe";
+///
+/// let (_, (_, params, conflicts)) = content_type_with_policy(DuplicatePolicy::FirstWins)(input).unwrap();
+/// assert_eq!(params, [("name".to_string(), "a.txt".to_string())]);
+/// assert_eq!(conflicts[0].values, ["a.txt", "b.exe"]);
+///
+/// assert!(content_type_with_policy(DuplicatePolicy::Error)(input).is_err());
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn content_type_with_policy(
+    policy: DuplicatePolicy,
+) -> impl Fn(&[u8]) -> NomResult<(String, Vec<(String, String)>, Vec<ParameterConflict>)> {
+    move |input| {
+        traced(
+            "content_type",
+            map_opt(
+                pair(delimited(ofws, _mime_type, ofws), _parameter_list),
+                |(mt, p)| {
+                    decode_parameter_list(p, policy).map(|(params, conflicts)| {
+                        (ascii_to_string(mt).to_lowercase(), params, conflicts)
+                    })
+                },
+            ),
+        )(input)
+    }
+}
+
+/// Parse a MIME `"Content-Type"` header, also returning the raw
+/// parameters.
+///
+/// Returns a tuple of the MIME type, the decoded parameters as
+/// returned by [`content_type`] and the exact bytes of every
+/// parameter in their original order, without the separating `";"`.
+/// Comparing both helps detecting parameters hidden by
+/// continuations, encodings or duplicates.
+/// # Examples
+/// ```
+/// use rustyknife::rfc2231::content_type_raw;
+///
+/// let input = b"text/plain; NAME*0=\"inv\"; name*1*=oice%2Eexe; name=invoice.txt";
+/// let (_, (_, params, raw)) = content_type_raw(input).unwrap();
+///
+/// assert_eq!(params, [("name".to_string(), "invoice.exe".to_string())]);
+/// assert_eq!(raw, [b"NAME*0=\"inv\"".as_ref(), b"name*1*=oice%2Eexe", b"name=invoice.txt"]);
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn content_type_raw(input: &[u8]) -> NomResult<(String, Vec<(String, String)>, Vec<&[u8]>)> {
+    traced(
+        "content_type",
+        map_opt(
+            pair(
+                delimited(ofws, _mime_type, ofws),
+                parameters(consumed(parameter)),
+            ),
+            |(mt, p)| {
+                let (raw, p): (Vec<_>, Vec<_>) = p.into_iter().unzip();
+                decode_parameter_list(p, DuplicatePolicy::PreferExtended)
+                    .map(|(params, _)| (ascii_to_string(mt).to_lowercase(), params, raw))
+            },
+        ),
+    )(input)
+}
+
+/// A MIME type and its parameters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MimeType {
+    /// The lowercase `"type/subtype"`.
+    pub mime_type: String,
+    /// The decoded parameters with lowercase names.
+    pub params: Vec<(String, String)>,
+}
+
+impl MimeType {
+    /// Return the value of the parameter `name`.
+    ///
+    /// `name` must be lowercase.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Return the encoding named by the `"charset"` parameter.
+    ///
+    /// The label is resolved with
+    /// [`encoding_from_label`](crate::charset::encoding_from_label).
+    /// # Examples
+    /// ```
+    /// use rustyknife::rfc2231::{content_type, CharsetError, MimeType};
+    ///
+    /// let (_, parsed) = content_type(b"text/plain; charset*=''CP932").unwrap();
+    /// let mt = MimeType::from(parsed);
+    /// assert_eq!(mt.charset().unwrap().whatwg_name(), Some("shift_jis"));
+    ///
+    /// let (_, parsed) = content_type(b"text/plain; charset=x-bogus").unwrap();
+    /// let mt = MimeType::from(parsed);
+    /// assert_eq!(mt.charset().err(), Some(CharsetError::Unknown("x-bogus".into())));
+    /// ```
+    pub fn charset(&self) -> Result<EncodingRef, CharsetError> {
+        let label = self.param("charset").ok_or(CharsetError::Missing)?;
+
+        encoding_from_label(label).ok_or_else(|| CharsetError::Unknown(label.into()))
+    }
+}
+
+/// Error returned by [`MimeType::charset`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CharsetError {
+    /// There is no `"charset"` parameter.
+    Missing,
+    /// The charset label is not supported.
+    Unknown(String),
+}
+
+impl Display for CharsetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CharsetError::Missing => write!(f, "missing charset parameter"),
+            CharsetError::Unknown(label) => write!(f, "unknown charset {:?}", label),
+        }
+    }
+}
+
+impl std::error::Error for CharsetError {}
+
+impl From<(String, Vec<(String, String)>)> for MimeType {
+    fn from((mime_type, params): (String, Vec<(String, String)>)) -> Self {
+        MimeType { mime_type, params }
+    }
+}
+
+/// Where a `"Content-Type"` header is found, which affects its default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentTypeContext {
+    /// A message or a body part of any multipart other than
+    /// `"multipart/digest"`.
+    Default,
+    /// A body part of a `"multipart/digest"`.
+    Digest,
+}
+
+/// Return the effective MIME type given an optional raw
+/// `"Content-Type"` header value.
+///
+/// Implements the defaults from [RFC 2045] and [RFC 2046]:
+/// - An absent header means `"text/plain; charset=us-ascii"`, or
+///   `"message/rfc822"` within a digest.
+/// - A header that cannot be parsed entirely means `"text/plain;
+///   charset=us-ascii"`, or `"application/octet-stream"` within a
+///   digest.
+/// - A `"text/plain"` type without a charset parameter gets
+///   `"charset=us-ascii"`.
+///
+/// # Examples
+/// ```
+/// use rustyknife::rfc2231::{effective_content_type, ContentTypeContext};
+///
+/// let mt = effective_content_type(None, ContentTypeContext::Default);
+/// assert_eq!(mt.mime_type, "text/plain");
+/// assert_eq!(mt.param("charset"), Some("us-ascii"));
+///
+/// let mt = effective_content_type(Some(b"garbage"), ContentTypeContext::Digest);
+/// assert_eq!(mt.mime_type, "application/octet-stream");
+///
+/// let mt = effective_content_type(Some(b" text/html; charset=utf-8 (comment)"), ContentTypeContext::Default);
+/// assert_eq!(mt.mime_type, "text/html");
+///
+/// let mt = effective_content_type(Some(b" text/html; charset=utf-8 garbage"), ContentTypeContext::Default);
+/// assert_eq!(mt.mime_type, "text/plain");
+/// ```
+///
+/// [RFC 2045]: https://tools.ietf.org/html/rfc2045#section-5.2
+/// [RFC 2046]: https://tools.ietf.org/html/rfc2046#section-5.1.5
+pub fn effective_content_type(raw: Option<&[u8]>, context: ContentTypeContext) -> MimeType {
+    let default_text = || MimeType {
+        mime_type: "text/plain".into(),
+        params: vec![("charset".into(), "us-ascii".into())],
+    };
+    let simple = |mime_type: &str| MimeType {
+        mime_type: mime_type.into(),
+        params: Vec::new(),
+    };
+
+    // Comments after the last parameter are allowed.
+    let parsed = raw.map(|raw| {
+        parse_exact(
+            terminated(content_type, opt(cfws::<Intl>)),
+            raw,
+            Trailing::Whitespace,
+        )
+    });
+
+    match (parsed, context) {
+        (None, ContentTypeContext::Default) => default_text(),
+        (None, ContentTypeContext::Digest) => simple("message/rfc822"),
+        (Some(Err(_)), ContentTypeContext::Default) => default_text(),
+        (Some(Err(_)), ContentTypeContext::Digest) => simple("application/octet-stream"),
+        (Some(Ok(parsed)), _) => {
+            let mut out = MimeType::from(parsed);
+            if out.mime_type == "text/plain" && out.param("charset").is_none() {
+                out.params.push(("charset".into(), "us-ascii".into()));
+            }
+            out
+        }
+    }
+}
+
 fn _x_token(input: &[u8]) -> NomResult<&str> {
     preceded(tag_no_case("x-"), token)(input)
 }
 
 /// Value from a MIME `"Content-Disposition"` header.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ContentDisposition {
     /// "inline"
     Inline,
@@ -355,16 +634,133 @@ fn _disposition(input: &[u8]) -> NomResult<ContentDisposition> {
 
 /// Parse a MIME `"Content-Disposition"` header.
 ///
-/// Returns a tuple of [`ContentDisposition`] and parameters.
+/// Returns a tuple of [`ContentDisposition`] and parameters. Duplicate
+/// parameters are resolved with [`DuplicatePolicy::PreferExtended`].
 pub fn content_disposition(input: &[u8]) -> NomResult<(ContentDisposition, Vec<(String, String)>)> {
     map(
-        pair(delimited(ofws, _disposition, ofws), _parameter_list),
-        |(disp, p)| (disp, decode_parameter_list(p)),
+        content_disposition_with_policy(DuplicatePolicy::PreferExtended),
+        |(disp, params, _)| (disp, params),
     )(input)
 }
 
+/// Parse a MIME `"Content-Disposition"` header, resolving duplicate
+/// parameters according to `policy`.
+///
+/// Returns a tuple of [`ContentDisposition`], parameters and the
+/// conflicts that were resolved.
+#[allow(clippy::type_complexity)]
+pub fn content_disposition_with_policy(
+    policy: DuplicatePolicy,
+) -> impl Fn(
+    &[u8],
+) -> NomResult<(
+    ContentDisposition,
+    Vec<(String, String)>,
+    Vec<ParameterConflict>,
+)> {
+    move |input| {
+        traced(
+            "content_disposition",
+            map_opt(
+                pair(delimited(ofws, _disposition, ofws), _parameter_list),
+                |(disp, p)| {
+                    decode_parameter_list(p, policy)
+                        .map(|(params, conflicts)| (disp, params, conflicts))
+                },
+            ),
+        )(input)
+    }
+}
+
+/// A disposition and its parameters with typed accessors for the
+/// parameters of [RFC 2183].
+///
+/// [RFC 2183]: https://tools.ietf.org/html/rfc2183#section-2
+/// # Examples
+/// ```
+/// use rustyknife::rfc2231::{content_disposition, ContentDisposition, Disposition};
+///
+/// let input = b"attachment; filename*=utf-8''r%C3%A9sum%C3%A9.pdf; size=1024;\r\n modification-date=\"Wed, 12 Feb 1997 16:29:51 -0500\"";
+/// let (_, parsed) = content_disposition(input).unwrap();
+/// let disp = Disposition::from(parsed);
+///
+/// assert_eq!(disp.disposition, ContentDisposition::Attachment);
+/// assert_eq!(disp.filename(), Some("résumé.pdf"));
+/// assert_eq!(disp.size(), Some(1024));
+/// assert_eq!(disp.modification_date().unwrap().year, 1997);
+/// assert!(disp.creation_date().is_none());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Disposition {
+    /// The disposition type.
+    pub disposition: ContentDisposition,
+    /// The decoded parameters with lowercase names.
+    pub params: Vec<(String, String)>,
+}
+
+impl Disposition {
+    /// Return the value of the parameter `name`.
+    ///
+    /// `name` must be lowercase.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The suggested file name.
+    pub fn filename(&self) -> Option<&str> {
+        self.param("filename")
+    }
+
+    /// The approximate size in bytes.
+    ///
+    /// `None` if absent or not a number.
+    pub fn size(&self) -> Option<u64> {
+        self.param("size")?.trim().parse().ok()
+    }
+
+    fn date(&self, name: &str) -> Option<DateTime> {
+        let value = self.param(name)?;
+        all_consuming(date_time::<Intl>)(value.as_bytes())
+            .ok()
+            .map(|(_, date)| date)
+    }
+
+    /// The creation date of the file.
+    ///
+    /// `None` if absent or not a valid date-time.
+    pub fn creation_date(&self) -> Option<DateTime> {
+        self.date("creation-date")
+    }
+
+    /// The last modification date of the file.
+    pub fn modification_date(&self) -> Option<DateTime> {
+        self.date("modification-date")
+    }
+
+    /// The last read date of the file.
+    pub fn read_date(&self) -> Option<DateTime> {
+        self.date("read-date")
+    }
+}
+
+impl From<(ContentDisposition, Vec<(String, String)>)> for Disposition {
+    fn from((disposition, params): (ContentDisposition, Vec<(String, String)>)) -> Self {
+        Disposition {
+            disposition,
+            params,
+        }
+    }
+}
+
 /// Value from a MIME `"Content-Transfer-Encoding"` header.
-#[derive(Debug, PartialEq)]
+///
+/// Extension and unknown values are normalized to lowercase since
+/// encoding names are case insensitive. The [`Display`] implementation
+/// gives the canonical lowercase form.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ContentTransferEncoding {
     /// "7bit"
     SevenBit,
@@ -391,8 +787,8 @@ impl Display for ContentTransferEncoding {
             CTE::Binary => write!(f, "binary"),
             CTE::Base64 => write!(f, "base64"),
             CTE::QuotedPrintable => write!(f, "quoted-printable"),
-            CTE::Extended(s) => write!(f, "x-{}", s),
-            CTE::Token(t) => write!(f, "{}", t),
+            CTE::Extended(s) => write!(f, "x-{}", s.to_ascii_lowercase()),
+            CTE::Token(t) => write!(f, "{}", t.to_ascii_lowercase()),
         }
     }
 }
@@ -402,6 +798,17 @@ use self::ContentTransferEncoding as CTE;
 /// Parse a MIME `"Content-Transfer-Encoding"` header.
 ///
 /// Returns a [`ContentTransferEncoding`].
+/// # Examples
+/// ```
+/// use rustyknife::rfc2231::{content_transfer_encoding, ContentTransferEncoding};
+///
+/// let (_, cte) = content_transfer_encoding(b" Quoted-Printable").unwrap();
+/// assert_eq!(cte, ContentTransferEncoding::QuotedPrintable);
+///
+/// let (_, cte) = content_transfer_encoding(b" X-UUEncode").unwrap();
+/// assert_eq!(cte, ContentTransferEncoding::Extended("uuencode".into()));
+/// assert_eq!(cte.to_string(), "x-uuencode");
+/// ```
 pub fn content_transfer_encoding(input: &[u8]) -> NomResult<ContentTransferEncoding> {
     delimited(
         ofws,
@@ -411,8 +818,8 @@ pub fn content_transfer_encoding(input: &[u8]) -> NomResult<ContentTransferEncod
             map(tag_no_case("binary"), |_| CTE::Binary),
             map(tag_no_case("base64"), |_| CTE::Base64),
             map(tag_no_case("quoted-printable"), |_| CTE::QuotedPrintable),
-            map(_x_token, |x| CTE::Extended(x.into())),
-            map(token, |t| CTE::Token(t.into())),
+            map(_x_token, |x| CTE::Extended(x.to_ascii_lowercase())),
+            map(token, |t| CTE::Token(t.to_ascii_lowercase())),
         )),
         ofws,
     )(input)
@@ -0,0 +1,264 @@
+//! [MHTML] resource resolution
+//!
+//! Maps the `cid:` and `"Content-Location"` URIs referenced from the
+//! root of a `"multipart/related"` entity, usually an HTML body, to
+//! the body parts holding the resources.
+//!
+//! [MHTML]: https://tools.ietf.org/html/rfc2557
+
+use crate::mime::{Body, Entity};
+
+// URI headers may be folded anywhere, the white space is not part of
+// the URI.
+fn uri_header(entity: &Entity, name: &[u8]) -> Option<String> {
+    let value: String = String::from_utf8_lossy(entity.header(name)?)
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+
+    (!value.is_empty()).then_some(value)
+}
+
+fn content_id(entity: &Entity) -> Option<String> {
+    let id = uri_header(entity, b"content-id")?;
+    let id = id.strip_prefix('<').unwrap_or(&id);
+    Some(id.strip_suffix('>').unwrap_or(id).into())
+}
+
+fn is_absolute(uri: &str) -> bool {
+    match uri.find(':') {
+        Some(end) => {
+            let mut scheme = uri[..end].chars();
+            matches!(scheme.next(), Some(c) if c.is_ascii_alphabetic())
+                && scheme.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+fn percent_decode(input: &str) -> String {
+    let mut out = Vec::with_capacity(input.len());
+    let mut rest = input.as_bytes();
+
+    while let Some((&c, tail)) = rest.split_first() {
+        let hex = tail.get(..2).and_then(|h| std::str::from_utf8(h).ok());
+        match hex.map(|h| u8::from_str_radix(h, 16)) {
+            Some(Ok(decoded)) if c == b'%' => {
+                out.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                out.push(c);
+                rest = tail;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn remove_dot_segments(path: &str) -> String {
+    let mut out: Vec<&str> = Vec::new();
+    let segments: Vec<_> = path.split('/').collect();
+
+    for (i, segment) in segments.iter().enumerate() {
+        let last = i == segments.len() - 1;
+        match *segment {
+            "." | ".." => {
+                if *segment == ".." && out.len() > 1 {
+                    out.pop();
+                }
+                if last {
+                    out.push("");
+                }
+            }
+            s => out.push(s),
+        }
+    }
+
+    out.join("/")
+}
+
+/// Resolve `uri` against the absolute URI `base` as done by
+/// [RFC 3986].
+///
+/// [RFC 3986]: https://tools.ietf.org/html/rfc3986#section-5.2
+fn resolve_uri(base: &str, uri: &str) -> String {
+    if is_absolute(uri) {
+        return uri.into();
+    }
+    let base = &base[..base.find('#').unwrap_or(base.len())];
+    let scheme_end = base.find(':').map(|i| i + 1).unwrap_or(0);
+    let authority_end = match base[scheme_end..].strip_prefix("//") {
+        Some(rest) => scheme_end + 2 + rest.find(['/', '?'].as_ref()).unwrap_or(rest.len()),
+        None => scheme_end,
+    };
+    let (prefix, path) = base.split_at(authority_end);
+    let path = &path[..path.find('?').unwrap_or(path.len())];
+
+    if uri.starts_with("//") {
+        format!("{}{}", &base[..scheme_end], uri)
+    } else if uri.starts_with('/') {
+        format!("{}{}", prefix, remove_dot_segments(uri))
+    } else if uri.starts_with('?') {
+        format!("{}{}{}", prefix, path, uri)
+    } else if uri.starts_with('#') || uri.is_empty() {
+        format!("{}{}", base, uri)
+    } else {
+        let dir = match path.rfind('/') {
+            Some(i) => &path[..=i],
+            None if authority_end > scheme_end => "/",
+            None => "",
+        };
+        format!(
+            "{}{}",
+            prefix,
+            remove_dot_segments(&format!("{}{}", dir, uri))
+        )
+    }
+}
+
+fn absolute(base: Option<&str>, uri: &str) -> String {
+    match base {
+        Some(base) => resolve_uri(base, uri),
+        None => uri.into(),
+    }
+}
+
+fn root_part<'e, 'a>(related: &'e Entity<'a>) -> Option<&'e Entity<'a>> {
+    let parts = related.parts();
+
+    match related.content_type.param("start") {
+        Some(start) => {
+            let start = start.trim_start_matches('<').trim_end_matches('>');
+            parts
+                .iter()
+                .find(|p| content_id(p).as_deref() == Some(start))
+        }
+        None => parts.first(),
+    }
+}
+
+/// The resources of a `"multipart/related"` entity.
+///
+/// Body parts are indexed by their `"Content-ID"` and
+/// `"Content-Location"`. Relative locations of the body parts are
+/// resolved against the `"Content-Location"` of the multipart entity
+/// itself if it is absolute. Relative URIs referenced from the root
+/// are resolved against the base URI of the root as described in
+/// section 5 of [RFC 2557]: its own `"Content-Location"`, else the
+/// one of the multipart entity. Body parts of nested multipart
+/// entities are included.
+///
+/// [RFC 2557]: https://tools.ietf.org/html/rfc2557#section-5
+/// # Examples
+/// ```
+/// use rustyknife::mime::parse_message;
+/// use rustyknife::rfc2557::Resources;
+///
+/// let input = b"Content-Type: multipart/related; boundary=b\r\n\
+///               Content-Location: http://example.org/page/\r\n\r\n\
+///               --b\r\nContent-Type: text/html\r\n\r\n<img src=\"cid:logo@x\"><img src=\"../a.png\">\r\n\
+///               --b\r\nContent-Type: image/png\r\nContent-ID: <logo@x>\r\n\r\nLOGO\r\n\
+///               --b\r\nContent-Type: image/png\r\nContent-Location: http://example.org/a.png\r\n\r\nA\r\n\
+///               --b--\r\n";
+/// let message = parse_message(input);
+/// let resources = Resources::new(&message).unwrap();
+///
+/// assert!(resources.root().unwrap().is("text/html"));
+/// assert_eq!(resources.resolve("cid:logo@x").unwrap().header(b"content-id"), Some(b" <logo@x>".as_ref()));
+/// assert!(resources.resolve("../a.png").is_some());
+/// assert!(resources.resolve("b.png").is_none());
+///
+/// // The root has its own location.
+/// let input = b"Content-Type: multipart/related; boundary=b\r\n\
+///               Content-Location: http://example.org/\r\n\r\n\
+///               --b\r\nContent-Type: text/html\r\nContent-Location: docs/index.html\r\n\r\n<img src=\"a.png\">\r\n\
+///               --b\r\nContent-Type: image/png\r\nContent-Location: docs/a.png\r\n\r\nA\r\n\
+///               --b--\r\n";
+/// let message = parse_message(input);
+/// let resources = Resources::new(&message).unwrap();
+///
+/// assert_eq!(resources.base(), Some("http://example.org/docs/index.html"));
+/// assert!(resources.resolve("a.png").unwrap().is("image/png"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Resources<'e, 'a> {
+    related: &'e Entity<'a>,
+    base: Option<String>,
+    ids: Vec<(String, &'e Entity<'a>)>,
+    locations: Vec<(String, &'e Entity<'a>)>,
+}
+
+impl<'e, 'a> Resources<'e, 'a> {
+    /// Index the body parts of `related`.
+    ///
+    /// Returns `None` if `related` is not a `"multipart/related"`
+    /// entity.
+    pub fn new(related: &'e Entity<'a>) -> Option<Self> {
+        if !related.is("multipart/related") {
+            return None;
+        }
+        let related_base = uri_header(related, b"content-location").filter(|uri| is_absolute(uri));
+        let root_base = root_part(related)
+            .and_then(|root| uri_header(root, b"content-location"))
+            .map(|uri| absolute(related_base.as_deref(), &uri))
+            .filter(|uri| is_absolute(uri));
+        let mut out = Resources {
+            related,
+            base: root_base.or_else(|| related_base.clone()),
+            ids: Vec::new(),
+            locations: Vec::new(),
+        };
+
+        let mut stack: Vec<_> = related.parts().iter().rev().collect();
+        while let Some(part) = stack.pop() {
+            if let Some(id) = content_id(part) {
+                out.ids.push((id, part));
+            }
+            if let Some(location) = uri_header(part, b"content-location") {
+                let location = absolute(related_base.as_deref(), &location);
+                out.locations.push((location, part));
+            }
+            if let Body::Multipart(parts) = &part.body {
+                stack.extend(parts.iter().rev());
+            }
+        }
+
+        Some(out)
+    }
+
+    /// The base URI used to resolve relative URIs referenced from the
+    /// root.
+    pub fn base(&self) -> Option<&str> {
+        self.base.as_deref()
+    }
+
+    /// The root body part.
+    ///
+    /// This is the part whose `"Content-ID"` matches the `start`
+    /// parameter or the first body part.
+    pub fn root(&self) -> Option<&'e Entity<'a>> {
+        root_part(self.related)
+    }
+
+    /// Find the body part referenced by `uri`.
+    ///
+    /// `cid:` URIs are matched against the `"Content-ID"` of the body
+    /// parts, other URIs are resolved against the base URI and matched
+    /// against their `"Content-Location"`.
+    pub fn resolve(&self, uri: &str) -> Option<&'e Entity<'a>> {
+        let uri = uri.trim();
+
+        if matches!(uri.get(..4), Some(scheme) if scheme.eq_ignore_ascii_case("cid:")) {
+            let id = percent_decode(&uri[4..]);
+            return self.ids.iter().find(|(i, _)| *i == id).map(|(_, e)| *e);
+        }
+
+        let uri = absolute(self.base.as_deref(), uri);
+        self.locations
+            .iter()
+            .find(|(l, _)| *l == uri)
+            .map(|(_, e)| *e)
+    }
+}
@@ -3,6 +3,7 @@
 //! [SMTP DSN]: https://tools.ietf.org/html/rfc3461
 
 use std::borrow::Cow;
+use std::fmt::{self, Display};
 use std::str;
 
 use crate::util::*;
@@ -10,11 +11,12 @@ use crate::util::*;
 use nom::branch::alt;
 use nom::bytes::complete::{tag, tag_no_case, take};
 use nom::character::is_hex_digit;
-use nom::combinator::{map, map_res, verify};
+use nom::combinator::{all_consuming, map, map_opt, map_res, not, verify};
 use nom::multi::many0;
-use nom::sequence::{preceded, separated_pair};
+use nom::sequence::{preceded, separated_pair, terminated};
 
-use crate::rfc5322::atom;
+use crate::behaviour::{Intl, Legacy};
+use crate::rfc5322::{addr_spec, atom};
 
 pub(crate) fn hexpair(input: &[u8]) -> NomResult<u8> {
     map_res(
@@ -41,24 +43,169 @@ fn _printable_xtext(input: &[u8]) -> NomResult<Vec<u8>> {
     })(input)
 }
 
+/// Address type of an ORCPT parameter.
+///
+/// Address types are case insensitive.
+/// # Examples
+/// ```
+/// use rustyknife::rfc3461::AddressType;
+///
+/// assert_eq!(AddressType::from("RFC822"), AddressType::Rfc822);
+/// assert_eq!(AddressType::from("X400"), AddressType::Unknown("x400".into()));
+/// assert_eq!(AddressType::Utf8.to_string(), "utf-8");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AddressType {
+    /// `rfc822`: an Internet mail address.
+    Rfc822,
+    /// `utf-8`: an internationalized mail address from [RFC 6533].
+    ///
+    /// [RFC 6533]: https://tools.ietf.org/html/rfc6533
+    Utf8,
+    /// Any other address type, stored in lowercase.
+    Unknown(String),
+}
+
+impl From<&str> for AddressType {
+    fn from(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "rfc822" => AddressType::Rfc822,
+            "utf-8" => AddressType::Utf8,
+            other => AddressType::Unknown(other.into()),
+        }
+    }
+}
+
+impl Display for AddressType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AddressType::Rfc822 => write!(f, "rfc822"),
+            AddressType::Utf8 => write!(f, "utf-8"),
+            AddressType::Unknown(t) => write!(f, "{}", t),
+        }
+    }
+}
+
+// Replace the `\x{HEXPOINT}` escapes of a utf-8-addr-xtext.
+fn unescape_utf8_addr(input: &str) -> Option<String> {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(pos) = rest.find("\\x{") {
+        out.push_str(&rest[..pos]);
+        let escaped = &rest[pos + 3..];
+        let end = escaped.find('}')?;
+        let point = u32::from_str_radix(escaped.get(..end).filter(|h| h.len() <= 6)?, 16).ok()?;
+        out.push(char::from_u32(point)?);
+        rest = &escaped[end + 1..];
+    }
+    out.push_str(rest);
+
+    Some(out)
+}
+
+fn typed_address(addr_type: AddressType, address: Cow<str>) -> Option<(AddressType, Cow<str>)> {
+    let address = match addr_type {
+        AddressType::Rfc822 => {
+            all_consuming(addr_spec::<Legacy>)(address.as_bytes()).ok()?;
+            address
+        }
+        AddressType::Utf8 => {
+            let unescaped = unescape_utf8_addr(&address)?;
+            all_consuming(addr_spec::<Intl>)(unescaped.as_bytes()).ok()?;
+            Cow::from(unescaped)
+        }
+        AddressType::Unknown(_) => address,
+    };
+
+    Some((addr_type, address))
+}
+
 /// Parse the ESMTP ORCPT parameter that may be present on a RCPT TO command.
 ///
-/// Returns the address type and the decoded original recipient address.
+/// Returns the address type and the decoded original recipient
+/// address. The address is validated for the `rfc822` and `utf-8`
+/// types and escaped characters in `utf-8` addresses are decoded.
 /// # Examples
 /// ```
-/// use rustyknife::rfc3461::orcpt_address;
+/// use rustyknife::rfc3461::{orcpt_address, AddressType};
 ///
 /// let (_, split) = orcpt_address(b"rfc822;bob@example.org").unwrap();
+/// assert_eq!(split, (AddressType::Rfc822, "bob@example.org".into()));
+///
+/// let (_, split) = orcpt_address(b"UTF-8;caf\\x{E9}@example.org").unwrap();
+/// assert_eq!(split, (AddressType::Utf8, "café@example.org".into()));
 ///
-/// assert_eq!(split, ("rfc822".into(), "bob@example.org".into()));
+/// assert!(orcpt_address(b"rfc822;not an address").is_err());
 /// ```
-pub fn orcpt_address(input: &[u8]) -> NomResult<(Cow<str>, Cow<str>)> {
-    map(
-        separated_pair(atom::<crate::behaviour::Legacy>, tag(";"), _printable_xtext),
-        |(a, b)| (ascii_to_string(a), ascii_to_string(b)),
+pub fn orcpt_address(input: &[u8]) -> NomResult<(AddressType, Cow<str>)> {
+    map_opt(
+        separated_pair(atom::<Legacy>, tag(";"), _printable_xtext),
+        |(a, b)| {
+            typed_address(
+                AddressType::from(ascii_to_string(a).as_ref()),
+                ascii_to_string(b),
+            )
+        },
     )(input)
 }
 
+/// Encode `input` as xtext.
+///
+/// Characters outside `"!"` to `"~"` and the `"+"` and `"="`
+/// characters are encoded as `"+"` followed by two uppercase
+/// hexadecimal digits.
+/// # Examples
+/// ```
+/// use rustyknife::rfc3461::encode_xtext;
+///
+/// assert_eq!(encode_xtext(b"a+b=c d"), "a+2Bb+3Dc+20d");
+/// ```
+pub fn encode_xtext(input: &[u8]) -> String {
+    let mut out = String::with_capacity(input.len());
+
+    for c in input {
+        match c {
+            33..=42 | 44..=60 | 62..=126 => out.push(*c as char),
+            _ => out.push_str(&format!("+{:02X}", c)),
+        }
+    }
+
+    out
+}
+
+/// Encode an original recipient as the value of an ORCPT parameter.
+///
+/// The address is xtext encoded. For the `utf-8` type, non ASCII
+/// characters and characters that would need xtext encoding are
+/// escaped as `"\x{HEXPOINT}"` instead, as required by [RFC 6533].
+///
+/// [RFC 6533]: https://tools.ietf.org/html/rfc6533#section-3
+/// # Examples
+/// ```
+/// use rustyknife::rfc3461::{encode_orcpt, orcpt_address, AddressType};
+///
+/// assert_eq!(encode_orcpt(&AddressType::Rfc822, "a+b@example.org"), "rfc822;a+2Bb@example.org");
+///
+/// let encoded = encode_orcpt(&AddressType::Utf8, "café+1@example.org");
+/// assert_eq!(encoded, "utf-8;caf\\x{E9}\\x{2B}1@example.org");
+/// assert_eq!(orcpt_address(encoded.as_bytes()).unwrap().1, (AddressType::Utf8, "café+1@example.org".into()));
+/// ```
+pub fn encode_orcpt(addr_type: &AddressType, address: &str) -> String {
+    let encoded = match addr_type {
+        AddressType::Utf8 => address
+            .chars()
+            .map(|c| match c {
+                '!'..='*' | ','..='<' | '>'..='[' | ']'..='~' => c.to_string(),
+                c => format!("\\x{{{:X}}}", c as u32),
+            })
+            .collect(),
+        _ => encode_xtext(address.as_bytes()),
+    };
+
+    format!("{};{}", addr_type, encoded)
+}
+
 /// The DSN return type desired by the sender.
 #[derive(Debug, PartialEq)]
 pub enum DSNRet {
@@ -149,32 +296,41 @@ pub fn dsn_mail_params<'a>(
     ))
 }
 
+/// DSN notification conditions requested by the sender.
+///
+/// All fields are `false` when `NOTIFY=NEVER` was specified.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Notify {
+    /// Notify on successful delivery.
     pub on_success: bool,
+    /// Notify on delivery failure.
     pub on_failure: bool,
+    /// Notify on delayed delivery.
     pub delay: bool,
 }
 
-fn convert_notify_list(input: Vec<&str>) -> Notify {
-    let mut on_success = false;
-    let mut on_failure = false;
-    let mut delay = false;
+fn convert_notify_list(input: Vec<&str>) -> Option<Notify> {
+    let mut out = Notify {
+        on_success: false,
+        on_failure: false,
+        delay: false,
+    };
 
     for item in input {
-        if item.eq_ignore_ascii_case("success") {
-            on_success = true
+        let flag = if item.eq_ignore_ascii_case("success") {
+            &mut out.on_success
         } else if item.eq_ignore_ascii_case("failure") {
-            on_failure = true
-        } else if item.eq_ignore_ascii_case("delay") {
-            delay = true
+            &mut out.on_failure
+        } else {
+            &mut out.delay
+        };
+        if *flag {
+            return None;
         }
+        *flag = true;
     }
 
-    Notify {
-        on_success,
-        on_failure,
-        delay,
-    }
+    Some(out)
 }
 
 fn notify_item(input: &str) -> Result<(&str, &str), nom::Err<()>> {
@@ -185,16 +341,197 @@ fn notify_item(input: &str) -> Result<(&str, &str), nom::Err<()>> {
     ))(input)
 }
 
+/// Parse the value of the ESMTP NOTIFY parameter that may be present
+/// on a RCPT TO command.
+///
+/// `NEVER` followed by other keywords and keywords given more than
+/// once are rejected.
+/// # Examples
+/// ```
+/// use rustyknife::rfc3461::dsn_notify;
+///
+/// let (_, notify) = dsn_notify("FAILURE,delay").unwrap();
+/// assert!(notify.on_failure && notify.delay && !notify.on_success);
+///
+/// assert!(dsn_notify("NEVER,SUCCESS").is_err());
+/// assert!(dsn_notify("SUCCESS,SUCCESS").is_err());
+/// ```
 pub fn dsn_notify(input: &str) -> Result<(&str, Notify), nom::Err<()>> {
     alt((
-        map(tag_no_case("never"), |_| Notify {
-            on_success: false,
-            on_failure: false,
-            delay: false,
+        map(terminated(tag_no_case("never"), not(tag(","))), |_| {
+            Notify {
+                on_success: false,
+                on_failure: false,
+                delay: false,
+            }
         }),
-        map(
+        map_opt(
             fold_prefix0(notify_item, preceded(tag(","), notify_item)),
             convert_notify_list,
         ),
     ))(input)
 }
+
+/// DSN parameters for the RCPT command.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DSNRcptParams {
+    /// The notification conditions.
+    ///
+    /// `None` if not specified.
+    pub notify: Option<Notify>,
+    /// The original recipient address type and decoded address.
+    ///
+    /// `None` if not specified.
+    pub orcpt: Option<(AddressType, String)>,
+}
+
+/// Error returned by [`dsn_rcpt_params`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DSNRcptError {
+    /// `NOTIFY=NEVER` was combined with another keyword.
+    NeverCombined,
+    /// A NOTIFY keyword was listed more than once.
+    DuplicateKeyword,
+    /// The NOTIFY parameter was given more than once.
+    DuplicateNotify,
+    /// The ORCPT parameter was given more than once.
+    DuplicateOrcpt,
+    /// The NOTIFY value is missing or invalid.
+    InvalidNotify,
+    /// The ORCPT value is missing or invalid.
+    InvalidOrcpt,
+}
+
+impl Display for DSNRcptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            DSNRcptError::NeverCombined => "NOTIFY=NEVER combined with other keywords",
+            DSNRcptError::DuplicateKeyword => "Duplicate NOTIFY keyword",
+            DSNRcptError::DuplicateNotify => "Duplicate NOTIFY",
+            DSNRcptError::DuplicateOrcpt => "Duplicate ORCPT",
+            DSNRcptError::InvalidNotify => "Invalid NOTIFY",
+            DSNRcptError::InvalidOrcpt => "Invalid ORCPT",
+        })
+    }
+}
+
+impl std::error::Error for DSNRcptError {}
+
+// Explain why a NOTIFY value was rejected by `dsn_notify`.
+fn notify_error(value: &str) -> DSNRcptError {
+    let items: Vec<_> = value.split(',').map(str::to_ascii_lowercase).collect();
+
+    if items.len() > 1 && items.iter().any(|i| i == "never") {
+        DSNRcptError::NeverCombined
+    } else if items
+        .iter()
+        .enumerate()
+        .any(|(i, a)| items[..i].contains(a))
+    {
+        DSNRcptError::DuplicateKeyword
+    } else {
+        DSNRcptError::InvalidNotify
+    }
+}
+
+/// Parse a list of ESMTP parameters on a RCPT TO command into a
+/// [`DSNRcptParams`] option block.
+///
+/// Returns the option block and a vector of parameters that were not
+/// consumed.
+/// # Examples
+/// ```
+/// use rustyknife::rfc3461::{dsn_rcpt_params, AddressType, DSNRcptError};
+///
+/// let input = &[("NOTIFY", Some("SUCCESS,FAILURE")),
+///               ("ORCPT", Some("rfc822;bob@example.org")),
+///               ("OTHER", None)];
+///
+/// let (params, other) = dsn_rcpt_params(input).unwrap();
+/// assert!(params.notify.unwrap().on_success);
+/// assert_eq!(params.orcpt, Some((AddressType::Rfc822, "bob@example.org".into())));
+/// assert_eq!(other, [("OTHER", None)]);
+///
+/// let input = &[("NOTIFY", Some("NEVER,DELAY"))];
+/// assert_eq!(dsn_rcpt_params(input), Err(DSNRcptError::NeverCombined));
+/// ```
+pub fn dsn_rcpt_params<'a>(
+    input: &[Param<'a>],
+) -> Result<(DSNRcptParams, Vec<Param<'a>>), DSNRcptError> {
+    let mut out = Vec::new();
+    let mut notify = None;
+    let mut orcpt = None;
+
+    for (name, value) in input {
+        match (name.to_lowercase().as_str(), value) {
+            ("notify", Some(value)) => {
+                if notify.is_some() {
+                    return Err(DSNRcptError::DuplicateNotify);
+                }
+                match all_consuming(dsn_notify)(value) {
+                    Ok((_, parsed)) => notify = Some(parsed),
+                    Err(_) => return Err(notify_error(value)),
+                }
+            }
+            ("orcpt", Some(value)) => {
+                if orcpt.is_some() {
+                    return Err(DSNRcptError::DuplicateOrcpt);
+                }
+                match all_consuming(orcpt_address)(value.as_bytes()) {
+                    Ok((_, (addr_type, address))) => orcpt = Some((addr_type, address.into())),
+                    Err(_) => return Err(DSNRcptError::InvalidOrcpt),
+                }
+            }
+            ("notify", None) => return Err(DSNRcptError::InvalidNotify),
+            ("orcpt", None) => return Err(DSNRcptError::InvalidOrcpt),
+            _ => out.push((*name, *value)),
+        }
+    }
+
+    Ok((DSNRcptParams { notify, orcpt }, out))
+}
+
+fn _decode_xtext(input: &[u8]) -> Option<Vec<u8>> {
+    all_consuming(xtext)(input).ok().map(|(_, decoded)| decoded)
+}
+
+/// Compare two ENVID values by their decoded content.
+///
+/// Relays may re-encode xtext differently, for example by encoding
+/// characters that did not need it. Values that are not valid xtext
+/// are compared as is.
+/// # Examples
+/// ```
+/// use rustyknife::rfc3461::envid_eq;
+///
+/// assert!(envid_eq("QQ314159", "QQ+33+314159"));
+/// assert!(!envid_eq("qq314159", "QQ314159"));
+/// ```
+pub fn envid_eq(a: &str, b: &str) -> bool {
+    match (_decode_xtext(a.as_bytes()), _decode_xtext(b.as_bytes())) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Compare two ORCPT values by their decoded content.
+///
+/// The address types are compared case insensitively and the
+/// addresses are compared after xtext decoding. Values that cannot
+/// be parsed are compared as is.
+/// # Examples
+/// ```
+/// use rustyknife::rfc3461::orcpt_eq;
+///
+/// assert!(orcpt_eq("rfc822;b+6Fb@example.org", "RFC822;bob@example.org"));
+/// assert!(!orcpt_eq("rfc822;bob@example.org", "x400;bob@example.org"));
+/// ```
+pub fn orcpt_eq(a: &str, b: &str) -> bool {
+    match (
+        all_consuming(orcpt_address)(a.as_bytes()),
+        all_consuming(orcpt_address)(b.as_bytes()),
+    ) {
+        (Ok((_, a)), Ok((_, b))) => a == b,
+        _ => a == b,
+    }
+}
@@ -0,0 +1,139 @@
+//! [Delivery status notification] fields
+//!
+//! Splits the body of a `"message/delivery-status"` part, as located
+//! by [`crate::rfc6522::multipart_report`], into its per-message and
+//! per-recipient field groups. The date valued fields are parsed with
+//! [`crate::rfc5322::date_time`].
+//!
+//! [Delivery status notification]: https://tools.ietf.org/html/rfc3464#section-2
+
+use crate::behaviour::Intl;
+use crate::headersection::{header, HeaderField};
+use crate::rfc5322::{date_time, DateTime};
+
+/// A group of delivery status fields.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fields<'a>(pub Vec<HeaderField<'a>>);
+
+impl<'a> Fields<'a> {
+    /// Return the raw value of the first field named `name`.
+    ///
+    /// The name is compared case insensitively.
+    pub fn get(&self, name: &[u8]) -> Option<&'a [u8]> {
+        self.0
+            .iter()
+            .flatten()
+            .find_map(|(n, v)| n.eq_ignore_ascii_case(name).then_some(*v))
+    }
+
+    /// Parse the date valued field named `name`.
+    ///
+    /// Returns `None` if the field is missing or not a valid date.
+    pub fn date(&self, name: &[u8]) -> Option<DateTime> {
+        date_time::<Intl>(self.get(name)?)
+            .ok()
+            .map(|(_, date)| date)
+    }
+}
+
+/// The per-message fields of a delivery status notification.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PerMessage<'a> {
+    /// All the fields of the group.
+    pub fields: Fields<'a>,
+}
+
+impl PerMessage<'_> {
+    /// The `"Arrival-Date:"`, when the reporting MTA received the
+    /// message.
+    pub fn arrival_date(&self) -> Option<DateTime> {
+        self.fields.date(b"arrival-date")
+    }
+}
+
+/// The fields of one recipient of a delivery status notification.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PerRecipient<'a> {
+    /// All the fields of the group.
+    pub fields: Fields<'a>,
+}
+
+impl PerRecipient<'_> {
+    /// The `"Last-Attempt-Date:"`, when the last delivery attempt was
+    /// made.
+    pub fn last_attempt_date(&self) -> Option<DateTime> {
+        self.fields.date(b"last-attempt-date")
+    }
+
+    /// The `"Will-Retry-Until:"`, when the reporting MTA gives up on a
+    /// delayed recipient.
+    pub fn will_retry_until(&self) -> Option<DateTime> {
+        self.fields.date(b"will-retry-until")
+    }
+}
+
+/// The body of a `"message/delivery-status"` part.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeliveryStatus<'a> {
+    /// The per-message fields.
+    pub message: PerMessage<'a>,
+    /// The per-recipient fields in order.
+    pub recipients: Vec<PerRecipient<'a>>,
+}
+
+/// Split the body of a `"message/delivery-status"` part into its field
+/// groups.
+///
+/// Groups are separated by blank lines, extra blank lines are ignored.
+/// The last line may lack its CRLF as in a body part.
+/// # Examples
+/// ```
+/// use rustyknife::rfc3464::delivery_status;
+///
+/// let input = b"Reporting-MTA: dns; mx.example.org\r\n\
+///               Arrival-Date: Mon, 3 Jun 2024 10:00:00 +0200\r\n\r\n\
+///               Final-Recipient: rfc822; a@example.com\r\n\
+///               Action: delayed\r\n\
+///               Last-Attempt-Date: Mon, 3 Jun 2024 14:00:00 +0200\r\n\
+///               Will-Retry-Until: Wed, 5 Jun 2024 10:00:00 +0200";
+/// let status = delivery_status(input);
+///
+/// assert_eq!(status.message.arrival_date().unwrap().hour, 10);
+/// assert_eq!(status.recipients.len(), 1);
+/// assert_eq!(status.recipients[0].fields.get(b"action"), Some(b" delayed".as_ref()));
+/// assert_eq!(status.recipients[0].last_attempt_date().unwrap().hour, 14);
+/// assert_eq!(status.recipients[0].will_retry_until().unwrap().day, 5);
+/// ```
+pub fn delivery_status(input: &[u8]) -> DeliveryStatus {
+    let mut groups = Vec::new();
+    let mut current = Vec::new();
+    let mut rest = input;
+
+    while let Ok((tail, field)) = header(rest) {
+        match field {
+            Some(field) => current.push(field),
+            None if !current.is_empty() => groups.push(Fields(std::mem::take(&mut current))),
+            None => (),
+        }
+        rest = tail;
+    }
+    // The last field may lack its CRLF, the MIME boundary takes it.
+    let last = rest.strip_suffix(b"\r\n").unwrap_or(rest);
+    if !last.is_empty() && !last.windows(2).any(|w| w == b"\r\n") {
+        current.push(match last.iter().position(|c| *c == b':') {
+            Some(colon) => Ok((&last[..colon], &last[colon + 1..])),
+            None => Err(last),
+        });
+    }
+    if !current.is_empty() {
+        groups.push(Fields(current));
+    }
+
+    let mut groups = groups.into_iter();
+    DeliveryStatus {
+        message: PerMessage {
+            fields: groups.next().unwrap_or(Fields(Vec::new())),
+        },
+        recipients: groups.map(|fields| PerRecipient { fields }).collect(),
+    }
+}
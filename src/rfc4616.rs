@@ -1,8 +1,12 @@
+//! [SASL PLAIN] authentication mechanism
+//!
+//! [SASL PLAIN]: https://tools.ietf.org/html/rfc4616
+
 use nom::{bytes::complete::tag_no_case, sequence::delimited};
 
 use crate::{
     rfc5234::crlf,
-    rfc5321::{SMTPString, UTF8Policy, _smtp_string},
+    rfc5321::{_smtp_string, SMTPString, UTF8Policy},
     NomResult,
 };
 
@@ -0,0 +1,164 @@
+//! [SMTP AUTH] extension
+//!
+//! [SMTP AUTH]: https://tools.ietf.org/html/rfc4954
+
+use nom::branch::alt;
+use nom::bytes::complete::{tag, tag_no_case, take_while1, take_while_m_n};
+use nom::combinator::{all_consuming, map, opt};
+use nom::sequence::{delimited, pair, preceded};
+
+use crate::behaviour::Intl;
+use crate::rfc3461::xtext;
+use crate::rfc5234::crlf;
+use crate::rfc5321::{mailbox, Param};
+use crate::types::Mailbox;
+use crate::util::*;
+
+/// The submitter identity from the AUTH parameter of a MAIL command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuthMailbox {
+    /// The message was submitted by this authenticated identity.
+    Mailbox(Mailbox),
+    /// `AUTH=<>`, the submitter is unknown or was not authenticated.
+    Unknown,
+}
+
+/// AUTH parameters for the MAIL command.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthMailParams {
+    /// The submitter identity.
+    ///
+    /// `None` if not specified.
+    pub auth: Option<AuthMailbox>,
+}
+
+type RawParam<'a> = (&'a str, Option<&'a str>);
+
+fn auth_mailbox(value: &str) -> Option<AuthMailbox> {
+    let (_, decoded) = all_consuming(xtext)(value.as_bytes()).ok()?;
+
+    if decoded == b"<>" {
+        return Some(AuthMailbox::Unknown);
+    }
+
+    let (_, mailbox) = all_consuming(mailbox::<Intl>)(&decoded).ok()?;
+    Some(AuthMailbox::Mailbox(mailbox))
+}
+
+/// Parse a list of ESMTP parameters on a MAIL FROM command into a
+/// [`AuthMailParams`] option block.
+///
+/// The value of `AUTH` is xtext decoded and must be either a mailbox
+/// or `<>`. Returns the option block and a vector of parameters that
+/// were not consumed.
+/// # Examples
+/// ```
+/// use rustyknife::rfc4954::{auth_mail_params, AuthMailbox};
+/// let input = &[("AUTH", Some("e+3Dmc2@example.com")),
+///               ("OTHER", None)];
+///
+/// let (params, other) = auth_mail_params(input).unwrap();
+///
+/// match params.auth {
+///     Some(AuthMailbox::Mailbox(mailbox)) => assert_eq!(mailbox.to_string(), "e=mc2@example.com"),
+///     _ => unreachable!(),
+/// }
+/// assert_eq!(other, [("OTHER", None)]);
+///
+/// let (params, _) = auth_mail_params(&[("auth", Some("<>"))]).unwrap();
+/// assert_eq!(params.auth, Some(AuthMailbox::Unknown));
+/// ```
+pub fn auth_mail_params<'a>(
+    input: &[RawParam<'a>],
+) -> Result<(AuthMailParams, Vec<RawParam<'a>>), &'static str> {
+    let mut out = Vec::new();
+    let mut auth_val: Option<AuthMailbox> = None;
+
+    for (name, value) in input {
+        match (name.to_lowercase().as_str(), value) {
+            ("auth", Some(value)) => {
+                if auth_val.is_some() {
+                    return Err("Duplicate AUTH");
+                }
+                match auth_mailbox(value) {
+                    Some(parsed) => auth_val = Some(parsed),
+                    None => return Err("Invalid AUTH"),
+                }
+            }
+            ("auth", None) => return Err("AUTH without value"),
+            _ => out.push((*name, *value)),
+        }
+    }
+
+    Ok((AuthMailParams { auth: auth_val }, out))
+}
+
+/// Return the submitter identity from the parameters of a parsed
+/// MAIL command.
+///
+/// Returns `Ok(None)` if there is no `AUTH` parameter. Fails like
+/// [`auth_mail_params`] if it is repeated or invalid.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc4954::{auth_param, AuthMailbox};
+/// use rustyknife::rfc5321::mail_command;
+///
+/// let (_, (_, params)) = mail_command::<Intl>(b"MAIL FROM:<a@example.org> AUTH=bob@example.org SIZE=10\r\n").unwrap();
+/// match auth_param(&params) {
+///     Ok(Some(AuthMailbox::Mailbox(mailbox))) => assert_eq!(mailbox.to_string(), "bob@example.org"),
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn auth_param(params: &[Param]) -> Result<Option<AuthMailbox>, &'static str> {
+    let params: Vec<_> = params
+        .iter()
+        .map(|Param(keyword, value)| (&**keyword, value.as_deref()))
+        .collect();
+
+    auth_mail_params(&params).map(|(params, _)| params.auth)
+}
+
+fn sasl_mech(input: &[u8]) -> NomResult<String> {
+    map(
+        take_while_m_n(1, 20, |c: u8| {
+            c.is_ascii_alphanumeric() || c == b'-' || c == b'_'
+        }),
+        |m| std::str::from_utf8(m).unwrap().to_ascii_uppercase(),
+    )(input)
+}
+
+fn initial_response(input: &[u8]) -> NomResult<String> {
+    map(
+        alt((
+            tag("="),
+            take_while1(|c: u8| c.is_ascii_alphanumeric() || b"+/=".contains(&c)),
+        )),
+        |r| std::str::from_utf8(r).unwrap().to_string(),
+    )(input)
+}
+
+/// Parse an AUTH command.
+///
+/// Returns the SASL mechanism in uppercase and the optional initial
+/// response. The initial response is still base64 encoded, an empty
+/// one is sent as `"="`.
+/// # Examples
+/// ```
+/// use rustyknife::rfc4954::auth_command;
+///
+/// let (_, (mech, response)) = auth_command(b"AUTH plain AGJvYgBzZWNyZXQ=\r\n").unwrap();
+/// assert_eq!(mech, "PLAIN");
+/// assert_eq!(response.as_deref(), Some("AGJvYgBzZWNyZXQ="));
+///
+/// let (_, (mech, response)) = auth_command(b"AUTH CRAM-MD5\r\n").unwrap();
+/// assert_eq!(mech, "CRAM-MD5");
+/// assert_eq!(response, None);
+/// ```
+pub fn auth_command(input: &[u8]) -> NomResult<(String, Option<String>)> {
+    delimited(
+        tag_no_case("AUTH "),
+        pair(sasl_mech, opt(preceded(tag(" "), initial_response))),
+        crlf,
+    )(input)
+}
@@ -1,25 +1,106 @@
+//! [ABNF] core rules
+//!
+//! These parsers implement the core rules from appendix B.1 and can be
+//! composed with other nom parsers to build mail related grammars.
+//!
+//! # Examples
+//! ```
+//! use rustyknife::nom::multi::many1;
+//! use rustyknife::nom::sequence::terminated;
+//! use rustyknife::rfc5234::{crlf, digit};
+//!
+//! let (rem, digits) = terminated(many1(digit), crlf)(b"250\r\nrest").unwrap();
+//!
+//! assert_eq!(digits, b"250");
+//! assert_eq!(rem, b"rest");
+//! ```
+//!
+//! [ABNF]: https://tools.ietf.org/html/rfc5234#appendix-B.1
+
 use nom::branch::alt;
 use nom::bytes::complete::tag;
 use nom::combinator::map;
+use nom::sequence::pair;
 
 use crate::util::*;
 
-fn sp(input: &[u8]) -> NomResult<&[u8]> {
-    tag(" ")(input)
+/// `ALPHA`: an ASCII letter.
+pub fn alpha(input: &[u8]) -> NomResult<u8> {
+    take1_filter(|c| c.is_ascii_alphabetic())(input)
+}
+
+/// `BIT`: `"0"` or `"1"`.
+pub fn bit(input: &[u8]) -> NomResult<u8> {
+    take1_filter(|c| c == b'0' || c == b'1')(input)
 }
 
-fn htab(input: &[u8]) -> NomResult<&[u8]> {
+/// `CR`: carriage return.
+pub fn cr(input: &[u8]) -> NomResult<&[u8]> {
+    tag("\r")(input)
+}
+
+/// `CRLF`: Internet standard newline.
+pub fn crlf(input: &[u8]) -> NomResult<&[u8]> {
+    tag("\r\n")(input)
+}
+
+/// `CTL`: a control character.
+pub fn ctl(input: &[u8]) -> NomResult<u8> {
+    take1_filter(|c| c < 0x20 || c == 0x7f)(input)
+}
+
+/// `DIGIT`: an ASCII digit from 0 to 9.
+pub fn digit(input: &[u8]) -> NomResult<u8> {
+    take1_filter(|c| c.is_ascii_digit())(input)
+}
+
+/// `DQUOTE`: a double quote.
+pub fn dquote(input: &[u8]) -> NomResult<&[u8]> {
+    tag("\"")(input)
+}
+
+/// `HEXDIG`: a hexadecimal digit.
+///
+/// Lowercase digits are accepted even though the ABNF definition
+/// only lists uppercase ones, since ABNF strings are case
+/// insensitive.
+pub fn hexdig(input: &[u8]) -> NomResult<u8> {
+    take1_filter(|c| c.is_ascii_hexdigit())(input)
+}
+
+/// `HTAB`: horizontal tab.
+pub fn htab(input: &[u8]) -> NomResult<&[u8]> {
     tag("\t")(input)
 }
 
-pub(crate) fn wsp(input: &[u8]) -> NomResult<u8> {
-    map(alt((sp, htab)), |x| x[0])(input)
+/// `LF`: line feed.
+pub fn lf(input: &[u8]) -> NomResult<&[u8]> {
+    tag("\n")(input)
+}
+
+/// `LWSP`: linear white space, possibly spanning many lines.
+///
+/// Always succeeds and returns the recognized white space.
+pub fn lwsp(input: &[u8]) -> NomResult<&[u8]> {
+    recognize_many0(alt((map(wsp, |_| ()), map(pair(crlf, wsp), |_| ()))))(input)
+}
+
+/// `OCTET`: any 8 bit value.
+pub fn octet(input: &[u8]) -> NomResult<u8> {
+    take1_filter(|_| true)(input)
+}
+
+/// `SP`: a space.
+pub fn sp(input: &[u8]) -> NomResult<&[u8]> {
+    tag(" ")(input)
 }
 
+/// `VCHAR`: a visible (printing) character.
 pub fn vchar(input: &[u8]) -> NomResult<char> {
     map(take1_filter(|c| (0x21..=0x7e).contains(&c)), char::from)(input)
 }
 
-pub fn crlf(input: &[u8]) -> NomResult<&[u8]> {
-    tag("\r\n")(input)
+/// `WSP`: a space or horizontal tab.
+pub fn wsp(input: &[u8]) -> NomResult<u8> {
+    map(alt((sp, htab)), |x| x[0])(input)
 }
@@ -4,6 +4,7 @@
 
 use std::convert::TryFrom;
 use std::fmt::{self, Display};
+use std::marker::PhantomData;
 use std::net::{Ipv4Addr, Ipv6Addr};
 use std::str::{self, FromStr};
 
@@ -11,25 +12,35 @@ use std::str::{self, FromStr};
 use serde::{Deserialize, Serialize};
 
 use nom::branch::alt;
-use nom::bytes::complete::{tag, tag_no_case, take_while1, take_while_m_n};
+use nom::bytes::complete::{tag, tag_no_case, take_while, take_while1, take_while_m_n};
 use nom::character::{is_alphanumeric, is_digit, is_hex_digit};
 use nom::combinator::{all_consuming, map, map_res, opt, recognize, verify};
 use nom::error::ParseError;
-use nom::multi::{many0, many1, many_m_n};
+use nom::multi::{fold_many_m_n, many0, many1, many_m_n};
 use nom::sequence::{delimited, pair, preceded, separated_pair, terminated};
 
-use crate::behaviour::{Intl, Legacy};
+use crate::behaviour::{EightBitPolicy, Intl, Legacy, LegacyWith};
 use crate::rfc5234::{crlf, wsp};
 use crate::rfc5322::utf8_non_ascii;
 use crate::types::*;
 use crate::util::*;
 
+pub mod reply;
+pub mod session;
+
 #[allow(missing_docs)] // Mostly internal
 pub trait UTF8Policy {
     fn atext(input: &[u8]) -> NomResult<char>;
     fn qtext_smtp(input: &[u8]) -> NomResult<char>;
     fn esmtp_value_char(input: &[u8]) -> NomResult<char>;
     fn sub_domain(input: &[u8]) -> NomResult<&[u8]>;
+
+    /// Parse the content of an address literal, without the brackets.
+    ///
+    /// Rejects every [`LiteralDeviation`] by default.
+    fn address_literal(input: &[u8]) -> NomResult<AddressLiteral> {
+        _inner_address_literal(input)
+    }
 }
 
 impl UTF8Policy for Legacy {
@@ -87,6 +98,70 @@ impl UTF8Policy for Intl {
     }
 }
 
+// SMTP has no replacement of octets above 127, they are rejected as
+// with Legacy.
+impl<E: EightBitPolicy> UTF8Policy for LegacyWith<E> {
+    fn atext(input: &[u8]) -> NomResult<char> {
+        Legacy::atext(input)
+    }
+
+    fn qtext_smtp(input: &[u8]) -> NomResult<char> {
+        Legacy::qtext_smtp(input)
+    }
+
+    fn esmtp_value_char(input: &[u8]) -> NomResult<char> {
+        Legacy::esmtp_value_char(input)
+    }
+
+    fn sub_domain(input: &[u8]) -> NomResult<&[u8]> {
+        Legacy::sub_domain(input)
+    }
+}
+
+/// Accepts the address literals with a [`LiteralDeviation`], otherwise
+/// the same as `P`.
+///
+/// Select it as the policy of [`mailbox`], [`mail_command`],
+/// [`rcpt_command`] and the other SMTP parsers to accept such
+/// literals from broken clients. Use [`address_literal_lenient`] to
+/// find out which deviations a literal has.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{mail_command, mailbox, LenientLiterals};
+///
+/// assert!(mailbox::<Intl>(b"bob@[192.0.2.010]").is_err());
+///
+/// let (_, mbox) = mailbox::<LenientLiterals<Intl>>(b"bob@[192.0.2.010]").unwrap();
+/// assert_eq!(mbox.to_string(), "bob@[192.0.2.10]");
+///
+/// let (_, (rp, _)) = mail_command::<LenientLiterals<Intl>>(b"MAIL FROM:<bob@[192.0.2.010]>\r\n").unwrap();
+/// assert_eq!(rp.to_string(), "<bob@[192.0.2.10]>");
+/// ```
+pub struct LenientLiterals<P>(PhantomData<P>);
+
+impl<P: UTF8Policy> UTF8Policy for LenientLiterals<P> {
+    fn atext(input: &[u8]) -> NomResult<char> {
+        P::atext(input)
+    }
+
+    fn qtext_smtp(input: &[u8]) -> NomResult<char> {
+        P::qtext_smtp(input)
+    }
+
+    fn esmtp_value_char(input: &[u8]) -> NomResult<char> {
+        P::esmtp_value_char(input)
+    }
+
+    fn sub_domain(input: &[u8]) -> NomResult<&[u8]> {
+        P::sub_domain(input)
+    }
+
+    fn address_literal(input: &[u8]) -> NomResult<AddressLiteral> {
+        _inner_address_literal_any(input)
+    }
+}
+
 /// ESMTP parameter.
 ///
 /// Represents an ESMTP parameter.
@@ -110,6 +185,7 @@ nom_fromstr!(Param, esmtp_param::<Intl>);
 
 impl Param {
     /// Build a new parameter from string values with syntax checking.
+    #[allow(clippy::result_unit_err)]
     pub fn new<T: AsRef<[u8]>>(keyword: T, value: Option<T>) -> Result<Self, ()> {
         let value = match value {
             Some(v) => Some(Value::try_from(v.as_ref()).map_err(|_| ())?),
@@ -131,6 +207,13 @@ impl Display for Param {
     }
 }
 
+impl Param {
+    /// Serialize as `"KEYWORD=value"` or `"KEYWORD"`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+}
+
 impl From<Param> for String {
     fn from(param: Param) -> String {
         param.to_string()
@@ -168,7 +251,7 @@ impl<'a> Display for Params<'a> {
 /// represents the "BODY" string in a parameter "BODY=8BIT".
 #[derive(Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Keyword(pub(crate) String);
+pub struct Keyword(pub(crate) CompactString);
 string_newtype!(Keyword);
 nom_fromstr!(Keyword, esmtp_keyword);
 
@@ -178,7 +261,7 @@ nom_fromstr!(Keyword, esmtp_keyword);
 /// represents the "8BIT" string in a parameter "BODY=8BIT".
 #[derive(Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Value(pub(crate) String);
+pub struct Value(pub(crate) CompactString);
 string_newtype!(Value);
 nom_fromstr!(Value, esmtp_value::<Intl>);
 
@@ -234,6 +317,16 @@ impl Display for ForwardPath {
     }
 }
 
+impl ForwardPath {
+    /// Serialize in angle brackets as in a RCPT command.
+    ///
+    /// The source route is dropped and the local part is quoted when
+    /// needed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+}
+
 /// Represents a reverse path from the `"MAIL FROM"` command.
 #[derive(Clone, Debug, PartialEq)]
 pub enum ReversePath {
@@ -253,6 +346,109 @@ impl Display for ReversePath {
     }
 }
 
+impl ReversePath {
+    /// Serialize in angle brackets as in a MAIL command.
+    ///
+    /// The source route is dropped and the local part is quoted when
+    /// needed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+}
+
+/// Builder for a MAIL or RCPT command line.
+///
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc3461::AddressType;
+/// use rustyknife::rfc5321::{rcpt_command, ForwardPath, Param, PathCommand};
+///
+/// let (_, (_, params)) = rcpt_command::<Intl>(b"RCPT TO:<bob@example.org> NOTIFY=NEVER\r\n").unwrap();
+///
+/// // Rewrite the recipient and record the original one.
+/// let rewritten: ForwardPath = "<\"bob smith\"@example.net>".parse().unwrap();
+/// let line = PathCommand::rcpt(&rewritten)
+///     .params(params)
+///     .orcpt(&AddressType::Rfc822, "bob@example.org")
+///     .param(Param::new("NOTIFY", Some("FAILURE")).unwrap());
+///
+/// assert_eq!(line.to_bytes(),
+///            b"RCPT TO:<\"bob smith\"@example.net> NOTIFY=FAILURE ORCPT=rfc822;bob@example.org\r\n".to_vec());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathCommand {
+    verb: &'static str,
+    path: String,
+    params: Vec<Param>,
+}
+
+impl PathCommand {
+    /// Start a MAIL command.
+    pub fn mail(path: &ReversePath) -> Self {
+        PathCommand {
+            verb: "MAIL FROM:",
+            path: path.to_string(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Start a RCPT command.
+    pub fn rcpt(path: &ForwardPath) -> Self {
+        PathCommand {
+            verb: "RCPT TO:",
+            path: path.to_string(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Add a parameter.
+    ///
+    /// A parameter with the same keyword, compared case
+    /// insensitively, is replaced in place.
+    pub fn param(mut self, param: Param) -> Self {
+        match self
+            .params
+            .iter_mut()
+            .find(|p| p.0.eq_ignore_ascii_case(&param.0))
+        {
+            Some(existing) => *existing = param,
+            None => self.params.push(param),
+        }
+        self
+    }
+
+    /// Add several parameters with [`param`](PathCommand::param).
+    pub fn params<I: IntoIterator<Item = Param>>(self, params: I) -> Self {
+        params.into_iter().fold(self, PathCommand::param)
+    }
+
+    /// Add an ORCPT parameter encoded with
+    /// [`encode_orcpt`](crate::rfc3461::encode_orcpt).
+    pub fn orcpt(self, addr_type: &crate::rfc3461::AddressType, address: &str) -> Self {
+        let value = crate::rfc3461::encode_orcpt(addr_type, address);
+        self.param(Param(
+            Keyword("ORCPT".into()),
+            Some(Value(value.as_str().into())),
+        ))
+    }
+
+    /// Serialize the command line with its CRLF.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format!("{}\r\n", self).into_bytes()
+    }
+}
+
+impl Display for PathCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.verb, self.path)?;
+        for param in &self.params {
+            write!(f, " {}", param)?;
+        }
+        Ok(())
+    }
+}
+
 impl From<ReversePath> for Option<Mailbox> {
     fn from(path: ReversePath) -> Option<Mailbox> {
         match path {
@@ -352,7 +548,22 @@ fn qcontent_smtp<P: UTF8Policy>(input: &[u8]) -> NomResult<char> {
     alt((P::qtext_smtp, quoted_pair_smtp))(input)
 }
 
-pub(crate) fn quoted_string<P: UTF8Policy>(input: &[u8]) -> NomResult<QuotedString> {
+/// Parse an SMTP `Quoted-string`.
+///
+/// Quoted pairs are unescaped. Unlike the [RFC 5322 variant], white
+/// space and comments around the quotes are not allowed.
+///
+/// [RFC 5322 variant]: crate::rfc5322::quoted_string
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::quoted_string;
+///
+/// let (rem, qs) = quoted_string::<Intl>(b"\"john \\\"jd\\\" doe\" rest").unwrap();
+/// assert_eq!(&*qs, "john \"jd\" doe");
+/// assert_eq!(rem, b" rest");
+/// ```
+pub fn quoted_string<P: UTF8Policy>(input: &[u8]) -> NomResult<QuotedString> {
     map(
         delimited(tag("\""), many0(qcontent_smtp::<P>), tag("\"")),
         |qs| QuotedString(qs.into_iter().collect()),
@@ -366,26 +577,40 @@ pub(crate) fn local_part<P: UTF8Policy>(input: &[u8]) -> NomResult<LocalPart> {
     ))(input)
 }
 
-fn _ip_int(input: &[u8]) -> NomResult<u8> {
-    map_res(take_while_m_n(1, 3, is_digit), |ip| {
-        str::from_utf8(ip).unwrap().parse()
+// Returns the value and whether it was written with a leading zero.
+fn _ip_int(input: &[u8]) -> NomResult<(u8, bool)> {
+    map_res(take_while_m_n(1, 3, is_digit), |ip: &[u8]| {
+        str::from_utf8(ip)
+            .unwrap()
+            .parse()
+            .map(|value| (value, ip.len() > 1 && ip[0] == b'0'))
     })(input)
 }
 
-fn _ipv4_literal(input: &[u8]) -> NomResult<AddressLiteral> {
+fn _ipv4_literal(input: &[u8]) -> NomResult<(AddressLiteral, Vec<LiteralDeviation>)> {
     map(
         pair(_ip_int, many_m_n(3, 3, preceded(tag("."), _ip_int))),
-        |(a, b)| (AddressLiteral::IP(Ipv4Addr::new(a, b[0], b[1], b[2]).into())),
+        |(a, b)| {
+            let deviations = if a.1 || b.iter().any(|i| i.1) {
+                vec![LiteralDeviation::LeadingZero]
+            } else {
+                vec![]
+            };
+            let ip = Ipv4Addr::new(a.0, b[0].0, b[1].0, b[2].0);
+            (AddressLiteral::IP(ip.into()), deviations)
+        },
     )(input)
 }
 
+// The tag is case insensitive like every string literal of the
+// RFC 5234 grammar.
 fn _ipv6_literal(input: &[u8]) -> NomResult<AddressLiteral> {
     map_res(
         preceded(
             tag_no_case("IPv6:"),
             take_while1(|c| is_hex_digit(c) || c == b':' || c == b'.'),
         ),
-        |addr| {
+        |addr: &[u8]| {
             Ipv6Addr::from_str(str::from_utf8(addr).unwrap())
                 .map(|ip| AddressLiteral::IP(ip.into()))
         },
@@ -396,10 +621,11 @@ fn dcontent(input: &[u8]) -> NomResult<u8> {
     take1_filter(|c| matches!(c, 33..=90 | 94..=126))(input)
 }
 
+// The IPv6 tag is reserved for valid IPv6 addresses.
 fn general_address_literal(input: &[u8]) -> NomResult<AddressLiteral> {
     map(
         separated_pair(
-            ldh_str,
+            verify(ldh_str, |tag: &[u8]| !tag.eq_ignore_ascii_case(b"IPv6")),
             tag(":"),
             map(recognize_many1(dcontent), |d| str::from_utf8(d).unwrap()),
         ),
@@ -407,8 +633,61 @@ fn general_address_literal(input: &[u8]) -> NomResult<AddressLiteral> {
     )(input)
 }
 
+fn _inner_address_literal_lenient(
+    input: &[u8],
+) -> NomResult<(AddressLiteral, Vec<LiteralDeviation>)> {
+    alt((
+        _ipv4_literal,
+        map(_ipv6_literal, |lit| (lit, vec![])),
+        map(general_address_literal, |lit| (lit, vec![])),
+    ))(input)
+}
+
 pub(crate) fn _inner_address_literal(input: &[u8]) -> NomResult<AddressLiteral> {
-    alt((_ipv4_literal, _ipv6_literal, general_address_literal))(input)
+    map(
+        verify(_inner_address_literal_lenient, |(_, deviations)| {
+            deviations.is_empty()
+        }),
+        |(lit, _)| lit,
+    )(input)
+}
+
+// The content of an address literal accepted with deviations.
+fn _inner_address_literal_any(input: &[u8]) -> NomResult<AddressLiteral> {
+    map(_inner_address_literal_lenient, |(lit, _)| lit)(input)
+}
+
+/// A deviation from the address literal syntax accepted by
+/// [`address_literal_lenient`] and the [`LenientLiterals`] policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LiteralDeviation {
+    /// An IPv4 component with a leading zero, as in `"[192.0.2.01]"`.
+    ///
+    /// Such components are read as decimal, although some systems
+    /// read them as octal.
+    LeadingZero,
+}
+
+/// Parse an address literal, accepting and reporting the deviations
+/// that are otherwise rejected.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::{address_literal_lenient, LiteralDeviation};
+/// use rustyknife::types::AddressLiteral;
+///
+/// let (_, (lit, deviations)) = address_literal_lenient(b"[192.0.2.010]").unwrap();
+/// assert_eq!(lit, AddressLiteral::IP("192.0.2.10".parse().unwrap()));
+/// assert_eq!(deviations, [LiteralDeviation::LeadingZero]);
+///
+/// let (_, (_, deviations)) = address_literal_lenient(b"[ipv6:2001:db8::1]").unwrap();
+/// assert!(deviations.is_empty());
+///
+/// assert!(AddressLiteral::from_smtp(b"[192.0.2.010]").is_err());
+/// assert!(AddressLiteral::from_smtp(b"[ipv6:2001:db8::1]").is_ok());
+/// assert!(AddressLiteral::from_smtp(b"[IPv6:2001:db8::g]").is_err());
+/// ```
+pub fn address_literal_lenient(input: &[u8]) -> NomResult<(AddressLiteral, Vec<LiteralDeviation>)> {
+    delimited(tag("["), _inner_address_literal_lenient, tag("]"))(input)
 }
 
 pub(crate) fn address_literal(input: &[u8]) -> NomResult<AddressLiteral> {
@@ -418,10 +697,14 @@ pub(crate) fn address_literal(input: &[u8]) -> NomResult<AddressLiteral> {
 pub(crate) fn _domain_part<P: UTF8Policy>(input: &[u8]) -> NomResult<DomainPart> {
     alt((
         map(domain::<P>, DomainPart::Domain),
-        map(address_literal, DomainPart::Address),
+        map(
+            delimited(tag("["), P::address_literal, tag("]")),
+            DomainPart::Address,
+        ),
     ))(input)
 }
 
+/// Parse an email address without angle brackets.
 pub fn mailbox<P: UTF8Policy>(input: &[u8]) -> NomResult<Mailbox> {
     map(
         separated_pair(local_part::<P>, tag("@"), _domain_part::<P>),
@@ -471,20 +754,23 @@ pub fn helo_command<P: UTF8Policy>(input: &[u8]) -> NomResult<Domain> {
 /// assert_eq!(params, [Param::new("BODY", Some("8BIT")).unwrap()]);
 /// ```
 pub fn mail_command<P: UTF8Policy>(input: &[u8]) -> NomResult<(ReversePath, Vec<Param>)> {
-    map(
-        delimited(
-            tag_no_case("MAIL FROM:"),
-            pair(
-                reverse_path::<P>,
-                opt(preceded(tag(" "), _esmtp_params::<P>)),
+    traced(
+        "mail_command",
+        map(
+            delimited(
+                tag_no_case("MAIL FROM:"),
+                pair(
+                    reverse_path::<P>,
+                    opt(preceded(tag(" "), _esmtp_params::<P>)),
+                ),
+                crlf,
             ),
-            crlf,
+            |(addr, params)| (addr, params.unwrap_or_default()),
         ),
-        |(addr, params)| (addr, params.unwrap_or_default()),
     )(input)
 }
 
-fn _forward_path<P: UTF8Policy>(input: &[u8]) -> NomResult<ForwardPath> {
+fn _postmaster_path<P: UTF8Policy>(input: &[u8]) -> NomResult<ForwardPath> {
     alt((
         map(tag_no_case("<postmaster>"), |_| {
             ForwardPath::PostMaster(None)
@@ -493,10 +779,13 @@ fn _forward_path<P: UTF8Policy>(input: &[u8]) -> NomResult<ForwardPath> {
             delimited(tag_no_case("<postmaster@"), domain::<P>, tag(">")),
             |d| ForwardPath::PostMaster(Some(d)),
         ),
-        map(path::<P>, ForwardPath::Path),
     ))(input)
 }
 
+fn _forward_path<P: UTF8Policy>(input: &[u8]) -> NomResult<ForwardPath> {
+    alt((_postmaster_path::<P>, map(path::<P>, ForwardPath::Path)))(input)
+}
+
 /// Parse an SMTP RCPT TO command.
 ///
 /// Returns a tuple with the forward path and ESMTP parameters.
@@ -511,19 +800,329 @@ fn _forward_path<P: UTF8Policy>(input: &[u8]) -> NomResult<ForwardPath> {
 /// assert_eq!(params, [Param::new("NOTIFY", Some("NEVER")).unwrap()]);
 /// ```
 pub fn rcpt_command<P: UTF8Policy>(input: &[u8]) -> NomResult<(ForwardPath, Vec<Param>)> {
-    map(
-        delimited(
-            tag_no_case("RCPT TO:"),
-            pair(
-                _forward_path::<P>,
-                opt(preceded(tag(" "), _esmtp_params::<P>)),
+    traced(
+        "rcpt_command",
+        map(
+            delimited(
+                tag_no_case("RCPT TO:"),
+                pair(
+                    _forward_path::<P>,
+                    opt(preceded(tag(" "), _esmtp_params::<P>)),
+                ),
+                crlf,
             ),
-            crlf,
+            |(path, params)| (path, params.unwrap_or_default()),
         ),
-        |(path, params)| (path, params.unwrap_or_default()),
     )(input)
 }
 
+// Returns `true` if a MAIL or RCPT command has more than `max` ESMTP
+// parameters. Parsing stops at the first parameter over the limit so
+// a long parameter list is not built only to be refused.
+pub(crate) fn exceeds_params<P: UTF8Policy>(input: &[u8], max: usize) -> bool {
+    let path = alt((
+        preceded(tag_no_case("MAIL FROM:"), map(reverse_path::<P>, |_| ())),
+        preceded(tag_no_case("RCPT TO:"), map(_forward_path::<P>, |_| ())),
+    ));
+    let param = preceded(
+        many1(wsp),
+        pair(esmtp_keyword, opt(preceded(tag("="), esmtp_value::<P>))),
+    );
+    let count = fold_many_m_n(0, max.saturating_add(1), param, || 0, |n, _| n + 1);
+
+    matches!(preceded(path, count)(input), Ok((_, n)) if n > max)
+}
+
+/// A non-standard path construct sent by some clients.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PathQuirk {
+    /// `"<@relay.example.org:bob@example.org>"`, an obsolete source
+    /// route.
+    SourceRoute,
+    /// `"<:bob@example.org>"`, a route separator without a route.
+    EmptyRoute,
+    /// `"<undisclosed-recipients:;>"`, an empty group without any
+    /// address.
+    EmptyGroup,
+}
+
+/// How [`mail_command_with_policy`] and [`rcpt_command_with_policy`]
+/// handle a [`PathQuirk`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PathPolicy {
+    /// No path is returned.
+    Reject,
+    /// The path is returned as sent, source route included. An empty
+    /// group is a null reverse path and no forward path.
+    Accept,
+    /// Like [`PathPolicy::Accept`] but the source route is removed.
+    Normalize,
+}
+
+fn group_name(input: &[u8]) -> NomResult<&[u8]> {
+    take_while(|c: u8| (c.is_ascii_graphic() && !b"<>@:;".contains(&c)) || c == b' ')(input)
+}
+
+// Returns the path if any and the quirk that was found.
+fn quirk_path<P: UTF8Policy>(input: &[u8]) -> NomResult<(Option<Path>, Option<PathQuirk>)> {
+    alt((
+        map(delimited(tag("<:"), mailbox::<P>, tag(">")), |m| {
+            (Some(Path(m, Vec::new())), Some(PathQuirk::EmptyRoute))
+        }),
+        map(path::<P>, |p| {
+            let quirk = (!p.1.is_empty()).then_some(PathQuirk::SourceRoute);
+            (Some(p), quirk)
+        }),
+        map(delimited(tag("<"), group_name, tag(":;>")), |_| {
+            (None, Some(PathQuirk::EmptyGroup))
+        }),
+    ))(input)
+}
+
+// Apply `policy` to a path found by quirk_path.
+fn apply_path_policy(
+    path: Option<Path>,
+    quirk: Option<PathQuirk>,
+    policy: PathPolicy,
+) -> Option<Path> {
+    match (quirk, policy) {
+        (None, _) => path,
+        (Some(_), PathPolicy::Reject) => None,
+        (Some(PathQuirk::SourceRoute), PathPolicy::Normalize) => {
+            path.map(|p| Path(p.0, Vec::new()))
+        }
+        (Some(_), _) => path,
+    }
+}
+
+/// Parse an SMTP MAIL FROM command, handling non-standard paths
+/// according to `policy`.
+///
+/// Returns a tuple with the reverse path, the ESMTP parameters and
+/// the quirk found in the path. The reverse path is `None` when the
+/// path was rejected.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{mail_command_with_policy, PathPolicy, PathQuirk, ReversePath};
+///
+/// let input = b"MAIL FROM:<@relay.example.org:bob@example.org>\r\n";
+///
+/// let (_, (rp, _, quirk)) = mail_command_with_policy::<Intl>(PathPolicy::Normalize)(input).unwrap();
+/// assert_eq!(rp.unwrap().to_string(), "<bob@example.org>");
+/// assert_eq!(quirk, Some(PathQuirk::SourceRoute));
+///
+/// let (_, (rp, _, quirk)) = mail_command_with_policy::<Intl>(PathPolicy::Accept)(b"MAIL FROM:<:;>\r\n").unwrap();
+/// assert_eq!(rp, Some(ReversePath::Null));
+/// assert_eq!(quirk, Some(PathQuirk::EmptyGroup));
+///
+/// let (_, (rp, _, _)) = mail_command_with_policy::<Intl>(PathPolicy::Reject)(input).unwrap();
+/// assert_eq!(rp, None);
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn mail_command_with_policy<P: UTF8Policy>(
+    policy: PathPolicy,
+) -> impl Fn(&[u8]) -> NomResult<(Option<ReversePath>, Vec<Param>, Option<PathQuirk>)> {
+    move |input| {
+        traced(
+            "mail_command",
+            map(
+                delimited(
+                    tag_no_case("MAIL FROM:"),
+                    pair(
+                        alt((
+                            map(tag("<>"), |_| (Some(ReversePath::Null), None)),
+                            map(quirk_path::<P>, |(path, quirk)| {
+                                let rp = match (quirk, policy) {
+                                    (Some(PathQuirk::EmptyGroup), PathPolicy::Reject) => None,
+                                    (Some(PathQuirk::EmptyGroup), _) => Some(ReversePath::Null),
+                                    _ => apply_path_policy(path, quirk, policy)
+                                        .map(ReversePath::Path),
+                                };
+                                (rp, quirk)
+                            }),
+                        )),
+                        opt(preceded(tag(" "), _esmtp_params::<P>)),
+                    ),
+                    crlf,
+                ),
+                |((rp, quirk), params)| (rp, params.unwrap_or_default(), quirk),
+            ),
+        )(input)
+    }
+}
+
+/// Parse an SMTP RCPT TO command, handling non-standard paths
+/// according to `policy`.
+///
+/// Returns a tuple with the forward path, the ESMTP parameters and
+/// the quirk found in the path. The forward path is `None` when the
+/// path was rejected or is an empty group.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{rcpt_command_with_policy, PathPolicy, PathQuirk};
+///
+/// let input = b"RCPT TO:<:bob@example.org>\r\n";
+///
+/// let (_, (p, _, quirk)) = rcpt_command_with_policy::<Intl>(PathPolicy::Accept)(input).unwrap();
+/// assert_eq!(p.unwrap().to_string(), "<bob@example.org>");
+/// assert_eq!(quirk, Some(PathQuirk::EmptyRoute));
+///
+/// let input = b"RCPT TO:<undisclosed-recipients:;>\r\n";
+/// let (_, (p, _, quirk)) = rcpt_command_with_policy::<Intl>(PathPolicy::Accept)(input).unwrap();
+/// assert_eq!(p, None);
+/// assert_eq!(quirk, Some(PathQuirk::EmptyGroup));
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn rcpt_command_with_policy<P: UTF8Policy>(
+    policy: PathPolicy,
+) -> impl Fn(&[u8]) -> NomResult<(Option<ForwardPath>, Vec<Param>, Option<PathQuirk>)> {
+    move |input| {
+        traced(
+            "rcpt_command",
+            map(
+                delimited(
+                    tag_no_case("RCPT TO:"),
+                    pair(
+                        alt((
+                            map(_postmaster_path::<P>, |p| (Some(p), None)),
+                            map(quirk_path::<P>, |(path, quirk)| {
+                                let fp =
+                                    apply_path_policy(path, quirk, policy).map(ForwardPath::Path);
+                                (fp, quirk)
+                            }),
+                        )),
+                        opt(preceded(tag(" "), _esmtp_params::<P>)),
+                    ),
+                    crlf,
+                ),
+                |((fp, quirk), params)| (fp, params.unwrap_or_default(), quirk),
+            ),
+        )(input)
+    }
+}
+
+/// A deviation from the command syntax accepted by
+/// [`mail_command_lenient`] and [`rcpt_command_lenient`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CommandDeviation {
+    /// The command verb is not in uppercase, as in `"mail from:"`.
+    ///
+    /// Verbs are case insensitive so this is only reported for
+    /// logging purposes.
+    LowercaseVerb,
+    /// White space before the colon, as in `"MAIL FROM :<>"`.
+    SpaceBeforeColon,
+    /// White space after the colon, as in
+    /// `"MAIL FROM: <user@example.org>"`.
+    SpaceAfterColon,
+    /// No space between the path and the parameters, as in
+    /// `"MAIL FROM:<>SIZE=10"`.
+    MissingParamSpace,
+    /// More than one space between the path and the parameters.
+    ExtraParamSpace,
+    /// White space before the CRLF.
+    TrailingSpace,
+}
+
+fn spaces(input: &[u8]) -> NomResult<usize> {
+    map(recognize_many0(wsp), <[u8]>::len)(input)
+}
+
+fn lenient_command<'a, P, O, F>(
+    verb: &'static str,
+    mut path: F,
+    input: &'a [u8],
+) -> NomResult<'a, (O, Vec<Param>, Vec<CommandDeviation>)>
+where
+    P: UTF8Policy,
+    F: FnMut(&'a [u8]) -> NomResult<'a, O>,
+{
+    let mut deviations = Vec::new();
+    let mut check = |cond: bool, deviation| {
+        if cond {
+            deviations.push(deviation)
+        }
+    };
+
+    let (rem, sent) = tag_no_case(verb)(input)?;
+    check(sent != verb.as_bytes(), CommandDeviation::LowercaseVerb);
+    let (rem, before) = terminated(spaces, tag(":"))(rem)?;
+    check(before > 0, CommandDeviation::SpaceBeforeColon);
+    let (rem, after) = spaces(rem)?;
+    check(after > 0, CommandDeviation::SpaceAfterColon);
+
+    let (rem, path) = path(rem)?;
+    let (rem, (sep, params)) = pair(spaces, opt(_esmtp_params::<P>))(rem)?;
+    let (rem, trailing) = terminated(spaces, crlf)(rem)?;
+    match params {
+        Some(_) => {
+            check(sep == 0, CommandDeviation::MissingParamSpace);
+            check(sep > 1, CommandDeviation::ExtraParamSpace);
+            check(trailing > 0, CommandDeviation::TrailingSpace);
+        }
+        None => check(sep > 0, CommandDeviation::TrailingSpace),
+    }
+
+    Ok((rem, (path, params.unwrap_or_default(), deviations)))
+}
+
+/// Parse an SMTP MAIL FROM command, tolerating common syntax
+/// deviations.
+///
+/// Returns a tuple with the reverse path, the ESMTP parameters and
+/// the [`CommandDeviation`]s that were found.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{mail_command_lenient, CommandDeviation};
+///
+/// let input = b"mail from: <bob@example.org>SIZE=10 \r\n";
+/// let (_, (rp, params, deviations)) = mail_command_lenient::<Intl>(input).unwrap();
+///
+/// assert_eq!(rp.to_string(), "<bob@example.org>");
+/// assert_eq!(params.len(), 1);
+/// assert_eq!(deviations, [CommandDeviation::LowercaseVerb,
+///                         CommandDeviation::SpaceAfterColon,
+///                         CommandDeviation::MissingParamSpace,
+///                         CommandDeviation::TrailingSpace]);
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn mail_command_lenient<P: UTF8Policy>(
+    input: &[u8],
+) -> NomResult<(ReversePath, Vec<Param>, Vec<CommandDeviation>)> {
+    traced("mail_command", |i| {
+        lenient_command::<P, _, _>("MAIL FROM", reverse_path::<P>, i)
+    })(input)
+}
+
+/// Parse an SMTP RCPT TO command, tolerating common syntax
+/// deviations.
+///
+/// Returns a tuple with the forward path, the ESMTP parameters and
+/// the [`CommandDeviation`]s that were found.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{rcpt_command_lenient, CommandDeviation};
+///
+/// let (_, (fp, _, deviations)) = rcpt_command_lenient::<Intl>(b"RCPT TO :<bob@example.org>\r\n").unwrap();
+/// assert_eq!(fp.to_string(), "<bob@example.org>");
+/// assert_eq!(deviations, [CommandDeviation::SpaceBeforeColon]);
+///
+/// let (_, (_, _, deviations)) = rcpt_command_lenient::<Intl>(b"RCPT TO:<bob@example.org>\r\n").unwrap();
+/// assert!(deviations.is_empty());
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn rcpt_command_lenient<P: UTF8Policy>(
+    input: &[u8],
+) -> NomResult<(ForwardPath, Vec<Param>, Vec<CommandDeviation>)> {
+    traced("rcpt_command", |i| {
+        lenient_command::<P, _, _>("RCPT TO", _forward_path::<P>, i)
+    })(input)
+}
+
 /// Parse an SMTP DATA command.
 pub fn data_command(input: &[u8]) -> NomResult<()> {
     map(tag_no_case("DATA\r\n"), |_| ())(input)
@@ -534,6 +1133,21 @@ pub fn rset_command(input: &[u8]) -> NomResult<()> {
     map(tag_no_case("RSET\r\n"), |_| ())(input)
 }
 
+/// Parse an SMTP `String`: an atom or a quoted string.
+///
+/// Used by the arguments of commands such as VRFY and AUTH. Quoted
+/// strings are unescaped.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::_smtp_string;
+///
+/// let (_, atom) = _smtp_string::<Intl>(b"postmaster").unwrap();
+/// assert_eq!(&*atom, "postmaster");
+///
+/// let (_, quoted) = _smtp_string::<Intl>(b"\"John Doe\"").unwrap();
+/// assert_eq!(&*quoted, "John Doe");
+/// ```
 pub fn _smtp_string<P: UTF8Policy>(input: &[u8]) -> NomResult<SMTPString> {
     alt((
         map(atom::<P>, |a| SMTPString(str::from_utf8(a).unwrap().into())),
@@ -577,7 +1191,8 @@ pub fn help_command<P: UTF8Policy>(input: &[u8]) -> NomResult<Option<SMTPString>
 /// The base SMTP command set
 ///
 /// The data on each variant corresponds to the return type of the
-/// *_command functions.
+/// *_command functions. `UNKNOWN` holds a line with an unrecognized
+/// verb, without its CRLF.
 #[derive(Debug)]
 #[allow(missing_docs)]
 pub enum Command {
@@ -592,10 +1207,32 @@ pub enum Command {
     VRFY(SMTPString),
     EXPN(SMTPString),
     HELP(Option<SMTPString>),
+    STARTTLS,
+    AUTH(String, Option<String>),
+    BDAT(u64, bool),
+    UNKNOWN(String),
 }
 
-/// Parse any basic SMTP command.
-pub fn command<P: UTF8Policy>(input: &[u8]) -> NomResult<Command> {
+const VERBS: &[&str] = &[
+    "EHLO", "HELO", "MAIL", "RCPT", "DATA", "RSET", "NOOP", "QUIT", "VRFY", "EXPN", "HELP",
+    "STARTTLS", "AUTH", "BDAT",
+];
+
+// A line whose verb is not one of VERBS.
+fn unknown_command(input: &[u8]) -> NomResult<String> {
+    let verb = verify(take_while1(is_alphanumeric), |v: &[u8]| {
+        !VERBS.iter().any(|k| k.as_bytes().eq_ignore_ascii_case(v))
+    });
+    let line = recognize(pair(verb, take_while(|c| c != b'\r' && c != b'\n')));
+
+    map_res(terminated(line, crlf), |l| {
+        str::from_utf8(l).map(String::from)
+    })(input)
+}
+
+// Every command with a known verb, used where an unknown verb must
+// fail to parse.
+pub(crate) fn known_command<P: UTF8Policy>(input: &[u8]) -> NomResult<Command> {
     alt((
         map(ehlo_command::<P>, Command::EHLO),
         map(helo_command::<P>, Command::HELO),
@@ -608,9 +1245,65 @@ pub fn command<P: UTF8Policy>(input: &[u8]) -> NomResult<Command> {
         map(vrfy_command::<P>, Command::VRFY),
         map(expn_command::<P>, Command::EXPN),
         map(help_command::<P>, Command::HELP),
+        map(starttls_command, |_| Command::STARTTLS),
+        map(crate::rfc4954::auth_command, |(m, r)| Command::AUTH(m, r)),
+        map(bdat_command, |(size, last)| Command::BDAT(size, last)),
     ))(input)
 }
 
+/// Parse any SMTP command.
+///
+/// A line with an unrecognized verb is returned as
+/// [`Command::UNKNOWN`] so that a server can answer it with a 500
+/// reply. A malformed command with a known verb is still an error.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{command, Command};
+///
+/// assert!(matches!(command::<Intl>(b"STARTTLS\r\n").unwrap().1, Command::STARTTLS));
+/// assert!(matches!(command::<Intl>(b"AUTH LOGIN\r\n").unwrap().1, Command::AUTH(ref m, None) if m == "LOGIN"));
+/// assert!(matches!(command::<Intl>(b"XCLIENT ADDR=192.0.2.1\r\n").unwrap().1,
+///                  Command::UNKNOWN(ref l) if l == "XCLIENT ADDR=192.0.2.1"));
+/// assert!(command::<Intl>(b"MAIL FROM:bob\r\n").is_err());
+/// ```
+pub fn command<P: UTF8Policy>(input: &[u8]) -> NomResult<Command> {
+    traced(
+        "command",
+        alt((known_command::<P>, map(unknown_command, Command::UNKNOWN))),
+    )(input)
+}
+
+/// Parse a sequence of commands such as a pipelined burst.
+///
+/// Returns each command with its byte offset in `input`. Parsing
+/// stops at the first invalid or unknown command, which is left in
+/// the remaining input.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{command_sequence, Command};
+///
+/// let input = b"MAIL FROM:<a@example.org>\r\nRCPT TO:<b@example.org>\r\nDATA\r\nSubject: hi\r\n";
+/// let (rem, commands) = command_sequence::<Intl>(input).unwrap();
+///
+/// let offsets: Vec<_> = commands.iter().map(|(offset, _)| *offset).collect();
+/// assert_eq!(offsets, [0, 27, 52]);
+/// assert!(matches!(commands[2].1, Command::DATA));
+/// assert_eq!(rem, b"Subject: hi\r\n");
+/// ```
+pub fn command_sequence<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<(usize, Command)>> {
+    let mut out = Vec::new();
+    let mut rem = input;
+
+    while let Ok((next, cmd)) = known_command::<P>(rem) {
+        out.push((input.len() - rem.len(), cmd));
+        rem = next;
+    }
+
+    Ok((rem, out))
+}
+
 /// Validates an email address.
 ///
 /// Does not accept the empty address.
@@ -627,6 +1320,128 @@ pub fn validate_address<P: UTF8Policy>(i: &[u8]) -> bool {
     all_consuming(mailbox::<P>)(i).is_ok()
 }
 
+/// Reason for rejecting an address in [`validate_address_detailed`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AddressError {
+    /// The address is empty.
+    Empty,
+    /// The local part contains an invalid character or is malformed.
+    InvalidLocalPart,
+    /// There is no `"@"` separating the local part and the domain.
+    MissingAt,
+    /// The domain or address literal is invalid.
+    InvalidDomain,
+}
+
+impl Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AddressError::Empty => write!(f, "empty address"),
+            AddressError::InvalidLocalPart => write!(f, "invalid local part"),
+            AddressError::MissingAt => write!(f, "missing @"),
+            AddressError::InvalidDomain => write!(f, "invalid domain"),
+        }
+    }
+}
+
+/// Validates an email address and reports why it is invalid.
+///
+/// On failure, returns the reason with the byte offset at which the
+/// address stopped being valid.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{validate_address_detailed, AddressError};
+///
+/// assert_eq!(validate_address_detailed::<Intl>(b"bob@example.org"), Ok(()));
+/// assert_eq!(validate_address_detailed::<Intl>(b"b b@example.org"), Err((AddressError::InvalidLocalPart, 1)));
+/// assert_eq!(validate_address_detailed::<Intl>(b"bob"), Err((AddressError::MissingAt, 3)));
+/// assert_eq!(validate_address_detailed::<Intl>(b"bob@exa_mple.org"), Err((AddressError::InvalidDomain, 7)));
+/// ```
+pub fn validate_address_detailed<P: UTF8Policy>(i: &[u8]) -> Result<(), (AddressError, usize)> {
+    let offset = |rem: &[u8]| i.len() - rem.len();
+
+    if i.is_empty() {
+        return Err((AddressError::Empty, 0));
+    }
+    let rem = match local_part_syntax::<P>(i) {
+        Ok((rem, _)) => rem,
+        Err(_) => return Err((AddressError::InvalidLocalPart, 0)),
+    };
+    let rem = match rem.strip_prefix(b"@") {
+        Some(rem) => rem,
+        None if rem.contains(&b'@') => return Err((AddressError::InvalidLocalPart, offset(rem))),
+        None => return Err((AddressError::MissingAt, offset(rem))),
+    };
+    match domain_part_syntax::<P>(rem) {
+        Ok((b"", _)) => Ok(()),
+        Ok((rem, _)) => Err((AddressError::InvalidDomain, offset(rem))),
+        Err(_) => Err((AddressError::InvalidDomain, offset(rem))),
+    }
+}
+
+// Same grammar as local_part and _domain_part, but only recognized so
+// that validation does not build the parsed values.
+fn local_part_syntax<P: UTF8Policy>(input: &[u8]) -> NomResult<&[u8]> {
+    alt((
+        recognize(pair(atom::<P>, recognize_many0(pair(tag("."), atom::<P>)))),
+        recognize(delimited(
+            tag("\""),
+            recognize_many0(qcontent_smtp::<P>),
+            tag("\""),
+        )),
+    ))(input)
+}
+
+fn domain_part_syntax<P: UTF8Policy>(input: &[u8]) -> NomResult<&[u8]> {
+    alt((
+        recognize(pair(
+            P::sub_domain,
+            recognize_many0(pair(tag("."), P::sub_domain)),
+        )),
+        recognize(delimited(tag("["), P::address_literal, tag("]"))),
+    ))(input)
+}
+
+/// Validates many email addresses.
+///
+/// Yields the result of [`validate_address_detailed`] for each
+/// address in order. The addresses are only recognized, no
+/// [`Mailbox`] is built, so checking an ASCII address does not
+/// allocate and there is no per-address setup to amortize. Only the
+/// IDNA check of non-ASCII domain labels with [`Intl`] and free form
+/// address literals allocate.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{validate_addresses, AddressError};
+///
+/// let input = ["bob@example.org", "", "b b@example.org", "alice@[192.0.2.1]"];
+/// let results: Vec<_> = validate_addresses::<Intl, _>(&input).collect();
+///
+/// assert_eq!(
+///     results,
+///     [
+///         Ok(()),
+///         Err((AddressError::Empty, 0)),
+///         Err((AddressError::InvalidLocalPart, 1)),
+///         Ok(())
+///     ]
+/// );
+/// ```
+pub fn validate_addresses<P, I>(
+    addresses: I,
+) -> impl Iterator<Item = Result<(), (AddressError, usize)>>
+where
+    P: UTF8Policy,
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    addresses
+        .into_iter()
+        .map(|address| validate_address_detailed::<P>(address.as_ref()))
+}
+
 /// Parse a STARTTLS command from RFC 3207
 pub fn starttls_command(input: &[u8]) -> NomResult<()> {
     map(tag_no_case("STARTTLS\r\n"), |_| ())(input)
@@ -648,3 +1463,284 @@ fn bdat_chunk_size(input: &[u8]) -> NomResult<u64> {
         std::str::from_utf8(s).unwrap().parse()
     })(input)
 }
+
+/// Reason for refusing a chunk in [`BdatTracker::chunk`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BdatError {
+    /// A chunk was sent after the chunk marked `LAST`.
+    AfterLast,
+    /// The message would exceed the maximum size.
+    TooLarge,
+}
+
+impl Display for BdatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BdatError::AfterLast => write!(f, "BDAT after LAST"),
+            BdatError::TooLarge => write!(f, "message too large"),
+        }
+    }
+}
+
+impl std::error::Error for BdatError {}
+
+/// Size accounting of a message sent with [RFC 3030] BDAT chunks.
+///
+/// [RFC 3030]: https://tools.ietf.org/html/rfc3030
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::{bdat_command, BdatError, BdatTracker};
+///
+/// let mut tracker = BdatTracker::with_max_size(1000);
+///
+/// for command in [&b"BDAT 400\r\n"[..], b"BDAT 100 LAST\r\n"].iter() {
+///     let (_, (size, last)) = bdat_command(command).unwrap();
+///     tracker.chunk(size, last).unwrap();
+/// }
+/// assert!(tracker.is_complete());
+/// assert_eq!((tracker.total(), tracker.chunks()), (500, 2));
+/// assert_eq!(tracker.chunk(0, true), Err(BdatError::AfterLast));
+///
+/// tracker.reset();
+/// assert_eq!(tracker.chunk(1001, false), Err(BdatError::TooLarge));
+/// // The transaction has failed, even a chunk that fits is refused.
+/// assert_eq!(tracker.chunk(10, true), Err(BdatError::TooLarge));
+/// assert!(tracker.is_failed() && !tracker.is_complete());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BdatTracker {
+    max_size: Option<u64>,
+    total: u64,
+    chunks: usize,
+    last: bool,
+    failed: bool,
+}
+
+impl BdatTracker {
+    /// Track a message of any size.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Track a message of at most `max_size` bytes, as advertised
+    /// with the SIZE extension.
+    pub fn with_max_size(max_size: u64) -> Self {
+        BdatTracker {
+            max_size: Some(max_size),
+            ..Default::default()
+        }
+    }
+
+    /// Account for a chunk of `size` bytes.
+    ///
+    /// A refused chunk is not accounted for. The client must still
+    /// send its data, which the server must discard.
+    ///
+    /// Once a chunk is refused as too large the transaction has
+    /// failed: every following chunk is refused with the same error
+    /// until [`reset`](BdatTracker::reset), as required by [RFC 3030].
+    ///
+    /// [RFC 3030]: https://tools.ietf.org/html/rfc3030#section-2
+    pub fn chunk(&mut self, size: u64, last: bool) -> Result<(), BdatError> {
+        if self.last {
+            return Err(BdatError::AfterLast);
+        }
+        let total = self
+            .total
+            .checked_add(size)
+            .filter(|total| !matches!(self.max_size, Some(max) if *total > max))
+            .filter(|_| !self.failed);
+        let total = match total {
+            Some(total) => total,
+            None => {
+                self.failed = true;
+                return Err(BdatError::TooLarge);
+            }
+        };
+
+        self.total = total;
+        self.chunks += 1;
+        self.last = last;
+        Ok(())
+    }
+
+    /// Total size of the accepted chunks.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Number of accepted chunks.
+    pub fn chunks(&self) -> usize {
+        self.chunks
+    }
+
+    /// Returns `true` once the chunk marked `LAST` was accepted.
+    pub fn is_complete(&self) -> bool {
+        self.last
+    }
+
+    /// Returns `true` once a chunk was refused as too large.
+    pub fn is_failed(&self) -> bool {
+        self.failed
+    }
+
+    /// Start a new transaction, as done by RSET or a completed
+    /// message. The maximum size is kept.
+    pub fn reset(&mut self) {
+        *self = BdatTracker {
+            max_size: self.max_size,
+            ..Default::default()
+        };
+    }
+}
+
+/// Transaction limits enforced by [`TransactionLimits`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Limits {
+    /// Maximum number of ESMTP parameters in a MAIL or RCPT command.
+    pub max_params: usize,
+    /// Maximum number of accepted recipients per transaction. RFC 5321
+    /// requires at least 100.
+    pub max_recipients: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_params: 16,
+            max_recipients: 100,
+        }
+    }
+}
+
+/// A limit exceeded in [`TransactionLimits`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LimitExceeded {
+    /// The command has too many ESMTP parameters.
+    TooManyParams,
+    /// The transaction has too many recipients.
+    TooManyRecipients,
+}
+
+impl LimitExceeded {
+    /// The reply code and enhanced status code to send.
+    ///
+    /// Too many recipients is a temporary failure with code 452 as
+    /// required by [RFC 5321], the client sends the remaining
+    /// recipients in another transaction. Some old servers used 552
+    /// instead.
+    ///
+    /// [RFC 5321]: https://tools.ietf.org/html/rfc5321#section-4.5.3.1.10
+    pub fn code(self) -> (u16, reply::EnhancedStatus) {
+        match self {
+            LimitExceeded::TooManyParams => (501, reply::EnhancedStatus::new(5, 5, 4)),
+            LimitExceeded::TooManyRecipients => (452, reply::EnhancedStatus::new(4, 5, 3)),
+        }
+    }
+
+    /// Build the reply to send.
+    pub fn reply(self) -> reply::Reply {
+        let (code, esc) = self.code();
+
+        reply::Reply {
+            code,
+            esc: Some(esc),
+            lines: vec![self.to_string()],
+            bare_lines: Vec::new(),
+        }
+    }
+}
+
+impl Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LimitExceeded::TooManyParams => write!(f, "Too many parameters"),
+            LimitExceeded::TooManyRecipients => write!(f, "Too many recipients"),
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+/// Limit enforcement for one SMTP transaction.
+///
+/// [`SmtpSession`](session::SmtpSession) applies these limits to the
+/// commands it decodes. Use this type directly when calling the
+/// command parsers without a session.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{rcpt_command, LimitExceeded, Limits, TransactionLimits};
+///
+/// let mut limits = TransactionLimits::new(Limits { max_params: 1, max_recipients: 2 });
+///
+/// for (i, rcpt) in ["a", "b", "c"].iter().enumerate() {
+///     let command = format!("RCPT TO:<{}@example.org> NOTIFY=NEVER\r\n", rcpt);
+///     let (_, (_, params)) = rcpt_command::<Intl>(command.as_bytes()).unwrap();
+///
+///     let res = limits.check_params(&params).and_then(|_| limits.add_recipient());
+///     assert_eq!(res.is_ok(), i < 2);
+/// }
+/// assert_eq!(limits.recipients(), 2);
+///
+/// let reply = LimitExceeded::TooManyRecipients.reply();
+/// assert_eq!(reply.to_bytes(), b"452 4.5.3 Too many recipients\r\n");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransactionLimits {
+    limits: Limits,
+    recipients: usize,
+}
+
+impl TransactionLimits {
+    /// Start a transaction with `limits`.
+    pub fn new(limits: Limits) -> Self {
+        TransactionLimits {
+            limits,
+            recipients: 0,
+        }
+    }
+
+    /// Check the number of ESMTP parameters of a MAIL or RCPT command.
+    pub fn check_params(&self, params: &[Param]) -> Result<(), LimitExceeded> {
+        if params.len() > self.limits.max_params {
+            Err(LimitExceeded::TooManyParams)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Account for a recipient about to be accepted.
+    ///
+    /// The recipient is not counted when refused.
+    pub fn add_recipient(&mut self) -> Result<(), LimitExceeded> {
+        if self.recipients >= self.limits.max_recipients {
+            return Err(LimitExceeded::TooManyRecipients);
+        }
+        self.recipients += 1;
+        Ok(())
+    }
+
+    /// Stop counting a recipient added with
+    /// [`add_recipient`](TransactionLimits::add_recipient) that was
+    /// refused for another reason.
+    pub fn remove_recipient(&mut self) {
+        self.recipients = self.recipients.saturating_sub(1);
+    }
+
+    /// The limits of the transaction.
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Number of accepted recipients.
+    pub fn recipients(&self) -> usize {
+        self.recipients
+    }
+
+    /// Start a new transaction, as done by RSET or a completed
+    /// message.
+    pub fn reset(&mut self) {
+        self.recipients = 0;
+    }
+}
@@ -0,0 +1,558 @@
+//! Parsers for [SMTP replies]
+//!
+//! [SMTP replies]: https://tools.ietf.org/html/rfc5321#section-4.2
+
+use std::fmt::{self, Display};
+use std::iter;
+
+use nom::bytes::complete::{tag, take_while, take_while_m_n};
+use nom::character::is_digit;
+use nom::combinator::{map, opt, verify};
+use nom::multi::many0;
+use nom::sequence::{pair, preceded, separated_pair, terminated, tuple};
+
+use crate::rfc5234::{crlf, sp};
+use crate::types::DomainPart;
+use crate::util::*;
+
+/// An [enhanced status code] such as `5.1.1`.
+///
+/// [enhanced status code]: https://tools.ietf.org/html/rfc3463#section-2
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EnhancedStatus {
+    /// The class, 2 for success, 4 for a temporary and 5 for a
+    /// permanent failure.
+    pub class: u8,
+    /// The subject, such as 1 for addressing.
+    pub subject: u16,
+    /// The detail within the subject.
+    pub detail: u16,
+}
+
+impl EnhancedStatus {
+    /// Build a status code from its parts.
+    pub fn new(class: u8, subject: u16, detail: u16) -> Self {
+        EnhancedStatus {
+            class,
+            subject,
+            detail,
+        }
+    }
+}
+
+impl Display for EnhancedStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.class, self.subject, self.detail)
+    }
+}
+
+/// A complete, possibly multi-line server reply.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reply {
+    /// The three digit reply code.
+    pub code: u16,
+    /// The enhanced status code of [RFC 2034], if present.
+    ///
+    /// [RFC 2034]: https://tools.ietf.org/html/rfc2034#section-4
+    pub esc: Option<EnhancedStatus>,
+    /// The text of each line, without the reply code, separator and
+    /// enhanced status code.
+    ///
+    /// Always contains at least one possibly empty line.
+    pub lines: Vec<String>,
+    /// Indices of the lines that do not start with the enhanced status
+    /// code, in increasing order. Empty if every line does.
+    pub bare_lines: Vec<usize>,
+}
+
+impl Reply {
+    /// Serialize the reply with the right separator on each line.
+    ///
+    /// The enhanced status code is written on every line except those
+    /// listed in [`Reply::bare_lines`], so a parsed reply is written
+    /// back as received.
+    /// # Examples
+    /// ```
+    /// use rustyknife::rfc5321::reply::{self, EnhancedStatus, Reply};
+    ///
+    /// let reply = Reply { code: 250, esc: None, lines: vec!["first".into(), "last".into()], bare_lines: vec![] };
+    /// assert_eq!(reply.to_bytes(), b"250-first\r\n250 last\r\n");
+    ///
+    /// let reply = Reply { code: 550, esc: Some(EnhancedStatus::new(5, 7, 1)), lines: vec!["Denied".into()], bare_lines: vec![] };
+    /// assert_eq!(reply.to_bytes(), b"550 5.7.1 Denied\r\n");
+    ///
+    /// let input = b"550-5.7.1 Denied\r\n550 see https://example.org\r\n";
+    /// assert_eq!(reply::reply(input).unwrap().1.to_bytes(), input);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let esc = self.esc.map(|e| e.to_string());
+
+        for (i, line) in self.lines.iter().enumerate() {
+            let separator = if i + 1 == self.lines.len() { ' ' } else { '-' };
+            let line = match &esc {
+                _ if self.bare_lines.binary_search(&i).is_ok() => line.clone(),
+                Some(esc) if line.is_empty() => esc.clone(),
+                Some(esc) => format!("{} {}", esc, line),
+                None => line.clone(),
+            };
+            out.extend_from_slice(format!("{}{}{}\r\n", self.code, separator, line).as_bytes());
+        }
+        if self.lines.is_empty() {
+            out.extend_from_slice(format!("{}\r\n", self.code).as_bytes());
+        }
+
+        out
+    }
+
+    // Room left for the text of a line after the enhanced status code.
+    fn max_text_length(&self, policy: TextPolicy) -> usize {
+        let esc = self.esc.map_or(0, |e| e.to_string().len() + 1);
+
+        policy.max_text_length().saturating_sub(esc)
+    }
+
+    /// Check that the text of every line can be forwarded as is.
+    ///
+    /// The first problem found is returned.
+    pub fn check_text(&self, policy: TextPolicy) -> Result<(), TextError> {
+        for (line, text) in self.lines.iter().enumerate() {
+            if let Some((offset, c)) = text.char_indices().find(|(_, c)| !policy.allows(*c)) {
+                return Err(match c {
+                    '\r' | '\n' => TextError::BareNewline { line, offset },
+                    c if c.is_ascii() => TextError::Control { line, offset },
+                    _ => TextError::NonAscii { line, offset },
+                });
+            }
+            if text.len() > self.max_text_length(policy) {
+                return Err(TextError::TooLong { line });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Return a copy of the reply that passes [`Reply::check_text`].
+    ///
+    /// Newlines and other control characters become spaces, non-ASCII
+    /// characters become `'?'` unless allowed and lines are truncated
+    /// to the maximum length.
+    /// # Examples
+    /// ```
+    /// use rustyknife::rfc5321::reply::{Reply, TextPolicy};
+    ///
+    /// let reply = Reply { code: 550, esc: None, lines: vec!["Bad\r\n250 OK caf\u{e9}".into()], bare_lines: vec![] };
+    /// let policy = TextPolicy::default();
+    ///
+    /// assert!(reply.check_text(policy).is_err());
+    /// let sanitized = reply.sanitize(policy);
+    /// assert_eq!(sanitized.lines, ["Bad  250 OK caf?"]);
+    /// assert!(sanitized.check_text(policy).is_ok());
+    /// ```
+    pub fn sanitize(&self, policy: TextPolicy) -> Reply {
+        let max = self.max_text_length(policy);
+        let lines = self
+            .lines
+            .iter()
+            .map(|text| {
+                let mut out = String::with_capacity(text.len());
+                for c in text.chars() {
+                    let c = if policy.allows(c) {
+                        c
+                    } else if c.is_ascii() {
+                        ' '
+                    } else {
+                        '?'
+                    };
+                    if out.len() + c.len_utf8() > max {
+                        break;
+                    }
+                    out.push(c);
+                }
+                out
+            })
+            .collect();
+
+        Reply {
+            code: self.code,
+            esc: self.esc,
+            lines,
+            bare_lines: self.bare_lines.clone(),
+        }
+    }
+}
+
+/// Maximum length of a reply line including the reply code and the
+/// CRLF, as set by [RFC 5321].
+///
+/// [RFC 5321]: https://tools.ietf.org/html/rfc5321#section-4.5.3.1.5
+pub const MAX_REPLY_LINE: usize = 512;
+
+/// What reply text a proxy may forward to its client.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextPolicy {
+    /// Allow UTF-8 text, only when the client enabled SMTPUTF8.
+    pub smtputf8: bool,
+    /// Maximum length of a line in bytes, reply code and CRLF
+    /// included.
+    pub max_line_length: usize,
+}
+
+impl Default for TextPolicy {
+    fn default() -> Self {
+        TextPolicy {
+            smtputf8: false,
+            max_line_length: MAX_REPLY_LINE,
+        }
+    }
+}
+
+impl TextPolicy {
+    fn allows(self, c: char) -> bool {
+        c == '\t' || (' '..='~').contains(&c) || (self.smtputf8 && !c.is_control() && !c.is_ascii())
+    }
+
+    // Room left for the text after the code, separator and CRLF.
+    fn max_text_length(self) -> usize {
+        self.max_line_length.saturating_sub(6)
+    }
+}
+
+/// A problem found by [`Reply::check_text`].
+///
+/// Offsets are in bytes from the start of the line text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TextError {
+    /// A CR or LF that would end the line early.
+    BareNewline {
+        /// Index of the line.
+        line: usize,
+        /// Offset of the character.
+        offset: usize,
+    },
+    /// Another control character.
+    Control {
+        /// Index of the line.
+        line: usize,
+        /// Offset of the character.
+        offset: usize,
+    },
+    /// A non-ASCII character without SMTPUTF8.
+    NonAscii {
+        /// Index of the line.
+        line: usize,
+        /// Offset of the character.
+        offset: usize,
+    },
+    /// The line is too long.
+    TooLong {
+        /// Index of the line.
+        line: usize,
+    },
+}
+
+impl Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TextError::BareNewline { line, offset } => {
+                write!(f, "bare newline in reply line {} at {}", line, offset)
+            }
+            TextError::Control { line, offset } => {
+                write!(f, "control character in reply line {} at {}", line, offset)
+            }
+            TextError::NonAscii { line, offset } => {
+                write!(
+                    f,
+                    "non-ASCII character in reply line {} at {}",
+                    line, offset
+                )
+            }
+            TextError::TooLong { line } => write!(f, "reply line {} too long", line),
+        }
+    }
+}
+
+impl std::error::Error for TextError {}
+
+fn reply_code(input: &[u8]) -> NomResult<u16> {
+    map(
+        tuple((
+            take1_filter(|c| (b'2'..=b'5').contains(&c)),
+            take1_filter(is_digit),
+            take1_filter(is_digit),
+        )),
+        |(a, b, c)| u16::from(a - b'0') * 100 + u16::from(b - b'0') * 10 + u16::from(c - b'0'),
+    )(input)
+}
+
+fn status_number(input: &[u8]) -> NomResult<u16> {
+    map(take_while_m_n(1, 3, is_digit), |n: &[u8]| {
+        n.iter().fold(0, |acc, d| acc * 10 + u16::from(d - b'0'))
+    })(input)
+}
+
+fn enhanced_status(input: &[u8]) -> NomResult<EnhancedStatus> {
+    map(
+        tuple((
+            take1_filter(|c| matches!(c, b'2' | b'4' | b'5')),
+            preceded(tag("."), status_number),
+            preceded(tag("."), status_number),
+        )),
+        |(class, subject, detail)| EnhancedStatus::new(class - b'0', subject, detail),
+    )(input)
+}
+
+// The enhanced status code at the start of `line` and the remaining
+// text.
+fn split_status(line: &str) -> Option<(EnhancedStatus, &str)> {
+    let (rem, esc) = enhanced_status(line.as_bytes()).ok()?;
+    let text = &line[line.len() - rem.len()..];
+
+    match text.strip_prefix(' ') {
+        Some(text) => Some((esc, text)),
+        None if text.is_empty() => Some((esc, text)),
+        None => None,
+    }
+}
+
+// Non-ASCII text is allowed for SMTPUTF8 and decoded lossily.
+fn textstring(input: &[u8]) -> NomResult<String> {
+    map(
+        take_while(|c| c == b'\t' || (32..=126).contains(&c) || c >= 0x80),
+        |t| String::from_utf8_lossy(t).into_owned(),
+    )(input)
+}
+
+fn continuation_line(input: &[u8]) -> NomResult<(u16, String)> {
+    terminated(separated_pair(reply_code, tag("-"), textstring), crlf)(input)
+}
+
+fn last_line(input: &[u8]) -> NomResult<(u16, String)> {
+    terminated(
+        pair(
+            reply_code,
+            map(opt(preceded(sp, textstring)), Option::unwrap_or_default),
+        ),
+        crlf,
+    )(input)
+}
+
+// Strip the enhanced status code of the first line from the lines
+// starting with it and record the others. The class must match the
+// reply code.
+fn with_status(code: u16, mut lines: Vec<String>) -> Reply {
+    let esc = split_status(&lines[0])
+        .map(|(esc, _)| esc)
+        .filter(|esc| u16::from(esc.class) == code / 100);
+    let mut bare_lines = Vec::new();
+
+    if let Some(esc) = esc {
+        for (i, line) in lines.iter_mut().enumerate() {
+            // An empty line after the code is written back as "2.1.0",
+            // not "2.1.0 ".
+            match split_status(line).filter(|(e, _)| *e == esc) {
+                Some((_, text)) if !(text.is_empty() && line.ends_with(' ')) => *line = text.into(),
+                _ => bare_lines.push(i),
+            }
+        }
+    }
+
+    Reply {
+        code,
+        esc,
+        lines,
+        bare_lines,
+    }
+}
+
+/// Parse a server reply.
+///
+/// All the lines of a multi-line reply must use the same code. An
+/// enhanced status code on the first line whose class matches the
+/// reply code is removed from all the lines.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::reply::reply;
+///
+/// let (_, parsed) = reply(b"250-mx.example.org\r\n250 SIZE 10240000\r\n").unwrap();
+///
+/// assert_eq!(parsed.code, 250);
+/// assert_eq!(parsed.esc, None);
+/// assert_eq!(parsed.lines, ["mx.example.org", "SIZE 10240000"]);
+///
+/// let (_, parsed) = reply(b"550-5.1.1 No such user\r\n550 5.1.1 Try again\r\n").unwrap();
+///
+/// assert_eq!(parsed.esc.unwrap().to_string(), "5.1.1");
+/// assert_eq!(parsed.lines, ["No such user", "Try again"]);
+/// ```
+pub fn reply(input: &[u8]) -> NomResult<Reply> {
+    traced(
+        "reply",
+        map(
+            verify(
+                pair(many0(continuation_line), last_line),
+                |(cont, (code, _))| cont.iter().all(|(c, _)| c == code),
+            ),
+            |(cont, (code, last))| {
+                with_status(
+                    code,
+                    cont.into_iter()
+                        .map(|(_, line)| line)
+                        .chain(iter::once(last))
+                        .collect(),
+                )
+            },
+        ),
+    )(input)
+}
+
+/// The initial reply sent by a server when a client connects.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Greeting {
+    /// `true` for a `220` greeting, `false` for a `554` rejection.
+    pub accepted: bool,
+    /// The host identity announced by the server.
+    ///
+    /// `None` if the first word is not a valid domain or address
+    /// literal.
+    pub host: Option<DomainPart>,
+    /// `true` if the first line mentions `ESMTP`, a conventional hint
+    /// that EHLO is supported.
+    pub esmtp: bool,
+    /// The complete reply.
+    pub reply: Reply,
+}
+
+/// Parse the greeting sent by a server when a client connects.
+///
+/// Only the `220` and `554` codes are accepted.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::reply::greeting;
+///
+/// let (_, parsed) = greeting(b"220 mx.example.org ESMTP Postfix\r\n").unwrap();
+///
+/// assert!(parsed.accepted);
+/// assert_eq!(parsed.host.unwrap().to_string(), "mx.example.org");
+/// assert!(parsed.esmtp);
+/// ```
+pub fn greeting(input: &[u8]) -> NomResult<Greeting> {
+    map(verify(reply, |r| r.code == 220 || r.code == 554), |reply| {
+        let mut words = reply.lines[0].split(' ');
+        let host = words
+            .next()
+            .and_then(|host| DomainPart::from_smtp(host.as_bytes()).ok());
+        let esmtp = words.any(|w| w.eq_ignore_ascii_case("ESMTP"));
+
+        Greeting {
+            accepted: reply.code == 220,
+            host,
+            esmtp,
+            reply,
+        }
+    })(input)
+}
+
+/// A service extension announced in an EHLO response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Extension {
+    /// The keyword in uppercase, such as `"SIZE"`.
+    pub keyword: String,
+    /// The parameters following the keyword.
+    pub params: Vec<String>,
+}
+
+/// The response of a server to EHLO.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EhloResponse {
+    /// The host identity announced by the server.
+    ///
+    /// `None` if the first word is not a valid domain or address
+    /// literal.
+    pub host: Option<DomainPart>,
+    /// The extensions in the order announced.
+    pub extensions: Vec<Extension>,
+    /// The complete reply.
+    pub reply: Reply,
+}
+
+impl EhloResponse {
+    /// Return the extension named `keyword`, compared case
+    /// insensitively.
+    pub fn extension(&self, keyword: &str) -> Option<&Extension> {
+        self.extensions
+            .iter()
+            .find(|e| e.keyword.eq_ignore_ascii_case(keyword))
+    }
+
+    /// Check whether the extension named `keyword` is supported.
+    pub fn supports(&self, keyword: &str) -> bool {
+        self.extension(keyword).is_some()
+    }
+
+    /// The maximum message size of the [SIZE] extension.
+    ///
+    /// Returns `Some(0)` when the server has no fixed limit and `None`
+    /// when the extension is not supported or the size is invalid.
+    ///
+    /// [SIZE]: https://tools.ietf.org/html/rfc1870
+    pub fn size(&self) -> Option<u64> {
+        match self.extension("SIZE")?.params.first() {
+            Some(size) => size.parse().ok(),
+            None => Some(0),
+        }
+    }
+}
+
+fn is_ehlo_keyword(keyword: &str) -> bool {
+    let mut chars = keyword.chars();
+
+    matches!(chars.next(), Some(c) if c.is_ascii_alphanumeric())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Parse the response of a server to EHLO.
+///
+/// Only the `250` code is accepted. Lines that do not start with a
+/// valid keyword are skipped. The obsolete `"AUTH=LOGIN"` form is
+/// kept as its own keyword.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::reply::ehlo_response;
+///
+/// let (_, parsed) = ehlo_response(b"250-mx.example.org Hello\r\n250-SIZE 10240000\r\n\
+///                                   250-8bitmime\r\n250-AUTH PLAIN LOGIN\r\n250 SMTPUTF8\r\n").unwrap();
+///
+/// assert_eq!(parsed.host.as_ref().unwrap().to_string(), "mx.example.org");
+/// assert_eq!(parsed.size(), Some(10240000));
+/// assert!(parsed.supports("8BITMIME") && parsed.supports("smtputf8"));
+/// assert_eq!(parsed.extension("auth").unwrap().params, ["PLAIN", "LOGIN"]);
+/// ```
+pub fn ehlo_response(input: &[u8]) -> NomResult<EhloResponse> {
+    map(verify(reply, |r| r.code == 250), |reply| {
+        let host = reply.lines[0]
+            .split(' ')
+            .next()
+            .and_then(|host| DomainPart::from_smtp(host.as_bytes()).ok());
+        let extensions = reply.lines[1..]
+            .iter()
+            .filter_map(|line| {
+                let mut words = line.split(' ').filter(|w| !w.is_empty());
+                let keyword = words
+                    .next()
+                    .filter(|k| is_ehlo_keyword(k.split('=').next().unwrap_or(k)))?;
+
+                Some(Extension {
+                    keyword: keyword.to_ascii_uppercase(),
+                    params: words.map(String::from).collect(),
+                })
+            })
+            .collect();
+
+        EhloResponse {
+            host,
+            extensions,
+            reply,
+        }
+    })(input)
+}
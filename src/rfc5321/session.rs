@@ -0,0 +1,373 @@
+//! Incremental decoding of the client side of an [SMTP session]
+//!
+//! [SMTP session]: https://tools.ietf.org/html/rfc5321#section-4.1
+
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+
+use nom::combinator::all_consuming;
+
+use super::{
+    command, exceeds_params, Command, LimitExceeded, Limits, TransactionLimits, UTF8Policy,
+};
+
+/// The default maximum length of a command line including its CRLF.
+///
+/// Extensions such as AUTH may need a longer limit.
+pub const MAX_COMMAND_LINE: usize = 512;
+
+/// The default maximum size of the content after DATA, the default
+/// message size limit of Postfix.
+pub const MAX_MESSAGE_SIZE: usize = 10_240_000;
+
+/// An event decoded by an [`SmtpSession`].
+#[derive(Debug)]
+pub enum Event {
+    /// A command. A line with an unknown verb is returned as
+    /// [`Command::UNKNOWN`].
+    Command(Command),
+    /// A line with a known verb that could not be parsed, without its
+    /// CRLF.
+    Invalid(Vec<u8>),
+    /// A command line longer than the maximum length. The line is
+    /// discarded up to its CRLF.
+    TooLong,
+    /// A MAIL or RCPT command refused by the [`Limits`] of the
+    /// session. The command is discarded, answer it with
+    /// [`LimitExceeded::reply`].
+    Refused(LimitExceeded),
+    /// Message content after DATA with dot-stuffing removed, or the
+    /// content of a BDAT chunk. Lines keep their CRLF.
+    Data(Vec<u8>),
+    /// The content after DATA went over the maximum message size. The
+    /// rest of the content is discarded up to the final `"."` line,
+    /// which is still returned as [`Event::EndOfData`].
+    TooLarge,
+    /// The `"."` line ending the content after DATA.
+    EndOfData,
+    /// The end of a BDAT chunk, `true` if it was marked `LAST`.
+    EndOfChunk(bool),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Command,
+    // Discarding a command line that is too long.
+    Discard,
+    // `size` counts the content so far, over `max_message_size` once
+    // TooLarge was returned.
+    Data { line_start: bool, size: usize },
+    Bdat { remaining: u64, last: bool },
+}
+
+/// Stateful decoder for the input of an SMTP server.
+///
+/// Network input is given to [`feed`](SmtpSession::feed) in chunks of
+/// any size and decoded events are read by iterating over the
+/// session. Partial lines are buffered until complete.
+///
+/// A DATA command switches to data mode until the final `"."` line. A
+/// client only sends the content once DATA is accepted, the server
+/// must call [`reject_data`](SmtpSession::reject_data) when it refuses
+/// DATA. A BDAT command always switches to reading its chunk since the
+/// client sends it without waiting. Content after DATA past the
+/// maximum message size is not returned, [`Event::TooLarge`] is
+/// returned instead.
+///
+/// MAIL and RCPT commands over the [`Limits`] of the session are
+/// returned as [`Event::Refused`]. Every other RCPT command counts
+/// as an accepted recipient of the transaction, the server must call
+/// [`reject_recipient`](SmtpSession::reject_recipient) when it
+/// refuses one. The transaction ends with MAIL, RSET, HELO, EHLO or
+/// the end of the message.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::Command;
+/// use rustyknife::rfc5321::session::{Event, SmtpSession};
+///
+/// let mut session = SmtpSession::<Intl>::new();
+///
+/// session.feed(b"MAIL FROM:<a@example.org>\r\nDA");
+/// assert!(matches!(session.next(), Some(Event::Command(Command::MAIL(..)))));
+/// assert!(session.next().is_none());
+///
+/// session.feed(b"TA\r\nSubject: hi\r\n\r\n..dot\r\n.\r\nQUIT\r\n");
+/// assert!(matches!(session.next(), Some(Event::Command(Command::DATA))));
+/// match session.next() {
+///     Some(Event::Data(data)) => assert_eq!(data, b"Subject: hi\r\n\r\n.dot\r\n"),
+///     other => panic!("{:?}", other),
+/// }
+/// assert!(matches!(session.next(), Some(Event::EndOfData)));
+/// assert!(matches!(session.next(), Some(Event::Command(Command::QUIT))));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SmtpSession<P> {
+    buffer: Vec<u8>,
+    // Start of the input not decoded yet. The decoded input is only
+    // removed from `buffer` on the next `feed` so that decoding many
+    // lines of one large input stays linear.
+    pos: usize,
+    mode: Mode,
+    max_line_length: usize,
+    max_message_size: usize,
+    limits: TransactionLimits,
+    policy: PhantomData<P>,
+}
+
+impl<P: UTF8Policy> Default for SmtpSession<P> {
+    fn default() -> Self {
+        Self::with_max_line_length(MAX_COMMAND_LINE)
+    }
+}
+
+impl<P: UTF8Policy> SmtpSession<P> {
+    /// Create a session in command mode with the default maximum line
+    /// length.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create a session accepting command lines of at most
+    /// `max_line_length` bytes including the CRLF.
+    pub fn with_max_line_length(max_line_length: usize) -> Self {
+        SmtpSession {
+            buffer: Vec::new(),
+            pos: 0,
+            mode: Mode::Command,
+            max_line_length,
+            max_message_size: MAX_MESSAGE_SIZE,
+            limits: TransactionLimits::default(),
+            policy: PhantomData,
+        }
+    }
+
+    /// Set the maximum size of the content after DATA, after
+    /// dot-stuffing is removed.
+    pub fn set_max_message_size(&mut self, max_message_size: usize) {
+        self.max_message_size = max_message_size;
+    }
+
+    /// Replace the default [`Limits`] and start a new transaction.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = TransactionLimits::new(limits);
+    }
+
+    /// The recipient limits of the current transaction.
+    pub fn transaction(&self) -> &TransactionLimits {
+        &self.limits
+    }
+
+    /// Stop counting the last RCPT command, as done when the server
+    /// refuses the recipient.
+    pub fn reject_recipient(&mut self) {
+        self.limits.remove_recipient();
+    }
+
+    /// Append network input.
+    pub fn feed(&mut self, input: &[u8]) {
+        self.buffer.drain(..self.pos);
+        self.pos = 0;
+        self.buffer.extend_from_slice(input);
+    }
+
+    /// Returns `true` while reading message content after DATA or
+    /// BDAT.
+    pub fn in_data(&self) -> bool {
+        matches!(self.mode, Mode::Data { .. } | Mode::Bdat { .. })
+    }
+
+    /// The input received but not decoded yet.
+    ///
+    /// This must be empty when TLS starts after STARTTLS, anything
+    /// else was pipelined in plain text and must be discarded.
+    pub fn pending(&self) -> &[u8] {
+        &self.buffer[self.pos..]
+    }
+
+    /// Return to command mode after a refused DATA command.
+    ///
+    /// Has no effect during a BDAT chunk.
+    pub fn reject_data(&mut self) {
+        if let Mode::Data { .. } = self.mode {
+            self.mode = Mode::Command;
+        }
+    }
+
+    /// Discard the pending input and return to command mode, as done
+    /// when TLS starts.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.pos = 0;
+        self.mode = Mode::Command;
+        self.limits.reset();
+    }
+
+    // Position of the next CRLF in the pending input.
+    fn find_crlf(&self) -> Option<usize> {
+        self.pending().windows(2).position(|w| w == b"\r\n")
+    }
+
+    fn command_event(&mut self) -> Option<Event> {
+        let end = match self.find_crlf() {
+            Some(end) if end + 2 > self.max_line_length => {
+                self.pos += end + 2;
+                return Some(Event::TooLong);
+            }
+            Some(end) => end,
+            None if self.pending().len() >= self.max_line_length => {
+                self.mode = Mode::Discard;
+                return Some(Event::TooLong);
+            }
+            None => return None,
+        };
+        let line = &self.buffer[self.pos..self.pos + end + 2];
+        self.pos += end + 2;
+
+        if exceeds_params::<P>(line, self.limits.limits().max_params) {
+            return Some(Event::Refused(LimitExceeded::TooManyParams));
+        }
+        let parsed = all_consuming(command::<P>)(line).map(|(_, command)| command);
+        Some(match parsed {
+            Ok(command) => {
+                match command {
+                    Command::DATA => {
+                        self.mode = Mode::Data {
+                            line_start: true,
+                            size: 0,
+                        }
+                    }
+                    Command::BDAT(remaining, last) => self.mode = Mode::Bdat { remaining, last },
+                    Command::MAIL(..) | Command::RSET | Command::HELO(_) | Command::EHLO(_) => {
+                        self.limits.reset()
+                    }
+                    Command::RCPT(..) => {
+                        if let Err(exceeded) = self.limits.add_recipient() {
+                            return Some(Event::Refused(exceeded));
+                        }
+                    }
+                    _ => (),
+                }
+                Event::Command(command)
+            }
+            Err(_) => Event::Invalid(line[..end].to_vec()),
+        })
+    }
+
+    fn discard(&mut self) {
+        match self.find_crlf() {
+            Some(end) => {
+                self.pos += end + 2;
+                self.mode = Mode::Command;
+            }
+            // Keep a CR that may start the CRLF.
+            None => {
+                let keep = self.pending().ends_with(b"\r") as usize;
+                self.pos = self.buffer.len() - keep;
+            }
+        }
+    }
+
+    fn data_event(&mut self, mut line_start: bool, size: usize) -> Option<Event> {
+        let mut out = Vec::new();
+
+        loop {
+            if line_start {
+                if self.pending().starts_with(b".\r\n") {
+                    if out.is_empty() {
+                        self.pos += 3;
+                        self.mode = Mode::Command;
+                        self.limits.reset();
+                        return Some(Event::EndOfData);
+                    }
+                    break;
+                }
+                if b".\r\n".starts_with(self.pending()) {
+                    break;
+                }
+                if self.pending()[0] == b'.' {
+                    self.pos += 1;
+                }
+                line_start = false;
+            }
+
+            let len = match self.find_crlf() {
+                Some(end) => {
+                    line_start = true;
+                    end + 2
+                }
+                // Keep a CR that may start the CRLF.
+                None => self.pending().len() - self.pending().ends_with(b"\r") as usize,
+            };
+            out.extend_from_slice(&self.buffer[self.pos..self.pos + len]);
+            self.pos += len;
+            if !line_start {
+                break;
+            }
+        }
+
+        if out.is_empty() {
+            self.mode = Mode::Data { line_start, size };
+            return None;
+        }
+        let new_size = size.saturating_add(out.len());
+        self.mode = Mode::Data {
+            line_start,
+            size: new_size,
+        };
+        if new_size <= self.max_message_size {
+            Some(Event::Data(out))
+        } else if size <= self.max_message_size {
+            Some(Event::TooLarge)
+        } else {
+            // Already reported, look for the end of the content.
+            self.data_event(line_start, new_size)
+        }
+    }
+
+    fn bdat_event(&mut self, remaining: u64, last: bool) -> Option<Event> {
+        if remaining == 0 {
+            self.mode = Mode::Command;
+            if last {
+                self.limits.reset();
+            }
+            return Some(Event::EndOfChunk(last));
+        }
+        if self.pending().is_empty() {
+            return None;
+        }
+
+        let len = self
+            .pending()
+            .len()
+            .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        self.mode = Mode::Bdat {
+            remaining: remaining - len as u64,
+            last,
+        };
+        let data = self.buffer[self.pos..self.pos + len].to_vec();
+        self.pos += len;
+        Some(Event::Data(data))
+    }
+}
+
+impl<P: UTF8Policy> Iterator for SmtpSession<P> {
+    type Item = Event;
+
+    /// Decode the next event from the buffered input.
+    ///
+    /// Returns `None` when more input is needed, iteration can resume
+    /// after the next [`feed`](SmtpSession::feed).
+    fn next(&mut self) -> Option<Event> {
+        if self.mode == Mode::Discard {
+            self.discard();
+        }
+
+        match self.mode {
+            Mode::Command => self.command_event(),
+            Mode::Discard => None,
+            Mode::Data { line_start, size } => self.data_event(line_start, size),
+            Mode::Bdat { remaining, last } => self.bdat_event(remaining, last),
+        }
+    }
+}
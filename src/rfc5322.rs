@@ -1,21 +1,30 @@
 //! Parsers for [Internet Message Format] messages.
 //!
-//! Comments are ignored. [RFC 2047] decoding is applied where appropriate.
+//! Comments are ignored, use [`comments`] to extract them. [RFC 2047]
+//! decoding is applied where appropriate.
 //!
 //! [Internet Message Format]: https://tools.ietf.org/html/rfc5322
 //! [RFC 2047]: https://tools.ietf.org/html/rfc2047
 
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::net::IpAddr;
+use std::ops::Range;
 use std::str;
 
 use nom::branch::alt;
-use nom::bytes::complete::{tag, take};
-use nom::combinator::{map, map_opt, opt, recognize};
-use nom::multi::{fold_many0, many0, many1};
+use nom::bytes::complete::{tag, tag_no_case, take, take_while_m_n};
+use nom::character::is_digit;
+use nom::combinator::{all_consuming, map, map_opt, opt, peek, recognize, verify};
+use nom::multi::{fold_many0, fold_many1, many0, many1};
 use nom::sequence::{delimited, pair, preceded, separated_pair, terminated};
 
 use crate::behaviour::*;
-use crate::rfc2047::encoded_word;
+use crate::headersection::{HeaderField, KnownHeader};
+use crate::rfc2047::{encoded_word, encoded_word_with_charsets, CharsetPolicy};
+use crate::rfc2231::{content_type, MimeType};
 use crate::rfc5234::*;
 use crate::types::{self, *};
 use crate::util::*;
@@ -27,6 +36,14 @@ pub trait UTF8Policy {
     fn atext(input: &[u8]) -> NomResult<char>;
     fn qtext(input: &[u8]) -> NomResult<char>;
     fn dtext(input: &[u8]) -> NomResult<char>;
+
+    /// Handle an octet above 127 that is not valid text.
+    ///
+    /// Returns `None` if the octet is dropped. Replaces it with U+FFFD
+    /// by default.
+    fn eight_bit(input: &[u8]) -> NomResult<Option<char>> {
+        map(_8bit_char, Some)(input)
+    }
 }
 
 impl UTF8Policy for Legacy {
@@ -43,12 +60,7 @@ impl UTF8Policy for Legacy {
 
     fn atext(input: &[u8]) -> NomResult<char> {
         map(
-            take1_filter(|c| {
-                b"!#$%&'*+-/=?^_`{|}~".contains(&c)
-                    || (b'0'..=b'9').contains(&c)
-                    || (b'A'..=b'Z').contains(&c)
-                    || (b'a'..=b'z').contains(&c)
-            }),
+            take1_filter(|c| b"!#$%&'*+-/=?^_`{|}~".contains(&c) || c.is_ascii_alphanumeric()),
             char::from,
         )(input)
     }
@@ -100,6 +112,40 @@ impl UTF8Policy for Intl {
     }
 }
 
+impl<E: EightBitPolicy> UTF8Policy for LegacyWith<E> {
+    fn vchar(input: &[u8]) -> NomResult<char> {
+        Legacy::vchar(input)
+    }
+
+    fn ctext(input: &[u8]) -> NomResult<char> {
+        Legacy::ctext(input)
+    }
+
+    fn atext(input: &[u8]) -> NomResult<char> {
+        Legacy::atext(input)
+    }
+
+    // Octets above 127 are left to `eight_bit`.
+    fn qtext(input: &[u8]) -> NomResult<char> {
+        map(
+            take1_filter(|c| matches!(c, 33 | 35..=91 | 93..=126)),
+            char::from,
+        )(input)
+    }
+
+    fn dtext(input: &[u8]) -> NomResult<char> {
+        Legacy::dtext(input)
+    }
+
+    fn eight_bit(input: &[u8]) -> NomResult<Option<char>> {
+        match E::EIGHT_BIT {
+            EightBit::Replace(c) => map(_8bit_char, |_| Some(c))(input),
+            EightBit::Strip => map(_8bit_char, |_| None)(input),
+            EightBit::Reject => Err(nom::Err::Error(())),
+        }
+    }
+}
+
 fn quoted_pair<P: UTF8Policy>(input: &[u8]) -> NomResult<char> {
     preceded(tag("\\"), alt((P::vchar, map(wsp, char::from))))(input)
 }
@@ -109,9 +155,24 @@ enum CommentContent<'a> {
     Text(Cow<'a, str>),
     Comment(Vec<CommentContent<'a>>),
     QP(char),
+    EncodedWord(String),
 }
 
-fn ccontent<P: UTF8Policy>(input: &[u8]) -> NomResult<CommentContent> {
+// An encoded word in a comment must be followed by white space or a
+// parenthesis.
+fn comment_encoded_word(input: &[u8]) -> NomResult<String> {
+    terminated(
+        encoded_word,
+        peek(alt((tag(" "), tag("\t"), tag("\r"), tag("("), tag(")")))),
+    )(input)
+}
+
+fn ccontent<P: UTF8Policy>(input: &[u8], decode: bool) -> NomResult<CommentContent> {
+    if decode {
+        if let Ok((rem, ew)) = comment_encoded_word(input) {
+            return Ok((rem, CommentContent::EncodedWord(ew)));
+        }
+    }
     alt((
         alt((
             map(recognize_many1(P::ctext), |ct| {
@@ -119,7 +180,7 @@ fn ccontent<P: UTF8Policy>(input: &[u8]) -> NomResult<CommentContent> {
             }),
             map(quoted_pair::<P>, CommentContent::QP),
         )),
-        map(comment::<P>, CommentContent::Comment),
+        map(|i| _comment::<P>(i, decode), CommentContent::Comment),
     ))(input)
 }
 
@@ -161,6 +222,7 @@ fn _concat_comment<'a, I: IntoIterator<Item = CommentContent<'a>>>(
         match comment {
             CommentContent::Text(text) => acc_text.push_str(&text),
             CommentContent::QP(qp) => acc_text.push(qp),
+            CommentContent::EncodedWord(ew) => acc_text.push_str(&ew),
             _ => {
                 push_text(&mut acc_text, &mut out);
                 out.push(comment)
@@ -173,15 +235,33 @@ fn _concat_comment<'a, I: IntoIterator<Item = CommentContent<'a>>>(
 }
 
 fn comment<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<CommentContent>> {
+    _comment::<P>(input, false)
+}
+
+// With `decode`, encoded words are decoded and the white space
+// between adjacent encoded words is removed.
+fn _comment<P: UTF8Policy>(input: &[u8], decode: bool) -> NomResult<Vec<CommentContent>> {
     map(
         delimited(
             tag("("),
             pair(
-                fold_many0(pair(ofws, ccontent::<P>), Vec::new, |mut acc, (fws, cc)| {
-                    acc.push(CommentContent::Text(fws));
-                    acc.push(cc);
-                    acc
-                }),
+                fold_many0(
+                    pair(ofws, |i| ccontent::<P>(i, decode)),
+                    Vec::new,
+                    |mut acc, (fws, cc)| {
+                        if !matches!(
+                            (acc.last(), &cc),
+                            (
+                                Some(CommentContent::EncodedWord(_)),
+                                CommentContent::EncodedWord(_)
+                            )
+                        ) {
+                            acc.push(CommentContent::Text(fws));
+                        }
+                        acc.push(cc);
+                        acc
+                    },
+                ),
                 ofws,
             ),
             tag(")"),
@@ -195,13 +275,73 @@ fn comment<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<CommentContent>> {
     )(input)
 }
 
-fn cfws<P: UTF8Policy>(input: &[u8]) -> NomResult<&[u8]> {
+pub(crate) fn cfws<P: UTF8Policy>(input: &[u8]) -> NomResult<&[u8]> {
     alt((
         recognize(pair(many1(pair(ofws, comment::<P>)), ofws)),
         recognize(fws),
     ))(input)
 }
 
+fn flatten_comment(content: &[CommentContent], out: &mut String) {
+    for cc in content {
+        match cc {
+            CommentContent::Text(text) => out.push_str(text),
+            CommentContent::QP(qp) => out.push(*qp),
+            CommentContent::EncodedWord(ew) => out.push_str(ew),
+            CommentContent::Comment(nested) => {
+                out.push('(');
+                flatten_comment(nested, out);
+                out.push(')');
+            }
+        }
+    }
+}
+
+/// Extract the comments of a structured header value.
+///
+/// Returns the text of each outermost comment in order. Nested
+/// comments are kept in the text of the enclosing comment with their
+/// parentheses. Quoted strings and domain literals are skipped, as
+/// are unterminated comments.
+///
+/// With `decode_encoded_words`, [RFC 2047] encoded words in comments
+/// are decoded.
+///
+/// [RFC 2047]: https://tools.ietf.org/html/rfc2047#section-5
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::comments;
+///
+/// let input = b" from mx.example.org (=?utf-8?Q?h=C3=B4te?= =?utf-8?Q?_1?= [192.0.2.1]) by \"(not one)\" (x (y))";
+///
+/// assert_eq!(comments::<Intl>(input, true), ["hôte 1 [192.0.2.1]", "x (y)"]);
+/// assert_eq!(comments::<Intl>(input, false)[0], "=?utf-8?Q?h=C3=B4te?= =?utf-8?Q?_1?= [192.0.2.1]");
+/// ```
+pub fn comments<P: UTF8Policy>(input: &[u8], decode_encoded_words: bool) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut rest = input;
+
+    while let Some((&c, tail)) = rest.split_first() {
+        rest = match c {
+            b'(' => match _comment::<P>(rest, decode_encoded_words) {
+                Ok((rem, content)) => {
+                    let mut text = String::new();
+                    flatten_comment(&content, &mut text);
+                    out.push(text);
+                    rem
+                }
+                Err(_) => tail,
+            },
+            b'"' => _inner_quoted_string::<P>(rest).map_or(tail, |(rem, _)| rem),
+            b'[' => no_fold_literal::<P>(rest).map_or(tail, |(rem, _)| rem),
+            _ => tail,
+        };
+    }
+
+    out
+}
+
 #[cfg(feature = "quoted-string-rfc2047")]
 fn qcontent<P: UTF8Policy>(input: &[u8]) -> NomResult<QContent> {
     alt((
@@ -209,6 +349,9 @@ fn qcontent<P: UTF8Policy>(input: &[u8]) -> NomResult<QContent> {
         map(recognize_many1(P::qtext), |q| {
             QContent::Literal(String::from_utf8_lossy(q))
         }),
+        map(P::eight_bit, |c| {
+            QContent::Literal(c.map(String::from).unwrap_or_default().into())
+        }),
         map(quoted_pair::<P>, QContent::QP),
     ))(input)
 }
@@ -219,6 +362,9 @@ fn qcontent<P: UTF8Policy>(input: &[u8]) -> NomResult<QContent> {
         map(recognize_many1(P::qtext), |q| {
             QContent::Literal(String::from_utf8_lossy(q))
         }),
+        map(P::eight_bit, |c| {
+            QContent::Literal(c.map(String::from).unwrap_or_default().into())
+        }),
         map(quoted_pair::<P>, QContent::QP),
     ))(input)
 }
@@ -252,7 +398,22 @@ fn _inner_quoted_string<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<QContent>>
     )(input)
 }
 
-pub(crate) fn quoted_string<P: UTF8Policy>(input: &[u8]) -> NomResult<QuotedString> {
+/// Parse an RFC 5322 `quoted-string`.
+///
+/// Surrounding white space and comments are skipped, folding is
+/// removed and quoted pairs are unescaped. With the
+/// `quoted-string-rfc2047` feature, encoded words within the quotes
+/// are decoded.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::quoted_string;
+///
+/// let (rem, qs) = quoted_string::<Intl>(b" (c) \"Mary\r\n Smith \\\"MS\\\"\" rest").unwrap();
+/// assert_eq!(&*qs, "Mary Smith \"MS\"");
+/// assert_eq!(rem, b"rest");
+/// ```
+pub fn quoted_string<P: UTF8Policy>(input: &[u8]) -> NomResult<QuotedString> {
     map(
         delimited(opt(cfws::<P>), _inner_quoted_string::<P>, opt(cfws::<P>)),
         |qc| QuotedString(concat_qs(qc.into_iter())),
@@ -260,6 +421,16 @@ pub(crate) fn quoted_string<P: UTF8Policy>(input: &[u8]) -> NomResult<QuotedStri
 }
 
 /// A single mailbox with an optional display name.
+///
+/// # Examples
+/// ```
+/// use rustyknife::rfc5322::Mailbox;
+///
+/// let mailbox: Mailbox = "John <j@example.org>".parse().unwrap();
+///
+/// assert_eq!(mailbox.dname.as_deref(), Some("John"));
+/// assert_eq!(mailbox.address.to_string(), "j@example.org");
+/// ```
 #[derive(Clone, Debug, PartialEq)]
 pub struct Mailbox {
     /// The display name.
@@ -267,6 +438,34 @@ pub struct Mailbox {
     /// The address of this mailbox.
     pub address: types::Mailbox,
 }
+nom_fromstr!(Mailbox, mailbox::<Intl>);
+
+impl Mailbox {
+    /// Return a copy of this mailbox with another domain part.
+    ///
+    /// The display name is preserved. See
+    /// [`types::Mailbox::with_domain`].
+    pub fn with_domain<'a>(&self, domain: &'a [u8]) -> Result<Self, nom::Err<NomError<'a>>> {
+        Ok(Mailbox {
+            dname: self.dname.clone(),
+            address: self.address.with_domain(domain)?,
+        })
+    }
+
+    /// Return a copy of this mailbox with another local part.
+    ///
+    /// The display name is preserved. See
+    /// [`types::Mailbox::with_local_part`].
+    pub fn with_local_part<'a>(
+        &self,
+        local_part: &'a [u8],
+    ) -> Result<Self, nom::Err<NomError<'a>>> {
+        Ok(Mailbox {
+            dname: self.dname.clone(),
+            address: self.address.with_local_part(local_part)?,
+        })
+    }
+}
 
 /// A group of many [`Mailbox`].
 #[derive(Clone, Debug, PartialEq)]
@@ -276,6 +475,7 @@ pub struct Group {
     /// The members of this group. May be empty.
     pub members: Vec<Mailbox>,
 }
+nom_fromstr!(Group, group::<Intl>);
 
 /// An address is either a single [`Mailbox`] or a [`Group`].
 #[derive(Clone, Debug, PartialEq)]
@@ -285,6 +485,233 @@ pub enum Address {
     /// [`Group`] of many [`Mailbox`].
     Group(Group),
 }
+nom_fromstr!(Address, address::<Intl>);
+
+impl Address {
+    /// Iterate over the mailboxes of this address.
+    ///
+    /// Yields a single mailbox for [`Address::Mailbox`] and the members
+    /// of a [`Address::Group`].
+    pub fn mailboxes(&self) -> std::slice::Iter<'_, Mailbox> {
+        match self {
+            Address::Mailbox(mailbox) => std::slice::from_ref(mailbox).iter(),
+            Address::Group(group) => group.members.iter(),
+        }
+    }
+
+    /// Convert this address into its mailboxes.
+    pub fn into_mailboxes(self) -> Vec<Mailbox> {
+        match self {
+            Address::Mailbox(mailbox) => vec![mailbox],
+            Address::Group(group) => group.members,
+        }
+    }
+}
+
+impl Group {
+    /// Create a group without members.
+    ///
+    /// Useful to fill a required address header without disclosing
+    /// any recipient.
+    /// # Examples
+    /// ```
+    /// use rustyknife::rfc5322::Group;
+    ///
+    /// assert_eq!(Group::empty("undisclosed-recipients").to_string(),
+    ///            "undisclosed-recipients:;");
+    /// ```
+    pub fn empty<S: Into<String>>(dname: S) -> Self {
+        Group {
+            dname: dname.into(),
+            members: Vec::new(),
+        }
+    }
+}
+
+impl Address {
+    /// The conventional `"undisclosed-recipients:;"` empty group.
+    /// # Examples
+    /// ```
+    /// use rustyknife::rfc5322::Address;
+    ///
+    /// let address = Address::undisclosed_recipients();
+    ///
+    /// assert_eq!(address.to_string(), "undisclosed-recipients:;");
+    /// assert_eq!(address.to_string().parse::<Address>().unwrap(), address);
+    /// ```
+    pub fn undisclosed_recipients() -> Self {
+        Address::Group(Group::empty("undisclosed-recipients"))
+    }
+}
+
+// Write a display name as a phrase, quoting it unless it parses back
+// to the same string unquoted. A name with control characters such as
+// CR or LF is written as encoded words since a quoted string would
+// carry them into the output and allow header injection.
+pub(crate) fn fmt_dname(f: &mut fmt::Formatter, dname: &str) -> fmt::Result {
+    if dname.chars().any(|c| c.is_control() && c != '\t') {
+        // UTF-8 represents any text and fits any single character.
+        let words = crate::rfc2047::encode_word(dname, &[]).ok_or(fmt::Error)?;
+        return write!(f, "{}", words);
+    }
+
+    match all_consuming(display_name::<Intl>)(dname.as_bytes()) {
+        Ok((_, parsed)) if parsed == dname => write!(f, "{}", dname),
+        _ => write!(f, "{}", QuotedString(dname.into()).quoted()),
+    }
+}
+
+impl fmt::Display for Mailbox {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        RoutedMailbox::fmt_parts(f, self, &[])
+    }
+}
+
+/// A [`Mailbox`] with the obsolete source route of its angle address,
+/// as returned by [`routed_mailboxes`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoutedMailbox {
+    /// The mailbox.
+    pub mailbox: Mailbox,
+    /// The route, as in `<@relay.example,@other.example:user@example.org>`.
+    ///
+    /// Empty unless the address used the obsolete syntax. The route
+    /// is ignored for delivery but kept as claimed by the message.
+    pub route: Vec<DomainPart>,
+}
+
+impl RoutedMailbox {
+    fn fmt_parts(f: &mut fmt::Formatter, mailbox: &Mailbox, route: &[DomainPart]) -> fmt::Result {
+        if let Some(dname) = &mailbox.dname {
+            fmt_dname(f, dname)?;
+            write!(f, " ")?;
+        } else if route.is_empty() {
+            return write!(f, "{}", mailbox.address);
+        }
+        write!(f, "<")?;
+        for (i, domain) in route.iter().enumerate() {
+            write!(f, "{}@{}", if i > 0 { "," } else { "" }, domain)?;
+        }
+        if !route.is_empty() {
+            write!(f, ":")?;
+        }
+        write!(f, "{}>", mailbox.address)
+    }
+}
+
+impl fmt::Display for RoutedMailbox {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        RoutedMailbox::fmt_parts(f, &self.mailbox, &self.route)
+    }
+}
+
+impl fmt::Display for Group {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_dname(f, &self.dname)?;
+        write!(f, ":")?;
+        for (i, member) in self.members.iter().enumerate() {
+            write!(f, "{}{}", if i > 0 { ", " } else { " " }, member)?;
+        }
+        write!(f, ";")
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Address::Mailbox(m) => write!(f, "{}", m),
+            Address::Group(g) => write!(f, "{}", g),
+        }
+    }
+}
+
+/// Iterate over the mailboxes of an address list.
+///
+/// Groups are flattened into their members and empty groups yield
+/// nothing.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::{from, mailboxes};
+///
+/// let (_, parsed) = from::<Intl>(b"a@example.org, Team: b@example.org, c@example.org;, Empty:;").unwrap();
+/// let addresses: Vec<_> = mailboxes(&parsed).map(|m| m.address.to_string()).collect();
+///
+/// assert_eq!(addresses, ["a@example.org", "b@example.org", "c@example.org"]);
+/// ```
+pub fn mailboxes<'a, I>(addresses: I) -> impl Iterator<Item = &'a Mailbox>
+where
+    I: IntoIterator<Item = &'a Address>,
+{
+    addresses.into_iter().flat_map(Address::mailboxes)
+}
+
+/// Expand groups into their member mailboxes.
+///
+/// The order of the mailboxes is preserved and empty groups are
+/// dropped.
+pub fn expand_groups<I: IntoIterator<Item = Address>>(addresses: I) -> Vec<Mailbox> {
+    addresses
+        .into_iter()
+        .flat_map(Address::into_mailboxes)
+        .collect()
+}
+
+/// Remove duplicate mailboxes.
+///
+/// Addresses are compared with [`types::Mailbox::semantic_eq`]. The
+/// position and address of the first occurrence are kept along with
+/// the first display name seen for that address.
+pub fn dedup_mailboxes<I: IntoIterator<Item = Mailbox>>(mailboxes: I) -> Vec<Mailbox> {
+    let mut out: Vec<Mailbox> = Vec::new();
+    let mut seen = HashMap::new();
+
+    for mailbox in mailboxes {
+        match seen.get(&mailbox.address.normalized()) {
+            Some(&idx) => {
+                let first: &mut Mailbox = &mut out[idx];
+                if first.dname.is_none() {
+                    first.dname = mailbox.dname;
+                }
+            }
+            None => {
+                seen.insert(mailbox.address.normalized(), out.len());
+                out.push(mailbox);
+            }
+        }
+    }
+
+    out
+}
+
+/// Merge many address lists into a single list of unique mailboxes.
+///
+/// Groups are expanded and duplicates are removed as done by
+/// [`dedup_mailboxes`]. Useful for combining the `"To:"` and `"Cc:"`
+/// headers of a message.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::{merge_address_lists, reply_to};
+///
+/// let (_, to_list) = reply_to::<Intl>(b"Bob <bob@example.org>, Team: alice@example.org;").unwrap();
+/// let (_, cc_list) = reply_to::<Intl>(b"bob@EXAMPLE.org, Alice <alice@example.org>").unwrap();
+///
+/// let merged = merge_address_lists(vec![to_list, cc_list]);
+/// let merged: Vec<_> = merged.iter()
+///     .map(|m| (m.dname.as_deref(), m.address.to_string()))
+///     .collect();
+///
+/// assert_eq!(merged, [(Some("Bob"), "bob@example.org".into()),
+///                     (Some("Alice"), "alice@example.org".into())]);
+/// ```
+pub fn merge_address_lists<I, L>(lists: I) -> Vec<Mailbox>
+where
+    I: IntoIterator<Item = L>,
+    L: IntoIterator<Item = Address>,
+{
+    dedup_mailboxes(lists.into_iter().flat_map(expand_groups))
+}
 
 #[derive(Clone, Debug)]
 enum QContent<'a> {
@@ -351,7 +778,7 @@ pub(crate) fn dot_atom<P: UTF8Policy>(input: &[u8]) -> NomResult<DotAtom> {
             )),
             opt(cfws::<P>),
         ),
-        |a| (DotAtom(str::from_utf8(a).unwrap().into())),
+        |a| DotAtom(str::from_utf8(a).unwrap().into()),
     )(input)
 }
 
@@ -397,7 +824,7 @@ where
 
 fn display_name<P: UTF8Policy>(input: &[u8]) -> NomResult<String> {
     map(many1(word::<P>), |words| {
-        _concat_atom_and_qs(words.into_iter().map(Into::into))
+        _concat_atom_and_qs(words.into_iter())
     })(input)
 }
 
@@ -445,6 +872,30 @@ pub(crate) fn addr_spec<P: UTF8Policy>(input: &[u8]) -> NomResult<types::Mailbox
     )(input)
 }
 
+fn obs_domain_list<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<DomainPart>> {
+    map(
+        pair(
+            preceded(
+                pair(many0(alt((cfws::<P>, tag(",")))), tag("@")),
+                domain::<P>,
+            ),
+            many0(preceded(
+                pair(tag(","), opt(cfws::<P>)),
+                opt(preceded(tag("@"), domain::<P>)),
+            )),
+        ),
+        |(first, rest)| {
+            std::iter::once(first)
+                .chain(rest.into_iter().flatten())
+                .collect()
+        },
+    )(input)
+}
+
+fn obs_route<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<DomainPart>> {
+    terminated(obs_domain_list::<P>, tag(":"))(input)
+}
+
 fn angle_addr<P: UTF8Policy>(input: &[u8]) -> NomResult<types::Mailbox> {
     delimited(
         pair(opt(cfws::<P>), tag("<")),
@@ -453,6 +904,17 @@ fn angle_addr<P: UTF8Policy>(input: &[u8]) -> NomResult<types::Mailbox> {
     )(input)
 }
 
+fn obs_angle_addr<P: UTF8Policy>(input: &[u8]) -> NomResult<(Vec<DomainPart>, types::Mailbox)> {
+    delimited(
+        pair(opt(cfws::<P>), tag("<")),
+        pair(
+            map(opt(obs_route::<P>), Option::unwrap_or_default),
+            addr_spec::<P>,
+        ),
+        pair(tag(">"), opt(cfws::<P>)),
+    )(input)
+}
+
 fn name_addr<P: UTF8Policy>(input: &[u8]) -> NomResult<Mailbox> {
     map(
         pair(opt(display_name::<P>), angle_addr::<P>),
@@ -460,7 +922,7 @@ fn name_addr<P: UTF8Policy>(input: &[u8]) -> NomResult<Mailbox> {
     )(input)
 }
 
-fn mailbox<P: UTF8Policy>(input: &[u8]) -> NomResult<Mailbox> {
+pub(crate) fn mailbox<P: UTF8Policy>(input: &[u8]) -> NomResult<Mailbox> {
     alt((
         name_addr::<P>,
         map(addr_spec::<P>, |a| Mailbox {
@@ -470,6 +932,80 @@ fn mailbox<P: UTF8Policy>(input: &[u8]) -> NomResult<Mailbox> {
     ))(input)
 }
 
+fn routed_mailbox<P: UTF8Policy>(input: &[u8]) -> NomResult<RoutedMailbox> {
+    alt((
+        map(
+            pair(opt(display_name::<P>), obs_angle_addr::<P>),
+            |(dname, (route, address))| RoutedMailbox {
+                mailbox: Mailbox { dname, address },
+                route,
+            },
+        ),
+        map(addr_spec::<P>, |address| RoutedMailbox {
+            mailbox: Mailbox {
+                dname: None,
+                address,
+            },
+            route: Vec::new(),
+        }),
+    ))(input)
+}
+
+fn routed_mailbox_list<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<RoutedMailbox>> {
+    fold_prefix0(routed_mailbox::<P>, preceded(tag(","), routed_mailbox::<P>))(input)
+}
+
+// An address as the list of its mailboxes.
+fn routed_address<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<RoutedMailbox>> {
+    alt((
+        map(routed_mailbox::<P>, |m| vec![m]),
+        map(
+            preceded(
+                pair(display_name::<P>, tag(":")),
+                terminated(
+                    opt(alt((routed_mailbox_list::<P>, map(cfws::<P>, |_| vec![])))),
+                    pair(tag(";"), opt(cfws::<P>)),
+                ),
+            ),
+            Option::unwrap_or_default,
+        ),
+    ))(input)
+}
+
+/// Parse an address list, accepting the obsolete source route of
+/// [RFC 5322 section 4.4] in angle addresses.
+///
+/// The other address parsers reject the route. This parser is meant
+/// for tooling that needs to see exactly what a message claimed.
+/// Returns the mailboxes in order with their route. Group members are
+/// included and the groups themselves are dropped, as with
+/// [`expand_groups`].
+///
+/// [RFC 5322 section 4.4]: https://tools.ietf.org/html/rfc5322#section-4.4
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::{from, routed_mailboxes};
+///
+/// let input = b" John <@relay.example,@[192.0.2.1]:jdoe@example.org>, Team: a@example.org;\r\n";
+/// assert!(from::<Intl>(input).is_err());
+///
+/// let (_, mailboxes) = routed_mailboxes::<Intl>(input).unwrap();
+/// assert_eq!(mailboxes[0].route.len(), 2);
+/// assert_eq!(mailboxes[0].to_string(), "John <@relay.example,@[192.0.2.1]:jdoe@example.org>");
+/// assert_eq!(mailboxes[1].mailbox.to_string(), "a@example.org");
+/// assert!(mailboxes[1].route.is_empty());
+/// ```
+pub fn routed_mailboxes<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<RoutedMailbox>> {
+    map(
+        terminated(
+            fold_prefix0(routed_address::<P>, preceded(tag(","), routed_address::<P>)),
+            opt(crlf),
+        ),
+        |addresses| addresses.into_iter().flatten().collect(),
+    )(input)
+}
+
 fn mailbox_list<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<Mailbox>> {
     fold_prefix0(mailbox::<P>, preceded(tag(","), mailbox::<P>))(input)
 }
@@ -514,24 +1050,24 @@ fn _8bit_char(input: &[u8]) -> NomResult<char> {
     map(take1_filter(|c| (0x80..=0xff).contains(&c)), |_| '\u{fffd}')(input)
 }
 
-/// Parse an unstructured header such as `"Subject:"`.
-///
-/// Returns a fully decoded string.
-pub fn unstructured<P: UTF8Policy>(input: &[u8]) -> NomResult<String> {
+fn _unstructured<P, F>(input: &[u8], ew: F) -> NomResult<String>
+where
+    P: UTF8Policy,
+    F: Fn(&[u8]) -> NomResult<String> + Copy,
+{
     map(
         pair(
             many0(alt((
                 pair(
                     ofws,
-                    map(
-                        fold_prefix0(encoded_word, preceded(fws, encoded_word)),
-                        |ew| ew.into_iter().collect(),
-                    ),
+                    map(fold_prefix0(ew, preceded(fws, ew)), |ew| {
+                        ew.into_iter().collect()
+                    }),
                 ),
                 pair(
                     ofws,
-                    map(many1(alt((P::vchar, _8bit_char))), |c| {
-                        c.iter().collect::<String>()
+                    map(many1(alt((map(P::vchar, Some), P::eight_bit))), |c| {
+                        c.iter().flatten().collect::<String>()
                     }),
                 ),
             ))),
@@ -549,6 +1085,157 @@ pub fn unstructured<P: UTF8Policy>(input: &[u8]) -> NomResult<String> {
     )(input)
 }
 
+/// Parse an unstructured header such as `"Subject:"`.
+///
+/// Returns a fully decoded string.
+pub fn unstructured<P: UTF8Policy>(input: &[u8]) -> NomResult<String> {
+    traced("unstructured", |i| _unstructured::<P, _>(i, encoded_word))(input)
+}
+
+/// Parse an unstructured header, decoding encoded words only in the
+/// charsets allowed by `P`.
+///
+/// See [`encoded_word_with_charsets`] for the handling of other
+/// charsets.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Legacy;
+/// use rustyknife::rfc2047::SAFE_CHARSETS;
+/// use rustyknife::rfc5322::unstructured_with_charsets;
+///
+/// let input = b"Re: =?utf-8?Q?caf=C3=A9?= =?gb2312?B?1tDOxA==?=";
+/// let (_, decoded) = unstructured_with_charsets::<Legacy>(SAFE_CHARSETS)(input).unwrap();
+///
+/// assert_eq!(decoded, "Re: café\u{fffd}\u{fffd}\u{fffd}\u{fffd}");
+/// ```
+pub fn unstructured_with_charsets<'l, P: UTF8Policy + CharsetPolicy + 'l>(
+    allowed: &'l [&'l str],
+) -> impl Fn(&[u8]) -> NomResult<String> + 'l {
+    move |input| {
+        let ew = encoded_word_with_charsets::<P>(allowed);
+        traced("unstructured", move |i| _unstructured::<P, _>(i, ew))(input)
+    }
+}
+
+/// A decoded byte range of a string and the input byte range it was
+/// decoded from.
+pub type SpanMap = Vec<(Range<usize>, Range<usize>)>;
+
+/// Parse an unstructured header, also mapping the decoded text back
+/// to the input.
+///
+/// Returns the same string as [`unstructured`] with a list of byte
+/// ranges of that string paired with the input bytes that produced
+/// them, in order. Each encoded word, run of white space and run of
+/// plain text gets its own entry. The white space between adjacent
+/// encoded words is removed by decoding and is not mapped.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::unstructured_spans;
+///
+/// let input = b"Re: =?utf-8?Q?caf=C3=A9?= =?utf-8?Q?_au_lait?= ok";
+/// let (_, (decoded, spans)) = unstructured_spans::<Intl>(input).unwrap();
+///
+/// assert_eq!(decoded, "Re: café au lait ok");
+/// let (out, raw) = spans.iter().find(|(out, _)| out.contains(&4)).unwrap();
+/// assert_eq!(&decoded[out.clone()], "café");
+/// assert_eq!(&input[raw.clone()], b"=?utf-8?Q?caf=C3=A9?=");
+/// ```
+pub fn unstructured_spans<P: UTF8Policy>(input: &[u8]) -> NomResult<(String, SpanMap)> {
+    let mut out = String::new();
+    let mut spans = Vec::new();
+    let (rem, ()) =
+        unstructured_into::<P, _>(input, &mut out, |decoded, raw| spans.push((decoded, raw)))?;
+
+    Ok((rem, (out, spans)))
+}
+
+fn push_piece<F>(out: &mut String, text: &str, raw: Range<usize>, span: &mut F)
+where
+    F: FnMut(Range<usize>, Range<usize>),
+{
+    if !text.is_empty() {
+        span(out.len()..out.len() + text.len(), raw);
+        out.push_str(text);
+    }
+}
+
+// Decode an unstructured header at the end of `out`, calling `span`
+// with the ranges of every piece of text in `out` and `input`.
+pub(crate) fn unstructured_into<'a, P, F>(
+    input: &'a [u8],
+    out: &mut String,
+    mut span: F,
+) -> NomResult<'a, ()>
+where
+    P: UTF8Policy,
+    F: FnMut(Range<usize>, Range<usize>),
+{
+    let offset = |rest: &[u8]| input.len() - rest.len();
+    let mut rest = input;
+
+    loop {
+        let (after_ws, ws) = ofws(rest)?;
+        let mark = out.len();
+
+        if let Ok((mut rem, word)) = encoded_word(after_ws) {
+            push_piece(out, &ws, offset(rest)..offset(after_ws), &mut span);
+            push_piece(out, &word, offset(after_ws)..offset(rem), &mut span);
+            while let Ok((next, word)) = preceded(fws, encoded_word)(rem) {
+                let raw = offset(next) - word_len(rem, next)..offset(next);
+                push_piece(out, &word, raw, &mut span);
+                rem = next;
+            }
+            rest = rem;
+            continue;
+        }
+
+        out.push_str(&ws);
+        let text_start = out.len();
+        let text = fold_many1(
+            alt((map(P::vchar, Some), P::eight_bit)),
+            || (),
+            |(), c| out.extend(c),
+        )(after_ws);
+        match text {
+            Ok((rem, ())) => {
+                if text_start > mark {
+                    span(mark..text_start, offset(rest)..offset(after_ws));
+                }
+                if out.len() > text_start {
+                    span(text_start..out.len(), offset(after_ws)..offset(rem));
+                }
+                rest = rem;
+            }
+            Err(_) => {
+                out.truncate(mark);
+                break;
+            }
+        }
+    }
+
+    let (rem, ws) = recognize_many0(wsp)(rest)?;
+    push_piece(
+        out,
+        str::from_utf8(ws).unwrap(),
+        offset(rest)..offset(rem),
+        &mut span,
+    );
+
+    Ok((rem, ()))
+}
+
+// Length of the encoded word at the end of the consumed input.
+fn word_len(before: &[u8], after: &[u8]) -> usize {
+    let consumed = &before[..before.len() - after.len()];
+    consumed.len()
+        - consumed
+            .iter()
+            .take_while(|c| c.is_ascii_whitespace())
+            .count()
+}
+
 /// Parse the content of a `"From:"` header.
 ///
 /// Returns a list of addresses, since [RFC 6854] allows multiple mail
@@ -556,19 +1243,1021 @@ pub fn unstructured<P: UTF8Policy>(input: &[u8]) -> NomResult<String> {
 ///
 /// [RFC 6854]: https://tools.ietf.org/html/rfc6854
 pub fn from<P: UTF8Policy>(i: &[u8]) -> NomResult<Vec<Address>> {
-    address_list_crlf::<P>(i)
+    traced("from", address_list_crlf::<P>)(i)
 }
 
 /// Parse the content of a `"Sender:"` header.
 ///
 /// Returns a single address.
 pub fn sender<P: UTF8Policy>(i: &[u8]) -> NomResult<Address> {
-    address_crlf::<P>(i)
+    traced("sender", address_crlf::<P>)(i)
+}
+
+/// The syntax of the addresses found in a `"From:"` or `"Sender:"`
+/// header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AuthorForm {
+    /// Only mailboxes, as required by [RFC 5322].
+    ///
+    /// [RFC 5322]: https://tools.ietf.org/html/rfc5322#section-3.6.2
+    Mailboxes,
+    /// At least one group, as allowed by [RFC 6854] for limited use.
+    ///
+    /// [RFC 6854]: https://tools.ietf.org/html/rfc6854#section-3
+    Group,
+}
+
+/// Whether [`from_with_policy`] and [`sender_with_policy`] allow group
+/// syntax.
+///
+/// A message submission agent should reject groups while a message
+/// generated by an automated system, such as a delivery status
+/// notification, may use them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GroupPolicy {
+    /// No address is returned when a group is found.
+    Reject,
+    /// Groups are returned.
+    Accept,
+}
+
+fn author_form<'a, I: IntoIterator<Item = &'a Address>>(addresses: I) -> AuthorForm {
+    if addresses
+        .into_iter()
+        .any(|a| matches!(a, Address::Group(_)))
+    {
+        AuthorForm::Group
+    } else {
+        AuthorForm::Mailboxes
+    }
+}
+
+/// Parse the content of a `"From:"` header, handling group syntax
+/// according to `policy`.
+///
+/// Returns the addresses, or `None` if they were rejected, with the
+/// form that was found.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::{from_with_policy, AuthorForm, GroupPolicy};
+///
+/// let input = b" Automated System:;";
+///
+/// let (_, (addresses, form)) = from_with_policy::<Intl>(GroupPolicy::Accept)(input).unwrap();
+/// assert_eq!((addresses.unwrap().len(), form), (1, AuthorForm::Group));
+///
+/// let (_, (addresses, form)) = from_with_policy::<Intl>(GroupPolicy::Reject)(input).unwrap();
+/// assert_eq!((addresses, form), (None, AuthorForm::Group));
+///
+/// let (_, (_, form)) = from_with_policy::<Intl>(GroupPolicy::Reject)(b" a@example.org").unwrap();
+/// assert_eq!(form, AuthorForm::Mailboxes);
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn from_with_policy<P: UTF8Policy>(
+    policy: GroupPolicy,
+) -> impl Fn(&[u8]) -> NomResult<(Option<Vec<Address>>, AuthorForm)> {
+    move |input| {
+        map(from::<P>, |addresses| {
+            let form = author_form(&addresses);
+            match (form, policy) {
+                (AuthorForm::Group, GroupPolicy::Reject) => (None, form),
+                _ => (Some(addresses), form),
+            }
+        })(input)
+    }
+}
+
+/// Parse the content of a `"Sender:"` header, handling group syntax
+/// according to `policy`.
+///
+/// See [`from_with_policy`].
+#[allow(clippy::type_complexity)]
+pub fn sender_with_policy<P: UTF8Policy>(
+    policy: GroupPolicy,
+) -> impl Fn(&[u8]) -> NomResult<(Option<Address>, AuthorForm)> {
+    move |input| {
+        map(sender::<P>, |address| {
+            let form = author_form(std::iter::once(&address));
+            match (form, policy) {
+                (AuthorForm::Group, GroupPolicy::Reject) => (None, form),
+                _ => (Some(address), form),
+            }
+        })(input)
+    }
 }
 
 /// Parse the content of a `"Reply-To:"` header.
 ///
 /// Returns a list of addresses.
 pub fn reply_to<P: UTF8Policy>(i: &[u8]) -> NomResult<Vec<Address>> {
-    address_list_crlf::<P>(i)
+    traced("reply_to", address_list_crlf::<P>)(i)
+}
+
+/// Parse an address list, also returning the position of each address.
+///
+/// Each [`Address`] is paired with the byte range it occupies in
+/// `input`, including its surrounding white space and comments but
+/// not the separating commas. Nothing is consumed after the last
+/// address.
+pub fn address_list_spans<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<(Address, Range<usize>)>> {
+    let (mut rem, first) = address::<P>(input)?;
+    let mut out = vec![(first, 0..input.len() - rem.len())];
+
+    while let Ok((next, parsed)) = preceded(tag(","), address::<P>)(rem) {
+        let start = input.len() - rem.len() + 1;
+        out.push((parsed, start..input.len() - next.len()));
+        rem = next;
+    }
+
+    Ok((rem, out))
+}
+
+#[derive(Clone, Debug)]
+enum RawAddress {
+    Original(Address, Range<usize>),
+    New(Address, Vec<u8>),
+}
+
+impl RawAddress {
+    fn address(&self) -> &Address {
+        match self {
+            RawAddress::Original(address, _) | RawAddress::New(address, _) => address,
+        }
+    }
+}
+
+/// Edit an address list while preserving untouched bytes.
+///
+/// Addresses that are not removed or replaced are written back
+/// exactly as received, including white space, comments and the raw
+/// form of encoded words. Anything following the list, such as the
+/// terminating CRLF, is also copied unmodified.
+///
+/// New addresses are validated and written with a single leading
+/// space. The list always starts with the leading white space of the
+/// original value, whichever address comes first.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::AddressListEditor;
+///
+/// let input = b" =?utf-8?q?J=C3=B6rg?= <jorg@example.org> (boss),\r\n bob@example.org\r\n";
+/// let mut editor = AddressListEditor::<Intl>::new(input).unwrap();
+///
+/// editor.replace(1, b"carol@example.org").unwrap();
+///
+/// assert_eq!(editor.to_bytes(),
+///            b" =?utf-8?q?J=C3=B6rg?= <jorg@example.org> (boss), carol@example.org\r\n".as_ref());
+///
+/// editor.remove(0);
+/// editor.insert(0, b"dave@example.org").unwrap();
+/// assert_eq!(editor.to_bytes(), b" dave@example.org, carol@example.org\r\n".as_ref());
+/// ```
+#[derive(Clone, Debug)]
+pub struct AddressListEditor<'a, P> {
+    input: &'a [u8],
+    addresses: Vec<RawAddress>,
+    lead: usize,
+    end: usize,
+    policy: PhantomData<P>,
+}
+
+fn is_fws_byte(c: &u8) -> bool {
+    b" \t\r\n".contains(c)
+}
+
+impl<'a, P: UTF8Policy> AddressListEditor<'a, P> {
+    /// Split the address list in `input` for editing.
+    pub fn new(input: &'a [u8]) -> Result<Self, nom::Err<NomError<'a>>> {
+        let (_, addresses) = address_list_spans::<P>(input)?;
+        let end = addresses.last().map(|(_, span)| span.end).unwrap_or(0);
+        let lead = addresses.first().map_or(0, |(_, span)| {
+            input[span.clone()]
+                .iter()
+                .take_while(|c| is_fws_byte(c))
+                .count()
+        });
+
+        Ok(AddressListEditor {
+            input,
+            addresses: addresses
+                .into_iter()
+                .map(|(address, span)| RawAddress::Original(address, span))
+                .collect(),
+            lead,
+            end,
+            policy: PhantomData,
+        })
+    }
+
+    /// Number of addresses.
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    /// Returns `true` if there are no addresses left.
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    /// Return the parsed address at `index`.
+    pub fn get(&self, index: usize) -> Option<&Address> {
+        self.addresses.get(index).map(RawAddress::address)
+    }
+
+    /// Iterate over the parsed addresses.
+    pub fn iter(&self) -> impl Iterator<Item = &Address> {
+        self.addresses.iter().map(RawAddress::address)
+    }
+
+    /// Remove the address at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Address {
+        match self.addresses.remove(index) {
+            RawAddress::Original(address, _) | RawAddress::New(address, _) => address,
+        }
+    }
+
+    /// Insert a new address at `index`.
+    ///
+    /// `address` must be a single valid address.
+    ///
+    /// # Panics
+    /// Panics if `index > len`.
+    pub fn insert<'b>(
+        &mut self,
+        index: usize,
+        address: &'b [u8],
+    ) -> Result<(), nom::Err<NomError<'b>>> {
+        let entry = Self::render(address)?;
+        self.addresses.insert(index, entry);
+        Ok(())
+    }
+
+    /// Append a new address after all the existing ones.
+    pub fn push<'b>(&mut self, address: &'b [u8]) -> Result<(), nom::Err<NomError<'b>>> {
+        self.insert(self.addresses.len(), address)
+    }
+
+    /// Replace the address at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn replace<'b>(
+        &mut self,
+        index: usize,
+        address: &'b [u8],
+    ) -> Result<(), nom::Err<NomError<'b>>> {
+        self.addresses[index] = Self::render(address)?;
+        Ok(())
+    }
+
+    fn render(raw: &[u8]) -> Result<RawAddress, nom::Err<NomError>> {
+        let (_, parsed) = all_consuming(address::<P>)(raw)?;
+        let mut out = Vec::with_capacity(raw.len() + 1);
+        out.push(b' ');
+        out.extend_from_slice(raw);
+
+        Ok(RawAddress::New(parsed, out))
+    }
+
+    /// Rebuild the address list with all edits applied.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.input.len());
+
+        out.extend_from_slice(&self.input[..self.lead]);
+        for (i, address) in self.addresses.iter().enumerate() {
+            let bytes = match address {
+                RawAddress::Original(_, span) => &self.input[span.clone()],
+                RawAddress::New(_, bytes) => &bytes[..],
+            };
+            let skip = if i > 0 {
+                out.push(b',');
+                0
+            } else {
+                bytes.iter().take_while(|c| is_fws_byte(c)).count()
+            };
+            out.extend_from_slice(&bytes[skip..]);
+        }
+        out.extend_from_slice(&self.input[self.end..]);
+
+        out
+    }
+}
+
+/// A date and time such as found in the `"Date:"` header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DateTime {
+    /// The day of the week from 1 for Monday to 7 for Sunday, if
+    /// present.
+    pub day_of_week: Option<u8>,
+    /// The year. Obsolete two and three digit years are converted
+    /// according to [RFC 5322].
+    ///
+    /// [RFC 5322]: https://tools.ietf.org/html/rfc5322#section-4.3
+    pub year: u16,
+    /// The month from 1 to 12.
+    pub month: u8,
+    /// The day of the month from 1 to 31.
+    pub day: u8,
+    /// The hour from 0 to 23.
+    pub hour: u8,
+    /// The minute from 0 to 59.
+    pub minute: u8,
+    /// The second from 0 to 60, 0 if omitted.
+    pub second: u8,
+    /// The offset from UTC in minutes.
+    ///
+    /// Obsolete military zones are interpreted as `"-0000"` and give
+    /// 0, as required by the RFC.
+    pub offset: i16,
+}
+
+#[cfg(feature = "chrono")]
+impl DateTime {
+    /// Convert to a [`chrono::DateTime`] with the same offset.
+    ///
+    /// A second of 60 is kept as a leap second. The day of the week is
+    /// not checked. Returns `None` for an invalid date such as
+    /// February 30.
+    /// # Examples
+    /// ```
+    /// use rustyknife::behaviour::Intl;
+    /// use rustyknife::rfc5322::date_time;
+    ///
+    /// let (_, date) = date_time::<Intl>(b" Fri, 21 Nov 1997 09:55:06 -0600").unwrap();
+    /// let converted = date.to_chrono().unwrap();
+    ///
+    /// assert_eq!(converted.timestamp(), 880127706);
+    /// assert_eq!(converted.offset().local_minus_utc(), -6 * 3600);
+    ///
+    /// let (_, date) = date_time::<Intl>(b" 30 Feb 2020 00:00 +0000").unwrap();
+    /// assert!(date.to_chrono().is_none());
+    /// ```
+    pub fn to_chrono(&self) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        let offset = chrono::FixedOffset::east_opt(i32::from(self.offset) * 60)?;
+        let (second, nano) = if self.second == 60 {
+            (59, 1_000_000_000)
+        } else {
+            (self.second, 0)
+        };
+
+        chrono::NaiveDate::from_ymd_opt(self.year.into(), self.month.into(), self.day.into())?
+            .and_hms_nano_opt(self.hour.into(), self.minute.into(), second.into(), nano)?
+            .and_local_timezone(offset)
+            .single()
+    }
+}
+
+#[cfg(feature = "time")]
+impl DateTime {
+    /// Convert to a [`time::OffsetDateTime`] with the same offset.
+    ///
+    /// The `time` crate has no leap seconds, a second of 60 becomes 59.
+    /// The day of the week is not checked. Returns `None` for an
+    /// invalid date such as February 30.
+    /// # Examples
+    /// ```
+    /// use rustyknife::behaviour::Intl;
+    /// use rustyknife::rfc5322::date_time;
+    ///
+    /// let (_, date) = date_time::<Intl>(b" Fri, 21 Nov 1997 09:55:06 -0600").unwrap();
+    /// let converted = date.to_time().unwrap();
+    ///
+    /// assert_eq!(converted.unix_timestamp(), 880127706);
+    /// assert_eq!(converted.offset().whole_minutes(), -360);
+    /// ```
+    pub fn to_time(&self) -> Option<time::OffsetDateTime> {
+        let month = std::convert::TryFrom::try_from(self.month).ok()?;
+        let date = time::Date::from_calendar_date(self.year.into(), month, self.day).ok()?;
+        let time = time::Time::from_hms(self.hour, self.minute, self.second.min(59)).ok()?;
+        let offset = time::UtcOffset::from_whole_seconds(i32::from(self.offset) * 60).ok()?;
+
+        Some(time::PrimitiveDateTime::new(date, time).assume_offset(offset))
+    }
+}
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+fn name_index(names: &'static [&'static str]) -> impl Fn(&[u8]) -> NomResult<u8> {
+    move |input| {
+        map_opt(take(3usize), |n: &[u8]| {
+            names
+                .iter()
+                .position(|name| n.eq_ignore_ascii_case(name.as_bytes()))
+                .map(|i| i as u8 + 1)
+        })(input)
+    }
+}
+
+fn number<T: str::FromStr>(min: usize, max: usize) -> impl Fn(&[u8]) -> NomResult<T> {
+    move |input| {
+        map_opt(take_while_m_n(min, max, is_digit), |n| {
+            str::from_utf8(n).unwrap().parse().ok()
+        })(input)
+    }
+}
+
+fn padded<'a, P: UTF8Policy, O, F>(parser: F) -> impl FnMut(&'a [u8]) -> NomResult<'a, O>
+where
+    F: FnMut(&'a [u8]) -> NomResult<'a, O>,
+{
+    delimited(opt(cfws::<P>), parser, opt(cfws::<P>))
+}
+
+fn year(input: &[u8]) -> NomResult<u16> {
+    map(recognize(take_while_m_n(2, 4, is_digit)), |y: &[u8]| {
+        let value = str::from_utf8(y).unwrap().parse::<u16>().unwrap();
+        match y.len() {
+            2 if value < 50 => value + 2000,
+            2 | 3 => value + 1900,
+            _ => value,
+        }
+    })(input)
+}
+
+fn obs_zone(input: &[u8]) -> NomResult<i16> {
+    alt((
+        map(alt((tag_no_case("UT"), tag_no_case("GMT"))), |_| 0),
+        map(tag_no_case("EDT"), |_| -4 * 60),
+        map(alt((tag_no_case("EST"), tag_no_case("CDT"))), |_| -5 * 60),
+        map(alt((tag_no_case("CST"), tag_no_case("MDT"))), |_| -6 * 60),
+        map(alt((tag_no_case("MST"), tag_no_case("PDT"))), |_| -7 * 60),
+        map(tag_no_case("PST"), |_| -8 * 60),
+        map(
+            take1_filter(|c| c.is_ascii_alphabetic() && !matches!(c, b'J' | b'j')),
+            |_| 0,
+        ),
+    ))(input)
+}
+
+fn zone(input: &[u8]) -> NomResult<i16> {
+    alt((
+        map(
+            pair(
+                alt((tag("+"), tag("-"))),
+                pair(
+                    number::<i16>(2, 2),
+                    verify(number::<i16>(2, 2), |m| *m < 60),
+                ),
+            ),
+            |(sign, (h, m))| {
+                if sign == b"-" {
+                    -(h * 60 + m)
+                } else {
+                    h * 60 + m
+                }
+            },
+        ),
+        obs_zone,
+    ))(input)
+}
+
+fn _date_time<P: UTF8Policy>(input: &[u8]) -> NomResult<DateTime> {
+    map(
+        pair(
+            opt(terminated(
+                padded::<P, _, _>(name_index(&DAY_NAMES)),
+                tag(","),
+            )),
+            pair(
+                pair(
+                    padded::<P, _, _>(verify(number::<u8>(1, 2), |d| (1..=31).contains(d))),
+                    pair(
+                        padded::<P, _, _>(name_index(&MONTH_NAMES)),
+                        padded::<P, _, _>(year),
+                    ),
+                ),
+                pair(
+                    pair(
+                        padded::<P, _, _>(verify(number::<u8>(2, 2), |h| *h < 24)),
+                        pair(
+                            preceded(
+                                tag(":"),
+                                padded::<P, _, _>(verify(number::<u8>(2, 2), |m| *m < 60)),
+                            ),
+                            opt(preceded(
+                                tag(":"),
+                                padded::<P, _, _>(verify(number::<u8>(2, 2), |s| *s <= 60)),
+                            )),
+                        ),
+                    ),
+                    padded::<P, _, _>(zone),
+                ),
+            ),
+        ),
+        |(day_of_week, ((day, (month, year)), ((hour, (minute, second)), offset)))| DateTime {
+            day_of_week,
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second: second.unwrap_or(0),
+            offset,
+        },
+    )(input)
+}
+
+/// Parse the content of a `"Date:"` header.
+///
+/// Obsolete syntax such as comments between the components, two
+/// digit years and named time zones is accepted.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::date_time;
+///
+/// let (_, date) = date_time::<Intl>(b" Fri, 21 Nov 1997 09:55:06 -0600").unwrap();
+/// assert_eq!((date.year, date.month, date.day), (1997, 11, 21));
+/// assert_eq!((date.hour, date.minute, date.second), (9, 55, 6));
+/// assert_eq!((date.day_of_week, date.offset), (Some(5), -360));
+///
+/// let (_, date) = date_time::<Intl>(b" 1 Jan 98 23:59 EST (Eastern)").unwrap();
+/// assert_eq!((date.year, date.offset), (1998, -300));
+/// ```
+pub fn date_time<P: UTF8Policy>(input: &[u8]) -> NomResult<DateTime> {
+    traced("date_time", terminated(_date_time::<P>, opt(crlf)))(input)
+}
+
+/// A message identifier such as found in the `"Message-ID:"` header.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MessageId {
+    left: String,
+    right: String,
+}
+
+impl MessageId {
+    /// Return the part to the left of the `"@"`.
+    pub fn left(&self) -> &str {
+        &self.left
+    }
+
+    /// Return the part to the right of the `"@"`.
+    pub fn right(&self) -> &str {
+        &self.right
+    }
+}
+
+impl fmt::Display for MessageId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{}@{}>", self.left, self.right)
+    }
+}
+
+fn dot_atom_text<P: UTF8Policy>(input: &[u8]) -> NomResult<&[u8]> {
+    recognize(pair(
+        recognize_many1(P::atext),
+        many0(pair(tag("."), recognize_many1(P::atext))),
+    ))(input)
+}
+
+fn no_fold_literal<P: UTF8Policy>(input: &[u8]) -> NomResult<&[u8]> {
+    recognize(delimited(tag("["), many0(P::dtext), tag("]")))(input)
+}
+
+fn utf8_string(input: &[u8]) -> String {
+    String::from_utf8_lossy(input).into_owned()
+}
+
+fn _msg_id<P: UTF8Policy>(input: &[u8]) -> NomResult<MessageId> {
+    map(
+        delimited(
+            pair(opt(cfws::<P>), tag("<")),
+            separated_pair(
+                alt((
+                    terminated(map(dot_atom_text::<P>, utf8_string), peek(tag("@"))),
+                    map(local_part::<P>, |lp| lp.to_string()),
+                )),
+                tag("@"),
+                alt((
+                    terminated(
+                        map(alt((dot_atom_text::<P>, no_fold_literal::<P>)), utf8_string),
+                        peek(tag(">")),
+                    ),
+                    map(domain::<P>, |d| d.to_string()),
+                )),
+            ),
+            pair(tag(">"), opt(cfws::<P>)),
+        ),
+        |(left, right)| MessageId { left, right },
+    )(input)
+}
+
+/// Parse the content of a `"Message-ID:"` header.
+///
+/// The obsolete syntax allowing comments and quoted strings is
+/// accepted.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::msg_id;
+///
+/// let (_, id) = msg_id::<Intl>(b" <1234.5678@mail.example.org>").unwrap();
+/// assert_eq!((id.left(), id.right()), ("1234.5678", "mail.example.org"));
+/// assert_eq!(id.to_string(), "<1234.5678@mail.example.org>");
+/// ```
+pub fn msg_id<P: UTF8Policy>(input: &[u8]) -> NomResult<MessageId> {
+    traced("msg_id", terminated(_msg_id::<P>, opt(crlf)))(input)
+}
+
+// Message identifiers mixed with the phrases allowed by the obsolete
+// syntax, at least one identifier is required.
+fn msg_id_list<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<MessageId>> {
+    verify(
+        map(
+            many1(alt((map(_msg_id::<P>, Some), map(word::<P>, |_| None)))),
+            |ids| ids.into_iter().flatten().collect(),
+        ),
+        |ids: &Vec<_>| !ids.is_empty(),
+    )(input)
+}
+
+/// Parse the content of an `"In-Reply-To:"` header.
+///
+/// The obsolete syntax mixing phrases with the identifiers is
+/// accepted, the phrases are dropped.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::in_reply_to;
+///
+/// let (_, ids) = in_reply_to::<Intl>(b" <a.1@example.org>\r\n <b.2@[192.0.2.1]>").unwrap();
+/// assert_eq!(ids[1].right(), "[192.0.2.1]");
+///
+/// let (_, ids) = in_reply_to::<Intl>(b" Your message of Monday <a.1@example.org>").unwrap();
+/// assert_eq!(ids[0].left(), "a.1");
+/// ```
+pub fn in_reply_to<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<MessageId>> {
+    traced("in_reply_to", terminated(msg_id_list::<P>, opt(crlf)))(input)
+}
+
+/// Parse the content of a `"References:"` header.
+///
+/// The identifiers are returned in order, oldest first. The obsolete
+/// syntax is accepted as for [`in_reply_to`].
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::references;
+///
+/// let (_, ids) = references::<Intl>(b" <root@example.org>\r\n\t<reply@example.org> <last@example.org>").unwrap();
+/// let ids: Vec<_> = ids.iter().map(|id| id.to_string()).collect();
+/// assert_eq!(ids, ["<root@example.org>", "<reply@example.org>", "<last@example.org>"]);
+/// ```
+pub fn references<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<MessageId>> {
+    traced("references", terminated(msg_id_list::<P>, opt(crlf)))(input)
+}
+
+/// A host named in the `from` or `by` clause of a `"Received:"`
+/// header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceHost {
+    /// The name as written. For `from`, usually the HELO argument.
+    pub name: String,
+    /// The name as a domain or address literal, `None` if invalid.
+    pub domain: Option<DomainPart>,
+    /// The comment following the name, holding the TCP information
+    /// such as `"mx.example.org [192.0.2.1]"` in most MTAs.
+    pub comment: Option<String>,
+    /// The IP address from the name or the first address literal of
+    /// the comment.
+    pub ip: Option<IpAddr>,
+}
+
+impl TraceHost {
+    fn new(name: &str, comment: Option<String>) -> Self {
+        let domain = DomainPart::from_smtp(name.as_bytes()).ok();
+        let literal_ip = |d: &DomainPart| match d {
+            DomainPart::Address(AddressLiteral::IP(ip)) => Some(*ip),
+            _ => None,
+        };
+        let ip = domain.as_ref().and_then(literal_ip).or_else(|| {
+            let comment = comment.as_deref()?;
+            comment.match_indices('[').find_map(|(start, _)| {
+                let end = start + comment[start..].find(']')?;
+                literal_ip(&DomainPart::from_smtp(&comment.as_bytes()[start..=end]).ok()?)
+            })
+        });
+
+        TraceHost {
+            name: name.into(),
+            domain,
+            comment,
+            ip,
+        }
+    }
+}
+
+/// The content of a `"Received:"` trace header.
+///
+/// The clauses are described in [RFC 5321]. Clauses that are missing
+/// or have no value are `None`.
+///
+/// [RFC 5321]: https://tools.ietf.org/html/rfc5321#section-4.4
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Received {
+    /// The text preceding the `";"`, unfolded and trimmed.
+    pub tokens: String,
+    /// The date and time following the last `";"`.
+    ///
+    /// `None` for the obsolete syntax without a date.
+    pub date_time: Option<DateTime>,
+    /// The `from` clause, the client.
+    pub from: Option<TraceHost>,
+    /// The `by` clause, the server adding the header.
+    pub by: Option<TraceHost>,
+    /// The `via` clause, the link type.
+    pub via: Option<String>,
+    /// The `with` clause, the protocol such as `"ESMTPS"`.
+    pub with: Option<String>,
+    /// The `id` clause, the queue identifier.
+    pub id: Option<String>,
+    /// The address of the `for` clause, `None` if invalid.
+    pub recipient: Option<types::Mailbox>,
+}
+
+enum TraceItem<'a> {
+    Word(&'a str),
+    Comment(String),
+}
+
+fn is_trace_keyword(word: &str) -> bool {
+    ["from", "by", "via", "with", "id", "for"]
+        .iter()
+        .any(|k| word.eq_ignore_ascii_case(k))
+}
+
+// Split the tokens into words and comments. Angle addresses and
+// address literals are single words.
+fn trace_items<P: UTF8Policy>(input: &str) -> Vec<TraceItem> {
+    let mut out = Vec::new();
+    let mut rest = input.trim_start();
+
+    while !rest.is_empty() {
+        if rest.starts_with('(') {
+            if let Ok((rem, content)) = _comment::<P>(rest.as_bytes(), false) {
+                let mut text = String::new();
+                flatten_comment(&content, &mut text);
+                out.push(TraceItem::Comment(text));
+                rest = &rest[rest.len() - rem.len()..];
+                rest = rest.trim_start();
+                continue;
+            }
+        }
+        let closing = match rest.as_bytes()[0] {
+            b'<' => rest.find('>'),
+            b'[' => rest.find(']'),
+            _ => None,
+        };
+        let end = match closing {
+            Some(end) => end + 1,
+            None => rest
+                .find(|c: char| c.is_whitespace() || c == '(')
+                .unwrap_or(rest.len())
+                .max(1),
+        };
+        out.push(TraceItem::Word(&rest[..end]));
+        rest = rest[end..].trim_start();
+    }
+
+    out
+}
+
+/// Parse the content of a `"Received:"` header.
+///
+/// The clauses are recognized by their keyword in any order and case.
+/// Unknown words are skipped, they remain in the tokens.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::received;
+///
+/// let (_, parsed) = received::<Intl>(b" from a.example.org\r\n by b.example.org; Tue, 1 Jul 2003 10:52:37 +0200").unwrap();
+/// assert_eq!(parsed.tokens, "from a.example.org by b.example.org");
+/// assert_eq!(parsed.date_time.unwrap().year, 2003);
+///
+/// let (_, parsed) = received::<Intl>(b" from helo.example (mail.example.org [192.0.2.1])\r\n \
+///                                      by mx.example.com (Postfix) with ESMTPS id 4F2B31C0\r\n \
+///                                      for <bob@example.com>; Mon, 3 Jun 2024 10:00:00 +0000").unwrap();
+/// let from = parsed.from.unwrap();
+/// assert_eq!(from.name, "helo.example");
+/// assert_eq!(from.ip.unwrap().to_string(), "192.0.2.1");
+/// assert_eq!(parsed.by.unwrap().comment.as_deref(), Some("Postfix"));
+/// assert_eq!(parsed.with.as_deref(), Some("ESMTPS"));
+/// assert_eq!(parsed.id.as_deref(), Some("4F2B31C0"));
+/// assert_eq!(parsed.recipient.unwrap().to_string(), "bob@example.com");
+/// ```
+pub fn received<P: UTF8Policy>(input: &[u8]) -> NomResult<Received> {
+    let (tokens, date) = match input.iter().rposition(|c| *c == b';') {
+        Some(pos) => {
+            let (rem, date) = date_time::<P>(&input[pos + 1..])?;
+            if !rem.is_empty() {
+                return Err(nom::Err::Error(()));
+            }
+            (&input[..pos], Some(date))
+        }
+        None => (input, None),
+    };
+    let tokens = tokens.strip_suffix(b"\r\n").unwrap_or(tokens);
+    let unfolded: Vec<u8> = tokens
+        .iter()
+        .copied()
+        .filter(|c| !matches!(c, b'\r' | b'\n'))
+        .collect();
+
+    let tokens = utf8_string(&unfolded).trim().to_string();
+
+    let mut out = Received {
+        tokens: String::new(),
+        date_time: date,
+        from: None,
+        by: None,
+        via: None,
+        with: None,
+        id: None,
+        recipient: None,
+    };
+    let items = trace_items::<P>(&tokens);
+    for (i, pair) in items.windows(2).enumerate() {
+        let (keyword, value) = match pair {
+            [TraceItem::Word(k), TraceItem::Word(v)] if !is_trace_keyword(v) => {
+                (k.to_ascii_lowercase(), *v)
+            }
+            _ => continue,
+        };
+        let comment = match items.get(i + 2) {
+            Some(TraceItem::Comment(c)) => Some(c.clone()),
+            _ => None,
+        };
+
+        match keyword.as_str() {
+            "from" if out.from.is_none() => out.from = Some(TraceHost::new(value, comment)),
+            "by" if out.by.is_none() => out.by = Some(TraceHost::new(value, comment)),
+            "via" => out.via = Some(value.into()),
+            "with" => out.with = Some(value.into()),
+            "id" => out.id = Some(value.into()),
+            "for" => {
+                let address = value.strip_prefix('<').unwrap_or(value);
+                let address = address.strip_suffix('>').unwrap_or(address);
+                out.recipient = types::Mailbox::from_smtp(address.as_bytes()).ok();
+            }
+            _ => (),
+        }
+    }
+    out.tokens = tokens;
+
+    Ok((&input[input.len()..], out))
+}
+
+/// A header value parsed according to its name by [`parse_header`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParsedHeader<'a> {
+    /// `"From:"`, `"To:"`, `"Cc:"`, `"Bcc:"`, `"Reply-To:"`,
+    /// `"Sender:"` and their `"Resent-"` counterparts.
+    AddressList(Vec<Address>),
+    /// `"Date:"` and `"Resent-Date:"`.
+    Date(DateTime),
+    /// `"Message-ID:"`, `"Resent-Message-ID:"` and `"Content-ID:"`.
+    MessageId(MessageId),
+    /// `"In-Reply-To:"` and `"References:"`.
+    MessageIds(Vec<MessageId>),
+    /// `"Subject:"`, `"Comments:"` and `"Content-Description:"`, with
+    /// the leading white space removed.
+    Unstructured(String),
+    /// `"Content-Type:"`.
+    MimeType(MimeType),
+    /// `"Received:"`.
+    Received(Box<Received>),
+    /// Any other header or a value that could not be parsed.
+    Raw(Cow<'a, [u8]>),
+}
+
+impl ParsedHeader<'_> {
+    /// Copy a borrowed raw value so the result outlives the input.
+    pub fn into_owned(self) -> ParsedHeader<'static> {
+        match self {
+            ParsedHeader::AddressList(a) => ParsedHeader::AddressList(a),
+            ParsedHeader::Date(d) => ParsedHeader::Date(d),
+            ParsedHeader::MessageId(m) => ParsedHeader::MessageId(m),
+            ParsedHeader::MessageIds(m) => ParsedHeader::MessageIds(m),
+            ParsedHeader::Unstructured(u) => ParsedHeader::Unstructured(u),
+            ParsedHeader::MimeType(m) => ParsedHeader::MimeType(m),
+            ParsedHeader::Received(r) => ParsedHeader::Received(r),
+            ParsedHeader::Raw(r) => ParsedHeader::Raw(Cow::Owned(r.into_owned())),
+        }
+    }
+}
+
+fn complete<'a, O, F>(mut parser: F, value: &'a [u8]) -> Option<O>
+where
+    F: FnMut(&'a [u8]) -> NomResult<'a, O>,
+{
+    match parser(value) {
+        Ok((rem, out)) if rem.iter().all(|c| c.is_ascii_whitespace()) => Some(out),
+        _ => None,
+    }
+}
+
+fn _parse_header<'a, P: UTF8Policy>(
+    name: KnownHeader,
+    value: &'a [u8],
+) -> Option<ParsedHeader<'a>> {
+    use KnownHeader::*;
+
+    Some(match name {
+        Bcc | ResentBcc if value.iter().all(|c| c.is_ascii_whitespace()) => {
+            ParsedHeader::AddressList(Vec::new())
+        }
+        From | To | Cc | Bcc | ReplyTo | ResentFrom | ResentTo | ResentCc | ResentBcc => {
+            ParsedHeader::AddressList(complete(address_list_crlf::<P>, value)?)
+        }
+        Sender | ResentSender => {
+            ParsedHeader::AddressList(vec![complete(address_crlf::<P>, value)?])
+        }
+        Date | ResentDate => ParsedHeader::Date(complete(date_time::<P>, value)?),
+        MessageId | ResentMessageId | ContentId => {
+            ParsedHeader::MessageId(complete(msg_id::<P>, value)?)
+        }
+        InReplyTo => ParsedHeader::MessageIds(complete(in_reply_to::<P>, value)?),
+        References => ParsedHeader::MessageIds(complete(references::<P>, value)?),
+        Subject | Comments | ContentDescription => {
+            ParsedHeader::Unstructured(complete(unstructured::<P>, value)?.trim_start().into())
+        }
+        ContentType => ParsedHeader::MimeType(complete(content_type, value)?.into()),
+        Received => ParsedHeader::Received(Box::new(complete(received::<P>, value)?)),
+        _ => return None,
+    })
+}
+
+/// Parse a header value according to the header name.
+///
+/// `name` is compared case insensitively. Unknown headers and values
+/// that do not parse are returned as [`ParsedHeader::Raw`].
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::{parse_header, ParsedHeader};
+///
+/// match parse_header::<Intl>(b"subject", b" =?utf-8?Q?caf=C3=A9?=") {
+///     ParsedHeader::Unstructured(text) => assert_eq!(text, "café"),
+///     other => panic!("unexpected {:?}", other),
+/// }
+/// assert!(matches!(parse_header::<Intl>(b"Date", b" 1 Jan 2020 00:00 +0000"), ParsedHeader::Date(_)));
+/// assert_eq!(parse_header::<Intl>(b"Date", b" yesterday"), ParsedHeader::Raw(b" yesterday".as_ref().into()));
+/// assert_eq!(parse_header::<Intl>(b"X-Spam", b" yes"), ParsedHeader::Raw(b" yes".as_ref().into()));
+/// ```
+pub fn parse_header<'a, P: UTF8Policy>(name: &[u8], value: &'a [u8]) -> ParsedHeader<'a> {
+    KnownHeader::from_name(name)
+        .and_then(|known| _parse_header::<P>(known, value))
+        .unwrap_or(ParsedHeader::Raw(Cow::Borrowed(value)))
+}
+
+/// Extract the addresses of all `"Delivered-To:"` headers.
+///
+/// Values that are not a valid address, with or without angle
+/// brackets, are skipped.
+pub fn delivered_to<P: UTF8Policy>(headers: &[HeaderField]) -> Vec<types::Mailbox> {
+    headers
+        .iter()
+        .filter_map(|h| match h {
+            Ok((name, value)) if KnownHeader::from_name(name) == Some(KnownHeader::DeliveredTo) => {
+                complete(alt((addr_spec::<P>, angle_addr::<P>)), value)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Check whether a message was already delivered to `recipient`.
+///
+/// Returns `true` if a `"Delivered-To:"` header holds an address
+/// equal to `recipient` according to [`types::Mailbox::semantic_eq`],
+/// meaning that delivering the message again would create a loop.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::headersection::header_section;
+/// use rustyknife::rfc5322::is_delivery_loop;
+/// use rustyknife::types::Mailbox;
+///
+/// let (_, headers) = header_section(b"Delivered-To: \"list\"@Example.ORG\r\nDelivered-To: <bob@example.org>\r\n\r\n").unwrap();
+///
+/// assert!(is_delivery_loop::<Intl>(&headers, &Mailbox::from_smtp(b"list@example.org").unwrap()));
+/// assert!(is_delivery_loop::<Intl>(&headers, &Mailbox::from_smtp(b"bob@example.org").unwrap()));
+/// assert!(!is_delivery_loop::<Intl>(&headers, &Mailbox::from_smtp(b"alice@example.org").unwrap()));
+/// ```
+pub fn is_delivery_loop<P: UTF8Policy>(
+    headers: &[HeaderField],
+    recipient: &types::Mailbox,
+) -> bool {
+    delivered_to::<P>(headers)
+        .iter()
+        .any(|address| address.semantic_eq(recipient))
 }
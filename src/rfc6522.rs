@@ -0,0 +1,128 @@
+//! [Multipart/Report] media type
+//!
+//! Locates the parts of delivery status notifications, feedback
+//! reports and message disposition notifications.
+//!
+//! [Multipart/Report]: https://tools.ietf.org/html/rfc6522
+
+use std::fmt::{self, Display};
+
+use crate::mime::Entity;
+
+/// The `report-type` parameter of a `"multipart/report"` entity.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ReportType {
+    /// `delivery-status`, a delivery status notification.
+    DeliveryStatus,
+    /// `feedback-report`, an abuse feedback report.
+    FeedbackReport,
+    /// `disposition-notification`, a message disposition notification.
+    DispositionNotification,
+    /// Any other report type, stored in lowercase.
+    Other(String),
+}
+
+impl From<&str> for ReportType {
+    fn from(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "delivery-status" => ReportType::DeliveryStatus,
+            "feedback-report" => ReportType::FeedbackReport,
+            "disposition-notification" => ReportType::DispositionNotification,
+            other => ReportType::Other(other.into()),
+        }
+    }
+}
+
+impl Display for ReportType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReportType::DeliveryStatus => write!(f, "delivery-status"),
+            ReportType::FeedbackReport => write!(f, "feedback-report"),
+            ReportType::DispositionNotification => write!(f, "disposition-notification"),
+            ReportType::Other(t) => write!(f, "{}", t),
+        }
+    }
+}
+
+/// The parts of a `"multipart/report"` entity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report<'e, 'a> {
+    /// The report type, `None` if the parameter is missing.
+    pub report_type: Option<ReportType>,
+    /// The human readable part, always the first body part.
+    pub human: Option<&'e Entity<'a>>,
+    /// The machine readable report such as `"message/delivery-status"`.
+    pub machine: Option<&'e Entity<'a>>,
+    /// The returned message or its header section.
+    pub original: Option<&'e Entity<'a>>,
+}
+
+fn is_machine(report_type: &str, mime_type: &str) -> bool {
+    mime_type
+        .strip_prefix("message/")
+        .map(|t| t == report_type || t.strip_prefix("global-") == Some(report_type))
+        .unwrap_or(false)
+}
+
+fn is_original(mime_type: &str) -> bool {
+    matches!(
+        mime_type,
+        "message/rfc822" | "message/global" | "text/rfc822-headers" | "message/global-headers"
+    )
+}
+
+/// Locate the parts of a `"multipart/report"` entity.
+///
+/// The machine readable part is found by matching its type against the
+/// `report-type` parameter, including the `"message/global-*"`
+/// variants. Returns `None` if `entity` is not a `"multipart/report"`.
+/// # Examples
+/// ```
+/// use rustyknife::mime::parse_message;
+/// use rustyknife::rfc6522::{multipart_report, ReportType};
+///
+/// let input = b"Content-Type: multipart/report; report-type=delivery-status; boundary=b\r\n\r\n\
+///               --b\r\n\r\nYour message could not be delivered.\r\n\
+///               --b\r\nContent-Type: message/delivery-status\r\n\r\nReporting-MTA: dns; mx.example.org\r\n\r\n\
+///               --b\r\nContent-Type: text/rfc822-headers\r\n\r\nSubject: hi\r\n\r\n--b--\r\n";
+/// let message = parse_message(input);
+/// let report = multipart_report(&message).unwrap();
+///
+/// assert_eq!(report.report_type, Some(ReportType::DeliveryStatus));
+/// assert!(report.human.unwrap().is("text/plain"));
+/// assert!(report.machine.unwrap().is("message/delivery-status"));
+/// assert!(report.original.unwrap().is("text/rfc822-headers"));
+/// ```
+pub fn multipart_report<'e, 'a>(entity: &'e Entity<'a>) -> Option<Report<'e, 'a>> {
+    if !entity.is("multipart/report") {
+        return None;
+    }
+    let report_type = entity
+        .content_type
+        .param("report-type")
+        .map(ReportType::from);
+    let parts = entity.parts();
+
+    let expected = report_type
+        .as_ref()
+        .map(|t| t.to_string())
+        .unwrap_or_default();
+    let machine = parts
+        .iter()
+        .skip(1)
+        .position(|p| is_machine(&expected, &p.content_type.mime_type))
+        .map(|i| i + 1)
+        .or((parts.len() > 1).then_some(1));
+    let after = machine.map(|i| i + 1).unwrap_or(1);
+    let original = parts[after.min(parts.len())..]
+        .iter()
+        .find(|p| is_original(&p.content_type.mime_type))
+        .or_else(|| parts.get(after));
+
+    Some(Report {
+        report_type,
+        human: parts.first(),
+        machine: machine.map(|i| &parts[i]),
+        original,
+    })
+}
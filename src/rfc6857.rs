@@ -0,0 +1,108 @@
+//! [Downgraded] internationalized headers
+//!
+//! A message with UTF-8 headers delivered to a system without
+//! SMTPUTF8 support may have its non-ASCII headers renamed with a
+//! `"Downgraded-"` prefix and their original value wrapped in
+//! [RFC 2047] encoded words, as in
+//! `"Downgraded-Message-Id: =?UTF-8?Q?<id@=E4=BE=8B>?="`. The functions
+//! of this module restore the original headers.
+//!
+//! [Downgraded]: https://tools.ietf.org/html/rfc6857#section-3.2
+//! [RFC 2047]: https://tools.ietf.org/html/rfc2047
+
+use crate::behaviour::Intl;
+use crate::headersection::{HeaderField, OwnedHeaderField};
+use crate::rfc5322::unstructured;
+use crate::{parse_exact, Trailing};
+
+/// Prefix of the downgraded header names.
+pub const PREFIX: &str = "Downgraded-";
+
+/// The downgraded headers defined by [RFC 6857].
+///
+/// [RFC 6857]: https://tools.ietf.org/html/rfc6857#section-4
+pub const DOWNGRADED_HEADERS: &[&str] = &[
+    "Downgraded-Message-Id",
+    "Downgraded-Resent-Message-Id",
+    "Downgraded-In-Reply-To",
+    "Downgraded-References",
+    "Downgraded-Original-Recipient",
+    "Downgraded-Final-Recipient",
+];
+
+/// Return the original name of a downgraded header.
+///
+/// Any header starting with [`PREFIX`] is accepted, not only the ones
+/// of [`DOWNGRADED_HEADERS`].
+/// # Examples
+/// ```
+/// use rustyknife::rfc6857::original_name;
+///
+/// assert_eq!(original_name(b"downgraded-in-reply-to"), Some("in-reply-to"));
+/// assert_eq!(original_name(b"In-Reply-To"), None);
+/// ```
+pub fn original_name(name: &[u8]) -> Option<&str> {
+    let (prefix, rest) = (name.get(..PREFIX.len())?, &name[PREFIX.len()..]);
+
+    if !prefix.eq_ignore_ascii_case(PREFIX.as_bytes()) || rest.is_empty() {
+        return None;
+    }
+    std::str::from_utf8(rest).ok()
+}
+
+/// Restore a downgraded header.
+///
+/// Returns the original header name and value, decoded from its
+/// encoded words. The value keeps the leading white space of the
+/// header so it can be given to the other parsers of this crate.
+/// Returns `None` if `name` is not a downgraded header or the value
+/// is not entirely unstructured text.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::msg_id;
+/// use rustyknife::rfc6857::upgrade;
+///
+/// let (name, value) = upgrade(b"Downgraded-Message-Id", b" =?UTF-8?Q?<id@=E4=BE=8B.example>?=").unwrap();
+///
+/// assert_eq!(name, "Message-Id");
+/// assert_eq!(value, " <id@例.example>");
+/// assert_eq!(msg_id::<Intl>(value.as_bytes()).unwrap().1.to_string(), "<id@例.example>");
+///
+/// assert_eq!(upgrade(b"Downgraded-Message-Id", b" <id@example.org>\x00<x@y>"), None);
+/// ```
+pub fn upgrade(name: &[u8], value: &[u8]) -> Option<(String, String)> {
+    let name = original_name(name)?;
+    let value = parse_exact(unstructured::<Intl>, value, Trailing::Whitespace).ok()?;
+
+    Some((name.into(), value))
+}
+
+/// Restore the downgraded headers of a header section.
+///
+/// Downgraded headers are replaced in place by the original header,
+/// other headers are copied. The restored values are UTF-8 encoded.
+/// # Examples
+/// ```
+/// use rustyknife::headersection::header_section;
+/// use rustyknife::rfc6857::upgrade_headers;
+///
+/// let input = b"Subject: hi\r\nDowngraded-In-Reply-To: =?UTF-8?Q?<a@=C3=A9.example>?=\r\n\r\n";
+/// let (_, headers) = header_section(input).unwrap();
+///
+/// let upgraded = upgrade_headers(&headers);
+/// assert_eq!(upgraded[0], Ok((b"Subject".to_vec(), b" hi".to_vec())));
+/// assert_eq!(upgraded[1], Ok((b"In-Reply-To".to_vec(), " <a@é.example>".into())));
+/// ```
+pub fn upgrade_headers(headers: &[HeaderField]) -> Vec<OwnedHeaderField> {
+    headers
+        .iter()
+        .map(|field| match field {
+            Ok((name, value)) => Ok(match upgrade(name, value) {
+                Some((name, value)) => (name.into_bytes(), value.into_bytes()),
+                None => (name.to_vec(), value.to_vec()),
+            }),
+            Err(invalid) => Err(invalid.to_vec()),
+        })
+        .collect()
+}
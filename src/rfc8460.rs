@@ -0,0 +1,155 @@
+//! [SMTP TLS Reporting] policy record
+//!
+//! Parses the `"_smtp._tls"` TXT record that publishes where TLS
+//! failure reports are sent.
+//!
+//! [SMTP TLS Reporting]: https://tools.ietf.org/html/rfc8460#section-3
+
+use nom::bytes::complete::{tag, take_while1};
+use nom::combinator::{map, map_opt};
+use nom::multi::{many0, separated_list1};
+use nom::sequence::tuple;
+
+use crate::rfc3461::hexpair;
+use crate::rfc5234::wsp;
+use crate::tagvalue::{tag_list, TagDuplicates, TagListOptions, Whitespace};
+use crate::util::*;
+
+/// A URI of the `rua` field.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ReportUri {
+    /// A `mailto:` URI, holding the address with percent-encoding
+    /// undone and any query removed.
+    Mailto(String),
+    /// An `https:` URI, holding the whole URI.
+    Https(String),
+    /// A URI with any other scheme, holding the whole URI.
+    Other(String),
+}
+
+/// A parsed TLSRPT record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TlsRptRecord {
+    /// The report destinations in order.
+    pub rua: Vec<ReportUri>,
+    /// Extension fields, names and values as written.
+    pub extensions: Vec<(String, String)>,
+}
+
+// The tag list options shared with MTA-STS records.
+pub(crate) const RECORD_OPTIONS: TagListOptions = TagListOptions {
+    separator: b';',
+    whitespace: Whitespace::Wsp,
+    duplicates: TagDuplicates::Error,
+    name_chars: b"_-.",
+    name_leading_digit: true,
+};
+
+// The ext-name of RFC 8460 and sts-ext-name of RFC 8461, a letter or
+// digit followed by at most 31 characters. tag_list already checked
+// the characters.
+const MAX_EXT_NAME: usize = 32;
+
+// The ext-value of both records: printable characters except "=" and
+// ";", without white space.
+fn is_ext_value(value: &[u8]) -> bool {
+    !value.is_empty()
+        && value
+            .iter()
+            .all(|c| matches!(c, 0x21..=0x7e) && *c != b'=' && *c != b';')
+}
+
+// Validate an extension field of a TLSRPT or MTA-STS record.
+pub(crate) fn extension(name: &str, value: &[u8]) -> Option<(String, String)> {
+    if name.len() > MAX_EXT_NAME || !is_ext_value(value) {
+        return None;
+    }
+    Some((name.to_string(), ascii_to_string(value).into()))
+}
+
+fn percent_decode(input: &[u8]) -> String {
+    let mut out = Vec::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some((c, tail)) = rest.split_first() {
+        match (c, hexpair(tail)) {
+            (b'%', Ok((tail, byte))) => {
+                out.push(byte);
+                rest = tail;
+            }
+            _ => {
+                out.push(*c);
+                rest = tail;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&out).into()
+}
+
+fn report_uri(input: &[u8]) -> NomResult<ReportUri> {
+    map(
+        take_while1(|c| matches!(c, 0x21..=0x7e) && c != b',' && c != b';'),
+        |uri: &[u8]| {
+            let lower = uri.to_ascii_lowercase();
+            if lower.starts_with(b"mailto:") {
+                let address = uri[7..].split(|c| *c == b'?').next().unwrap();
+                ReportUri::Mailto(percent_decode(address))
+            } else if lower.starts_with(b"https:") {
+                ReportUri::Https(ascii_to_string(uri).into())
+            } else {
+                ReportUri::Other(ascii_to_string(uri).into())
+            }
+        },
+    )(input)
+}
+
+fn report_uris(input: &[u8]) -> NomResult<Vec<ReportUri>> {
+    let uri_delim = tuple((many0(wsp), tag(","), many0(wsp)));
+
+    separated_list1(uri_delim, report_uri)(input)
+}
+
+/// Parse a TLSRPT TXT record.
+///
+/// The record must start with `"v=TLSRPTv1"` and have exactly one
+/// `rua` field. Fields may not repeat. Extension names have at most
+/// 32 characters and their values no white space, `"="` or `";"`.
+/// The strings of a TXT record must be concatenated first.
+/// # Examples
+/// ```
+/// use rustyknife::rfc8460::{tlsrpt_record, ReportUri};
+///
+/// let (_, record) = tlsrpt_record(b"v=TLSRPTv1; rua=mailto:tls%2Breports@example.org,https://reports.example.org/v1").unwrap();
+/// assert_eq!(record.rua, [
+///     ReportUri::Mailto("tls+reports@example.org".into()),
+///     ReportUri::Https("https://reports.example.org/v1".into()),
+/// ]);
+///
+/// assert!(tlsrpt_record(b"v=TLSRPTv1; x-ext=1").is_err());
+/// assert!(tlsrpt_record(b"v=tlsrptv1; rua=mailto:a@example.org").is_err());
+/// ```
+pub fn tlsrpt_record(input: &[u8]) -> NomResult<TlsRptRecord> {
+    map_opt(tag_list(RECORD_OPTIONS), |tags| {
+        let (version, fields) = tags.split_first()?;
+        if *version != ("v", b"TLSRPTv1".as_ref()) {
+            return None;
+        }
+
+        let mut rua = None;
+        let mut extensions = Vec::new();
+
+        for &(name, value) in fields {
+            match name {
+                "v" => return None,
+                "rua" => rua = Some(parse_exact(report_uris, value, Trailing::Reject).ok()?),
+                _ => extensions.push(extension(name, value)?),
+            }
+        }
+
+        Some(TlsRptRecord {
+            rua: rua?,
+            extensions,
+        })
+    })(input)
+}
@@ -0,0 +1,70 @@
+//! [MTA-STS] policy record
+//!
+//! Parses the `"_mta-sts"` TXT record that announces an MTA-STS
+//! policy and its version.
+//!
+//! [MTA-STS]: https://tools.ietf.org/html/rfc8461#section-3.1
+
+use nom::combinator::map_opt;
+
+use crate::rfc8460::{extension, RECORD_OPTIONS};
+use crate::tagvalue::tag_list;
+use crate::util::*;
+
+/// A parsed MTA-STS record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StsRecord {
+    /// The policy identifier. A change tells senders to fetch the
+    /// policy again.
+    pub id: String,
+    /// Extension fields, names and values as written.
+    pub extensions: Vec<(String, String)>,
+}
+
+/// Parse an MTA-STS TXT record.
+///
+/// The record must start with `"v=STSv1"` and have exactly one `id`
+/// field of 1 to 32 letters and digits. Fields may not repeat.
+/// Extensions follow the same rules as in
+/// [`tlsrpt_record`](crate::rfc8460::tlsrpt_record). The
+/// strings of a TXT record must be concatenated first.
+/// # Examples
+/// ```
+/// use rustyknife::rfc8461::mta_sts_record;
+///
+/// let (_, record) = mta_sts_record(b"v=STSv1; id=20240601T120000;").unwrap();
+/// assert_eq!(record.id, "20240601T120000");
+/// assert!(record.extensions.is_empty());
+///
+/// assert!(mta_sts_record(b"v=STSv1; id=1; id=2").is_err());
+/// assert!(mta_sts_record(b"v=spf1 -all").is_err());
+/// ```
+pub fn mta_sts_record(input: &[u8]) -> NomResult<StsRecord> {
+    map_opt(tag_list(RECORD_OPTIONS), |tags| {
+        let (version, fields) = tags.split_first()?;
+        if *version != ("v", b"STSv1".as_ref()) {
+            return None;
+        }
+
+        let mut id = None;
+        let mut extensions = Vec::new();
+
+        for &(name, value) in fields {
+            match name {
+                "v" => return None,
+                "id" if (1..=32).contains(&value.len())
+                    && value.iter().all(u8::is_ascii_alphanumeric) =>
+                {
+                    id = Some(ascii_to_string(value).into())
+                }
+                "id" => return None,
+                _ => extensions.push(extension(name, value)?),
+            }
+        }
+
+        Some(StsRecord {
+            id: id?,
+            extensions,
+        })
+    })(input)
+}
@@ -0,0 +1,193 @@
+//! [S/MIME] structure detection
+//!
+//! Classifies signed and encrypted entities from their MIME types and
+//! parameters. No cryptographic operation is done: a signature is not
+//! verified and encrypted content is not decrypted.
+//!
+//! [S/MIME]: https://tools.ietf.org/html/rfc8551
+
+use std::fmt::{self, Display};
+
+use crate::mime::Entity;
+
+/// The `smime-type` parameter of an `"application/pkcs7-mime"` entity.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SmimeType {
+    /// `enveloped-data`, encrypted content.
+    EnvelopedData,
+    /// `authEnveloped-data`, authenticated encrypted content.
+    AuthEnvelopedData,
+    /// `signed-data`, opaque signed content.
+    SignedData,
+    /// `compressed-data`, compressed content.
+    CompressedData,
+    /// `certs-only`, certificates without content.
+    CertsOnly,
+    /// Any other type, stored in lowercase.
+    Other(String),
+}
+
+impl From<&str> for SmimeType {
+    fn from(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "enveloped-data" => SmimeType::EnvelopedData,
+            "authenveloped-data" => SmimeType::AuthEnvelopedData,
+            "signed-data" => SmimeType::SignedData,
+            "compressed-data" => SmimeType::CompressedData,
+            "certs-only" => SmimeType::CertsOnly,
+            other => SmimeType::Other(other.into()),
+        }
+    }
+}
+
+impl Display for SmimeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SmimeType::EnvelopedData => write!(f, "enveloped-data"),
+            SmimeType::AuthEnvelopedData => write!(f, "authEnveloped-data"),
+            SmimeType::SignedData => write!(f, "signed-data"),
+            SmimeType::CompressedData => write!(f, "compressed-data"),
+            SmimeType::CertsOnly => write!(f, "certs-only"),
+            SmimeType::Other(t) => write!(f, "{}", t),
+        }
+    }
+}
+
+/// The signed or encrypted structure of an entity.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Smime<'e, 'a> {
+    /// A `"multipart/signed"` entity with a detached signature.
+    ///
+    /// The protocol is not restricted to S/MIME and may also be
+    /// `"application/pgp-signature"`.
+    MultipartSigned {
+        /// The lowercase `protocol` parameter.
+        protocol: Option<String>,
+        /// The lowercase algorithms of the `micalg` parameter.
+        micalg: Vec<String>,
+        /// The signed content, the first body part.
+        content: Option<&'e Entity<'a>>,
+        /// The signature, the second body part.
+        signature: Option<&'e Entity<'a>>,
+    },
+    /// An `"application/pkcs7-mime"` entity.
+    Pkcs7Mime {
+        /// The `smime-type` parameter, `None` if missing.
+        smime_type: Option<SmimeType>,
+    },
+    /// An `"application/pkcs7-signature"` entity outside of a
+    /// `"multipart/signed"`.
+    Pkcs7Signature,
+}
+
+impl<'e, 'a> Smime<'e, 'a> {
+    /// Returns `true` if the content is signed.
+    pub fn is_signed(&self) -> bool {
+        match self {
+            Smime::MultipartSigned { .. } => true,
+            Smime::Pkcs7Mime { smime_type } => smime_type == &Some(SmimeType::SignedData),
+            Smime::Pkcs7Signature => false,
+        }
+    }
+
+    /// Returns `true` if the content is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        matches!(
+            self,
+            Smime::Pkcs7Mime {
+                smime_type: Some(SmimeType::EnvelopedData) | Some(SmimeType::AuthEnvelopedData)
+            }
+        )
+    }
+}
+
+/// Classify the signed or encrypted structure of `entity`.
+///
+/// The legacy `"application/x-pkcs7-*"` types are also recognized.
+/// Returns `None` for any other entity.
+/// # Examples
+/// ```
+/// use rustyknife::mime::parse_message;
+/// use rustyknife::smime::{smime_structure, Smime, SmimeType};
+///
+/// let message = parse_message(b"Content-Type: application/pkcs7-mime; smime-type=enveloped-data\r\n\r\nMIAG");
+/// let structure = smime_structure(&message).unwrap();
+///
+/// assert_eq!(structure, Smime::Pkcs7Mime { smime_type: Some(SmimeType::EnvelopedData) });
+/// assert!(structure.is_encrypted());
+/// ```
+pub fn smime_structure<'e, 'a>(entity: &'e Entity<'a>) -> Option<Smime<'e, 'a>> {
+    let param = |name| {
+        entity
+            .content_type
+            .param(name)
+            .map(|v| v.to_ascii_lowercase())
+    };
+
+    match entity.content_type.mime_type.as_str() {
+        "multipart/signed" => {
+            let parts = entity.parts();
+            Some(Smime::MultipartSigned {
+                protocol: param("protocol"),
+                micalg: param("micalg")
+                    .map(|m| m.split(',').map(|a| a.trim().to_string()).collect())
+                    .unwrap_or_default(),
+                content: parts.first(),
+                signature: parts.get(1),
+            })
+        }
+        "application/pkcs7-mime" | "application/x-pkcs7-mime" => Some(Smime::Pkcs7Mime {
+            smime_type: entity.content_type.param("smime-type").map(SmimeType::from),
+        }),
+        "application/pkcs7-signature" | "application/x-pkcs7-signature" => {
+            Some(Smime::Pkcs7Signature)
+        }
+        _ => None,
+    }
+}
+
+/// Find the signed and encrypted entities in a message.
+///
+/// The signature part of a `"multipart/signed"` entity is not
+/// reported separately.
+/// # Examples
+/// ```
+/// use rustyknife::mime::parse_message;
+/// use rustyknife::smime::{find_smime, Smime};
+///
+/// let input = b"Content-Type: multipart/signed; protocol=\"application/pkcs7-signature\"; micalg=sha-256; boundary=s\r\n\r\n\
+///               --s\r\n\r\nsigned text\r\n\
+///               --s\r\nContent-Type: application/pkcs7-signature\r\n\r\nMIAG\r\n--s--\r\n";
+/// let message = parse_message(input);
+/// let found: Vec<_> = find_smime(&message).collect();
+///
+/// assert_eq!(found.len(), 1);
+/// match &found[0].1 {
+///     Smime::MultipartSigned { protocol, micalg, content, .. } => {
+///         assert_eq!(protocol.as_deref(), Some("application/pkcs7-signature"));
+///         assert_eq!(micalg, &["sha-256"]);
+///         assert!(content.unwrap().is("text/plain"));
+///     }
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn find_smime<'e, 'a>(
+    message: &'e Entity<'a>,
+) -> impl Iterator<Item = (&'e Entity<'a>, Smime<'e, 'a>)> {
+    let mut signatures = Vec::new();
+
+    message.walk().filter_map(move |entity| {
+        if signatures.iter().any(|s| std::ptr::eq(*s, entity)) {
+            return None;
+        }
+        let structure = smime_structure(entity)?;
+        if let Smime::MultipartSigned {
+            signature: Some(signature),
+            ..
+        } = &structure
+        {
+            signatures.push(*signature);
+        }
+        Some((entity, structure))
+    })
+}
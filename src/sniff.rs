@@ -0,0 +1,209 @@
+//! Content sniffing
+//!
+//! Detects well-known binary formats from their magic numbers to flag
+//! body parts whose content does not match their declared
+//! `"Content-Type"`, such as an executable sent as `"text/plain"`.
+//! Only signatures are checked, the content is not otherwise parsed.
+
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+
+use crate::mime::Entity;
+
+/// A binary format recognized by its signature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Signature {
+    /// Windows executable, an `"MZ"` header pointing to a
+    /// `"PE\0\0"` header.
+    Pe,
+    /// ELF executable.
+    Elf,
+    /// Mach-O executable.
+    MachO,
+    /// ZIP archive, also used by office documents and Java archives.
+    Zip,
+    /// RAR archive.
+    Rar,
+    /// 7-Zip archive.
+    SevenZip,
+    /// Gzip compressed data.
+    Gzip,
+    /// OLE compound file, used by legacy office documents.
+    Ole,
+    /// PDF document.
+    Pdf,
+    /// PNG image.
+    Png,
+    /// JPEG image.
+    Jpeg,
+    /// GIF image.
+    Gif,
+}
+
+const SIGNATURES: &[(&[u8], Signature)] = &[
+    (b"\x7fELF", Signature::Elf),
+    (b"\xfe\xed\xfa\xce", Signature::MachO),
+    (b"\xfe\xed\xfa\xcf", Signature::MachO),
+    (b"\xce\xfa\xed\xfe", Signature::MachO),
+    (b"\xcf\xfa\xed\xfe", Signature::MachO),
+    (b"PK\x03\x04", Signature::Zip),
+    (b"PK\x05\x06", Signature::Zip),
+    (b"Rar!\x1a\x07", Signature::Rar),
+    (b"7z\xbc\xaf\x27\x1c", Signature::SevenZip),
+    (b"\x1f\x8b", Signature::Gzip),
+    (b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1", Signature::Ole),
+    (b"%PDF-", Signature::Pdf),
+    (b"\x89PNG\r\n\x1a\n", Signature::Png),
+    (b"\xff\xd8\xff", Signature::Jpeg),
+    (b"GIF87a", Signature::Gif),
+    (b"GIF89a", Signature::Gif),
+];
+
+impl Signature {
+    /// Returns `true` for executable formats.
+    pub fn is_executable(self) -> bool {
+        matches!(self, Signature::Pe | Signature::Elf | Signature::MachO)
+    }
+
+    /// MIME types consistent with this format. Entries ending with
+    /// `"/"`, `"."` or `"-"` match any type starting with them.
+    pub fn mime_types(self) -> &'static [&'static str] {
+        match self {
+            Signature::Pe | Signature::Elf | Signature::MachO => &[
+                "application/x-msdownload",
+                "application/x-dosexec",
+                "application/x-executable",
+                "application/x-elf",
+                "application/x-mach-binary",
+                "application/vnd.microsoft.portable-executable",
+            ],
+            Signature::Zip => &[
+                "application/zip",
+                "application/x-zip-compressed",
+                "application/java-archive",
+                "application/epub+zip",
+                "application/vnd.openxmlformats-officedocument.",
+                "application/vnd.oasis.opendocument.",
+                "application/vnd.ms-",
+            ],
+            Signature::Rar => &["application/x-rar-compressed", "application/vnd.rar"],
+            Signature::SevenZip => &["application/x-7z-compressed"],
+            Signature::Gzip => &["application/gzip", "application/x-gzip"],
+            Signature::Ole => &[
+                "application/msword",
+                "application/vnd.ms-",
+                "application/vnd.visio",
+                "application/x-msi",
+            ],
+            Signature::Pdf => &["application/pdf"],
+            Signature::Png | Signature::Jpeg | Signature::Gif => &["image/"],
+        }
+    }
+
+    /// Returns `true` if `mime_type` is consistent with this format.
+    ///
+    /// `"application/octet-stream"` is consistent with any format.
+    /// `mime_type` must be lowercase.
+    pub fn matches(self, mime_type: &str) -> bool {
+        mime_type == "application/octet-stream"
+            || self.mime_types().iter().any(|t| {
+                if t.ends_with('/') || t.ends_with('.') || t.ends_with('-') {
+                    mime_type.starts_with(t)
+                } else {
+                    mime_type == *t
+                }
+            })
+    }
+}
+
+impl Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Signature::Pe => "PE",
+            Signature::Elf => "ELF",
+            Signature::MachO => "Mach-O",
+            Signature::Zip => "ZIP",
+            Signature::Rar => "RAR",
+            Signature::SevenZip => "7z",
+            Signature::Gzip => "gzip",
+            Signature::Ole => "OLE",
+            Signature::Pdf => "PDF",
+            Signature::Png => "PNG",
+            Signature::Jpeg => "JPEG",
+            Signature::Gif => "GIF",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Offset of e_lfanew, the offset of the PE header, in the MZ header.
+const PE_OFFSET: usize = 0x3c;
+
+// Text starting with "MZ" is common, so the PE header the MZ header
+// points to must be present as well.
+fn is_pe(data: &[u8]) -> bool {
+    let offset = match data.get(PE_OFFSET..PE_OFFSET + 4) {
+        Some(e_lfanew) if data.starts_with(b"MZ") => {
+            u32::from_le_bytes([e_lfanew[0], e_lfanew[1], e_lfanew[2], e_lfanew[3]])
+        }
+        _ => return false,
+    };
+
+    usize::try_from(offset)
+        .ok()
+        .and_then(|offset| data.get(offset..offset.checked_add(4)?))
+        == Some(b"PE\0\0")
+}
+
+/// Detect the format of `data` from its first bytes.
+/// # Examples
+/// ```
+/// use rustyknife::sniff::{sniff, Signature};
+///
+/// let mut pe = vec![0; 0x44];
+/// pe[..2].copy_from_slice(b"MZ");
+/// pe[0x3c] = 0x40;
+/// pe[0x40..].copy_from_slice(b"PE\0\0");
+///
+/// assert_eq!(sniff(&pe), Some(Signature::Pe));
+/// assert_eq!(sniff(b"MZ is not enough"), None);
+/// assert_eq!(sniff(b"PK\x03\x04\x14\x00"), Some(Signature::Zip));
+/// assert_eq!(sniff(b"Hello"), None);
+/// ```
+pub fn sniff(data: &[u8]) -> Option<Signature> {
+    if is_pe(data) {
+        return Some(Signature::Pe);
+    }
+    SIGNATURES
+        .iter()
+        .find(|(magic, _)| data.starts_with(magic))
+        .map(|(_, signature)| *signature)
+}
+
+/// Check decoded content against its declared MIME type.
+///
+/// Returns the detected format if it is not consistent with
+/// `mime_type`, which must be lowercase. Content without a known
+/// signature is never flagged.
+/// # Examples
+/// ```
+/// use rustyknife::sniff::{check_declared, Signature};
+///
+/// assert_eq!(check_declared("text/plain", b"MZ\x90\x00"), None);
+/// assert_eq!(check_declared("text/plain", b"%PDF-1.4"), Some(Signature::Pdf));
+/// assert_eq!(check_declared("application/vnd.openxmlformats-officedocument.wordprocessingml.document", b"PK\x03\x04"), None);
+/// assert_eq!(check_declared("image/png", b"GIF89a"), None);
+/// assert_eq!(check_declared("image/jpeg", b"\x7fELF\x02"), Some(Signature::Elf));
+/// ```
+pub fn check_declared(mime_type: &str, data: &[u8]) -> Option<Signature> {
+    sniff(data).filter(|signature| !signature.matches(mime_type))
+}
+
+/// Check the decoded body of `entity` against its effective content
+/// type.
+///
+/// `decoded` is the body with its transfer encoding undone. See
+/// [`check_declared`].
+pub fn check_entity(entity: &Entity, decoded: &[u8]) -> Option<Signature> {
+    check_declared(&entity.content_type.mime_type, decoded)
+}
@@ -0,0 +1,211 @@
+//! Generic tag=value list parser
+//!
+//! Implements the tag-list syntax of [DKIM] that is shared by ARC,
+//! DMARC and other DNS or header based policies. The separator, the
+//! white space allowed around tags and values and the handling of
+//! duplicate tags are configurable with [`TagListOptions`].
+//!
+//! The MTA-STS and TLSRPT records of [`rfc8461`](crate::rfc8461) and
+//! [`rfc8460`](crate::rfc8460) are parsed with it, and the `h=` tag it
+//! returns for a DKIM signature is the input of
+//! [`dkim_select_headers`](crate::headersection::dkim_select_headers).
+//!
+//! [DKIM]: https://tools.ietf.org/html/rfc6376#section-3.2
+
+use std::collections::HashMap;
+use std::str;
+
+use nom::bytes::complete::{tag, take_while};
+use nom::combinator::{map, map_opt, opt, recognize, verify};
+use nom::multi::many0;
+use nom::sequence::{pair, preceded, separated_pair, terminated, tuple};
+
+use crate::rfc5234::wsp;
+use crate::rfc5322::ofws;
+use crate::util::*;
+
+/// White space allowed around tags, values and separators and
+/// between the words of a value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Whitespace {
+    /// Folding white space, as in header fields.
+    Fws,
+    /// Spaces and tabs only, as in DNS TXT records.
+    Wsp,
+    /// No white space at all.
+    None,
+}
+
+/// How [`tag_list`] handles a tag given more than once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TagDuplicates {
+    /// Fail to parse, as required by DKIM.
+    Error,
+    /// Keep the first value.
+    FirstWins,
+    /// Keep the last value, at the position of the first occurrence.
+    LastWins,
+}
+
+/// Options for [`tag_list`].
+///
+/// The default follows DKIM: tags are separated by `";"`, folding
+/// white space is allowed and duplicate tags are an error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TagListOptions {
+    /// The tag separator. It may not appear in values.
+    pub separator: u8,
+    /// The white space allowed.
+    pub whitespace: Whitespace,
+    /// The handling of duplicate tags.
+    pub duplicates: TagDuplicates,
+    /// Characters allowed in tag names besides letters and digits.
+    /// Names start with a letter, or a digit with
+    /// [`name_leading_digit`](TagListOptions::name_leading_digit).
+    pub name_chars: &'static [u8],
+    /// Allow tag names to start with a digit.
+    pub name_leading_digit: bool,
+}
+
+impl Default for TagListOptions {
+    fn default() -> Self {
+        TagListOptions {
+            separator: b';',
+            whitespace: Whitespace::Fws,
+            duplicates: TagDuplicates::Error,
+            name_chars: b"_",
+            name_leading_digit: false,
+        }
+    }
+}
+
+fn ws(kind: Whitespace, input: &[u8]) -> NomResult<&[u8]> {
+    match kind {
+        Whitespace::Fws => recognize(ofws)(input),
+        Whitespace::Wsp => recognize_many0(wsp)(input),
+        Whitespace::None => Ok((input, &input[..0])),
+    }
+}
+
+fn tag_name(options: TagListOptions, input: &[u8]) -> NomResult<&str> {
+    map(
+        recognize(pair(
+            take1_filter(|c| {
+                c.is_ascii_alphabetic() || (options.name_leading_digit && c.is_ascii_digit())
+            }),
+            take_while(|c: u8| c.is_ascii_alphanumeric() || options.name_chars.contains(&c)),
+        )),
+        |name| str::from_utf8(name).unwrap(),
+    )(input)
+}
+
+// Words of a value separated by white space. The surrounding white
+// space is not included.
+fn tag_value(options: TagListOptions, input: &[u8]) -> NomResult<&[u8]> {
+    let tval = |i| {
+        recognize_many1(take1_filter(|c| {
+            c != options.separator && (0x21..=0x7e).contains(&c)
+        }))(i)
+    };
+
+    recognize(opt(pair(
+        tval,
+        many0(pair(
+            verify(|i| ws(options.whitespace, i), |w: &[u8]| !w.is_empty()),
+            tval,
+        )),
+    )))(input)
+}
+
+fn tag_spec(options: TagListOptions, input: &[u8]) -> NomResult<(&str, &[u8])> {
+    let ws = |i| ws(options.whitespace, i);
+
+    map(
+        tuple((
+            ws,
+            separated_pair(
+                terminated(|i| tag_name(options, i), ws),
+                pair(tag("="), ws),
+                |i| tag_value(options, i),
+            ),
+            ws,
+        )),
+        |(_, spec, _)| spec,
+    )(input)
+}
+
+fn apply_duplicates<'a>(
+    tags: Vec<(&'a str, &'a [u8])>,
+    policy: TagDuplicates,
+) -> Option<Vec<(&'a str, &'a [u8])>> {
+    let mut out: Vec<(&str, &[u8])> = Vec::with_capacity(tags.len());
+    let mut seen = HashMap::new();
+
+    for (name, value) in tags {
+        match (seen.get(name), policy) {
+            (None, _) => {
+                seen.insert(name, out.len());
+                out.push((name, value));
+            }
+            (Some(_), TagDuplicates::Error) => return None,
+            (Some(_), TagDuplicates::FirstWins) => (),
+            (Some(&i), TagDuplicates::LastWins) => out[i].1 = value,
+        }
+    }
+
+    Some(out)
+}
+
+/// Build a parser for a tag=value list.
+///
+/// Returns the tag names and their raw values in order. Tag names are
+/// case sensitive. White space between the words of a value is kept
+/// and a trailing separator is accepted. An empty list is valid.
+/// # Examples
+/// ```
+/// use rustyknife::tagvalue::{tag_list, TagDuplicates, TagListOptions, Whitespace};
+///
+/// let input = b"v=1; a=rsa-sha256; d=example.org;\r\n h=from : to; bh=abc\r\n def=;";
+/// let (rem, tags) = tag_list(TagListOptions::default())(input).unwrap();
+///
+/// assert!(rem.is_empty());
+/// assert_eq!(tags[3], ("h", b"from : to".as_ref()));
+/// assert_eq!(tags[4], ("bh", b"abc\r\n def=".as_ref()));
+/// assert!(tag_list(TagListOptions::default())(b"v=1; v=2").is_err());
+///
+/// let options = TagListOptions {
+///     separator: b',',
+///     whitespace: Whitespace::Wsp,
+///     duplicates: TagDuplicates::LastWins,
+///     ..TagListOptions::default()
+/// };
+/// let (_, tags) = tag_list(options)(b"a=1, b=2, a=3").unwrap();
+/// assert_eq!(tags, [("a", b"3".as_ref()), ("b", b"2")]);
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn tag_list(options: TagListOptions) -> impl Fn(&[u8]) -> NomResult<Vec<(&str, &[u8])>> {
+    move |input| {
+        let spec = |i| tag_spec(options, i);
+        let sep = |i| tag([options.separator])(i);
+
+        traced(
+            "tag_list",
+            map_opt(
+                terminated(
+                    map(
+                        opt(pair(spec, many0(preceded(sep, spec)))),
+                        |specs| match specs {
+                            Some((first, mut rest)) => {
+                                rest.insert(0, first);
+                                rest
+                            }
+                            None => Vec::new(),
+                        },
+                    ),
+                    pair(opt(sep), |i| ws(options.whitespace, i)),
+                ),
+                move |tags| apply_duplicates(tags, options.duplicates),
+            ),
+        )(input)
+    }
+}
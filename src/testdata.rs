@@ -0,0 +1,158 @@
+//! Conformance test corpora
+//!
+//! Curated valid and invalid inputs with their expected outcome, as
+//! used by the tests of this crate. Run them against your own
+//! integration to check that it behaves the same way. Every case is
+//! parsed with [`Intl`](crate::behaviour::Intl) behaviour.
+//!
+//! Only available with the `testdata` feature.
+//! # Examples
+//! ```
+//! use rustyknife::rfc5321::validate_address;
+//! use rustyknife::behaviour::Intl;
+//! use rustyknife::testdata::SMTP_ADDRESSES;
+//!
+//! for case in SMTP_ADDRESSES {
+//!     assert_eq!(validate_address::<Intl>(case.input), case.is_valid());
+//! }
+//! ```
+
+/// A test input and its expected outcome.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Case {
+    /// The input as sent on the wire.
+    pub input: &'static [u8],
+    /// The [`Display`](std::fmt::Display) form of the parsed value,
+    /// `None` if the whole input must be rejected.
+    pub expected: Option<&'static str>,
+}
+
+impl Case {
+    /// Returns `true` if the input must be accepted.
+    pub fn is_valid(&self) -> bool {
+        self.expected.is_some()
+    }
+}
+
+const fn valid(input: &'static [u8], expected: &'static str) -> Case {
+    Case {
+        input,
+        expected: Some(expected),
+    }
+}
+
+const fn invalid(input: &'static [u8]) -> Case {
+    Case {
+        input,
+        expected: None,
+    }
+}
+
+/// SMTP mailboxes as parsed by [`rfc5321::mailbox`](crate::rfc5321::mailbox).
+pub const SMTP_ADDRESSES: &[Case] = &[
+    valid(b"mrbob@example.org", "mrbob@example.org"),
+    valid(b"mrbob?@example.org", "mrbob?@example.org"),
+    valid(b"\"mr bob\"@example.org", "\"mr bob\"@example.org"),
+    valid(b"\"mrbob\"@example.org", "\"mrbob\"@example.org"),
+    valid(b"mrbob@[127.0.0.1]", "mrbob@[127.0.0.1]"),
+    valid(b"mrbob@[IPv6:2001:db8::1]", "mrbob@[IPv6:2001:db8::1]"),
+    valid(b"mrbob@[ipv6:2001:db8::1]", "mrbob@[IPv6:2001:db8::1]"),
+    valid(
+        "δοκιμή@παράδειγμα.δοκιμή".as_bytes(),
+        "δοκιμή@παράδειγμα.δοκιμή",
+    ),
+    invalid(b"mrbob\"@example.org"),
+    invalid(b"pa^^&*(sarobas@example.org"),
+    invalid(b"mrbob"),
+    invalid(b"@example.org"),
+    invalid(b"mrbob@"),
+    invalid(b"mr bob@example.org"),
+    invalid(b".mrbob@example.org"),
+    invalid(b"mr..bob@example.org"),
+    invalid(b"mrbob@example..org"),
+    invalid(b"mrbob@[127.0.0.256]"),
+    invalid(b"mrbob@[127.0.0.01]"),
+    invalid(b"mrbob@[IPv6:2001:db8::12345]"),
+];
+
+/// SMTP `MAIL FROM` commands as parsed by
+/// [`rfc5321::mail_command`](crate::rfc5321::mail_command). The
+/// expected value is the reverse path.
+pub const MAIL_COMMANDS: &[Case] = &[
+    valid(b"MAIL FROM:<>\r\n", "<>"),
+    valid(b"MAIL FROM:<mrbob@example.org>\r\n", "<mrbob@example.org>"),
+    valid(b"mail from:<mrbob@example.org>\r\n", "<mrbob@example.org>"),
+    valid(
+        b"MAIL FROM:<mrbob@example.org> SIZE=1000 BODY=8BITMIME\r\n",
+        "<mrbob@example.org>",
+    ),
+    valid(
+        b"MAIL FROM:<\"mr bob\"@example.org> RET=FULL ENVID=abc123\r\n",
+        "<\"mr bob\"@example.org>",
+    ),
+    valid(
+        b"MAIL FROM:<\"bob the \\\"great \\\\ powerful\\\"\"@example.com>\r\n",
+        "<\"bob the \\\"great \\\\ powerful\\\"\"@example.com>",
+    ),
+    invalid(b"MAIL FROM:<pa^^&*(sarobas@example.org>\r\n"),
+    invalid(b"MAIL FROM:mrbob@example.org\r\n"),
+    invalid(b"MAIL FROM: <mrbob@example.org>\r\n"),
+    invalid(b"MAIL FROM:<mrbob@example.org>"),
+    invalid(b"MAIL FROM:<mrbob@example.org>  SIZE=1000\r\n"),
+];
+
+/// SMTP `RCPT TO` commands as parsed by
+/// [`rfc5321::rcpt_command`](crate::rfc5321::rcpt_command). The
+/// expected value is the forward path.
+pub const RCPT_COMMANDS: &[Case] = &[
+    valid(b"RCPT TO:<mrbob@example.org>\r\n", "<mrbob@example.org>"),
+    valid(b"RCPT TO:<postmaster>\r\n", "<postmaster>"),
+    valid(
+        b"RCPT TO:<mrbob@example.org> NOTIFY=NEVER\r\n",
+        "<mrbob@example.org>",
+    ),
+    valid(
+        b"RCPT TO:<mrbob?@example.org> ORCPT=rfc822;mrbob+AD@example.org\r\n",
+        "<mrbob?@example.org>",
+    ),
+    valid(b"RCPT TO:<bob@[127.0.0.1]>\r\n", "<bob@[127.0.0.1]>"),
+    valid(b"RCPT TO:<pOstmaster>\r\n", "<postmaster>"),
+    valid(
+        b"RCPT TO:<pOstmaster@Domain.example.org>\r\n",
+        "<postmaster@Domain.example.org>",
+    ),
+    invalid(b"RCPT TO:<>\r\n"),
+    invalid(b"RCPT TO:<pa^^&*(sarobas@example.org>\r\n"),
+    invalid(b"RCPT TO:<mrbob@example.org\r\n"),
+];
+
+/// `"From:"` header values as parsed by
+/// [`rfc5322::from`](crate::rfc5322::from). The expected value is the
+/// list of addresses separated by `", "`.
+pub const FROM_HEADERS: &[Case] = &[
+    valid(b" mrbob@example.org\r\n", "mrbob@example.org"),
+    valid(
+        b" Mr Bob <mrbob@example.org>\r\n",
+        "Mr Bob <mrbob@example.org>",
+    ),
+    valid(
+        b" \"Bob, Mr\" <mrbob@example.org> (comment)\r\n",
+        "\"Bob, Mr\" <mrbob@example.org>",
+    ),
+    valid(
+        b" =?utf-8?Q?Andr=C3=A9?= <andre@example.org>\r\n",
+        "André <andre@example.org>",
+    ),
+    valid(
+        b" a@example.org, B <b@example.org>\r\n",
+        "a@example.org, B <b@example.org>",
+    ),
+    valid(
+        b"John Doe <jdoe@machine.example>\r\n",
+        "John Doe <jdoe@machine.example>",
+    ),
+    valid(b" undisclosed-recipients:;\r\n", "undisclosed-recipients:;"),
+    invalid(b" <mrbob@example.org\r\n"),
+    invalid(b" Mr Bob\r\n"),
+    invalid(b" mrbob@example.org, \r\n"),
+];
@@ -1,4 +1,34 @@
+#[cfg(feature = "c-api")]
+mod test_capi;
+mod test_dkim;
+mod test_envelope;
 mod test_headersection;
+mod test_mime;
+mod test_owned;
+mod test_proxy;
+mod test_rfc2046;
+mod test_rfc2047;
 mod test_rfc2231;
+mod test_rfc4954;
 mod test_rfc5321;
 mod test_rfc5322;
+mod test_rfc8460;
+mod test_rfc8461;
+mod test_tagvalue;
+mod test_transcript;
+mod test_xforward;
+
+use crate::testdata::Case;
+
+/// Check `parse` against a corpus, `parse` returns the `Display` form
+/// of the parsed value.
+fn check_corpus<F: Fn(&[u8]) -> Option<String>>(cases: &[Case], parse: F) {
+    for case in cases {
+        assert_eq!(
+            parse(case.input).as_deref(),
+            case.expected,
+            "{:?}",
+            String::from_utf8_lossy(case.input)
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use std::os::raw::{c_int, c_void};
+use std::slice;
+
+use crate::capi::*;
+
+type Collected = Vec<(Option<Vec<u8>>, Vec<u8>)>;
+
+unsafe extern "C" fn collect(
+    user: *mut c_void,
+    name: *const u8,
+    name_len: usize,
+    value: *const u8,
+    value_len: usize,
+) -> c_int {
+    let out = &mut *(user as *mut Collected);
+    let name = if name.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(name, name_len).to_vec())
+    };
+    out.push((name, slice::from_raw_parts(value, value_len).to_vec()));
+    0
+}
+
+fn feed_chunks(input: &[u8], chunk: usize, limit: usize) -> (c_int, Collected, u64) {
+    let mut out = Collected::new();
+    let ctx = rk_header_new(limit, &mut out as *mut Collected as *mut c_void);
+    let mut state = RK_HEADER_MORE;
+
+    for c in input.chunks(chunk) {
+        state = unsafe { rk_header_feed(ctx, c.as_ptr(), c.len(), Some(collect)) };
+    }
+    let consumed = unsafe { rk_header_consumed(ctx) };
+    unsafe { rk_header_free(ctx) };
+
+    (state, out, consumed)
+}
+
+#[test]
+fn header_feed_chunks() {
+    let input = b"Subject: a\r\n folded\r\nbogus\r\nTo: b\r\n\r\nbody";
+
+    for chunk in 1..input.len() {
+        let (state, out, consumed) = feed_chunks(input, chunk, 32);
+        assert_eq!(state, RK_HEADER_DONE, "chunk {}", chunk);
+        assert_eq!(
+            out,
+            [
+                (Some(b"Subject".to_vec()), b" a\r\n folded".to_vec()),
+                (None, b"bogus".to_vec()),
+                (Some(b"To".to_vec()), b" b".to_vec()),
+            ]
+        );
+        assert_eq!(consumed, input.len() as u64 - 4);
+    }
+}
+
+#[test]
+fn header_feed_too_long() {
+    let (state, out, _) = feed_chunks(b"Subject: 0123456789\r\n\r\n", 4, 8);
+
+    assert_eq!(state, RK_HEADER_TOO_LONG);
+    assert!(out.is_empty());
+}
+
+#[test]
+fn header_feed_too_large() {
+    let input = b"A: 1\r\nB: 2\r\nC: 3\r\n\r\n";
+
+    for chunk in 1..input.len() {
+        let mut out = Collected::new();
+        let ctx = rk_header_new(16, &mut out as *mut Collected as *mut c_void);
+        let mut state = unsafe { rk_header_set_limits(ctx, 13, 0) };
+
+        for c in input.chunks(chunk) {
+            state = unsafe { rk_header_feed(ctx, c.as_ptr(), c.len(), Some(collect)) };
+        }
+        assert_eq!(state, RK_HEADER_TOO_LARGE, "chunk {}", chunk);
+        assert_eq!(out.len(), 2);
+        assert_eq!(unsafe { rk_header_consumed(ctx) }, 12);
+        unsafe { rk_header_free(ctx) };
+    }
+}
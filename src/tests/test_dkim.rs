@@ -0,0 +1,44 @@
+use crate::dkim::*;
+
+use self::BodyCanonicalization::*;
+
+#[test]
+fn rfc6376_example() {
+    let body = b" C \r\nD \t E\r\n\r\n\r\n";
+
+    assert_eq!(canonicalize_body(body, Simple), b" C \r\nD \t E\r\n");
+    assert_eq!(canonicalize_body(body, Relaxed), b" C\r\nD E\r\n");
+    assert_eq!(canonical_body_length(body, Relaxed), 9);
+    assert_eq!(canonicalize_body(b"\r\n\r\n", Relaxed), b"");
+}
+
+#[test]
+fn length_offsets() {
+    let body = b"a \t b  \r\nc";
+
+    // "a b\r\nc\r\n": the space maps to the first white space byte,
+    // the CR to the CR even after trailing white space.
+    let offsets: Vec<_> = (0..=9)
+        .map(|l| body_length_offset(body, Relaxed, l))
+        .collect();
+    assert_eq!(
+        offsets,
+        [
+            Some(0),
+            Some(1),
+            Some(2),
+            Some(5),
+            Some(8),
+            Some(9),
+            Some(10),
+            Some(10),
+            Some(10),
+            None
+        ]
+    );
+
+    assert_eq!(canonical_body_length(body, Simple), 12);
+    assert_eq!(body_length_offset(body, Simple, 11), Some(10));
+    assert_eq!(body_length_offset(b"", Simple, 2), Some(0));
+    assert_eq!(body_length_offset(b"", Relaxed, 1), None);
+}
@@ -0,0 +1,40 @@
+use crate::behaviour::Intl;
+use crate::envelope::*;
+use crate::headersection::header_section;
+use crate::rfc5321::{ForwardPath, ReversePath};
+
+#[test]
+fn envelope_mismatches() {
+    let sender: ReversePath = "<list-bounces@lists.example.org>".parse().unwrap();
+    let rcpts: Vec<ForwardPath> = vec![
+        "<Bob@example.com>".parse().unwrap(),
+        "<carol@example.com>".parse().unwrap(),
+        "<postmaster>".parse().unwrap(),
+    ];
+    let (_, headers) = header_section(
+        b"Return-Path: <\"list-bounces\"@LISTS.example.org>\r\n\
+          From: alice@Example.ORG\r\n\
+          To: list <Bob@example.com>\r\n\r\n",
+    )
+    .unwrap();
+
+    let found = check_envelope::<Intl>(&sender, &rcpts, &headers, Alignment::Relaxed);
+    assert_eq!(
+        found,
+        [Mismatch::HiddenRecipient(
+            "carol@example.com".parse().unwrap()
+        )]
+    );
+
+    let found = check_envelope::<Intl>(&sender, &rcpts, &headers, Alignment::Strict);
+    assert!(matches!(found[0], Mismatch::FromDomain { .. }));
+
+    let (_, headers) = header_section(b"Return-Path: <alice@example.org>\r\n\r\n").unwrap();
+    let found = check_envelope::<Intl>(&ReversePath::Null, &[], &headers, Alignment::Relaxed);
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0], Mismatch::MissingFrom);
+    assert_eq!(
+        found[1].to_string(),
+        "Return-Path <alice@example.org> differs from envelope sender <>"
+    );
+}
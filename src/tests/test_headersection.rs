@@ -88,3 +88,164 @@ fn big_garbage() {
         ]
     );
 }
+
+#[test]
+fn spans() {
+    let input = b"A: 1\r\nbad\r\nB: 2\r\n folded\r\n\r\nbody";
+    let (rem, parsed) = header_section_spans(input).unwrap();
+    assert_eq!(rem, b"body");
+    assert_eq!(
+        parsed,
+        [
+            (Ok((b"A".as_ref(), b" 1".as_ref())), 0..6),
+            (Err(b"bad".as_ref()), 6..11),
+            (Ok((b"B".as_ref(), b" 2\r\n folded".as_ref())), 11..26),
+        ]
+    );
+}
+
+#[test]
+fn editor_untouched() {
+    let input = b"A: 1\r\nbad\r\nB: 2\r\n folded\r\n\r\nbody";
+    let editor = HeaderEditor::new(input).unwrap();
+    assert_eq!(editor.len(), 3);
+    assert_eq!(editor.to_bytes(), input.as_ref());
+}
+
+#[test]
+fn editor_replace_invalid() {
+    let input = b"A: 1\r\nbad\r\n\r\n";
+    let mut editor = HeaderEditor::new(input).unwrap();
+    assert_eq!(editor.name(1), None);
+    editor.replace(1, b"X-Fixed: yes").unwrap();
+    editor.push(b"C", b" 3").unwrap();
+    assert_eq!(
+        editor.to_bytes(),
+        b"A: 1\r\nX-Fixed: yes\r\nC: 3\r\n\r\n".as_ref()
+    );
+}
+
+#[test]
+fn iter_matches_section() {
+    let input = b"X-Mozilla-Status: 0001\r\nbad header\r\nX-Mozilla-Status2: 00800000\r\n\r\nbody";
+    let (rem, parsed) = header_section(input).unwrap();
+
+    let mut iter = header_section_iter(input);
+    let lazy: Vec<_> = iter.by_ref().collect();
+    assert_eq!(lazy, parsed);
+    assert_eq!(iter.remainder(), rem);
+    assert!(!iter.is_incomplete());
+}
+
+#[test]
+fn iter_incomplete() {
+    let mut iter = header_section_iter(b"A: 1\r\nB: 2");
+    assert_eq!(iter.next(), Some(Ok((b"A".as_ref(), b" 1".as_ref()))));
+    assert_eq!(iter.next(), None);
+    assert!(iter.is_incomplete());
+    assert_eq!(iter.remainder(), b"B: 2");
+}
+
+#[test]
+fn dkim_select_bottom_up() {
+    let input = b"From: a\r\nbad header\r\nSUBJECT: s\r\nfrom: b\r\n\r\n";
+    let (_, headers) = header_section_spans(input).unwrap();
+
+    let selected: Vec<_> = dkim_select_headers(&headers, "From:Subject:from:from:Date")
+        .into_iter()
+        .map(|r| &input[r])
+        .collect();
+    assert_eq!(
+        selected,
+        [&b"from: b\r\n"[..], b"SUBJECT: s\r\n", b"From: a\r\n"]
+    );
+    assert!(dkim_select_headers(&headers, "").is_empty());
+}
+
+#[test]
+fn editor_rejects_injection() {
+    let input = b"A: 1\r\n\r\nbody";
+    let mut editor = HeaderEditor::new(input).unwrap();
+
+    assert_eq!(editor.push(b"B\r\nC", b" 2"), Err(InjectionError::Name(1)));
+    assert_eq!(editor.push(b"", b" 2"), Err(InjectionError::Name(0)));
+    assert_eq!(
+        editor.push(b"B", b" 2\r\n\r\nx"),
+        Err(InjectionError::Value(2))
+    );
+    assert_eq!(editor.replace(0, b" 1\n"), Err(InjectionError::Value(2)));
+    assert_eq!(editor.push(b"B", b" 2\r\n\t3"), Ok(()));
+    assert_eq!(
+        editor.to_bytes(),
+        b"A: 1\r\nB: 2\r\n\t3\r\n\r\nbody".as_ref()
+    );
+}
+
+#[test]
+fn diff_new_hop() {
+    let before = b"Received: from a\r\nSubject: hi\r\nSubject: again\r\n\r\n";
+    let after = b"Received: from b\r\nReceived: from a\r\nSubject: hi\r\nSubject: changed\r\n\r\n";
+
+    let changes = header_diff(
+        &HeaderMap::new(before).unwrap(),
+        &HeaderMap::new(after).unwrap(),
+    );
+    assert_eq!(
+        changes,
+        [
+            HeaderChange::Modified {
+                name: HeaderName::new(b"Subject").unwrap(),
+                before: 31..47,
+                after: 49..67,
+            },
+            HeaderChange::Added {
+                name: KnownHeader::Received.into(),
+                after: 0..18,
+            },
+        ]
+    );
+    assert_eq!(&after[49..67], b"Subject: changed\r\n");
+}
+
+#[test]
+fn diff_many_headers() {
+    let mut before = Vec::new();
+    for i in 0..20_000 {
+        before.extend_from_slice(format!("X-Seq: {}\r\n", i).as_bytes());
+    }
+    let mut after = b"Received: from b\r\n".to_vec();
+    after.extend_from_slice(&before);
+    before.extend_from_slice(b"\r\n");
+    after.extend_from_slice(b"\r\n");
+
+    let changes = header_diff(
+        &HeaderMap::new(&before).unwrap(),
+        &HeaderMap::new(&after).unwrap(),
+    );
+    assert_eq!(
+        changes,
+        [HeaderChange::Added {
+            name: KnownHeader::Received.into(),
+            after: 0..18,
+        }]
+    );
+}
+
+#[test]
+fn known_header_names() {
+    for known in KnownHeader::ALL {
+        let name = known.as_str();
+
+        assert_eq!(KnownHeader::from_name(name.as_bytes()), Some(*known));
+        assert_eq!(
+            KnownHeader::from_name(name.to_ascii_uppercase().as_bytes()),
+            Some(*known)
+        );
+        assert_eq!(
+            KnownHeader::from_name(format!("{}x", name).as_bytes()),
+            None
+        );
+    }
+    assert_eq!(KnownHeader::from_name(&[b'a'; 100]), None);
+    assert_eq!(KnownHeader::from_name(b""), None);
+}
@@ -0,0 +1,194 @@
+use crate::mime::*;
+use crate::rfc2231::ContentDisposition;
+use crate::rfc6522::*;
+
+#[test]
+fn digest_and_encapsulated() {
+    let input = b"Content-Type: multipart/digest; boundary=d\r\n\r\n\
+                  --d\r\n\r\nSubject: first\r\n\r\nbody\r\n\
+                  --d\r\nContent-Type: text/plain\r\n\r\nnote\r\n--d--\r\n";
+    let message = parse_message(input);
+
+    let parts = message.parts();
+    assert_eq!(parts.len(), 2);
+    assert!(parts[0].is("message/rfc822"));
+    match &parts[0].body {
+        Body::Message(inner) => {
+            assert_eq!(inner.header(b"SUBJECT"), Some(&b" first"[..]));
+            assert_eq!(inner.body, Body::Single(b"body"));
+        }
+        other => panic!("unexpected body {:?}", other),
+    }
+    assert!(parts[1].is("text/plain"));
+    assert_eq!(message.walk().count(), 4);
+}
+
+#[test]
+fn multipart_without_boundary() {
+    let message = parse_message(b"Content-Type: multipart/mixed\r\n\r\n--b\r\n\r\nx\r\n--b--\r\n");
+
+    assert!(message.parts().is_empty());
+    assert!(matches!(message.body, Body::Single(_)));
+}
+
+#[test]
+fn feedback_report() {
+    let input = b"Content-Type: multipart/report; report-type=feedback-report; boundary=f\r\n\r\n\
+                  --f\r\n\r\nThis is an abuse report.\r\n\
+                  --f\r\nContent-Type: message/feedback-report\r\n\r\nFeedback-Type: abuse\r\n\r\n\
+                  --f\r\nContent-Type: message/rfc822\r\n\r\nSubject: spam\r\n\r\nbuy\r\n--f--\r\n";
+    let message = parse_message(input);
+    let report = multipart_report(&message).unwrap();
+
+    assert_eq!(report.report_type, Some(ReportType::FeedbackReport));
+    assert!(report.machine.unwrap().is("message/feedback-report"));
+    assert!(report.original.unwrap().is("message/rfc822"));
+
+    assert_eq!(multipart_report(report.original.unwrap()), None);
+}
+
+#[test]
+fn delivery_status_groups() {
+    use crate::rfc3464::delivery_status;
+
+    let input = b"Content-Type: multipart/report; report-type=delivery-status; boundary=b\r\n\r\n\
+                  --b\r\n\r\nDelivery failed.\r\n\
+                  --b\r\nContent-Type: message/delivery-status\r\n\r\n\
+                  Reporting-MTA: dns; mx.example.org\r\nArrival-Date: bogus\r\n\r\n\r\n\
+                  Final-Recipient: rfc822; a@example.com\r\n\r\n\
+                  Final-Recipient: rfc822; b@example.com\r\n\
+                  Last-Attempt-Date: 1 Jan 2024 00:00 GMT\r\n--b--\r\n";
+    let message = parse_message(input);
+    let machine = multipart_report(&message).unwrap().machine.unwrap();
+    let status = match machine.body {
+        Body::Single(body) => delivery_status(body),
+        _ => unreachable!(),
+    };
+
+    assert!(status.message.fields.get(b"reporting-mta").is_some());
+    assert_eq!(status.message.arrival_date(), None);
+    assert_eq!(status.recipients.len(), 2);
+    assert_eq!(status.recipients[0].last_attempt_date(), None);
+    assert_eq!(status.recipients[1].last_attempt_date().unwrap().year, 2024);
+}
+
+#[test]
+fn calendar_prefers_method() {
+    use crate::calendar::*;
+
+    let input = b"Content-Type: multipart/mixed; boundary=m\r\n\r\n\
+                  --m\r\nContent-Type: text/calendar\r\n\r\nBEGIN:VCALENDAR\r\n\
+                  --m\r\nContent-Type: text/calendar; method=\"x-custom\"\r\n\r\nBEGIN:VCALENDAR\r\n--m--\r\n";
+    let message = parse_message(input);
+    let (entity, params) = calendar_part(&message).unwrap();
+
+    assert!(std::ptr::eq(entity, &message.parts()[1]));
+    assert_eq!(params.method, Some(Method::Other("X-CUSTOM".into())));
+    assert_eq!(params.component, None);
+}
+
+#[test]
+fn related_resources() {
+    use crate::rfc2557::Resources;
+
+    let input = b"Content-Type: multipart/related; boundary=r; start=\"<root@x>\"\r\n\r\n\
+                  --r\r\nContent-Type: image/gif\r\nContent-ID: <a%b@x>\r\n\
+                  Content-Location: images/\r\n dot.gif\r\n\r\nGIF\r\n\
+                  --r\r\nContent-Type: multipart/alternative; boundary=a\r\nContent-ID: <root@x>\r\n\r\n\
+                  --a\r\nContent-Type: text/html\r\n\r\n<img src=\"images/dot.gif\">\r\n--a--\r\n\
+                  --r--\r\n";
+    let message = parse_message(input);
+    let resources = Resources::new(&message).unwrap();
+
+    assert_eq!(resources.base(), None);
+    assert!(resources.root().unwrap().is("multipart/alternative"));
+    assert!(resources.resolve("cid:a%25b@x").unwrap().is("image/gif"));
+    assert!(resources.resolve("images/dot.gif").unwrap().is("image/gif"));
+    assert!(resources.resolve("cid:root@x").is_some());
+    assert!(Resources::new(&message.parts()[0]).is_none());
+}
+
+#[test]
+fn text_part_decoding() {
+    let decode = |input: &[u8]| decode_text_part(&parse_message(input)).unwrap();
+
+    // The BOM wins over the declared charset.
+    assert_eq!(
+        decode(b"Content-Type: text/plain; charset=iso-8859-1\r\n\r\n\xef\xbb\xbfna\xc3\xafve"),
+        ("naïve".to_string(), false)
+    );
+    assert_eq!(
+        decode(b"Content-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\nw6k=\r\n"),
+        ("é".to_string(), false)
+    );
+    assert_eq!(
+        decode(b"Content-Transfer-Encoding: quoted-printable\r\n\r\nline  \r\n=3D=ZZ"),
+        ("line\r\n==ZZ".to_string(), true)
+    );
+    // Spaces before a soft line break are literal.
+    assert_eq!(
+        decode(b"Content-Transfer-Encoding: quoted-printable\r\n\r\na  =\r\n\r\nb  ="),
+        ("a  \r\nb  ".to_string(), false)
+    );
+    assert_eq!(
+        decode(b"Content-Type: text/plain; charset=utf-8\r\n\r\nbad \xff"),
+        ("bad \u{fffd}".to_string(), true)
+    );
+
+    let message = parse_message(
+        b"Content-Type: multipart/mixed; boundary=b\r\n\r\n--b\r\n\r\nx\r\n--b--\r\n",
+    );
+    assert_eq!(decode_text_part(&message), None);
+}
+
+#[test]
+fn attachment_selection() {
+    let input = b"Content-Type: multipart/mixed; boundary=b\r\n\r\n\
+                  --b\r\nContent-Type: multipart/alternative; boundary=a\r\n\r\n\
+                  --a\r\n\r\nplain\r\n--a\r\nContent-Type: text/html\r\n\r\n<p>html</p>\r\n--a--\r\n\
+                  --b\r\nContent-Type: image/png; name=\"=?utf-8?B?w6l0w6kucG5n?=\"\r\n\
+                  Content-Disposition: inline\r\n\r\nPNG\r\n\
+                  --b\r\nContent-Type: text/csv\r\nContent-Disposition: attachment\r\n\r\na,b\r\n--b--\r\n";
+    let message = parse_message(input);
+
+    let found: Vec<_> = attachments(&message).collect();
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].filename.as_deref(), Some("été.png"));
+    assert_eq!(
+        found[0].disposition.as_ref().map(|d| &d.disposition),
+        Some(&ContentDisposition::Inline)
+    );
+    assert_eq!(found[1].filename, None);
+    assert_eq!(found[1].content_type.mime_type, "text/csv");
+    assert_eq!(&found[1].content[..], b"a,b");
+}
+
+#[test]
+fn forwarded_message_attachment() {
+    let eml = b"Subject: original\r\nContent-Type: multipart/mixed; boundary=i\r\n\r\n\
+                --i\r\n\r\ntext\r\n\
+                --i\r\nContent-Type: image/png\r\n\r\nPNG\r\n--i--\r\n";
+    let mut input = b"Content-Type: multipart/mixed; boundary=b\r\n\r\n\
+                      --b\r\n\r\nSee the forwarded message.\r\n\
+                      --b\r\nContent-Type: message/rfc822\r\n\
+                      Content-Disposition: attachment; filename=original.eml\r\n\r\n"
+        .to_vec();
+    input.extend_from_slice(eml);
+    input.extend_from_slice(b"\r\n--b--\r\n");
+    let message = parse_message(&input);
+
+    let found: Vec<_> = attachments(&message).collect();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].filename.as_deref(), Some("original.eml"));
+    assert_eq!(found[0].content_type.mime_type, "message/rfc822");
+    assert_eq!(&found[0].content[..], &eml[..]);
+    assert_eq!(parse_message(&found[0].content).walk().count(), 3);
+
+    // Inline encapsulated messages are still searched.
+    let input = b"Content-Type: message/rfc822\r\n\r\n\
+                  Content-Type: image/png\r\nContent-Disposition: attachment\r\n\r\nPNG";
+    let message = parse_message(input);
+    let found: Vec<_> = attachments(&message).collect();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].content_type.mime_type, "image/png");
+}
@@ -0,0 +1,95 @@
+use std::thread;
+
+use crate::behaviour::Intl;
+use crate::headersection::*;
+use crate::rfc5322::{parse_header, ParsedHeader};
+
+fn assert_owned<T: Send + Sync + 'static>() {}
+
+#[test]
+fn owned_results_are_send_sync() {
+    assert_owned::<crate::calendar::CalendarParams>();
+    assert_owned::<crate::mime::Attachment<'static>>();
+    assert_owned::<crate::mime::OwnedEntity>();
+    assert_owned::<crate::proxy::ProxyHeader>();
+    assert_owned::<crate::rfc2231::ContentDisposition>();
+    assert_owned::<crate::rfc2231::ContentTransferEncoding>();
+    assert_owned::<crate::rfc2231::Disposition>();
+    assert_owned::<crate::rfc2231::MimeType>();
+    assert_owned::<crate::rfc2231::ParameterConflict>();
+    assert_owned::<crate::rfc3461::DSNMailParams>();
+    assert_owned::<crate::rfc3461::DSNRcptParams>();
+    assert_owned::<crate::rfc4954::AuthMailParams>();
+    assert_owned::<crate::rfc5321::Command>();
+    assert_owned::<crate::rfc5321::ForwardPath>();
+    assert_owned::<crate::rfc5321::Param>();
+    assert_owned::<crate::rfc5321::ReversePath>();
+    assert_owned::<crate::rfc5321::reply::Reply>();
+    assert_owned::<crate::rfc5322::Address>();
+    assert_owned::<crate::rfc5322::DateTime>();
+    assert_owned::<crate::rfc5322::MessageId>();
+    assert_owned::<crate::rfc5322::Received>();
+    assert_owned::<ParsedHeader<'static>>();
+    assert_owned::<crate::transcript::Exchange>();
+    assert_owned::<crate::transcript::Recipient>();
+    assert_owned::<crate::types::Mailbox>();
+    assert_owned::<crate::xforward::Param>();
+    assert_owned::<HeaderChange>();
+    assert_owned::<HeaderName>();
+    assert_owned::<OwnedHeaderField>();
+}
+
+#[test]
+fn owned_results_cross_threads() {
+    let input = b"Subject: hi\r\nX-Spam: yes\r\n\r\n".to_vec();
+    let (_, headers) = header_section(&input).unwrap();
+
+    let values: Vec<_> = headers
+        .iter()
+        .filter_map(|h| h.as_ref().ok())
+        .map(|(name, value)| parse_header::<Intl>(name, value).into_owned())
+        .collect();
+    let fields: Vec<_> = headers.into_iter().map(into_owned_field).collect();
+    drop(input);
+
+    let (values, fields) = thread::spawn(move || (values, fields)).join().unwrap();
+    assert_eq!(
+        values,
+        [
+            ParsedHeader::Unstructured("hi".into()),
+            ParsedHeader::Raw(b" yes".to_vec().into())
+        ]
+    );
+    assert_eq!(fields[1], Ok((b"X-Spam".to_vec(), b" yes".to_vec())));
+}
+
+#[test]
+fn owned_entity_cross_threads() {
+    let input = b"Content-Type: multipart/mixed; boundary=b\r\n\r\n\
+                  --b\r\nContent-Type: text/plain\r\n\r\nfirst\r\n\
+                  --b\r\nContent-Type: application/octet-stream\r\n\
+                  Content-Transfer-Encoding: base64\r\n\r\naGk=\r\n--b--\r\n"
+        .to_vec();
+    let borrowed = crate::mime::parse_message(&input);
+    let owned = borrowed.clone().into_owned();
+    assert_eq!(owned.as_entity(), borrowed);
+    drop(borrowed);
+    drop(input);
+
+    let owned = thread::spawn(move || owned).join().unwrap();
+    let message = owned.as_entity();
+    let bodies: Vec<_> = message
+        .parts()
+        .iter()
+        .map(|part| part.decoded_body().unwrap().0.into_owned())
+        .collect();
+    assert_eq!(bodies, [b"first".to_vec(), b"hi".to_vec()]);
+
+    let attachment = crate::mime::attachments(&message)
+        .next()
+        .unwrap()
+        .into_owned();
+    drop(message);
+    drop(owned);
+    assert_eq!(&attachment.content[..], b"hi");
+}
@@ -0,0 +1,60 @@
+use crate::proxy::*;
+
+#[test]
+fn v1_tcp6() {
+    let (rem, header) = proxy_header(b"PROXY TCP6 2001:db8::1 ::1 65535 587\r\n").unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(
+        header,
+        ProxyHeader::Proxied {
+            source: "[2001:db8::1]:65535".parse().unwrap(),
+            destination: "[::1]:587".parse().unwrap(),
+        }
+    );
+}
+
+#[test]
+fn v1_unknown() {
+    let (rem, header) = proxy_header(b"PROXY UNKNOWN ffff::1 ::1 1 2\r\nEHLO").unwrap();
+    assert_eq!(rem, b"EHLO");
+    assert_eq!(header, ProxyHeader::Unknown);
+}
+
+#[test]
+fn v1_invalid() {
+    assert!(proxy_v1(b"PROXY TCP4 2001:db8::1 192.0.2.1 1 2\r\n").is_err());
+    assert!(proxy_v1(b"PROXY TCP4 192.0.2.1 192.0.2.2 01 2\r\n").is_err());
+    assert!(proxy_v1(b"PROXY TCP4 192.0.2.1 192.0.2.2 65536 2\r\n").is_err());
+}
+
+#[test]
+fn v1_length_limit() {
+    let longest = b"PROXY TCP6 ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff 65535 65535\r\n";
+    assert_eq!(longest.len(), V1_MAX_LENGTH - 3);
+    assert!(proxy_v1(longest).is_ok());
+
+    let mut unknown = b"PROXY UNKNOWN ".to_vec();
+    unknown.resize(V1_MAX_LENGTH - 2, b'x');
+    unknown.extend_from_slice(b"\r\nEHLO");
+    assert_eq!(proxy_v1(&unknown).unwrap().0, b"EHLO");
+
+    unknown.insert(20, b'x');
+    assert!(proxy_v1(&unknown).is_err());
+}
+
+#[test]
+fn v2_local_with_tlv() {
+    let mut input = b"\r\n\r\n\0\r\nQUIT\n\x20\x00\x00\x03".to_vec();
+    input.extend_from_slice(&[0x04, 0, 0]);
+    input.extend_from_slice(b"EHLO");
+
+    let (rem, header) = proxy_header(&input).unwrap();
+    assert_eq!(rem, b"EHLO");
+    assert_eq!(header, ProxyHeader::Unknown);
+}
+
+#[test]
+fn v2_truncated() {
+    let input = b"\r\n\r\n\0\r\nQUIT\n\x21\x21\x00\x24\x20\x01";
+    assert!(proxy_v2(input).is_err());
+}
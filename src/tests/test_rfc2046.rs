@@ -0,0 +1,36 @@
+use crate::rfc2046::*;
+
+#[test]
+fn roundtrip_padding_lf() {
+    let body = b"pre\n--b  \n\nA\r\n--b\t\r\nB\n--b-- \nepi\n";
+    let m = split_multipart(body, b"b").unwrap();
+    assert_eq!(m.preamble, b"pre");
+    assert_eq!(m.parts[0].delimiter, b"\n--b  \n");
+    assert_eq!(m.contents().collect::<Vec<_>>(), [&b"\nA"[..], b"B"]);
+    assert_eq!(m.close_delimiter, b"\n--b-- \n");
+    assert_eq!(m.epilogue, b"epi\n");
+    assert_eq!(m.to_bytes(), &body[..]);
+}
+
+#[test]
+fn empty_part() {
+    let body = b"--b\r\n--b\r\nX\r\n--b--";
+    let m = split_multipart(body, b"b").unwrap();
+    assert_eq!(m.preamble, b"");
+    assert_eq!(m.contents().collect::<Vec<_>>(), [&b""[..], b"X"]);
+    assert_eq!(m.to_bytes(), &body[..]);
+}
+
+#[test]
+fn truncated() {
+    let body = b"--b\r\nX\r\n--bb\r\nY";
+    let m = split_multipart(body, b"b").unwrap();
+    assert_eq!(m.contents().collect::<Vec<_>>(), [&b"X\r\n--bb\r\nY"[..]]);
+    assert_eq!(m.close_delimiter, b"");
+    assert_eq!(m.to_bytes(), &body[..]);
+}
+
+#[test]
+fn no_delimiter() {
+    assert_eq!(split_multipart(b"text\r\n--other\r\n", b"b"), None);
+}
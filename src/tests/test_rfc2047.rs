@@ -0,0 +1,99 @@
+use crate::rfc2047::*;
+
+fn decode_all(words: &EncodedWords) -> String {
+    words
+        .words
+        .iter()
+        .map(|w| encoded_word(w.as_bytes()).unwrap().1)
+        .collect()
+}
+
+#[test]
+fn encode_words_split() {
+    let text = "Grüße aus Zürich, ça va? ".repeat(4);
+
+    for encoding in [WordEncoding::B, WordEncoding::Q].iter() {
+        let encoded = encode_words(&text, "utf-8", *encoding).unwrap();
+        assert!(encoded.words.len() > 1);
+        assert!(encoded.words.iter().all(|w| w.len() <= MAX_WORD_LEN));
+        assert_eq!(decode_all(&encoded), text);
+    }
+
+    assert_eq!(
+        encode_words("", "utf-8", WordEncoding::Q)
+            .unwrap()
+            .words
+            .len(),
+        0
+    );
+    assert_eq!(encode_words("😀", "shift_jis", WordEncoding::B), None);
+    assert_eq!(encode_words("a", "x-unknown", WordEncoding::Q), None);
+}
+
+#[test]
+fn q_encode_strict_roundtrip() {
+    let text = "Re: (1/2) \"quoted\" a_b=c? \\ café";
+
+    for context in [WordContext::Text, WordContext::Comment, WordContext::Phrase].iter() {
+        let word = format!("=?utf-8?Q?{}?=", q_encode(text.as_bytes(), *context));
+        let (rem, decoded) = encoded_word_strict(*context)(word.as_bytes()).unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(decoded, text);
+    }
+}
+
+#[test]
+fn encode_unstructured_folding() {
+    use crate::behaviour::Intl;
+    use crate::rfc5322::unstructured;
+
+    let decode = |value: &str| unstructured::<Intl>(value.as_bytes()).unwrap().1;
+
+    assert_eq!(
+        encode_unstructured("plain ASCII subject", &[], 9),
+        "plain ASCII subject"
+    );
+    assert_eq!(
+        encode_unstructured("literal =?x?Q?y?=", &[], 9),
+        "literal =?utf-8?B?PT94P1E/eT89?="
+    );
+
+    // Spaces between encoded words are kept inside the encoding.
+    let text = "été  à Paris";
+    let value = encode_unstructured(text, &["iso-8859-1"], 9);
+    assert_eq!(value, "=?iso-8859-1?B?6XTpICDg?= Paris");
+    assert_eq!(decode(&value), text);
+
+    let text = "会議の議事録を送付します。ご確認のほどよろしくお願いいたします。";
+    let value = encode_unstructured(text, &["iso-2022-jp", "utf-8"], 9);
+    let lines: Vec<_> = value.split("\r\n").collect();
+    assert!(lines.len() > 1);
+    assert!(lines[0].len() + 9 <= MAX_LINE_LEN);
+    assert!(lines.iter().all(|l| l.len() <= MAX_LINE_LEN));
+    assert!(lines.iter().all(|l| l.contains("?iso-2022-jp?B?")));
+    assert_eq!(decode(&value), text);
+}
+
+#[test]
+fn encode_unstructured_trailing_spaces() {
+    let long = "x".repeat(70);
+
+    assert_eq!(
+        encode_unstructured(&format!("{} ", long), &[], 9),
+        format!("{} ", long)
+    );
+    assert_eq!(
+        encode_unstructured(&format!("{}   ", long), &[], 9),
+        format!("{}   ", long)
+    );
+    assert_eq!(
+        encode_unstructured(&format!("{}  y", long), &[], 9),
+        format!("{} \r\n y", long)
+    );
+    for value in [" a", "a  ", "  "]
+        .iter()
+        .map(|t| encode_unstructured(t, &[], 70))
+    {
+        assert!(value.split("\r\n").skip(1).all(|l| !l.trim().is_empty()));
+    }
+}
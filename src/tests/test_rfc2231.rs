@@ -146,13 +146,21 @@ fn encoded_single_no_encoding() {
     assert_eq!(params, [("title".into(), "This is ***fun***".into())]);
 }
 
+#[test]
+fn encoded_mail_alias() {
+    let (rem, (_, params)) =
+        content_type(b"application/x-stuff;\r\n title*=cp932''%93%FA%96%7B\r\n").unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(params, [("title".into(), "日本".into())]);
+}
+
 #[test]
 fn cd_mixed() {
     const CASES: &[&[u8]] = &[b"inline", b"attachment", b"x-whatever"];
     for input in CASES.iter() {
         let (rem, (disp, params)) = content_disposition(input).unwrap();
         assert_eq!(rem.len(), 0);
-        assert_eq!(disp.to_string(), std::str::from_utf8(*input).unwrap());
+        assert_eq!(disp.to_string(), std::str::from_utf8(input).unwrap());
         assert_eq!(params, []);
     }
 }
@@ -191,7 +199,7 @@ fn cte_mixed() {
     for input in CASES.iter() {
         let (rem, parsed) = content_transfer_encoding(input).unwrap();
         assert_eq!(rem.len(), 0);
-        assert_eq!(parsed.to_string(), std::str::from_utf8(*input).unwrap());
+        assert_eq!(parsed.to_string(), std::str::from_utf8(input).unwrap());
     }
 }
 
@@ -303,3 +311,70 @@ fn attfnbrokentokenutf() {
     let (rem, _) = content_disposition(b"attachment; filename=foo-\xC3\xA4.html").unwrap();
     assert_eq!(rem.len(), 0);
 }
+
+#[test]
+fn cte_canonical() {
+    let (rem, parsed) = content_transfer_encoding(b"X-Whatever").unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(parsed, CTE::Extended("whatever".into()));
+    assert_eq!(parsed.to_string(), "x-whatever");
+
+    let (rem, parsed) = content_transfer_encoding(b"UUEncode").unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(parsed, CTE::Token("uuencode".into()));
+    assert_eq!(CTE::Token("UUEncode".into()).to_string(), "uuencode");
+}
+
+#[test]
+fn effective_defaults() {
+    let mt = effective_content_type(None, ContentTypeContext::Digest);
+    assert_eq!(mt.mime_type, "message/rfc822");
+    assert!(mt.params.is_empty());
+
+    let mt = effective_content_type(Some(b"/bad"), ContentTypeContext::Default);
+    assert_eq!(mt.mime_type, "text/plain");
+    assert_eq!(mt.param("charset"), Some("us-ascii"));
+
+    let mt = effective_content_type(
+        Some(b" Text/Plain; format=flowed"),
+        ContentTypeContext::Default,
+    );
+    assert_eq!(mt.mime_type, "text/plain");
+    assert_eq!(mt.param("format"), Some("flowed"));
+    assert_eq!(mt.param("charset"), Some("us-ascii"));
+
+    let mt = effective_content_type(
+        Some(b"text/html; charset=utf-8"),
+        ContentTypeContext::Digest,
+    );
+    assert_eq!(mt.mime_type, "text/html");
+    assert_eq!(mt.params, [("charset".into(), "utf-8".into())]);
+}
+
+#[test]
+fn duplicate_policy() {
+    let input = b"attachment; filename=a.txt; FILENAME=b.txt; size=1";
+
+    let (_, (_, params, conflicts)) =
+        content_disposition_with_policy(DuplicatePolicy::LastWins)(input).unwrap();
+    assert_eq!(
+        params,
+        [
+            ("filename".into(), "b.txt".into()),
+            ("size".into(), "1".into())
+        ]
+    );
+    assert_eq!(
+        conflicts,
+        [ParameterConflict {
+            name: "filename".into(),
+            values: vec!["a.txt".into(), "b.txt".into()],
+            chosen: 1,
+        }]
+    );
+
+    let (_, (_, params, _)) =
+        content_disposition_with_policy(DuplicatePolicy::FirstWins)(input).unwrap();
+    assert_eq!(params[0], ("filename".into(), "a.txt".into()));
+    assert!(content_disposition_with_policy(DuplicatePolicy::Error)(input).is_err());
+}
@@ -0,0 +1,67 @@
+use crate::behaviour::Intl;
+use crate::rfc4954::*;
+use crate::rfc5321::mail_command;
+
+fn auth(input: &[u8]) -> Result<Option<AuthMailbox>, &'static str> {
+    let (_, (_, params)) = mail_command::<Intl>(input).unwrap();
+    auth_param(&params)
+}
+
+#[test]
+fn auth_param_mailbox() {
+    match auth(b"MAIL FROM:<a@example.org> auth=e+3Dmc2@example.org\r\n") {
+        Ok(Some(AuthMailbox::Mailbox(mailbox))) => {
+            assert_eq!(mailbox.to_string(), "e=mc2@example.org")
+        }
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(
+        auth(b"MAIL FROM:<a@example.org> AUTH=<>\r\n"),
+        Ok(Some(AuthMailbox::Unknown))
+    );
+    assert_eq!(
+        auth(b"MAIL FROM:<a@example.org> AUTH=+3C+3E\r\n"),
+        Ok(Some(AuthMailbox::Unknown))
+    );
+    assert_eq!(auth(b"MAIL FROM:<a@example.org> SIZE=10\r\n"), Ok(None));
+}
+
+#[test]
+fn auth_param_invalid() {
+    assert_eq!(
+        auth(b"MAIL FROM:<a@example.org> AUTH=<> AUTH=b@example.org\r\n"),
+        Err("Duplicate AUTH")
+    );
+    assert_eq!(
+        auth(b"MAIL FROM:<a@example.org> AUTH=bob\r\n"),
+        Err("Invalid AUTH")
+    );
+    assert_eq!(
+        auth(b"MAIL FROM:<a@example.org> AUTH=b+ZZ@example.org\r\n"),
+        Err("Invalid AUTH")
+    );
+    assert_eq!(
+        auth(b"MAIL FROM:<a@example.org> AUTH\r\n"),
+        Err("AUTH without value")
+    );
+}
+
+#[test]
+fn auth_mail_params_keeps_others() {
+    let (params, other) =
+        auth_mail_params(&[("SIZE", Some("10")), ("Auth", Some("<>")), ("BODY", None)]).unwrap();
+
+    assert_eq!(params.auth, Some(AuthMailbox::Unknown));
+    assert_eq!(other, [("SIZE", Some("10")), ("BODY", None)]);
+}
+
+#[test]
+fn auth_command_mechanisms() {
+    let (_, (mech, response)) = auth_command(b"auth scram-sha-256 =\r\n").unwrap();
+    assert_eq!(mech, "SCRAM-SHA-256");
+    assert_eq!(response.as_deref(), Some("="));
+
+    assert!(auth_command(b"AUTH\r\n").is_err());
+    assert!(auth_command(b"AUTH PLAIN bad!\r\n").is_err());
+    assert!(auth_command(b"AUTH A23456789012345678901\r\n").is_err());
+}
@@ -3,9 +3,13 @@ use std::str::FromStr;
 
 use crate::behaviour::*;
 use crate::rfc5321::*;
+use crate::testdata::*;
 use crate::types::*;
+use nom::combinator::all_consuming;
 
-fn dp<T: Into<String>>(value: T) -> DomainPart {
+use super::check_corpus;
+
+fn dp(value: &str) -> DomainPart {
     DomainPart::Domain(Domain(value.into()))
 }
 
@@ -17,21 +21,17 @@ fn empty_from() {
 }
 
 #[test]
-#[should_panic]
-fn empty_rcpt() {
-    rcpt_command::<Intl>(b"RCPT TO:<>\r\n").unwrap();
-}
-
-#[test]
-#[should_panic]
-fn invalid_from() {
-    mail_command::<Intl>(b"MAIL FROM:<pa^^&*(sarobas@example.org>\r\n").unwrap();
-}
-
-#[test]
-#[should_panic]
-fn invalid_rcpt() {
-    rcpt_command::<Intl>(b"RCPT TO:<pa^^&*(sarobas@example.org>\r\n").unwrap();
+fn command_corpora() {
+    check_corpus(MAIL_COMMANDS, |i| {
+        all_consuming(mail_command::<Intl>)(i)
+            .ok()
+            .map(|(_, (p, _))| p.to_string())
+    });
+    check_corpus(RCPT_COMMANDS, |i| {
+        all_consuming(rcpt_command::<Intl>)(i)
+            .ok()
+            .map(|(_, (p, _))| p.to_string())
+    });
 }
 
 #[test]
@@ -126,8 +126,22 @@ fn postmaster_rcpt() {
 
 #[test]
 fn validate() {
-    assert!(validate_address::<Intl>(b"mrbob@example.org"));
-    assert!(!validate_address::<Intl>(b"mrbob\"@example.org"));
+    check_corpus(SMTP_ADDRESSES, |i| {
+        all_consuming(mailbox::<Intl>)(i)
+            .ok()
+            .map(|(_, m)| m.to_string())
+    });
+    for case in SMTP_ADDRESSES {
+        assert_eq!(validate_address::<Intl>(case.input), case.is_valid());
+    }
+
+    let inputs = SMTP_ADDRESSES.iter().map(|case| case.input);
+    for (case, res) in SMTP_ADDRESSES
+        .iter()
+        .zip(validate_addresses::<Intl, _>(inputs))
+    {
+        assert_eq!(res.is_ok(), case.is_valid(), "{:?}", case);
+    }
 }
 
 #[test]
@@ -143,3 +157,393 @@ fn normal_quoted_lp() {
     lp.smtp_try_unquote();
     assert_eq!(lp, LocalPart::Quoted(QuotedString("a b".into())));
 }
+
+#[test]
+fn reply_multiline() {
+    let (rem, parsed) = reply::reply(b"250-first\r\n250-\r\n250\r\nnext").unwrap();
+    assert_eq!(rem, b"next");
+    assert_eq!(parsed.code, 250);
+    assert_eq!(parsed.lines, ["first", "", ""]);
+
+    let (_, parsed) = reply::reply(b"250-2.1.0\r\n250 2.1.5 ok\r\n").unwrap();
+    assert_eq!(parsed.esc, Some(reply::EnhancedStatus::new(2, 1, 0)));
+    assert_eq!(parsed.lines, ["", "2.1.5 ok"]);
+    assert_eq!(parsed.bare_lines, [1]);
+    assert_eq!(parsed.to_bytes(), b"250-2.1.0\r\n250 2.1.5 ok\r\n");
+    for input in [
+        &b"250-2.1.0 \r\n250 2.1.0\r\n"[..],
+        b"550-5.1.1 a\r\n550-b\r\n550 5.1.1 c\r\n",
+    ]
+    .iter()
+    {
+        assert_eq!(reply::reply(input).unwrap().1.to_bytes(), *input);
+    }
+    let (_, parsed) = reply::reply(b"250 5.1.1 mismatched class\r\n").unwrap();
+    assert_eq!(parsed.esc, None);
+    let (_, parsed) = reply::reply(b"451 4.3.0x\r\n").unwrap();
+    assert_eq!(parsed.esc, None);
+
+    assert!(reply::reply(b"250-first\r\n251 second\r\n").is_err());
+    assert!(reply::reply(b"650 bad code\r\n").is_err());
+}
+
+#[test]
+fn greeting_reject() {
+    let (_, parsed) =
+        reply::greeting(b"554-[192.0.2.1] go away\r\n554 No SMTP service here\r\n").unwrap();
+    assert!(!parsed.accepted);
+    assert_eq!(
+        parsed.host,
+        Some(DomainPart::Address(AddressLiteral::IP(IpAddr::V4(
+            Ipv4Addr::new(192, 0, 2, 1)
+        ))))
+    );
+    assert!(!parsed.esmtp);
+    assert_eq!(parsed.reply.lines.len(), 2);
+
+    let (_, parsed) = reply::greeting(b"220 mail.example.net Service ready\r\n").unwrap();
+    assert!(parsed.accepted);
+    assert_eq!(parsed.host.unwrap().to_string(), "mail.example.net");
+    assert!(!parsed.esmtp);
+    let (_, parsed) = reply::greeting(b"220 *** Service ready\r\n").unwrap();
+    assert_eq!(parsed.host, None);
+    assert!(reply::greeting(b"250 mx.example.org\r\n").is_err());
+}
+
+#[test]
+fn ehlo_exchange() {
+    let (_, domain) = ehlo_command::<Intl>(b"EHLO [192.0.2.1]\r\n").unwrap();
+    assert_eq!(domain.to_string(), "[192.0.2.1]");
+    let (_, domain) = helo_command::<Intl>(b"helo client.example\r\n").unwrap();
+    assert_eq!(domain.to_string(), "client.example");
+    assert!(helo_command::<Intl>(b"HELO [192.0.2.1]\r\n").is_err());
+
+    let (_, parsed) =
+        reply::ehlo_response(b"250-mx.example.org\r\n250-SIZE\r\n250--bogus\r\n250 AUTH=LOGIN\r\n")
+            .unwrap();
+    assert_eq!(parsed.size(), Some(0));
+    assert_eq!(parsed.extensions.len(), 2);
+    assert!(parsed.supports("auth=login"));
+    assert!(reply::ehlo_response(b"550 go away\r\n").is_err());
+}
+
+#[test]
+fn reply_sanitize() {
+    let original = reply::Reply {
+        code: 250,
+        esc: None,
+        lines: vec!["\u{e9}t\u{e9}\x07".into(), "x".repeat(600)],
+        bare_lines: vec![],
+    };
+    let policy = reply::TextPolicy {
+        smtputf8: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        original.check_text(policy),
+        Err(reply::TextError::Control { line: 0, offset: 5 })
+    );
+    let sanitized = original.sanitize(policy);
+    assert_eq!(sanitized.lines[0], "\u{e9}t\u{e9} ");
+    assert_eq!(sanitized.lines[1].len(), reply::MAX_REPLY_LINE - 6);
+
+    let bytes = sanitized.to_bytes();
+    let (rem, parsed) = reply::reply(&bytes).unwrap();
+    assert!(rem.is_empty());
+    assert_eq!(parsed, sanitized);
+    assert!(bytes
+        .split(|c| *c == b'\n')
+        .all(|l| l.len() < reply::MAX_REPLY_LINE));
+}
+
+#[test]
+fn sequence_offsets() {
+    let input = b"EHLO example.org\r\nNOOP\r\nbogus\r\nQUIT\r\n";
+    let (rem, commands) = command_sequence::<Intl>(input).unwrap();
+
+    assert_eq!(commands.len(), 2);
+    assert_eq!(commands[1].0, 18);
+    assert!(matches!(commands[1].1, Command::NOOP(None)));
+    assert_eq!(rem, b"bogus\r\nQUIT\r\n");
+
+    let (rem, commands) = command_sequence::<Intl>(b"").unwrap();
+    assert!(commands.is_empty() && rem.is_empty());
+}
+
+#[test]
+fn path_policy() {
+    let rcpt = |policy, input: &[u8]| rcpt_command_with_policy::<Intl>(policy)(input).unwrap().1;
+
+    let (p, params, quirk) = rcpt(
+        PathPolicy::Accept,
+        b"RCPT TO:<@a.example.org,@b.example.org:bob@example.org> NOTIFY=NEVER\r\n",
+    );
+    match p {
+        Some(ForwardPath::Path(path)) => assert_eq!(path.1.len(), 2),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert_eq!(params.len(), 1);
+    assert_eq!(quirk, Some(PathQuirk::SourceRoute));
+
+    let (p, _, quirk) = rcpt(PathPolicy::Reject, b"RCPT TO:<:bob@example.org>\r\n");
+    assert_eq!((p, quirk), (None, Some(PathQuirk::EmptyRoute)));
+
+    let (p, _, quirk) = rcpt(PathPolicy::Reject, b"RCPT TO:<Postmaster>\r\n");
+    assert_eq!((p, quirk), (Some(ForwardPath::PostMaster(None)), None));
+
+    let (p, _, quirk) = rcpt(PathPolicy::Normalize, b"RCPT TO:<bob@example.org>\r\n");
+    assert_eq!(p.unwrap().to_string(), "<bob@example.org>");
+    assert_eq!(quirk, None);
+
+    let (rp, _, quirk) =
+        mail_command_with_policy::<Intl>(PathPolicy::Reject)(b"MAIL FROM:<list:;>\r\n")
+            .unwrap()
+            .1;
+    assert_eq!((rp, quirk), (None, Some(PathQuirk::EmptyGroup)));
+}
+
+#[test]
+fn command_dispatch() {
+    let parse = |line: &[u8]| command::<Intl>(line).unwrap().1;
+
+    assert!(matches!(parse(b"data\r\n"), Command::DATA));
+    assert!(matches!(parse(b"StartTLS\r\n"), Command::STARTTLS));
+    assert!(matches!(
+        parse(b"BDAT 10 LAST\r\n"),
+        Command::BDAT(10, true)
+    ));
+    assert!(
+        matches!(parse(b"AUTH PLAIN =\r\n"), Command::AUTH(ref m, Some(ref r)) if m == "PLAIN" && r == "=")
+    );
+    assert!(
+        matches!(parse(b"XFORWARD NAME=mx\r\n"), Command::UNKNOWN(ref l) if l == "XFORWARD NAME=mx")
+    );
+
+    assert!(command::<Intl>(b"AUTH\r\n").is_err());
+    assert!(command::<Intl>(b"STARTTLS now\r\n").is_err());
+    assert!(command::<Intl>(b"XFORWARD").is_err());
+}
+
+#[test]
+fn session_byte_by_byte() {
+    use crate::rfc5321::session::{Event, SmtpSession};
+
+    let input = b"DATA\r\nline\r\n..\r\n.\r\nBDAT 5 LAST\r\nab\r\ncNOOP\r\n";
+    let mut session = SmtpSession::<Intl>::new();
+    let mut events = Vec::new();
+    let mut data = Vec::new();
+
+    for c in input.iter() {
+        session.feed(&[*c]);
+        for event in &mut session {
+            match event {
+                Event::Data(d) => data.extend(d),
+                other => events.push(format!("{:?}", other)),
+            }
+        }
+    }
+
+    assert_eq!(data, b"line\r\n.\r\nab\r\nc");
+    assert_eq!(
+        events,
+        [
+            "Command(DATA)",
+            "EndOfData",
+            "Command(BDAT(5, true))",
+            "EndOfChunk(true)",
+            "Command(NOOP(None))"
+        ]
+    );
+}
+
+#[test]
+fn session_recovery() {
+    use crate::rfc5321::session::{Event, SmtpSession};
+
+    let mut session = SmtpSession::<Intl>::with_max_line_length(16);
+    session.feed(b"NOOP 0123456789abcdef");
+    assert!(matches!(session.next(), Some(Event::TooLong)));
+    session.feed(b"more\r\nMAIL FROM:bob\r\nDATA\r\n");
+    assert!(matches!(session.next(), Some(Event::Invalid(ref l)) if l == b"MAIL FROM:bob"));
+    assert!(matches!(
+        session.next(),
+        Some(Event::Command(Command::DATA))
+    ));
+    assert!(session.in_data());
+
+    session.reject_data();
+    session.feed(b"RSET\r\n");
+    assert!(matches!(
+        session.next(),
+        Some(Event::Command(Command::RSET))
+    ));
+    assert!(session.next().is_none());
+}
+
+#[test]
+fn session_limits() {
+    use crate::rfc5321::session::{Event, SmtpSession};
+
+    let mut session = SmtpSession::<Intl>::new();
+    session.set_limits(Limits {
+        max_params: 2,
+        max_recipients: 2,
+    });
+    session.feed(
+        b"MAIL FROM:<a@example.org> A B C\r\n\
+          MAIL FROM:<a@example.org> A B\r\n\
+          RCPT TO:<b@example.org>\r\n\
+          RCPT TO:<c@example.org>\r\n\
+          RCPT TO:<d@example.org>\r\n",
+    );
+    let events: Vec<_> = session.by_ref().map(|e| format!("{:?}", e)).collect();
+    assert_eq!(events[0], "Refused(TooManyParams)");
+    assert!(events[1].starts_with("Command(MAIL"));
+    assert!(events[2].starts_with("Command(RCPT"));
+    assert!(events[3].starts_with("Command(RCPT"));
+    assert_eq!(events[4], "Refused(TooManyRecipients)");
+    assert_eq!(session.transaction().recipients(), 2);
+
+    // A recipient refused by the server frees its place.
+    session.reject_recipient();
+    session.feed(b"RCPT TO:<d@example.org>\r\nRSET\r\n");
+    assert!(matches!(
+        session.next(),
+        Some(Event::Command(Command::RCPT(..)))
+    ));
+    assert!(matches!(
+        session.next(),
+        Some(Event::Command(Command::RSET))
+    ));
+    assert_eq!(session.transaction().recipients(), 0);
+}
+
+#[test]
+fn session_message_size() {
+    use crate::rfc5321::session::{Event, SmtpSession};
+
+    let mut session = SmtpSession::<Intl>::new();
+    session.set_max_message_size(10);
+    session.feed(b"DATA\r\n0123456789");
+    let events: Vec<_> = session.by_ref().map(|e| format!("{:?}", e)).collect();
+    assert_eq!(
+        events,
+        [
+            "Command(DATA)",
+            "Data([48, 49, 50, 51, 52, 53, 54, 55, 56, 57])"
+        ]
+    );
+
+    // Nothing after the limit is returned, not even the line it ends.
+    session.feed(b"\r\nmore\r\n");
+    assert!(matches!(session.next(), Some(Event::TooLarge)));
+    session.feed(b"and more\r\n");
+    assert!(session.next().is_none());
+    session.feed(b"..\r\n.\r\nQUIT\r\n");
+    assert!(matches!(session.next(), Some(Event::EndOfData)));
+    assert!(matches!(
+        session.next(),
+        Some(Event::Command(Command::QUIT))
+    ));
+
+    // The size restarts with the next message.
+    session.feed(b"DATA\r\n0123456\r\n.\r\n");
+    let events: Vec<_> = session.map(|e| format!("{:?}", e)).collect();
+    assert_eq!(
+        events,
+        [
+            "Command(DATA)",
+            "Data([48, 49, 50, 51, 52, 53, 54, 13, 10])",
+            "EndOfData"
+        ]
+    );
+}
+
+#[test]
+fn session_large_input() {
+    use crate::rfc5321::session::{Event, SmtpSession};
+
+    // Decoding must stay linear when a whole message arrives in one
+    // read, this takes minutes when every line shifts the buffer.
+    let line = b"..0123456789012345678901234567890123456789012345678901234567890123\r\n";
+    let lines = 100_000;
+    let mut input = b"DATA\r\n".to_vec();
+    for _ in 0..lines {
+        input.extend_from_slice(line);
+    }
+    input.extend_from_slice(b".\r\n");
+    for _ in 0..lines {
+        input.extend_from_slice(b"NOOP\r\n");
+    }
+
+    let mut session = SmtpSession::<Intl>::new();
+    session.feed(&input);
+
+    assert!(matches!(
+        session.next(),
+        Some(Event::Command(Command::DATA))
+    ));
+    match session.next() {
+        Some(Event::Data(data)) => {
+            assert_eq!(data.len(), (line.len() - 1) * lines);
+            assert!(data.starts_with(&line[1..]));
+        }
+        other => panic!("{:?}", other),
+    }
+    assert!(matches!(session.next(), Some(Event::EndOfData)));
+    assert_eq!(session.by_ref().count(), lines);
+    assert!(session.pending().is_empty());
+}
+
+#[test]
+fn path_command_roundtrip() {
+    let input = b"MAIL FROM:<\"a\\\"b\"@example.org> BODY=8BITMIME SIZE=100\r\n";
+    let (_, (path, params)) = mail_command::<Intl>(input).unwrap();
+
+    let line = PathCommand::mail(&path)
+        .params(params)
+        .param(Param::new("size", Some("200")).unwrap());
+    assert_eq!(
+        line.to_string(),
+        "MAIL FROM:<\"a\\\"b\"@example.org> BODY=8BITMIME size=200"
+    );
+    let (_, (reparsed, params)) = mail_command::<Intl>(&line.to_bytes()).unwrap();
+    assert_eq!(reparsed, path);
+    assert_eq!(params.len(), 2);
+
+    assert_eq!(
+        PathCommand::mail(&ReversePath::Null).to_bytes(),
+        b"MAIL FROM:<>\r\n"
+    );
+}
+
+#[test]
+fn mailbox_display_injection() {
+    let mbox = Mailbox::from_smtp(b"user@example.org").unwrap();
+    let out = mbox
+        .display(MailboxFormat::named("John\r\nBcc: x@example.org"))
+        .to_string();
+
+    assert!(!out.contains(['\r', '\n']), "{:?}", out);
+    assert!(out.ends_with(" <user@example.org>"), "{:?}", out);
+}
+
+#[test]
+fn bdat_failure_is_sticky() {
+    let mut tracker = BdatTracker::with_max_size(100);
+
+    tracker.chunk(60, false).unwrap();
+    assert_eq!(tracker.chunk(60, false), Err(BdatError::TooLarge));
+    // A LAST chunk after the refused one must not complete a
+    // truncated message.
+    assert_eq!(tracker.chunk(10, true), Err(BdatError::TooLarge));
+    assert!(tracker.is_failed());
+    assert!(!tracker.is_complete());
+    assert_eq!((tracker.total(), tracker.chunks()), (60, 1));
+
+    tracker.reset();
+    assert!(!tracker.is_failed());
+    tracker.chunk(100, true).unwrap();
+    assert!(tracker.is_complete());
+}
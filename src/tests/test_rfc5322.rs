@@ -1,8 +1,16 @@
-use crate::behaviour::{Intl, Legacy};
-use crate::rfc5322::{from, reply_to, sender, unstructured, Address, Group, Mailbox};
+use crate::behaviour::{Intl, Legacy, LegacyWith, QuestionMark, Reject, Strip};
+use crate::rfc5322::{
+    date_time, dedup_mailboxes, expand_groups, from, in_reply_to, msg_id, parse_header, received,
+    references, reply_to, routed_mailboxes, sender, unstructured, unstructured_spans, Address,
+    AddressListEditor, DateTime, Group, Mailbox, ParsedHeader,
+};
+use crate::testdata::FROM_HEADERS;
 use crate::types::{Mailbox as SMTPMailbox, *};
+use nom::combinator::all_consuming;
 
-fn dp<T: Into<String>>(value: T) -> DomainPart {
+use super::check_corpus;
+
+fn dp(value: &str) -> DomainPart {
     DomainPart::Domain(Domain(value.into()))
 }
 
@@ -59,6 +67,19 @@ fn simple_from() {
     )
 }
 
+#[test]
+fn from_corpus() {
+    check_corpus(FROM_HEADERS, |i| {
+        all_consuming(from::<Intl>)(i).ok().map(|(_, addresses)| {
+            addresses
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+    });
+}
+
 #[test]
 fn simple_sender() {
     let (rem, parsed) = sender::<Intl>(b"Michael Jones <mjones@machine.example>\r\n").unwrap();
@@ -182,3 +203,331 @@ fn invalid_latin1() {
     assert_eq!(rem.len(), 0);
     assert_eq!(parsed, "\u{fffd}");
 }
+
+#[test]
+fn dedup_keeps_first_dname() {
+    let mboxes = vec![
+        Mailbox {
+            dname: None,
+            address: SMTPMailbox::from_smtp(b"bob@example.org").unwrap(),
+        },
+        Mailbox {
+            dname: Some("Bob".into()),
+            address: SMTPMailbox::from_smtp(b"\"bob\"@EXAMPLE.ORG").unwrap(),
+        },
+        Mailbox {
+            dname: Some("Robert".into()),
+            address: SMTPMailbox::from_smtp(b"bob@example.org").unwrap(),
+        },
+        Mailbox {
+            dname: None,
+            address: SMTPMailbox::from_smtp(b"BOB@example.org").unwrap(),
+        },
+    ];
+
+    let deduped = dedup_mailboxes(mboxes);
+    assert_eq!(
+        deduped,
+        [
+            Mailbox {
+                dname: Some("Bob".into()),
+                address: SMTPMailbox::from_smtp(b"bob@example.org").unwrap(),
+            },
+            Mailbox {
+                dname: None,
+                address: SMTPMailbox::from_smtp(b"BOB@example.org").unwrap(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn expand_empty_group() {
+    let (_, parsed) = from::<Intl>(b"undisclosed-recipients:;, a@example.org").unwrap();
+    let expanded = expand_groups(parsed);
+    assert_eq!(
+        expanded,
+        [Mailbox {
+            dname: None,
+            address: SMTPMailbox::from_smtp(b"a@example.org").unwrap(),
+        }]
+    );
+}
+
+#[test]
+fn rewrite_keeps_dname() {
+    let parsed = parse_single(from::<Intl>, b"John Doe <jdoe@machine.example>\r\n");
+
+    let rewritten = parsed.with_domain(b"example.org").unwrap();
+    assert_eq!(rewritten.dname, Some("John Doe".into()));
+    assert_eq!(rewritten.address.to_string(), "jdoe@example.org");
+
+    let rewritten = rewritten.with_local_part(b"\"john doe\"").unwrap();
+    assert_eq!(rewritten.dname, Some("John Doe".into()));
+    assert_eq!(rewritten.address.to_string(), "\"john doe\"@example.org");
+
+    assert!(parsed.with_local_part(b"john doe").is_err());
+}
+
+#[test]
+fn obs_route_kept() {
+    let input = b"John <@relay.example,,@[192.0.2.1], (c) :jdoe@machine.example>\r\n";
+    assert!(from::<Intl>(input).is_err());
+
+    let (rem, parsed) = routed_mailboxes::<Intl>(input).unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(parsed[0].mailbox.dname, Some("John".into()));
+    assert_eq!(
+        parsed[0].mailbox.address.to_string(),
+        "jdoe@machine.example"
+    );
+    assert_eq!(
+        parsed[0].route,
+        [
+            dp("relay.example"),
+            DomainPart::Address(AddressLiteral::IP("192.0.2.1".parse().unwrap()))
+        ]
+    );
+
+    let (_, parsed) = routed_mailboxes::<Intl>(b"<jdoe@machine.example>\r\n").unwrap();
+    assert!(parsed[0].route.is_empty());
+    assert!(
+        all_consuming(routed_mailboxes::<Intl>)(b"<@relay.example jdoe@machine.example>").is_err()
+    );
+}
+
+#[test]
+fn editor_roundtrip() {
+    let input = b" (c)  Team:a@example.org,\r\n\t\"B\" <b@example.org>;,=?utf-8?b?w6k=?= <c@example.org> \r\n";
+    let mut editor = AddressListEditor::<Intl>::new(input).unwrap();
+    assert_eq!(editor.len(), 2);
+    assert_eq!(editor.to_bytes(), input.as_ref());
+
+    editor.remove(0);
+    editor.push(b"d@example.org").unwrap();
+    assert!(editor.push(b"d@example.org, e@example.org").is_err());
+    assert_eq!(
+        editor.to_bytes(),
+        b" =?utf-8?b?w6k=?= <c@example.org>, d@example.org \r\n".as_ref()
+    );
+
+    let mut editor = AddressListEditor::<Intl>::new(b"\t a@example.org, c@example.org").unwrap();
+    editor.replace(0, b"x@example.org").unwrap();
+    assert_eq!(
+        editor.to_bytes(),
+        b"\t x@example.org, c@example.org".as_ref()
+    );
+    editor.remove(0);
+    assert_eq!(editor.to_bytes(), b"\t c@example.org".as_ref());
+}
+
+#[test]
+fn parse_str() {
+    use std::convert::TryFrom;
+
+    let group: Group = "Team: a@example.org, B <b@example.org>;".parse().unwrap();
+    assert_eq!(group.dname, "Team");
+    assert_eq!(group.members.len(), 2);
+
+    let address = Address::try_from(b"=?utf-8?q?J=C3=B6rg?= <j@example.org>".as_ref()).unwrap();
+    assert!(matches!(address, Address::Mailbox(Mailbox { dname: Some(ref d), .. }) if d == "Jörg"));
+
+    assert!("a@example.org, b@example.org".parse::<Address>().is_err());
+    assert!("Team: a@example.org;".parse::<Mailbox>().is_err());
+}
+
+#[test]
+fn date_time_obsolete() {
+    let (_, date) = date_time::<Intl>(b" (c) Sun , 6 Nov 94 08:49:37 (x) GMT\r\n").unwrap();
+    assert_eq!(
+        date,
+        DateTime {
+            day_of_week: Some(7),
+            year: 1994,
+            month: 11,
+            day: 6,
+            hour: 8,
+            minute: 49,
+            second: 37,
+            offset: 0,
+        }
+    );
+    assert_eq!(
+        date_time::<Intl>(b"1 Jan 101 00:00 Z").unwrap().1.year,
+        2001
+    );
+    assert!(date_time::<Intl>(b"32 Jan 2020 00:00 +0000").is_err());
+    assert!(date_time::<Intl>(b"1 Foo 2020 00:00 +0000").is_err());
+    assert!(date_time::<Intl>(b"1 Jan 2020 24:00 +0000").is_err());
+}
+
+#[test]
+fn msg_id_obsolete() {
+    let (_, id) = msg_id::<Intl>(b" <\"a b\"@[192.0.2.1]> (comment)").unwrap();
+    assert_eq!((id.left(), id.right()), ("\"a b\"", "[192.0.2.1]"));
+    assert!(msg_id::<Intl>(b"no-brackets@example.org").is_err());
+}
+
+#[test]
+fn dispatch_headers() {
+    match parse_header::<Intl>(b"TO", b" a@example.org, Group: b@example.org;") {
+        ParsedHeader::AddressList(list) => assert_eq!(list.len(), 2),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert_eq!(
+        parse_header::<Intl>(b"Bcc", b" "),
+        ParsedHeader::AddressList(vec![])
+    );
+    match parse_header::<Intl>(b"Content-Type", b" text/plain; charset=utf-8") {
+        ParsedHeader::MimeType(mt) => assert_eq!(mt.param("charset"), Some("utf-8")),
+        other => panic!("unexpected {:?}", other),
+    }
+    match parse_header::<Intl>(b"Received", b" from localhost") {
+        ParsedHeader::Received(r) => {
+            assert_eq!((r.tokens.as_str(), r.date_time), ("from localhost", None))
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    assert_eq!(
+        parse_header::<Intl>(b"Message-ID", b" <a@b> trailing"),
+        ParsedHeader::Raw(b" <a@b> trailing".as_ref().into())
+    );
+}
+
+#[test]
+fn display_roundtrip() {
+    for input in [
+        "undisclosed-recipients:;",
+        "\"Smith, Mary\":;",
+        "Team: John Doe <jdoe@example.org>, \"Smith, Mary\" <mary@x.test>;",
+        "jdoe@example.org",
+    ]
+    .iter()
+    {
+        let address: Address = input.parse().unwrap();
+        assert_eq!(address.to_string(), *input);
+    }
+
+    let (_, parsed) = from::<Intl>(b"(none) Empty : (nobody) ;").unwrap();
+    assert_eq!(parsed[0].to_string(), "Empty:;");
+
+    let input = "<@relay.example,@[192.0.2.1]:jdoe@example.org>";
+    let (_, parsed) = routed_mailboxes::<Intl>(input.as_bytes()).unwrap();
+    assert_eq!(parsed[0].to_string(), input);
+}
+
+#[test]
+fn display_name_injection() {
+    let mut address: Address = "Team: Bob <bob@example.org>;".parse().unwrap();
+    if let Address::Group(group) = &mut address {
+        group.dname = "Team\r\nBcc: x@example.org\r\n".into();
+        group.members[0].dname = Some("Bob\r\nBcc: y@example.org".into());
+    }
+
+    let out = address.to_string();
+    assert!(!out.contains(['\r', '\n']), "{:?}", out);
+    assert!(out.starts_with("=?utf-8?"), "{:?}", out);
+
+    // The encoded words decode back to the original names.
+    let reparsed: Address = out.parse().unwrap();
+    assert_eq!(reparsed, address);
+}
+
+#[test]
+fn eight_bit_policy() {
+    let input = b"\"caf\xe9\" <a@b>";
+
+    let (_, parsed) = from::<Legacy>(input).unwrap();
+    assert_eq!(parsed[0].to_string(), "caf\u{fffd} <a@b>");
+    let (_, parsed) = from::<LegacyWith<QuestionMark>>(input).unwrap();
+    assert_eq!(parsed[0].to_string(), "caf? <a@b>");
+    let (_, parsed) = from::<LegacyWith<Strip>>(input).unwrap();
+    assert_eq!(parsed[0].to_string(), "caf <a@b>");
+    assert!(from::<LegacyWith<Reject>>(input).is_err());
+
+    // The same policy type is accepted by the SMTP parsers.
+    let (_, mbox) =
+        crate::rfc5321::mailbox::<LegacyWith<QuestionMark>>(b"bob@example.org").unwrap();
+    assert_eq!(mbox.to_string(), "bob@example.org");
+    assert!(
+        crate::rfc5321::mailbox::<LegacyWith<QuestionMark>>(b"b\xc3\xa9b@example.org").is_err()
+    );
+}
+
+#[test]
+fn quoted_utf8_display_name() {
+    let input = b"\"caf\xc3\xa9\" <a@b>";
+
+    let (_, parsed) = from::<Legacy>(input).unwrap();
+    assert_eq!(parsed[0].to_string(), "café <a@b>");
+    let (_, parsed) = from::<Intl>(input).unwrap();
+    assert_eq!(parsed[0].to_string(), "café <a@b>");
+    let (_, parsed) = from::<LegacyWith<QuestionMark>>(input).unwrap();
+    assert_eq!(parsed[0].to_string(), "caf?? <a@b>");
+}
+
+#[test]
+fn unstructured_spans_match() {
+    for input in [
+        &b"Hello World"[..],
+        b" =?utf-8?Q?a?=\r\n =?utf-8?Q?b?= c \t",
+        b"x =?utf-8?B?w6k=?=y\r\n z",
+        b"caf\xe9 ",
+    ]
+    .iter()
+    {
+        let (rem, decoded) = unstructured::<Legacy>(input).unwrap();
+        let (rem_spans, (decoded_spans, spans)) = unstructured_spans::<Legacy>(input).unwrap();
+        assert_eq!((rem, &decoded), (rem_spans, &decoded_spans));
+
+        let mut end = 0;
+        for (out, raw) in spans {
+            assert!(out.start >= end && raw.end <= input.len());
+            end = out.end;
+        }
+        assert_eq!(end, decoded.len());
+    }
+}
+
+#[test]
+fn received_clauses() {
+    let (_, parsed) = received::<Intl>(
+        b" from [192.0.2.7] (helo=client.example)\r\n\tby mx.example.org with esmtpa (Exim 4.96)\r\n\
+          \t(envelope-from <a@example.com>)\r\n\tid 1sEZ9v-0001\r\n\tfor bob@example.org; Mon, 3 Jun 2024 10:00:00 +0000",
+    )
+    .unwrap();
+    let from = parsed.from.unwrap();
+    assert_eq!(from.ip.unwrap().to_string(), "192.0.2.7");
+    assert_eq!(from.comment.as_deref(), Some("helo=client.example"));
+    let by = parsed.by.unwrap();
+    assert_eq!(by.domain, Some(dp("mx.example.org")));
+    assert_eq!((by.comment, by.ip), (None, None));
+    assert_eq!(parsed.with.as_deref(), Some("esmtpa"));
+    assert_eq!(parsed.id.as_deref(), Some("1sEZ9v-0001"));
+    assert_eq!(parsed.recipient.unwrap().to_string(), "bob@example.org");
+    assert_eq!(parsed.via, None);
+
+    let (_, parsed) =
+        received::<Intl>(b" by localhost (8.14/8.14) id x8; 1 Jan 2020 00:00 GMT").unwrap();
+    assert_eq!(parsed.from, None);
+    assert_eq!(parsed.by.unwrap().name, "localhost");
+
+    let (_, parsed) = received::<Intl>(b" from by with").unwrap();
+    assert_eq!((parsed.from, parsed.by, parsed.with), (None, None, None));
+}
+
+#[test]
+fn thread_ids() {
+    let (rem, ids) = references::<Intl>(b" <a@b> (first)\r\n <c@d>\r\n").unwrap();
+    assert!(rem.is_empty());
+    assert_eq!(ids.len(), 2);
+
+    assert!(in_reply_to::<Intl>(b" no identifier here").is_err());
+    let (rem, ids) = in_reply_to::<Intl>(b" <a@b> <c d@e>").unwrap();
+    assert_eq!((ids.len(), rem), (1, &b"<c d@e>"[..]));
+
+    match parse_header::<Intl>(b"in-reply-to", b" <x@y> <z@w>") {
+        ParsedHeader::MessageIds(ids) => assert_eq!(ids[1].left(), "z"),
+        other => panic!("unexpected {:?}", other),
+    }
+}
@@ -0,0 +1,80 @@
+use crate::rfc8460::*;
+
+fn parse(input: &[u8]) -> Option<TlsRptRecord> {
+    match tlsrpt_record(input) {
+        Ok(([], record)) => Some(record),
+        _ => None,
+    }
+}
+
+#[test]
+fn basic() {
+    let record = parse(b"v=TLSRPTv1;rua=mailto:reports@example.org").unwrap();
+    assert_eq!(
+        record.rua,
+        [ReportUri::Mailto("reports@example.org".into())]
+    );
+    assert!(record.extensions.is_empty());
+}
+
+#[test]
+fn rua_list() {
+    let record =
+        parse(b"v=TLSRPTv1; rua=mailto:a@example.org?subject=tls , https://example.org/r,x:y")
+            .unwrap();
+    assert_eq!(
+        record.rua,
+        [
+            ReportUri::Mailto("a@example.org".into()),
+            ReportUri::Https("https://example.org/r".into()),
+            ReportUri::Other("x:y".into()),
+        ]
+    );
+}
+
+#[test]
+fn extensions() {
+    let record = parse(b"v=TLSRPTv1; rua=mailto:a@example.org; 1ext=a.b; x_y-z=!\"#:<>~").unwrap();
+    assert_eq!(
+        record.extensions,
+        [
+            ("1ext".into(), "a.b".into()),
+            ("x_y-z".into(), "!\"#:<>~".into())
+        ]
+    );
+
+    let name = format!("v=TLSRPTv1; rua=mailto:a@example.org; {}=1", "a".repeat(32));
+    assert_eq!(parse(name.as_bytes()).unwrap().extensions.len(), 1);
+    let name = format!("v=TLSRPTv1; rua=mailto:a@example.org; {}=1", "a".repeat(33));
+    assert_eq!(parse(name.as_bytes()), None);
+}
+
+#[test]
+fn invalid_extensions() {
+    for input in [
+        b"v=TLSRPTv1; rua=mailto:a@example.org; ext=a b".as_ref(),
+        b"v=TLSRPTv1; rua=mailto:a@example.org; ext=a=b",
+        b"v=TLSRPTv1; rua=mailto:a@example.org; ext=",
+        b"v=TLSRPTv1; rua=mailto:a@example.org; -ext=1",
+    ]
+    .iter()
+    {
+        assert_eq!(parse(input), None, "{:?}", String::from_utf8_lossy(input));
+    }
+}
+
+#[test]
+fn invalid() {
+    for input in [
+        b"rua=mailto:a@example.org; v=TLSRPTv1".as_ref(),
+        b"v=TLSRPTv1",
+        b"v=TLSRPTv1; rua=mailto:a@example.org; v=TLSRPTv1",
+        b"v=TLSRPTv1; rua=mailto:a@example.org; rua=mailto:b@example.org",
+        b"v=TLSRPTv1; rua=",
+        b"v=TLSRPTv1; rua=mailto:a@example.org,",
+    ]
+    .iter()
+    {
+        assert_eq!(parse(input), None, "{:?}", String::from_utf8_lossy(input));
+    }
+}
@@ -0,0 +1,49 @@
+use crate::rfc8461::*;
+
+fn parse(input: &[u8]) -> Option<StsRecord> {
+    match mta_sts_record(input) {
+        Ok(([], record)) => Some(record),
+        _ => None,
+    }
+}
+
+#[test]
+fn basic() {
+    let record = parse(b"v=STSv1;id=20160831085700Z").unwrap();
+    assert_eq!(record.id, "20160831085700Z");
+    assert!(record.extensions.is_empty());
+
+    let id = format!("v=STSv1; id={}", "a1".repeat(16));
+    assert_eq!(parse(id.as_bytes()).unwrap().id.len(), 32);
+}
+
+#[test]
+fn extensions() {
+    let record = parse(b"v=STSv1; id=1; 2ext=x.y; ext-b=:~").unwrap();
+    assert_eq!(
+        record.extensions,
+        [("2ext".into(), "x.y".into()), ("ext-b".into(), ":~".into())]
+    );
+
+    let name = format!("v=STSv1; id=1; {}=1", "e".repeat(33));
+    assert_eq!(parse(name.as_bytes()), None);
+}
+
+#[test]
+fn invalid() {
+    let long_id = format!("v=STSv1; id={}", "a".repeat(33));
+    for input in [
+        b"v=STSv1".as_ref(),
+        b"v=STSv1; id=",
+        b"v=STSv1; id=2016-08-31",
+        long_id.as_bytes(),
+        b"v=STSv1; id=1; v=STSv1",
+        b"v=STSv1; id=1; ext=a b",
+        b"v=STSv1; id=1; ext=a=b",
+        b"id=1; v=STSv1",
+    ]
+    .iter()
+    {
+        assert_eq!(parse(input), None, "{:?}", String::from_utf8_lossy(input));
+    }
+}
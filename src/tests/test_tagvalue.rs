@@ -0,0 +1,84 @@
+use crate::tagvalue::*;
+
+type Tags = Vec<(&'static str, &'static [u8])>;
+
+fn parse(whitespace: Whitespace, duplicates: TagDuplicates, input: &'static [u8]) -> Option<Tags> {
+    let options = TagListOptions {
+        separator: b';',
+        whitespace,
+        duplicates,
+        ..TagListOptions::default()
+    };
+    match tag_list(options)(input) {
+        Ok(([], tags)) => Some(tags),
+        _ => None,
+    }
+}
+
+const WHITESPACE: [Whitespace; 3] = [Whitespace::Fws, Whitespace::Wsp, Whitespace::None];
+const DUPLICATES: [TagDuplicates; 3] = [
+    TagDuplicates::Error,
+    TagDuplicates::FirstWins,
+    TagDuplicates::LastWins,
+];
+
+#[test]
+fn duplicates_all_whitespace() {
+    for ws in WHITESPACE.iter() {
+        let input = b"a=1;b=2;a=3";
+        assert_eq!(parse(*ws, TagDuplicates::Error, input), None);
+        assert_eq!(
+            parse(*ws, TagDuplicates::FirstWins, input),
+            Some(vec![("a", b"1".as_ref()), ("b", b"2")])
+        );
+        assert_eq!(
+            parse(*ws, TagDuplicates::LastWins, input),
+            Some(vec![("a", b"3".as_ref()), ("b", b"2")])
+        );
+    }
+}
+
+#[test]
+fn whitespace_all_duplicates() {
+    for dup in DUPLICATES.iter() {
+        let spaced = b" a = x y ;\tb=2 ";
+        let folded = b"a=x\r\n y;\r\n b=2";
+        let expected = |a: &'static [u8]| Some(vec![("a", a), ("b", b"2".as_ref())]);
+
+        assert_eq!(parse(Whitespace::Fws, *dup, spaced), expected(b"x y"));
+        assert_eq!(parse(Whitespace::Fws, *dup, folded), expected(b"x\r\n y"));
+
+        assert_eq!(parse(Whitespace::Wsp, *dup, spaced), expected(b"x y"));
+        assert_eq!(parse(Whitespace::Wsp, *dup, folded), None);
+
+        assert_eq!(parse(Whitespace::None, *dup, spaced), None);
+        assert_eq!(parse(Whitespace::None, *dup, folded), None);
+        assert_eq!(parse(Whitespace::None, *dup, b"a=x;b=2;"), expected(b"x"));
+    }
+}
+
+#[test]
+fn duplicates_with_whitespace() {
+    for ws in WHITESPACE.iter() {
+        for dup in DUPLICATES.iter() {
+            assert_eq!(parse(*ws, *dup, b""), Some(vec![]));
+
+            let twice = parse(*ws, *dup, b"v=1;v=1");
+            match dup {
+                TagDuplicates::Error => assert_eq!(twice, None),
+                _ => assert_eq!(twice, Some(vec![("v", b"1".as_ref())])),
+            }
+
+            let spaced = parse(*ws, *dup, b"v=1; v=2");
+            match (ws, dup) {
+                (Whitespace::None, _) | (_, TagDuplicates::Error) => assert_eq!(spaced, None),
+                (_, TagDuplicates::FirstWins) => {
+                    assert_eq!(spaced, Some(vec![("v", b"1".as_ref())]))
+                }
+                (_, TagDuplicates::LastWins) => {
+                    assert_eq!(spaced, Some(vec![("v", b"2".as_ref())]))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::behaviour::Intl;
+use crate::rfc5321::Command;
+use crate::transcript::*;
+
+#[test]
+fn data_exchange() {
+    let transcript = b"S: 220-mx.example.org\r\n\
+                       S: 220 ESMTP\r\n\
+                       C: EHLO client.example.org\r\n\
+                       S: 250 mx.example.org\r\n\
+                       C: DATA\r\n\
+                       S: 354 Go ahead\r\n\
+                       C: Subject: test\r\n\
+                       C: \r\n\
+                       C: ..leading dot\r\n\
+                       C: .\r\n\
+                       S: 250 Queued\r\n\
+                       C: BOGUS\r\n\
+                       C: QUIT\r\n\
+                       S: 500 Unknown command\r\n";
+
+    let exchanges = parse_transcript::<Intl>(transcript);
+    let codes: Vec<_> = exchanges
+        .iter()
+        .map(|e| e.reply.as_ref().map(|r| r.code))
+        .collect();
+    assert_eq!(
+        codes,
+        [Some(220), Some(250), Some(354), Some(250), Some(500), None]
+    );
+
+    assert_eq!(exchanges[0].reply.as_ref().unwrap().lines.len(), 2);
+    match &exchanges[3].client {
+        Some(ClientInput::Message(content)) => {
+            assert_eq!(content, b"Subject: test\r\n\r\n.leading dot\r\n")
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(matches!(exchanges[4].client, Some(ClientInput::Invalid(ref l)) if l == b"BOGUS"));
+    assert!(matches!(
+        exchanges[5].client,
+        Some(ClientInput::Command(Command::QUIT))
+    ));
+}
+
+#[test]
+fn smtp_recipient_outcomes() {
+    let transcript = b"C: MAIL FROM:<a@example.org>\r\n\
+                       C: RCPT TO:<b@example.org>\r\n\
+                       C: RCPT TO:<c@example.org>\r\n\
+                       C: DATA\r\n\
+                       S: 250 OK\r\n\
+                       S: 250 OK\r\n\
+                       S: 250 OK\r\n\
+                       S: 554 No valid recipients\r\n\
+                       C: RSET\r\n\
+                       S: 250 OK\r\n\
+                       C: MAIL FROM:<a@example.org>\r\n\
+                       S: 250 OK\r\n\
+                       C: RCPT TO:<d@example.org>\r\n\
+                       S: 250 OK\r\n\
+                       C: DATA\r\n\
+                       S: 354 Go ahead\r\n\
+                       C: .\r\n\
+                       S: 250 Queued\r\n\
+                       C: RCPT TO:<e@example.org>\r\n";
+
+    let exchanges = parse_transcript::<Intl>(transcript);
+    let recipients = recipient_outcomes(&exchanges, false);
+
+    assert_eq!(recipients.len(), 4);
+    assert!(matches!(&recipients[0].outcome, Outcome::Failed(r) if r.code == 554));
+    assert!(matches!(&recipients[1].outcome, Outcome::Failed(r) if r.code == 554));
+    assert!(matches!(&recipients[2].outcome, Outcome::Delivered(r) if r.code == 250));
+    assert_eq!(recipients[3].outcome, Outcome::Unanswered);
+    assert_eq!(recipients[3].rcpt_reply, None);
+}
@@ -0,0 +1,23 @@
+use crate::xforward::*;
+
+#[test]
+fn hostname_grammar() {
+    assert!(validate_hostname("mx-1.example.org"));
+    assert!(validate_hostname("_dmarc.example.org"));
+    assert!(!validate_hostname(""));
+    assert!(!validate_hostname("-mx.example.org"));
+    assert!(!validate_hostname("mx..example.org"));
+    assert!(!validate_hostname("192.0.2.1"));
+    assert!(!validate_hostname(&"a".repeat(64)));
+}
+
+#[test]
+fn param_validity() {
+    let (_, params) = xforward_params(
+        b"ADDR=IPV6:2001:db8::1 PORT=[UNAVAILABLE] PROTO=esmtp HELO=[192.0.2.1] SOURCE=nowhere",
+    )
+    .unwrap();
+    let valid: Vec<_> = params.iter().map(Param::is_valid).collect();
+
+    assert_eq!(valid, [true, true, true, true, false]);
+}
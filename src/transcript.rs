@@ -0,0 +1,286 @@
+//! SMTP session transcripts
+//!
+//! Parses logs of SMTP sessions in the format used by the examples of
+//! [RFC 5321]: every line sent by the client is prefixed by `C:` and
+//! every line sent by the server by `S:`. Lines may end with CRLF or
+//! LF and lines with any other prefix are ignored.
+//!
+//! [RFC 5321]: https://tools.ietf.org/html/rfc5321#appendix-D
+
+use nom::combinator::all_consuming;
+
+use crate::rfc5321::reply::{reply, Reply};
+use crate::rfc5321::{known_command, Command, ForwardPath, UTF8Policy};
+
+/// Something sent by the client.
+#[derive(Debug)]
+pub enum ClientInput {
+    /// A valid command.
+    Command(Command),
+    /// The message content sent after `DATA`.
+    ///
+    /// Lines are terminated by CRLF and dot-stuffing is removed. The
+    /// terminating `"."` line is not included.
+    Message(Vec<u8>),
+    /// A line that could not be parsed as a command.
+    Invalid(Vec<u8>),
+}
+
+/// Client input paired with the reply the server sent for it.
+#[derive(Debug)]
+pub struct Exchange {
+    /// The client input.
+    ///
+    /// `None` for replies that do not answer any client input, such
+    /// as the greeting.
+    pub client: Option<ClientInput>,
+    /// The server reply.
+    ///
+    /// `None` if the transcript ends before the reply.
+    pub reply: Option<Reply>,
+}
+
+fn is_data(exchange: &Exchange) -> bool {
+    matches!(exchange.client, Some(ClientInput::Command(Command::DATA)))
+}
+
+/// Parse a transcript into a sequence of exchanges.
+///
+/// Replies are matched to client input in order, which supports
+/// pipelined sessions. Client lines following a `354` reply to `DATA`
+/// are collected as the message content.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::Command;
+/// use rustyknife::transcript::{parse_transcript, ClientInput};
+///
+/// let transcript = b"S: 220 mx.example.org ESMTP\n\
+///                    C: MAIL FROM:<bob@example.org>\n\
+///                    C: RCPT TO:<alice@example.org>\n\
+///                    S: 250 OK\n\
+///                    S: 550 No such user\n";
+///
+/// let exchanges = parse_transcript::<Intl>(transcript);
+///
+/// assert!(exchanges[0].client.is_none());
+/// assert_eq!(exchanges[0].reply.as_ref().unwrap().code, 220);
+/// assert!(matches!(exchanges[2].client, Some(ClientInput::Command(Command::RCPT(..)))));
+/// assert_eq!(exchanges[2].reply.as_ref().unwrap().code, 550);
+/// ```
+pub fn parse_transcript<P: UTF8Policy>(input: &[u8]) -> Vec<Exchange> {
+    let mut out: Vec<Exchange> = Vec::new();
+    // Index of the first exchange that may still be waiting for a reply.
+    let mut pending = 0;
+    let mut server = Vec::new();
+    let mut message: Option<Vec<u8>> = None;
+
+    for line in input.split(|c| *c == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+        if let Some(text) = line.strip_prefix(b"C:") {
+            let text = text.strip_prefix(b" ").unwrap_or(text);
+
+            if let Some(content) = message.as_mut() {
+                if text == b"." {
+                    out.push(Exchange {
+                        client: message.take().map(ClientInput::Message),
+                        reply: None,
+                    });
+                } else {
+                    content.extend_from_slice(text.strip_prefix(b".").unwrap_or(text));
+                    content.extend_from_slice(b"\r\n");
+                }
+                continue;
+            }
+
+            let mut line = text.to_vec();
+            line.extend_from_slice(b"\r\n");
+            let client = match all_consuming(known_command::<P>)(&line) {
+                Ok((_, command)) => ClientInput::Command(command),
+                Err(_) => ClientInput::Invalid(text.to_vec()),
+            };
+            out.push(Exchange {
+                client: Some(client),
+                reply: None,
+            });
+        } else if let Some(text) = line.strip_prefix(b"S:") {
+            let text = text.strip_prefix(b" ").unwrap_or(text);
+
+            server.extend_from_slice(text);
+            server.extend_from_slice(b"\r\n");
+            if text.get(3) == Some(&b'-') {
+                continue;
+            }
+
+            // Unparsable replies are dropped.
+            if let Ok((_, parsed)) = all_consuming(reply)(&server) {
+                match out[pending..].iter().position(|e| e.reply.is_none()) {
+                    Some(idx) => {
+                        let exchange = &mut out[pending + idx];
+                        if is_data(exchange) && parsed.code == 354 {
+                            message = Some(Vec::new());
+                        }
+                        exchange.reply = Some(parsed);
+                        pending += idx + 1;
+                    }
+                    None => {
+                        out.push(Exchange {
+                            client: None,
+                            reply: Some(parsed),
+                        });
+                        pending = out.len();
+                    }
+                }
+            }
+            server.clear();
+        }
+    }
+
+    if let Some(content) = message {
+        out.push(Exchange {
+            client: Some(ClientInput::Message(content)),
+            reply: None,
+        });
+    }
+
+    out
+}
+
+/// The outcome of a single recipient.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The RCPT command was not answered.
+    Unanswered,
+    /// The RCPT command was refused with this reply.
+    Refused(Reply),
+    /// The RCPT command was accepted but no message was transmitted
+    /// or the final reply is missing.
+    Accepted,
+    /// The message was accepted for this recipient with this reply.
+    Delivered(Reply),
+    /// The message was refused for this recipient with this reply.
+    ///
+    /// This is also used when `DATA` itself was refused.
+    Failed(Reply),
+}
+
+/// A recipient and its outcome.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recipient {
+    /// The path of the RCPT command.
+    pub path: ForwardPath,
+    /// The reply to the RCPT command.
+    pub rcpt_reply: Option<Reply>,
+    /// What happened to the recipient.
+    pub outcome: Outcome,
+}
+
+fn is_positive(reply: &Reply) -> bool {
+    (200..300).contains(&reply.code)
+}
+
+fn final_outcome(reply: Option<&Reply>) -> Outcome {
+    match reply {
+        Some(reply) if is_positive(reply) => Outcome::Delivered(reply.clone()),
+        Some(reply) => Outcome::Failed(reply.clone()),
+        None => Outcome::Accepted,
+    }
+}
+
+/// Pair the RCPT commands of `exchanges` with their outcome.
+///
+/// Recipients are returned in command order for all the
+/// transactions. With SMTP the reply to the message content applies
+/// to every accepted recipient. With [LMTP] (`lmtp` set) one reply
+/// per accepted recipient follows the message content, in RCPT order.
+///
+/// [LMTP]: https://tools.ietf.org/html/rfc2033#section-4.2
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::transcript::{parse_transcript, recipient_outcomes, Outcome};
+///
+/// let transcript = b"C: MAIL FROM:<bob@example.org>\n\
+///                    S: 250 OK\n\
+///                    C: RCPT TO:<alice@example.org>\n\
+///                    S: 250 OK\n\
+///                    C: RCPT TO:<carol@example.org>\n\
+///                    S: 550 No such user\n\
+///                    C: RCPT TO:<dave@example.org>\n\
+///                    S: 250 OK\n\
+///                    C: DATA\n\
+///                    S: 354 Go ahead\n\
+///                    C: Subject: hi\n\
+///                    C: .\n\
+///                    S: 250 Delivered\n\
+///                    S: 452 Mailbox full\n";
+///
+/// let exchanges = parse_transcript::<Intl>(transcript);
+/// let recipients = recipient_outcomes(&exchanges, true);
+///
+/// let codes: Vec<_> = recipients.iter().map(|r| match &r.outcome {
+///     Outcome::Refused(reply) | Outcome::Delivered(reply) | Outcome::Failed(reply) => reply.code,
+///     _ => 0,
+/// }).collect();
+/// assert_eq!(codes, [250, 550, 452]);
+/// assert_eq!(recipients[2].path.to_string(), "<dave@example.org>");
+/// ```
+pub fn recipient_outcomes(exchanges: &[Exchange], lmtp: bool) -> Vec<Recipient> {
+    let mut out: Vec<Recipient> = Vec::new();
+    // Indices in `out` of the accepted recipients of the current
+    // transaction.
+    let mut accepted = Vec::new();
+    let mut iter = exchanges.iter().peekable();
+
+    while let Some(exchange) = iter.next() {
+        match &exchange.client {
+            Some(ClientInput::Command(Command::MAIL(..)))
+            | Some(ClientInput::Command(Command::RSET)) => accepted.clear(),
+            Some(ClientInput::Command(Command::RCPT(path, _))) => {
+                let outcome = match &exchange.reply {
+                    Some(reply) if is_positive(reply) => {
+                        accepted.push(out.len());
+                        Outcome::Accepted
+                    }
+                    Some(reply) => Outcome::Refused(reply.clone()),
+                    None => Outcome::Unanswered,
+                };
+                out.push(Recipient {
+                    path: path.clone(),
+                    rcpt_reply: exchange.reply.clone(),
+                    outcome,
+                });
+            }
+            Some(ClientInput::Command(Command::DATA)) => match &exchange.reply {
+                Some(reply) if reply.code != 354 => {
+                    for idx in accepted.drain(..) {
+                        out[idx].outcome = Outcome::Failed(reply.clone());
+                    }
+                }
+                _ => (),
+            },
+            Some(ClientInput::Message(_)) => {
+                let mut reply = exchange.reply.as_ref();
+                for (i, idx) in accepted.drain(..).enumerate() {
+                    if lmtp && i > 0 {
+                        reply = match iter.peek() {
+                            Some(Exchange {
+                                client: None,
+                                reply,
+                            }) => {
+                                iter.next();
+                                reply.as_ref()
+                            }
+                            _ => None,
+                        };
+                    }
+                    out[idx].outcome = final_outcome(reply);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    out
+}
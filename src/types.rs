@@ -22,12 +22,149 @@ use crate::rfc5322 as imf;
 use crate::util::*;
 
 /// A domain name such as used by DNS.
+///
+/// Equality, ordering and hashing are those of the string value, so
+/// they are case sensitive. Lowercase domains before using them as
+/// keys for case insensitive lookups.
+/// # Examples
+/// ```
+/// use std::collections::BTreeMap;
+/// use rustyknife::types::Domain;
+///
+/// let mut routes = BTreeMap::new();
+/// routes.insert(Domain::from_smtp(b"example.org").unwrap(), "mx1");
+/// routes.insert(Domain::from_smtp(b"example.com").unwrap(), "mx2");
+///
+/// assert_eq!(routes.get("example.org"), Some(&"mx1"));
+/// assert_eq!(routes.keys().next().map(AsRef::<str>::as_ref), Some("example.com"));
+/// ```
 #[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub struct Domain(pub(crate) String);
+pub struct Domain(pub(crate) CompactString);
 string_newtype!(Domain);
 impl Domain {
     nom_from_smtp!(smtp::domain::<Intl>);
     nom_from_imf!(imf::_domain::<Intl>);
+
+    /// Iterate over the labels of this domain from left to right.
+    ///
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::Domain;
+    ///
+    /// let domain = Domain::from_smtp(b"mx1.example.org").unwrap();
+    ///
+    /// assert_eq!(domain.labels().collect::<Vec<_>>(), ["mx1", "example", "org"]);
+    /// ```
+    pub fn labels(&self) -> std::str::Split<'_, char> {
+        self.0.split('.')
+    }
+
+    /// Returns `true` if this domain is equal to or below `other`.
+    ///
+    /// Labels are compared case insensitively.
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::Domain;
+    ///
+    /// let parent = Domain::from_smtp(b"Example.org").unwrap();
+    ///
+    /// assert!(Domain::from_smtp(b"mx.example.ORG").unwrap().is_subdomain_of(&parent));
+    /// assert!(parent.is_subdomain_of(&parent));
+    /// assert!(!Domain::from_smtp(b"badexample.org").unwrap().is_subdomain_of(&parent));
+    /// ```
+    pub fn is_subdomain_of(&self, other: &Domain) -> bool {
+        let mut labels = self.0.rsplit('.');
+
+        other.0.rsplit('.').all(|o| {
+            labels
+                .next()
+                .map(|l| l.to_lowercase() == o.to_lowercase())
+                .unwrap_or(false)
+        })
+    }
+
+    /// Returns `true` if the domain fits the DNS length limits.
+    ///
+    /// Each label must be at most 63 octets and the whole name at most
+    /// 253 octets. Internationalized labels are measured after
+    /// conversion to their ASCII form.
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::Domain;
+    ///
+    /// let long_label = format!("{}.example.org", "a".repeat(64));
+    ///
+    /// assert!(Domain::from_smtp(b"example.org").unwrap().has_valid_length());
+    /// assert!(!Domain::from_imf(long_label.as_bytes()).unwrap().has_valid_length());
+    /// ```
+    pub fn has_valid_length(&self) -> bool {
+        let ascii = match idna::domain_to_ascii(&self.0) {
+            Ok(ascii) => ascii,
+            Err(_) => return false,
+        };
+
+        ascii.len() <= 253 && ascii.split('.').all(|l| !l.is_empty() && l.len() <= 63)
+    }
+
+    /// Convert the domain to its ASCII form with [IDNA] labels.
+    ///
+    /// [IDNA]: https://tools.ietf.org/html/rfc5891
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::Domain;
+    ///
+    /// let domain = Domain::from_smtp("bücher.example".as_bytes()).unwrap();
+    ///
+    /// assert_eq!(domain.to_ascii().unwrap().to_string(), "xn--bcher-kva.example");
+    /// ```
+    #[allow(clippy::result_unit_err)]
+    pub fn to_ascii(&self) -> Result<Domain, ()> {
+        idna::domain_to_ascii(&self.0)
+            .map(|ascii| Domain(ascii.as_str().into()))
+            .map_err(|_| ())
+    }
+
+    /// Convert the IDNA labels of the domain to Unicode.
+    ///
+    /// Returns an error if a label is not valid IDNA.
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::Domain;
+    ///
+    /// let domain = Domain::from_smtp(b"xn--bcher-kva.example").unwrap();
+    ///
+    /// assert_eq!(domain.to_unicode().unwrap().to_string(), "bücher.example");
+    /// ```
+    #[allow(clippy::result_unit_err)]
+    pub fn to_unicode(&self) -> Result<Domain, ()> {
+        match idna::domain_to_unicode(&self.0) {
+            (unicode, Ok(())) => Ok(Domain(unicode.as_str().into())),
+            (_, Err(_)) => Err(()),
+        }
+    }
+
+    /// Return the registrable part of this domain.
+    ///
+    /// This is the public suffix of the domain with one more label,
+    /// looked up in `list`. Returns `None` if the domain is itself a
+    /// public suffix or no suffix matched.
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::Domain;
+    ///
+    /// let list: publicsuffix::List = "// ===BEGIN ICANN DOMAINS===\nuk\nco.uk\n".parse().unwrap();
+    /// let domain = Domain::from_smtp(b"mx.Example.co.uk").unwrap();
+    ///
+    /// assert_eq!(domain.registrable_domain(&list), Some("Example.co.uk"));
+    /// assert_eq!(Domain::from_smtp(b"co.uk").unwrap().registrable_domain(&list), None);
+    /// ```
+    #[cfg(feature = "public-suffix")]
+    pub fn registrable_domain<L: publicsuffix::Psl>(&self, list: &L) -> Option<&str> {
+        let lower = self.0.to_ascii_lowercase();
+        let found = list.domain(lower.as_bytes())?;
+
+        Some(&self.0[self.0.len() - found.as_bytes().len()..])
+    }
 }
 
 /// The local part of an address preceding the `"@"` in an email address.
@@ -73,6 +210,9 @@ impl From<DotAtom> for LocalPart {
 ///
 /// This is used in places such as SMTP local parts and IMF display
 /// names.
+///
+/// Equality, ordering and hashing are those of the unquoted string
+/// value.
 #[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct QuotedString(pub(crate) String);
 string_newtype!(QuotedString);
@@ -113,9 +253,11 @@ impl QuotedString {
 ///
 /// See [RFC 5322] for the full syntax.
 ///
+/// Equality, ordering and hashing are those of the string value.
+///
 /// [RFC 5322]: https://tools.ietf.org/html/rfc5322#section-3.2.3
 #[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub struct DotAtom(pub(crate) String);
+pub struct DotAtom(pub(crate) CompactString);
 string_newtype!(DotAtom);
 
 impl DotAtom {
@@ -210,6 +352,7 @@ impl AddressLiteral {
     /// assert_eq!(valid.upgrade(), Ok(AddressLiteral::IP("192.0.2.1".parse().unwrap())));
     /// assert_eq!(invalid.upgrade(), Err(()));
     /// ```
+    #[allow(clippy::result_unit_err)]
     pub fn upgrade(&self) -> Result<Self, ()> {
         if let AddressLiteral::FreeForm(s) = self {
             let (rem, parsed) = smtp::_inner_address_literal(s.as_bytes()).map_err(|_| ())?;
@@ -242,6 +385,16 @@ impl Display for AddressLiteral {
 }
 
 /// A valid email address.
+///
+/// Mailboxes are ordered by local part, then by domain part. Dot-atom
+/// local parts sort before quoted ones and domain names before
+/// address literals; within a kind the string values are compared
+/// case sensitively. Call [`Mailbox::normalize`] before using
+/// mailboxes as keys to group equivalent addresses.
+///
+/// Since the address is not stored as a single string, a mailbox
+/// cannot be borrowed as `&str`. Use [`ToString::to_string`] for
+/// string keys.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "serde", serde(try_from = "&str", into = "String"))]
@@ -275,8 +428,94 @@ impl Mailbox {
         self.0.smtp_try_unquote()
     }
 
+    /// Return a copy of this address with another domain part.
+    ///
+    /// The new domain part is validated using SMTP syntax.
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::Mailbox;
+    ///
+    /// let mbox = Mailbox::from_smtp(b"bob@old.example.org").unwrap();
+    ///
+    /// assert_eq!(mbox.with_domain(b"new.example.org").unwrap().to_string(), "bob@new.example.org");
+    /// assert!(mbox.with_domain(b"bad..example.org").is_err());
+    /// ```
+    pub fn with_domain<'a>(&self, domain: &'a [u8]) -> Result<Self, nom::Err<NomError<'a>>> {
+        Ok(Mailbox(self.0.clone(), DomainPart::from_smtp(domain)?))
+    }
+
+    /// Return a copy of this address with another local part.
+    ///
+    /// The new local part is validated using SMTP syntax and must be
+    /// quoted if it contains spaces or special characters.
+    pub fn with_local_part<'a>(
+        &self,
+        local_part: &'a [u8],
+    ) -> Result<Self, nom::Err<NomError<'a>>> {
+        Ok(Mailbox(LocalPart::from_smtp(local_part)?, self.1.clone()))
+    }
+
+    /// Normalize this address for comparison purposes.
+    ///
+    /// The local part is unquoted if quoted needlessly and the domain
+    /// name is lowercased. The case of the local part is preserved
+    /// since it may be significant to the receiving system.
+    pub fn normalize(&mut self) {
+        self.smtp_try_unquote();
+        if let DomainPart::Domain(domain) = &mut self.1 {
+            domain.0 = domain.0.to_lowercase().as_str().into();
+        }
+    }
+
+    /// Compare two addresses after normalization.
+    ///
+    /// See [`Mailbox::normalize`] for the rules applied.
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::Mailbox;
+    ///
+    /// let a = Mailbox::from_smtp(b"\"bob\"@Example.ORG").unwrap();
+    /// let b = Mailbox::from_smtp(b"bob@example.org").unwrap();
+    /// let c = Mailbox::from_smtp(b"Bob@example.org").unwrap();
+    ///
+    /// assert!(a.semantic_eq(&b));
+    /// assert!(!b.semantic_eq(&c));
+    /// ```
+    pub fn semantic_eq(&self, other: &Mailbox) -> bool {
+        self.normalized() == other.normalized()
+    }
+
+    pub(crate) fn normalized(&self) -> Mailbox {
+        let mut out = self.clone();
+        out.normalize();
+        out
+    }
+
     nom_from_smtp!(smtp::mailbox::<Intl>);
     nom_from_imf!(imf::addr_spec::<Intl>);
+
+    /// Parse a mailbox with an optional display name.
+    ///
+    /// Accepts the `Display Name <user@example.org>` and bare
+    /// `user@example.org` forms using Internet Message Format syntax,
+    /// including encoded words in the display name.
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::Mailbox;
+    ///
+    /// let (dname, mailbox) = Mailbox::from_display(b"\"Doe, John\" <john@example.org>").unwrap();
+    ///
+    /// assert_eq!(dname.as_deref(), Some("Doe, John"));
+    /// assert_eq!(mailbox.to_string(), "john@example.org");
+    ///
+    /// let (dname, _) = Mailbox::from_display(b"john@example.org").unwrap();
+    /// assert_eq!(dname, None);
+    /// ```
+    pub fn from_display(value: &[u8]) -> Result<(Option<String>, Self), nom::Err<NomError>> {
+        let (_, mailbox) = nom::combinator::all_consuming(imf::mailbox::<Intl>)(value)?;
+
+        Ok((mailbox.dname, mailbox.address))
+    }
 }
 
 // FIXME: is type unification a good thing ?
@@ -288,6 +527,147 @@ impl Display for Mailbox {
     }
 }
 
+/// Parse the address of an [`email_address::EmailAddress`] with the
+/// SMTP grammar of this crate, which is stricter. The display name is
+/// dropped.
+/// # Examples
+/// ```
+/// use std::convert::TryFrom;
+/// use email_address::EmailAddress;
+/// use rustyknife::types::Mailbox;
+///
+/// let email = EmailAddress::new_unchecked("Bob <bob@example.org>");
+/// assert_eq!(Mailbox::try_from(&email).unwrap().to_string(), "bob@example.org");
+///
+/// let email = EmailAddress::new_unchecked("a..b@example.org");
+/// assert!(Mailbox::try_from(&email).is_err());
+/// ```
+#[cfg(feature = "email_address")]
+impl std::convert::TryFrom<&email_address::EmailAddress> for Mailbox {
+    type Error = nom::Err<()>;
+
+    fn try_from(value: &email_address::EmailAddress) -> Result<Self, Self::Error> {
+        Mailbox::from_smtp(value.email().as_bytes())
+    }
+}
+
+#[cfg(feature = "email_address")]
+impl std::convert::TryFrom<email_address::EmailAddress> for Mailbox {
+    type Error = nom::Err<()>;
+
+    fn try_from(value: email_address::EmailAddress) -> Result<Self, Self::Error> {
+        Mailbox::try_from(&value)
+    }
+}
+
+/// Convert to an [`email_address::EmailAddress`].
+///
+/// Fails for the addresses that crate rejects, such as IPv6 address
+/// literals with a zone or overly long domains.
+/// # Examples
+/// ```
+/// use std::convert::TryFrom;
+/// use email_address::EmailAddress;
+/// use rustyknife::types::Mailbox;
+///
+/// let mbox = Mailbox::from_smtp(b"\"john doe\"@example.org").unwrap();
+/// let email = EmailAddress::try_from(&mbox).unwrap();
+///
+/// assert_eq!(email.local_part(), "\"john doe\"");
+/// assert_eq!(email.domain(), "example.org");
+/// ```
+#[cfg(feature = "email_address")]
+impl std::convert::TryFrom<&Mailbox> for email_address::EmailAddress {
+    type Error = email_address::Error;
+
+    fn try_from(value: &Mailbox) -> Result<Self, Self::Error> {
+        value.to_string().parse()
+    }
+}
+
+#[cfg(feature = "email_address")]
+impl std::convert::TryFrom<Mailbox> for email_address::EmailAddress {
+    type Error = email_address::Error;
+
+    fn try_from(value: Mailbox) -> Result<Self, Self::Error> {
+        email_address::EmailAddress::try_from(&value)
+    }
+}
+
+impl Mailbox {
+    /// Format the mailbox according to `format`.
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::{Mailbox, MailboxFormat};
+    ///
+    /// let mbox = Mailbox::from_smtp(b"user@example.org").unwrap();
+    ///
+    /// assert_eq!(mbox.display(MailboxFormat::default()).to_string(), "user@example.org");
+    /// assert_eq!(mbox.display(MailboxFormat::bracketed()).to_string(), "<user@example.org>");
+    /// assert_eq!(
+    ///     mbox.display(MailboxFormat::named("Doe, John")).to_string(),
+    ///     "\"Doe, John\" <user@example.org>"
+    /// );
+    /// ```
+    pub fn display<'a>(&'a self, format: MailboxFormat<'a>) -> MailboxDisplay<'a> {
+        MailboxDisplay {
+            mailbox: self,
+            format,
+        }
+    }
+}
+
+/// Formatting options for [`Mailbox::display`].
+///
+/// The default is the minimal SMTP form, `user@example.org`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MailboxFormat<'a> {
+    /// Enclose the address in angle brackets.
+    pub brackets: bool,
+    /// Display name written before the bracketed address as an IMF
+    /// `name-addr`. It is quoted if needed, or written as encoded words
+    /// if it contains control characters such as CR and LF.
+    pub display_name: Option<&'a str>,
+}
+
+impl<'a> MailboxFormat<'a> {
+    /// The bracketed form, `<user@example.org>`.
+    pub fn bracketed() -> Self {
+        MailboxFormat {
+            brackets: true,
+            display_name: None,
+        }
+    }
+
+    /// The IMF `name-addr` form, `Name <user@example.org>`.
+    pub fn named(display_name: &'a str) -> Self {
+        MailboxFormat {
+            brackets: true,
+            display_name: Some(display_name),
+        }
+    }
+}
+
+/// A [`Mailbox`] formatted with [`Mailbox::display`].
+#[derive(Clone, Copy, Debug)]
+pub struct MailboxDisplay<'a> {
+    mailbox: &'a Mailbox,
+    format: MailboxFormat<'a>,
+}
+
+impl Display for MailboxDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(dname) = self.format.display_name {
+            imf::fmt_dname(f, dname)?;
+            write!(f, " <{}>", self.mailbox)
+        } else if self.format.brackets {
+            write!(f, "<{}>", self.mailbox)
+        } else {
+            write!(f, "{}", self.mailbox)
+        }
+    }
+}
+
 impl From<Mailbox> for String {
     fn from(mailbox: Mailbox) -> String {
         mailbox.to_string()
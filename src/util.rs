@@ -6,6 +6,7 @@ use encoding::{DecoderTrap, Encoding};
 
 use nom::bytes::complete::take;
 use nom::combinator::{map, recognize, verify};
+use nom::error::{ErrorKind, ParseError};
 use nom::multi::{fold_many0, fold_many1};
 use nom::{IResult, InputLength};
 // Change this to something else that implements ParseError to get a
@@ -15,6 +16,142 @@ pub(crate) type NomError<'a> = ();
 /// Shortcut type for taking in bytes and spitting out a success or NomError.
 pub type NomResult<'a, O, E = NomError<'a>> = IResult<&'a [u8], O, E>;
 
+/// Convert the error type of a parser from this crate.
+///
+/// The parsers in this crate use a zero sized error type to keep
+/// failures cheap. This adapter converts that error into any type
+/// implementing [`ParseError`] such as nom's `VerboseError` so the
+/// parsers can be composed with code using a richer error type.
+///
+/// Since the original error carries no information, the converted
+/// error points to the start of the input given to `parser` with an
+/// [`ErrorKind::Fail`] kind.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::nom::error::{ErrorKind, VerboseError, VerboseErrorKind};
+/// use rustyknife::rfc5321::mailbox;
+/// use rustyknife::with_error;
+///
+/// let res = with_error::<_, VerboseError<&[u8]>, _>(mailbox::<Intl>)(b"no at sign");
+///
+/// match res {
+///     Err(rustyknife::nom::Err::Error(e)) => {
+///         assert_eq!(e.errors, [(b"no at sign".as_ref(), VerboseErrorKind::Nom(ErrorKind::Fail))])
+///     }
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn with_error<'a, O, E, F>(mut parser: F) -> impl FnMut(&'a [u8]) -> NomResult<'a, O, E>
+where
+    F: FnMut(&'a [u8]) -> NomResult<'a, O>,
+    E: ParseError<&'a [u8]>,
+{
+    move |input| {
+        parser(input).map_err(|err| err.map(|_| E::from_error_kind(input, ErrorKind::Fail)))
+    }
+}
+
+/// What [`parse_exact`] accepts after the parsed value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Trailing {
+    /// Nothing may follow.
+    Reject,
+    /// Spaces, tabs, CR and LF may follow.
+    Whitespace,
+}
+
+/// Reason for a failure of [`parse_exact`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ExactError {
+    /// The parser failed.
+    Invalid,
+    /// The parser succeeded but left input unconsumed, starting at
+    /// this offset.
+    Trailing(usize),
+}
+
+impl std::fmt::Display for ExactError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ExactError::Invalid => write!(f, "invalid syntax"),
+            ExactError::Trailing(offset) => write!(f, "trailing data at offset {}", offset),
+        }
+    }
+}
+
+impl std::error::Error for ExactError {}
+
+/// Run `parser` on the whole of `input`.
+///
+/// Succeeds only if the parser consumes all of the input, except for
+/// the trailing white space allowed by `trailing`.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::mailbox;
+/// use rustyknife::{parse_exact, ExactError, Trailing};
+///
+/// let mbox = parse_exact(mailbox::<Intl>, b"bob@example.org\r\n", Trailing::Whitespace).unwrap();
+/// assert_eq!(mbox.to_string(), "bob@example.org");
+///
+/// assert_eq!(parse_exact(mailbox::<Intl>, b"bob@example.org\r\n", Trailing::Reject).unwrap_err(), ExactError::Trailing(15));
+/// assert_eq!(parse_exact(mailbox::<Intl>, b"bob@example.org>", Trailing::Whitespace).unwrap_err(), ExactError::Trailing(15));
+/// assert_eq!(parse_exact(mailbox::<Intl>, b"bob", Trailing::Whitespace).unwrap_err(), ExactError::Invalid);
+/// ```
+pub fn parse_exact<'a, O, F>(
+    mut parser: F,
+    input: &'a [u8],
+    trailing: Trailing,
+) -> Result<O, ExactError>
+where
+    F: FnMut(&'a [u8]) -> NomResult<'a, O>,
+{
+    let (rem, out) = parser(input).map_err(|_| ExactError::Invalid)?;
+    let ignored = match trailing {
+        Trailing::Reject => false,
+        Trailing::Whitespace => rem.iter().all(|c| b" \t\r\n".contains(c)),
+    };
+
+    if rem.is_empty() || ignored {
+        Ok(out)
+    } else {
+        Err(ExactError::Trailing(input.len() - rem.len()))
+    }
+}
+
+/// A parser as exported by this crate.
+///
+/// Implemented by every function or closure taking bytes and returning
+/// a [`NomResult`] whose output does not borrow from the input. Naming
+/// parsers through this trait keeps your code independent of the
+/// module a parser lives in. It is not named `Parser` so that glob
+/// importing the [prelude](crate::prelude) does not shadow
+/// [`nom::Parser`].
+/// # Examples
+/// ```
+/// use rustyknife::prelude::*;
+///
+/// fn parse_all<O>(parser: impl RkParser<O>, inputs: &[&[u8]]) -> Vec<Option<O>> {
+///     inputs.iter().map(|i| parser(i).ok().map(|(_, o)| o)).collect()
+/// }
+///
+/// let parsed = parse_all(unstructured::<Intl>, &[b"Hello", b" =?utf-8?Q?caf=C3=A9?="]);
+/// assert_eq!(parsed, [Some("Hello".to_string()), Some(" café".to_string())]);
+/// ```
+pub trait RkParser<O>: for<'a> Fn(&'a [u8]) -> NomResult<'a, O> {}
+
+impl<O, F> RkParser<O> for F where F: for<'a> Fn(&'a [u8]) -> NomResult<'a, O> {}
+
+/// String storage for the short strings of the hot-path types.
+///
+/// Strings of up to 23 bytes are stored inline with the `compact`
+/// feature.
+#[cfg(feature = "compact")]
+pub(crate) type CompactString = smartstring::alias::String;
+#[cfg(not(feature = "compact"))]
+pub(crate) type CompactString = String;
+
 pub fn ascii_to_string<'a, T: Into<Cow<'a, [u8]>>>(i: T) -> Cow<'a, str> {
     let i = i.into();
 
@@ -85,6 +222,17 @@ macro_rules! string_newtype {
                 self.0.as_bytes()
             }
         }
+        impl std::convert::AsRef<str> for $type {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+        // Equality, ordering and hashing of the inner string match `str`.
+        impl std::borrow::Borrow<str> for $type {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
         impl std::ops::Deref for $type {
             type Target = str;
             fn deref(&self) -> &Self::Target {
@@ -93,7 +241,7 @@ macro_rules! string_newtype {
         }
         impl From<$type> for String {
             fn from(value: $type) -> String {
-                value.0
+                value.0.into()
             }
         }
 
@@ -105,6 +253,37 @@ macro_rules! string_newtype {
     };
 }
 
+/// Emit a tracing span around `parser` named after the grammar rule.
+///
+/// The span records the length of the remaining input so the position
+/// of the failure within a larger buffer can be computed.
+#[cfg(feature = "trace")]
+pub(crate) fn traced<'a, O, E, F>(
+    rule: &'static str,
+    mut parser: F,
+) -> impl FnMut(&'a [u8]) -> NomResult<'a, O, E>
+where
+    F: FnMut(&'a [u8]) -> NomResult<'a, O, E>,
+{
+    move |input| {
+        let span = tracing::trace_span!("parse", rule, remaining = input.len());
+        let _enter = span.enter();
+
+        let res = parser(input);
+        match &res {
+            Ok((rem, _)) => tracing::trace!(consumed = input.len() - rem.len(), "matched"),
+            Err(_) => tracing::trace!("failed"),
+        }
+        res
+    }
+}
+
+#[cfg(not(feature = "trace"))]
+#[inline(always)]
+pub(crate) fn traced<F>(_rule: &'static str, parser: F) -> F {
+    parser
+}
+
 pub(crate) fn fold_prefix0<I, O, E, F, G>(
     mut prefix: F,
     mut cont: G,
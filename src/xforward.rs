@@ -2,6 +2,8 @@
 //!
 //! [XFORWARD]: http://www.postfix.org/XFORWARD_README.html
 
+use std::net::{Ipv4Addr, Ipv6Addr};
+
 use nom::branch::alt;
 use nom::bytes::complete::{tag, tag_no_case};
 use nom::combinator::{map, opt};
@@ -13,10 +15,135 @@ use crate::rfc5234::{crlf, wsp};
 use crate::util::*;
 
 /// XFORWARD parameter name and value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Param(pub &'static str, pub Value);
+
+/// XFORWARD attribute value.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Value {
+    /// An xtext decoded value.
+    Available(String),
+    /// `"[UNAVAILABLE]"`, the information is not available.
+    Unavailable,
+    /// `"[TEMPUNAVAIL]"`, the information is temporarily unavailable
+    /// such as when a reverse DNS lookup failed.
+    TempUnavailable,
+}
+
+impl Value {
+    /// Return the decoded value if it is available.
+    pub fn as_option(&self) -> Option<&str> {
+        match self {
+            Value::Available(value) => Some(value),
+            Value::Unavailable | Value::TempUnavailable => None,
+        }
+    }
+}
+
+impl From<Value> for Option<String> {
+    fn from(value: Value) -> Option<String> {
+        match value {
+            Value::Available(value) => Some(value),
+            Value::Unavailable | Value::TempUnavailable => None,
+        }
+    }
+}
+
+impl Param {
+    /// Returns `true` if the value is valid for the attribute.
+    ///
+    /// Unavailable values are always valid. See [`validate_value`].
+    pub fn is_valid(&self) -> bool {
+        match &self.1 {
+            Value::Available(value) => validate_value(self.0, value),
+            Value::Unavailable | Value::TempUnavailable => true,
+        }
+    }
+}
+
+/// Validate a `NAME` hostname.
 ///
-/// `"[UNAVAILABLE]"` is represented with a value of `None`.
-#[derive(Clone, Debug)]
-pub struct Param(pub &'static str, pub Option<String>);
+/// Labels are 1 to 63 letters, digits, hyphens or underscores and do
+/// not start or end with a hyphen. The whole name is at most 255
+/// characters and the last label is not numeric.
+pub fn validate_hostname(value: &str) -> bool {
+    let labels: Vec<_> = value.split('.').collect();
+
+    value.len() <= 255
+        && labels.iter().all(|label| {
+            (1..=63).contains(&label.len())
+                && label
+                    .bytes()
+                    .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_')
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+        })
+        && !matches!(labels.last(), Some(l) if l.bytes().all(|c| c.is_ascii_digit()))
+}
+
+/// Validate an `ADDR` value.
+///
+/// An IPv4 address or an IPv6 address prefixed with `"IPv6:"`.
+/// # Examples
+/// ```
+/// use rustyknife::xforward::validate_address;
+///
+/// assert!(validate_address("192.0.2.1"));
+/// assert!(validate_address("IPv6:2001:db8::1"));
+/// assert!(!validate_address("2001:db8::1"));
+/// ```
+pub fn validate_address(value: &str) -> bool {
+    match value.get(..5) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("ipv6:") => {
+            value[5..].parse::<Ipv6Addr>().is_ok()
+        }
+        _ => value.parse::<Ipv4Addr>().is_ok(),
+    }
+}
+
+/// Validate a `PORT` value, a decimal TCP port number.
+pub fn validate_port(value: &str) -> bool {
+    value.bytes().all(|c| c.is_ascii_digit()) && value.parse::<u16>().is_ok()
+}
+
+/// Validate a `PROTO` value, `"SMTP"` or `"ESMTP"` in any case.
+pub fn validate_protocol(value: &str) -> bool {
+    value.eq_ignore_ascii_case("smtp") || value.eq_ignore_ascii_case("esmtp")
+}
+
+/// Validate a `HELO` value.
+///
+/// The client may send anything in HELO so any non-empty printable
+/// ASCII text of at most 255 characters is accepted.
+pub fn validate_helo(value: &str) -> bool {
+    (1..=255).contains(&value.len()) && value.bytes().all(|c| (32..=126).contains(&c))
+}
+
+/// Validate the decoded value of the attribute `name`.
+///
+/// `name` must be lowercase. `IDENT` accepts any value and `SOURCE`
+/// accepts `"LOCAL"` or `"REMOTE"`. Unknown attributes are invalid.
+/// # Examples
+/// ```
+/// use rustyknife::xforward::validate_value;
+///
+/// assert!(validate_value("name", "mx.example.org"));
+/// assert!(validate_value("port", "25"));
+/// assert!(!validate_value("port", "65536"));
+/// assert!(!validate_value("proto", "LMTP"));
+/// ```
+pub fn validate_value(name: &str, value: &str) -> bool {
+    match name {
+        "addr" => validate_address(value),
+        "helo" => validate_helo(value),
+        "ident" => true,
+        "name" => validate_hostname(value),
+        "port" => validate_port(value),
+        "proto" => validate_protocol(value),
+        "source" => value.eq_ignore_ascii_case("local") || value.eq_ignore_ascii_case("remote"),
+        _ => false,
+    }
+}
 
 fn command_name(input: &[u8]) -> NomResult<&'static str> {
     alt((
@@ -30,12 +157,18 @@ fn command_name(input: &[u8]) -> NomResult<&'static str> {
     ))(input)
 }
 
-fn unavailable(input: &[u8]) -> NomResult<Option<String>> {
-    map(tag_no_case("[unavailable]"), |_| None)(input)
+fn unavailable(input: &[u8]) -> NomResult<Value> {
+    alt((
+        map(tag_no_case("[unavailable]"), |_| Value::Unavailable),
+        map(tag_no_case("[tempunavail]"), |_| Value::TempUnavailable),
+    ))(input)
 }
 
-fn value(input: &[u8]) -> NomResult<Option<String>> {
-    alt((unavailable, map(xtext, |x| Some(ascii_to_string(x).into()))))(input)
+fn value(input: &[u8]) -> NomResult<Value> {
+    alt((
+        unavailable,
+        map(xtext, |x| Value::Available(ascii_to_string(x).into())),
+    ))(input)
 }
 
 fn param(input: &[u8]) -> NomResult<Param> {
@@ -49,16 +182,107 @@ fn param(input: &[u8]) -> NomResult<Param> {
 /// Returns a vector of [`Param`].
 ///
 /// The parameter names must be valid and are normalized to
-/// lowercase. The values are xtext decoded and a value of
-/// `[UNAVAILABLE]` is translated to `None`. No other validation is
-/// done.
+/// lowercase. The values are xtext decoded and the special values
+/// `[UNAVAILABLE]` and `[TEMPUNAVAIL]` are translated to
+/// [`Value::Unavailable`] and [`Value::TempUnavailable`]. No other
+/// validation is done.
+/// # Examples
+/// ```
+/// use rustyknife::xforward::{xforward_params, Param, Value};
+///
+/// let (_, params) = xforward_params(b"NAME=[TEMPUNAVAIL] ADDR=192.0.2.1 IDENT=abc+2B123").unwrap();
+///
+/// assert_eq!(params, [Param("name", Value::TempUnavailable),
+///                     Param("addr", Value::Available("192.0.2.1".into())),
+///                     Param("ident", Value::Available("abc+123".into()))]);
+/// ```
 pub fn xforward_params(input: &[u8]) -> NomResult<Vec<Param>> {
-    fold_prefix0(
-        preceded(opt(many1(wsp)), param),
-        preceded(many1(wsp), param),
+    traced(
+        "xforward_params",
+        fold_prefix0(
+            preceded(opt(many1(wsp)), param),
+            preceded(many1(wsp), param),
+        ),
     )(input)
 }
 
+/// Parse a complete XFORWARD command.
 pub fn command(input: &[u8]) -> NomResult<Vec<Param>> {
     delimited(tag_no_case("XFORWARD "), xforward_params, crlf)(input)
 }
+
+/// XFORWARD attributes merged from successive commands.
+///
+/// Postfix splits the attributes across several XFORWARD commands
+/// when they do not fit on a single line.
+/// # Examples
+/// ```
+/// use rustyknife::xforward::{xforward_params, Attributes, Value};
+///
+/// let mut attrs = Attributes::new();
+/// attrs.merge(xforward_params(b"NAME=mx.example.org ADDR=192.0.2.1").unwrap().1).unwrap();
+/// attrs.merge(xforward_params(b"PROTO=ESMTP ADDR=192.0.2.1").unwrap().1).unwrap();
+///
+/// assert_eq!(attrs.get("addr"), Some(&Value::Available("192.0.2.1".into())));
+/// assert_eq!(attrs.len(), 3);
+///
+/// let conflict = xforward_params(b"ADDR=192.0.2.2").unwrap().1;
+/// assert_eq!(attrs.merge(conflict), Err("addr"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Attributes(Vec<Param>);
+
+impl Attributes {
+    /// Create an empty attribute set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merge the parameters of one XFORWARD command.
+    ///
+    /// An attribute may be repeated with the same value. On a
+    /// contradictory value, nothing is merged and the name of the
+    /// attribute is returned.
+    pub fn merge<I: IntoIterator<Item = Param>>(&mut self, params: I) -> Result<(), &'static str> {
+        let mut merged = self.0.clone();
+
+        for Param(name, value) in params {
+            match merged.iter().find(|p| p.0 == name) {
+                Some(Param(_, existing)) if *existing != value => return Err(name),
+                Some(_) => (),
+                None => merged.push(Param(name, value)),
+            }
+        }
+        self.0 = merged;
+
+        Ok(())
+    }
+
+    /// Return the value of the attribute `name`.
+    ///
+    /// `name` must be lowercase.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.0.iter().find(|p| p.0 == name).map(|p| &p.1)
+    }
+
+    /// Number of attributes.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if no attribute was merged.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterate over the attributes in the order they were first seen.
+    pub fn iter(&self) -> std::slice::Iter<'_, Param> {
+        self.0.iter()
+    }
+}
+
+impl From<Attributes> for Vec<Param> {
+    fn from(attrs: Attributes) -> Vec<Param> {
+        attrs.0
+    }
+}